@@ -0,0 +1,140 @@
+use std::sync::atomic::Ordering;
+
+use axum::{extract::State, http::HeaderMap, routing::get, Json, Router};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::api::extract::AppJson;
+use crate::error::{AppError, Result};
+use crate::state::AppState;
+
+/// Header carrying the operator's admin key
+const ADMIN_KEY_HEADER: &str = "x-admin-key";
+
+/// Capacity snapshot, built from in-memory state only
+#[derive(Debug, Serialize)]
+pub struct StatsResponse {
+    /// Rooms with at least one open WebSocket connection
+    pub rooms: usize,
+    /// Rooms with media state in the gateway
+    pub media_rooms: usize,
+    pub connections: usize,
+    pub publishers: usize,
+    pub subscribers: usize,
+    /// Connections per room with connections (0 when there are none)
+    pub avg_participants_per_room: f64,
+    pub uptime_seconds: u64,
+    pub started_at: DateTime<Utc>,
+    /// Result of the last TURN probe (None = no TURN or not checked yet)
+    pub turn_reachable: Option<bool>,
+    pub turn_failed_checks: u64,
+    /// Publishers the reconciliation task found in only one of Redis and the gateway
+    pub publisher_inconsistencies: u64,
+    /// Subscribers of the most-watched feed (compare with `MAX_SUBSCRIBERS_PER_FEED`)
+    pub busiest_feed_subscribers: usize,
+}
+
+/// Switch the runtime maintenance flag
+#[derive(Debug, Deserialize)]
+pub struct MaintenanceRequest {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MaintenanceResponse {
+    /// Effective state (config or runtime flag)
+    pub enabled: bool,
+    /// `MAINTENANCE_MODE` is set, so the runtime flag can't turn it off
+    pub forced_by_config: bool,
+}
+
+/// Admin routes
+pub fn admin_routes() -> Router<AppState> {
+    Router::new()
+        .route("/stats", get(get_stats))
+        .route("/maintenance", get(get_maintenance).put(set_maintenance))
+}
+
+/// Check `X-Admin-Key`; the admin API does not exist unless a key is configured
+pub(crate) fn require_admin(state: &AppState, headers: &HeaderMap) -> Result<()> {
+    let expected = state
+        .config
+        .admin_api_key
+        .as_deref()
+        .ok_or_else(|| AppError::NotFound("Route not found".to_string()))?;
+
+    let provided = headers
+        .get(ADMIN_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| AppError::Unauthorized("Admin key required".to_string()))?;
+
+    // Compare digests so the check doesn't leak the key length/prefix through timing
+    if Sha256::digest(provided.trim().as_bytes()) != Sha256::digest(expected.trim().as_bytes()) {
+        return Err(AppError::Unauthorized("Invalid admin key".to_string()));
+    }
+
+    Ok(())
+}
+
+/// GET /api/v1/admin/stats - Capacity snapshot for dashboards
+async fn get_stats(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<StatsResponse>> {
+    require_admin(&state, &headers)?;
+
+    let rooms = state.connections.room_count();
+    let connections = state.connections.connection_count();
+    let (publishers, subscribers) = state.media_gateway.session_totals();
+    let avg_participants_per_room = if rooms == 0 {
+        0.0
+    } else {
+        connections as f64 / rooms as f64
+    };
+
+    Ok(Json(StatsResponse {
+        rooms,
+        media_rooms: state.media_gateway.room_count(),
+        connections,
+        publishers,
+        subscribers,
+        avg_participants_per_room,
+        uptime_seconds: (Utc::now() - state.started_at).num_seconds().max(0) as u64,
+        started_at: state.started_at,
+        turn_reachable: state.turn_health.reachable(),
+        turn_failed_checks: state.turn_health.failed_checks(),
+        publisher_inconsistencies: state.publisher_inconsistencies.load(Ordering::Relaxed),
+        busiest_feed_subscribers: state.media_gateway.busiest_feed_subscribers().await,
+    }))
+}
+
+/// GET /api/v1/admin/maintenance - Current maintenance state
+async fn get_maintenance(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<MaintenanceResponse>> {
+    require_admin(&state, &headers)?;
+
+    Ok(Json(MaintenanceResponse {
+        enabled: state.in_maintenance().await?,
+        forced_by_config: state.config.maintenance_mode,
+    }))
+}
+
+/// PUT /api/v1/admin/maintenance - Pause or resume new rooms and joins
+async fn set_maintenance(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AppJson(request): AppJson<MaintenanceRequest>,
+) -> Result<Json<MaintenanceResponse>> {
+    require_admin(&state, &headers)?;
+
+    state.room_repo.set_maintenance(request.enabled).await?;
+    tracing::warn!(enabled = request.enabled, "Maintenance mode changed");
+
+    Ok(Json(MaintenanceResponse {
+        enabled: state.in_maintenance().await?,
+        forced_by_config: state.config.maintenance_mode,
+    }))
+}
@@ -0,0 +1,20 @@
+use axum::{extract::State, http::HeaderMap, routing::get, Json, Router};
+
+use crate::error::Result;
+use crate::models::WhoAmIResponse;
+use crate::state::AppState;
+
+/// Auth routes
+pub fn auth_routes() -> Router<AppState> {
+    Router::new().route("/whoami", get(whoami))
+}
+
+/// GET /api/v1/auth/whoami - Decode the bearer token as the server sees it.
+/// Invalid or expired tokens get a 401 naming the reason.
+async fn whoami(State(state): State<AppState>, headers: HeaderMap) -> Result<Json<WhoAmIResponse>> {
+    let claims = state.auth.extract_bearer(&headers)?;
+
+    let now = chrono::Utc::now().timestamp();
+
+    Ok(Json(WhoAmIResponse::new(claims, now)))
+}
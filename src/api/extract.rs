@@ -0,0 +1,53 @@
+//! Extractors that reject with `AppError`, so malformed paths, queries and
+//! bodies get the same JSON `{ error, code }` shape as handler errors
+
+use std::collections::HashMap;
+
+use axum::extract::{FromRequest, FromRequestParts, Path};
+use axum::http::request::Parts;
+
+use crate::api::rooms::resolve_room_ref;
+use crate::error::AppError;
+use crate::state::AppState;
+
+/// `axum::Json` with a JSON rejection keeping axum's status (400, 413, 415, 422)
+#[derive(Debug, FromRequest)]
+#[from_request(via(axum::Json), rejection(AppError))]
+pub struct AppJson<T>(pub T);
+
+/// `axum::extract::Query` with a JSON 400 rejection
+#[derive(Debug, FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(AppError))]
+pub struct AppQuery<T>(pub T);
+
+/// `axum::extract::Path` with a JSON 400 rejection
+#[derive(Debug, FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(AppError))]
+pub struct AppPath<T>(pub T);
+
+/// Room named by the route's `{room_id}` segment, as its canonical UUID: the
+/// segment may be a UUID or, with `ROOM_SLUGS_ENABLED`, a slug (resolved
+/// here). Anything else is rejected with `AppError::InvalidRoomId`.
+#[derive(Debug, Clone)]
+pub struct RoomId(pub String);
+
+impl FromRequestParts<AppState> for RoomId {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let Path(params) =
+            Path::<HashMap<String, String>>::from_request_parts(parts, state).await?;
+        let room_ref = params
+            .get("room_id")
+            .ok_or_else(|| AppError::InternalError("Route has no room_id segment".to_string()))?;
+        resolve_room_ref(state, room_ref).await.map(RoomId)
+    }
+}
+
+/// Fallback for unmatched routes
+pub async fn not_found() -> AppError {
+    AppError::NotFound("Route not found".to_string())
+}
@@ -1,8 +1,14 @@
-use axum::{extract::State, routing::get, Json, Router};
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
 use chrono::Utc;
 use serde::Serialize;
 
-use crate::error::Result;
+use crate::error::{AppError, Result};
 use crate::state::AppState;
 
 /// Health response structure
@@ -12,15 +18,109 @@ pub struct HealthResponse {
     pub redis: String,
     pub media_gateway: String,
     pub timestamp: String,
+    pub instance_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+}
+
+/// Readiness response; `turn` is informational and never makes it fail
+#[derive(Debug, Serialize)]
+pub struct ReadyResponse {
+    pub ready: bool,
+    pub redis: String,
+    pub media_gateway: String,
+    /// `not_configured`, `unchecked`, `reachable` or `unreachable`
+    pub turn: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub turn_last_checked_at: Option<String>,
+    /// New rooms and joins are refused; running meetings continue
+    pub maintenance: bool,
+}
+
+/// Body format of `/health`, picked from the `Accept` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthFormat {
+    /// Full JSON report (default)
+    Json,
+    /// Bare `OK`/`FAIL`, with 503 on failure, for simple uptime checkers
+    Text,
+}
+
+impl HealthFormat {
+    /// Text only when the client asks for `text/plain` and not for JSON
+    pub fn from_accept(headers: &HeaderMap) -> Self {
+        let accept = headers
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        if accept.contains("text/plain") && !accept.contains("application/json") {
+            HealthFormat::Text
+        } else {
+            HealthFormat::Json
+        }
+    }
 }
 
 /// Health routes
 pub fn health_routes() -> Router<AppState> {
-    Router::new().route("/health", get(health_check))
+    Router::new()
+        .route("/health", get(health_check))
+        .route("/ready", get(ready_check))
+        .route("/metrics", get(metrics))
+}
+
+/// GET /metrics - Prometheus counters; 404 unless `METRICS_ENABLED`
+async fn metrics(State(state): State<AppState>) -> Result<Response> {
+    if !state.config.metrics_enabled {
+        return Err(AppError::NotFound("Metrics are disabled".to_string()));
+    }
+    let content_type = [(header::CONTENT_TYPE, "text/plain; version=0.0.4")];
+    Ok((content_type, state.invite_funnel.render()).into_response())
+}
+
+/// GET /ready - 200 when the instance can serve traffic, 503 otherwise
+async fn ready_check(State(state): State<AppState>) -> (StatusCode, Json<ReadyResponse>) {
+    let redis_ok = matches!(state.room_repo.health_check().await, Ok(true));
+    let media_ok = state.media_gateway.is_healthy();
+
+    let turn = if state.config.turn_server.is_none() {
+        "not_configured"
+    } else {
+        match state.turn_health.reachable() {
+            None => "unchecked",
+            Some(true) => "reachable",
+            Some(false) => "unreachable",
+        }
+    };
+
+    let ready = redis_ok && media_ok;
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status,
+        Json(ReadyResponse {
+            ready,
+            redis: if redis_ok { "connected" } else { "disconnected" }.to_string(),
+            media_gateway: if media_ok { "ready" } else { "not_ready" }.to_string(),
+            turn: turn.to_string(),
+            turn_last_checked_at: state
+                .turn_health
+                .last_checked_at()
+                .await
+                .map(|t| t.to_rfc3339()),
+            maintenance: state.in_maintenance().await.unwrap_or(state.config.maintenance_mode),
+        }),
+    )
 }
 
-/// GET /health - Health check endpoint
-async fn health_check(State(state): State<AppState>) -> Result<Json<HealthResponse>> {
+/// GET /health - Health check endpoint (JSON, or `OK`/`FAIL` for `Accept: text/plain`)
+async fn health_check(State(state): State<AppState>, headers: HeaderMap) -> Result<Response> {
     let redis_status = match state.room_repo.health_check().await {
         Ok(true) => "connected",
         Ok(false) => "error",
@@ -39,10 +139,46 @@ async fn health_check(State(state): State<AppState>) -> Result<Json<HealthRespon
         "unhealthy"
     };
 
+    if HealthFormat::from_accept(&headers) == HealthFormat::Text {
+        let (status, body) = if overall_status == "healthy" {
+            (StatusCode::OK, "OK")
+        } else {
+            (StatusCode::SERVICE_UNAVAILABLE, "FAIL")
+        };
+        let content_type = [(header::CONTENT_TYPE, "text/plain; charset=utf-8")];
+        return Ok((status, content_type, body).into_response());
+    }
+
     Ok(Json(HealthResponse {
         status: overall_status.to_string(),
         redis: redis_status.to_string(),
         media_gateway: media_gateway_status.to_string(),
         timestamp: Utc::now().to_rfc3339(),
-    }))
+        instance_id: state.instance_id.clone(),
+        region: state.config.server_region.clone(),
+    })
+    .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accept(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_health_format_negotiation() {
+        let (json, text) = (HealthFormat::Json, HealthFormat::Text);
+        assert_eq!(HealthFormat::from_accept(&HeaderMap::new()), json);
+        assert_eq!(HealthFormat::from_accept(&accept("*/*")), json);
+        assert_eq!(HealthFormat::from_accept(&accept("text/plain")), text);
+        assert_eq!(
+            HealthFormat::from_accept(&accept("application/json, text/plain;q=0.5")),
+            json
+        );
+    }
 }
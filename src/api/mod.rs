@@ -1,19 +1,142 @@
+pub mod admin;
+pub mod auth;
+pub mod extract;
 pub mod health;
 pub mod rooms;
+pub mod users;
 
+use axum::extract::DefaultBodyLimit;
+use axum::http::{HeaderName, HeaderValue};
 use axum::Router;
+use tower_http::set_header::SetResponseHeaderLayer;
 
 use crate::state::AppState;
 
+/// Response header naming the region and instance that handled a request
+pub const SERVED_BY_HEADER: &str = "x-served-by";
+
 /// Create the API router with all routes
 pub fn create_router(state: AppState) -> Router {
+    // Every REST body is small JSON (SDP goes over the WebSocket), so one
+    // limit covers all routes; larger bodies get a JSON 413
+    let body_limit = DefaultBodyLimit::max(state.config.max_request_body_bytes);
+
     Router::new()
         .nest("/api/v1", api_routes())
         .merge(health::health_routes())
+        .layer(body_limit)
         .with_state(state)
 }
 
+/// Layer stamping every response with `X-Served-By`; goes on the outermost
+/// router so fallbacks and panics carry it too
+pub fn served_by_layer(served_by: &str) -> SetResponseHeaderLayer<Option<HeaderValue>> {
+    // The region charset is checked at config load, so this always parses
+    SetResponseHeaderLayer::overriding(
+        HeaderName::from_static(SERVED_BY_HEADER),
+        HeaderValue::from_str(served_by).ok(),
+    )
+}
+
 /// API v1 routes
 fn api_routes() -> Router<AppState> {
-    Router::new().nest("/rooms", rooms::room_routes())
+    Router::new()
+        .nest("/rooms", rooms::room_routes())
+        .nest("/users", users::user_routes())
+        .nest("/auth", auth::auth_routes())
+        .nest("/admin", admin::admin_routes())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Request, StatusCode};
+    use axum::routing::post;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::api::extract::AppJson;
+
+    async fn echo(AppJson(body): AppJson<serde_json::Value>) -> axum::Json<serde_json::Value> {
+        axum::Json(body)
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Named {
+        #[allow(dead_code)]
+        name: String,
+    }
+
+    async fn named(AppJson(_): AppJson<Named>) {}
+
+    fn post_json(body: String) -> Request<Body> {
+        Request::post("/")
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_oversized_body_is_json_413() {
+        let app = Router::new()
+            .route("/", post(echo))
+            .layer(DefaultBodyLimit::max(1024));
+
+        let small = app
+            .clone()
+            .oneshot(post_json(r#"{"name":"ok"}"#.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(small.status(), StatusCode::OK);
+
+        let oversized = format!(r#"{{"name":"{}"}}"#, "x".repeat(4096));
+        let response = app.oneshot(post_json(oversized)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["code"], 413);
+        assert!(json["error"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_json_rejections_keep_their_status() {
+        let app = Router::new().route("/", post(named));
+
+        let no_content_type = Request::post("/")
+            .body(Body::from(r#"{"name":"ok"}"#))
+            .unwrap();
+        let response = app.clone().oneshot(no_content_type).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let malformed = app
+            .clone()
+            .oneshot(post_json("{not json".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(malformed.status(), StatusCode::BAD_REQUEST);
+
+        let wrong_shape = app
+            .oneshot(post_json(r#"{"name":42}"#.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(wrong_shape.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let body = to_bytes(wrong_shape.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["code"], 422);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_carries_served_by() {
+        let app = Router::new()
+            .route("/", post(named))
+            .fallback(extract::not_found)
+            .layer(served_by_layer("eu-west/instance-1"));
+
+        let request = Request::get("/nowhere").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers()[SERVED_BY_HEADER], "eu-west/instance-1");
+    }
 }
@@ -1,29 +1,66 @@
 use axum::{
-    extract::{Path, Query, State},
-    routing::{get, post},
+    extract::State,
+    http::HeaderMap,
+    routing::{delete, get, patch, post},
     Json, Router,
 };
 use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
+use crate::api::admin::require_admin;
+use crate::api::extract::{AppJson, AppPath, AppQuery, RoomId};
 use crate::error::{AppError, Result};
+use crate::metrics::InviteStage;
+use crate::models::user::MemberInfo;
 use crate::models::{
-    CreateInvitationRequest, CreateInvitationResponse, CreateRoomRequest, CreateRoomResponse,
-    IceServer, InvitationInfo, JoinRequest, JoinResponse, PublisherInfo, Room, RoomInvitation,
-    InviteEmailRequest, InviteEmailResponse,
+    AnnounceRequest, Announcement, BanRequest, BanResponse, BatchCreateRoomResult, BatchItemError, CohostRequest, CohostResponse, CreateInvitationRequest, CreateInvitationResponse,
+    CreateRoomRequest, CreateRoomResponse, IceServer, InvitationCountResponse, InvitationInfo, JoinRequest, JoinResponse, NetworkHint,
+    LockRoomRequest, LockRoomResponse, ModeratorRequest, PublisherInfo, Room, RoomConfigResponse, RoomInvitation, InviteEmailRequest, InviteEmailResponse,
+    RotateInviteCodeRequest, RotateInviteCodeResponse, RotateKeyRequest, RotateKeyResponse,
+    TransferHostRequest, TransferHostResponse, UnbanRequest, UpdateInvitationRequest, UpdateRoomRequest, UpdateRoomResponse,
+};
+use crate::security::{
+    generate_creator_key, sign_invite_token, sign_magic_link, verify_invite_token,
+    verify_magic_link, InviteToken, MagicLink,
 };
 use crate::state::AppState;
+use crate::ws::{
+    msg_types, BannedPayload, HostTransferredPayload, MemberLeftPayload, RoleChangedPayload,
+    RoomLockPayload, SignalingMessage,
+};
+use crate::validation::{
+    ensure_client_version, normalize_codec_list, normalize_fingerprint, normalize_slug, sanitize_announcement, sanitize_display,
+    DisplayNameRules, InviteUsePolicy, TextField, TextLimits, CLIENT_VERSION_HEADER,
+};
+
+/// Header carrying the host's creator_key on requests without a body
+const CREATOR_KEY_HEADER: &str = "x-creator-key";
 
 /// Room routes
 pub fn room_routes() -> Router<AppState> {
     Router::new()
         .route("/", get(list_rooms).post(create_room))
-        .route("/{room_id}", get(get_room))
+        .route("/batch", post(create_rooms_batch))
+        .route("/{room_id}", get(get_room).patch(update_room))
         .route("/{room_id}/join", post(join_room))
         .route("/{room_id}/leave", post(leave_room))
+        .route("/{room_id}/config", get(get_room_config))
+        .route("/{room_id}/ice", get(get_ice_servers))
         .route("/{room_id}/invite", post(create_invitation))
+        .route("/{room_id}/invite/{token}", get(get_room_invitation).patch(update_invitation))
+        .route("/{room_id}/invite/{token}/use", post(use_room_invitation))
+        .route("/{room_id}/invite/{token}/rotate-code", post(rotate_invite_code))
         .route("/{room_id}/invites", get(list_invitations))
+        .route("/{room_id}/invites/count", get(count_invitations))
         .route("/{room_id}/invite-email", post(send_invite_email))
+        .route("/{room_id}/rotate-key", post(rotate_creator_key))
+        .route("/{room_id}/transfer-host", post(transfer_host))
+        .route("/{room_id}/announce", post(announce))
+        .route("/{room_id}/lock", post(lock_room))
+        .route("/{room_id}/unlock", post(unlock_room))
+        .route("/{room_id}/cohosts", post(add_cohost))
+        .route("/{room_id}/cohosts/{user_id}", delete(revoke_cohost))
+        .route("/{room_id}/ban", post(ban).delete(unban))
         .route("/invite/{token}", get(get_invitation))
         .route("/invite/{token}/use", post(use_invitation))
 }
@@ -52,19 +89,6 @@ fn normalize_invite_code(input: &str) -> String {
     }
 }
 
-/// Generates host-only creator key (stored locally on creator device)
-fn gen_creator_key() -> String {
-    use rand::Rng;
-    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
-    let mut rng = rand::rng();
-    (0..32)
-        .map(|_| {
-            let idx = rng.random_range(0..CHARSET.len());
-            CHARSET[idx] as char
-        })
-        .collect()
-}
-
 /// 6 digits, displayed like 761-221
 fn gen_invite_code() -> String {
     use rand::Rng;
@@ -74,52 +98,706 @@ fn gen_invite_code() -> String {
     format!("{:03}-{:03}", a, b)
 }
 
+/// Verify the host's creator_key against the stored hash
+async fn verify_creator_key(state: &AppState, room_id: &str, creator_key: &str) -> Result<()> {
+    let expected = state
+        .room_repo
+        .get_creator_key_hash(room_id)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Access denied".to_string()))?;
+
+    if !creator_key_matches(&state.config.invite_code_salt, creator_key, &expected) {
+        return Err(AppError::BadRequest("Invalid creator key".to_string()));
+    }
+
+    Ok(())
+}
+
+fn creator_key_matches(pepper: &str, creator_key: &str, expected_hash: &str) -> bool {
+    hash_code(pepper, creator_key.trim()) == expected_hash
+}
+
+fn invite_code_matches(pepper: &str, raw_code: &str, expected_hash: &str) -> bool {
+    hash_code(pepper, &normalize_invite_code(raw_code)) == expected_hash
+}
+
+/// A room path segment before lookup
+#[derive(Debug, PartialEq, Eq)]
+pub enum RoomRef {
+    Id(String),
+    Slug(String),
+}
+
+/// Classify a path segment as a room UUID or, when slugs are enabled, a
+/// well-formed slug; anything else is `AppError::InvalidRoomId`
+pub fn parse_room_ref(raw: &str, slugs_enabled: bool) -> Result<RoomRef> {
+    if Uuid::parse_str(raw).is_ok() {
+        return Ok(RoomRef::Id(raw.to_string()));
+    }
+    if !slugs_enabled {
+        return Err(AppError::InvalidRoomId);
+    }
+    normalize_slug(raw)
+        .map(RoomRef::Slug)
+        .map_err(|_| AppError::InvalidRoomId)
+}
+
+/// Resolve a path segment that is either a room UUID or (when enabled) a room slug
+pub(crate) async fn resolve_room_ref(state: &AppState, room_ref: &str) -> Result<String> {
+    match parse_room_ref(room_ref, state.config.room_slugs_enabled)? {
+        RoomRef::Id(room_id) => Ok(room_id),
+        RoomRef::Slug(slug) => state
+            .room_repo
+            .resolve_slug(&slug)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Room {} not found", room_ref))),
+    }
+}
+
+/// Whether a token issued for `claim_room_id` covers `requested`, given the
+/// canonical id `requested` resolved to (`None` when slugs are disabled or it
+/// did not resolve): a slug matches the UUID it points at
+pub fn token_room_matches(claim_room_id: &str, requested: &str, resolved: Option<&str>) -> bool {
+    claim_room_id == requested || resolved == Some(claim_room_id)
+}
+
+/// `ensure_media_capacity` against this instance's connections and gateway.
+/// A fast path only: the WebSocket may land on another instance, so the WS
+/// upgrade and the gateway's `create_publisher` enforce the limits.
+fn check_media_capacity(state: &AppState, publishes: bool) -> Result<()> {
+    let (publishers, _) = state.media_gateway.session_totals();
+    ensure_media_capacity(
+        state.connections.connection_count(),
+        state.config.max_connections,
+        publishers,
+        state.config.max_publishers_total,
+        publishes,
+    )
+}
+
+/// Members who can publish may not outnumber the room's publisher cap;
+/// observers are left out of the count. A moderator in a room with
+/// `moderator_preemption` always gets in: the cap is enforced when they
+/// publish, by demoting a guest publisher.
+async fn ensure_capacity(state: &AppState, room: &Room, moderator: bool) -> Result<()> {
+    if moderator && room.moderator_preemption {
+        return Ok(());
+    }
+    let participants = state
+        .room_repo
+        .get_member_infos(&room.room_id)
+        .await?
+        .iter()
+        .filter(|member| !member.observer)
+        .count();
+    if !seat_available(false, false, participants, room.max_publishers) {
+        return Err(AppError::RoomFull);
+    }
+    Ok(())
+}
+
+/// Whether a client may take part given `seats_taken` (non-observer members)
+/// out of `max_publishers`. A member already holds their seat and observers
+/// never take one.
+pub fn seat_available(
+    member: bool,
+    observer: bool,
+    seats_taken: usize,
+    max_publishers: u32,
+) -> bool {
+    member || observer || seats_taken < max_publishers as usize
+}
+
+/// TTL of a new room: a requested TTL of 0 takes the deployment default, and
+/// anything above `max_ttl` is clamped to it (0 = no maximum)
+pub fn effective_room_ttl(requested: u64, default_ttl: u64, max_ttl: u64) -> u64 {
+    let ttl = if requested > 0 {
+        requested
+    } else {
+        default_ttl
+    };
+    if max_ttl > 0 {
+        ttl.min(max_ttl)
+    } else {
+        ttl
+    }
+}
+
+/// Deployment limits on how long a room lives, applied the same way when a
+/// room is created and when its TTL is refreshed
+#[derive(Debug, Clone, Copy)]
+pub struct RoomTtlRules {
+    pub default_ttl: u64,
+    /// Longest TTL a single create or refresh may set (0 = no maximum)
+    pub max_ttl: u64,
+    /// Absolute cap on the room's life from creation (0 = none)
+    pub max_lifetime: u64,
+}
+
+impl RoomTtlRules {
+    pub fn from_config(config: &crate::config::Config) -> Self {
+        Self {
+            default_ttl: config.room_ttl_seconds,
+            max_ttl: config.max_room_ttl_seconds,
+            max_lifetime: config.max_room_lifetime_seconds,
+        }
+    }
+
+    /// TTL `room` gets when `requested` seconds are asked for at `now`:
+    /// `effective_room_ttl`, then no further than `max_lifetime` after the
+    /// room's creation. `None` once that lifetime is used up.
+    pub fn ttl_for(
+        &self,
+        room: &Room,
+        requested: u64,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Option<u64> {
+        let ttl = effective_room_ttl(requested, self.default_ttl, self.max_ttl);
+        room.capped_ttl(ttl, self.max_lifetime, now)
+    }
+}
+
+/// In rooms with `require_unique_display`, refuse a display name another
+/// member (not `user_id` itself) already uses, compared case-insensitively,
+/// and suggest the first free numbered variant
+pub fn ensure_display_available(
+    display: &str,
+    user_id: &str,
+    members: &[MemberInfo],
+) -> Result<()> {
+    let taken = |name: &str| {
+        let name = name.to_lowercase();
+        members
+            .iter()
+            .any(|m| m.user_id != user_id && m.display.to_lowercase() == name)
+    };
+    if !taken(display) {
+        return Ok(());
+    }
+
+    let suggestion = (2..)
+        .map(|n| format!("{} {}", display, n))
+        .find(|candidate| !taken(candidate))
+        .unwrap_or_default();
+    Err(AppError::Conflict(format!(
+        "Display name '{}' is already taken in this room, try '{}'",
+        display, suggestion
+    )))
+}
+
+/// Record a member under `display`, under the room lock: in rooms with
+/// `require_unique_display` two simultaneous joins can't both take the same
+/// name, and the unjoined-room reaper (which deletes under the lock too)
+/// can't remove the room between the check that it exists and the join.
+pub(crate) async fn join_member_checked(
+    state: &AppState,
+    room_id: &str,
+    user_id: &str,
+    display: &str,
+    observer: bool,
+) -> Result<()> {
+    state
+        .room_repo
+        .with_room_lock(room_id, async {
+            let room = state
+                .room_repo
+                .get_room(room_id)
+                .await?
+                .ok_or_else(|| AppError::NotFound(format!("Room {} not found", room_id)))?;
+            if room.require_unique_display {
+                let members = state.room_repo.get_member_infos(room_id).await?;
+                ensure_display_available(display, user_id, &members)?;
+            }
+            state
+                .room_repo
+                .join_member(room_id, user_id, display, observer)
+                .await
+        })
+        .await
+}
+
+/// Rename a member, with the same uniqueness rule (and lock) as joining
+pub(crate) async fn rename_member_checked(
+    state: &AppState,
+    room_id: &str,
+    user_id: &str,
+    display: &str,
+) -> Result<bool> {
+    if !requires_unique_display(state, room_id).await? {
+        return state
+            .room_repo
+            .rename_member(room_id, user_id, display)
+            .await;
+    }
+
+    state
+        .room_repo
+        .with_room_lock(room_id, async {
+            let members = state.room_repo.get_member_infos(room_id).await?;
+            ensure_display_available(display, user_id, &members)?;
+            state.room_repo.rename_member(room_id, user_id, display).await
+        })
+        .await
+}
+
+async fn requires_unique_display(state: &AppState, room_id: &str) -> Result<bool> {
+    Ok(state
+        .room_repo
+        .get_room(room_id)
+        .await?
+        .is_some_and(|room| room.require_unique_display))
+}
+
+/// Observers (watch-only participants) may subscribe but never publish
+pub fn ensure_can_publish(observer: bool) -> Result<()> {
+    if observer {
+        return Err(AppError::Forbidden(
+            "Observers can watch but not publish".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Locked rooms only admit the host and co-hosts
+pub fn ensure_unlocked(locked: bool, privileged: bool) -> Result<()> {
+    if locked && !privileged {
+        return Err(AppError::RoomLocked);
+    }
+    Ok(())
+}
+
+/// In maintenance mode no rooms are created and nobody new joins; members
+/// already in a meeting keep signaling over their WebSocket
+pub fn ensure_accepting(maintenance: bool) -> Result<()> {
+    if maintenance {
+        return Err(AppError::ServiceUnavailable(
+            "Server is in maintenance mode: new rooms and joins are paused, please retry shortly"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Refuse a join the media gateway could not serve: past `max_connections`
+/// WebSocket connections, or past `max_publishers_total` publishers for a
+/// member who may publish (0 = unlimited)
+pub fn ensure_media_capacity(
+    connections: usize,
+    max_connections: usize,
+    publishers: usize,
+    max_publishers_total: usize,
+    publishes: bool,
+) -> Result<()> {
+    let connections_full = max_connections > 0 && connections >= max_connections;
+    let publishers_full =
+        publishes && max_publishers_total > 0 && publishers >= max_publishers_total;
+    if connections_full || publishers_full {
+        return Err(AppError::ServiceUnavailable(
+            "Server at capacity: no media capacity left for new participants, please retry later"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Refuse a new invitation once the room has `max` live ones (0 = unlimited)
+pub fn ensure_invite_capacity(live: usize, max: usize) -> Result<()> {
+    if max > 0 && live >= max {
+        return Err(AppError::Conflict(format!(
+            "Room already has the maximum of {} active invitations",
+            max
+        )));
+    }
+    Ok(())
+}
+
+/// Refuse a subscribe naming more than `max` feeds (0 = unlimited)
+pub fn ensure_subscription_limit(requested: usize, max: usize) -> Result<()> {
+    if max > 0 && requested > max {
+        return Err(AppError::BadRequest(format!(
+            "Subscription limit exceeded: at most {} feeds per subscriber ({} requested)",
+            max, requested
+        )));
+    }
+    Ok(())
+}
+
+/// Ban set entries for a participant and their fingerprints (blank and
+/// duplicate values skipped)
+pub fn ban_identities(user_id: Option<&str>, fingerprints: &[String]) -> Vec<String> {
+    let mut identities: Vec<String> = Vec::new();
+    if let Some(user_id) = user_id.map(str::trim).filter(|u| !u.is_empty()) {
+        identities.push(format!("user:{}", user_id));
+    }
+    for fingerprint in fingerprints {
+        let fingerprint = fingerprint.trim();
+        let identity = format!("fp:{}", fingerprint);
+        if !fingerprint.is_empty() && !identities.contains(&identity) {
+            identities.push(identity);
+        }
+    }
+    identities
+}
+
+/// Ban entries a ban/unban request names: the participant, the fingerprint
+/// they joined with and any fingerprint given directly
+async fn requested_ban_identities(
+    state: &AppState,
+    room_id: &str,
+    user_id: Option<&str>,
+    fingerprint: Option<&str>,
+) -> Result<Vec<String>> {
+    let mut fingerprints: Vec<String> = normalize_fingerprint(fingerprint)?.into_iter().collect();
+    if let Some(user_id) = user_id {
+        let joined_with = state
+            .room_repo
+            .get_member_fingerprint(room_id, user_id)
+            .await?;
+        fingerprints.extend(joined_with);
+    }
+
+    let identities = ban_identities(user_id, &fingerprints);
+    if identities.is_empty() {
+        return Err(AppError::BadRequest(
+            "user_id or fingerprint is required".to_string(),
+        ));
+    }
+    Ok(identities)
+}
+
+/// Whether a member is banned, by user id or by the fingerprint they joined with
+pub(crate) async fn member_is_banned(
+    state: &AppState,
+    room_id: &str,
+    user_id: &str,
+) -> Result<bool> {
+    let fingerprints: Vec<String> = state
+        .room_repo
+        .get_member_fingerprint(room_id, user_id)
+        .await?
+        .into_iter()
+        .collect();
+    let identities = ban_identities(Some(user_id), &fingerprints);
+    state.room_repo.is_banned(room_id, &identities).await
+}
+
+/// Whether the request carries a bearer JWT of a co-host of this room
+async fn bearer_is_cohost(state: &AppState, room_id: &str, headers: &HeaderMap) -> Result<bool> {
+    match state.auth.extract_bearer(headers) {
+        Ok(claims) if claims.room_id == room_id => {
+            state.room_repo.is_cohost(room_id, &claims.sub).await
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Reject forged/garbage invite tokens before any Redis lookup.
+/// Returns the room id embedded in signed tokens (`None` for legacy ones).
+/// Invalid tokens get the same error as unknown ones so probing learns nothing.
+fn check_invite_token(state: &AppState, token: &str) -> Result<Option<String>> {
+    match verify_invite_token(&state.config.invite_code_salt, token) {
+        InviteToken::Signed { room_id } => Ok(Some(room_id)),
+        InviteToken::Legacy => Ok(None),
+        InviteToken::Invalid => Err(AppError::NotFound(
+            "Invitation not found or expired".to_string(),
+        )),
+    }
+}
+
+/// Whether an invitation may be honored here: it must belong to the room the
+/// route names (if any) and to the room a signed token was minted for
+pub fn invitation_bound_to(
+    invitation: &RoomInvitation,
+    route_room: Option<&str>,
+    signed_room: Option<&str>,
+) -> bool {
+    [route_room, signed_room]
+        .into_iter()
+        .flatten()
+        .all(|room| room == invitation.room_id)
+}
+
+/// Load an invitation honored for `route_room`. An invitation of another
+/// room reads as unknown, so probing can't tell them apart.
+async fn bound_invitation(
+    state: &AppState,
+    token: &str,
+    route_room: Option<&str>,
+) -> Result<RoomInvitation> {
+    let signed_room = check_invite_token(state, token)?;
+    state
+        .room_repo
+        .get_invitation(token)
+        .await?
+        .filter(|inv| invitation_bound_to(inv, route_room, signed_room.as_deref()))
+        .ok_or_else(|| AppError::NotFound("Invitation not found or expired".to_string()))
+}
+
+/// With `invite_room_bound_only`, the room-less invitation routes answer
+/// like unknown routes
+pub fn ensure_roomless_invites_allowed(room_bound_only: bool) -> Result<()> {
+    if room_bound_only {
+        return Err(AppError::NotFound("Not found".to_string()));
+    }
+    Ok(())
+}
+
+/// Moderation gate: accepts the host's creator_key, or a bearer JWT issued
+/// for this room whose `sub` has been promoted to co-host
+async fn require_moderator(
+    state: &AppState,
+    room_id: &str,
+    creator_key: Option<&str>,
+    headers: &HeaderMap,
+) -> Result<()> {
+    if let Some(creator_key) = creator_key.map(str::trim).filter(|s| !s.is_empty()) {
+        return verify_creator_key(state, room_id, creator_key).await;
+    }
+
+    let claims = state.auth.extract_bearer(headers)?;
+    if claims.room_id != room_id {
+        return Err(AppError::Unauthorized(
+            "Token room_id does not match".to_string(),
+        ));
+    }
+
+    if !state.room_repo.is_cohost(room_id, &claims.sub).await? {
+        return Err(AppError::Unauthorized(
+            "Moderator privileges required".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
 /// POST /api/v1/rooms - Create a new room
 async fn create_room(
     State(state): State<AppState>,
-    Json(request): Json<CreateRoomRequest>,
+    headers: HeaderMap,
+    AppJson(request): AppJson<CreateRoomRequest>,
 ) -> Result<Json<CreateRoomResponse>> {
+    ensure_accepting(state.in_maintenance().await?)?;
+
+    // Tie the room to an upstream account when anonymous creation is off
+    let created_by = if state.config.create_requires_auth {
+        Some(state.auth.verify_creator(&headers)?)
+    } else {
+        None
+    };
+
+    let new_room = prepare_room(&state, request, created_by)?;
+    persist_room(&state, &new_room).await?;
+
+    tracing::info!(
+        room_id = %new_room.room.room_id,
+        name = %new_room.room.name,
+        created_by = ?new_room.room.created_by,
+        "Room created"
+    );
+
+    Ok(Json(created_room_response(new_room)))
+}
+
+/// POST /api/v1/rooms/batch - Create many rooms at once (admin key), e.g. for
+/// scheduled events. Items succeed or fail on their own; rooms without a slug
+/// are written in a single Redis pipeline.
+async fn create_rooms_batch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AppJson(requests): AppJson<Vec<CreateRoomRequest>>,
+) -> Result<Json<Vec<BatchCreateRoomResult>>> {
+    require_admin(&state, &headers)?;
+    ensure_accepting(state.in_maintenance().await?)?;
+    ensure_batch_size(requests.len(), state.config.max_room_batch_size)?;
+
+    let prepared: Vec<Result<NewRoom>> = requests
+        .into_iter()
+        .map(|request| prepare_room(&state, request, None))
+        .collect();
+
+    // Slugged rooms keep the single-create path: their slug is claimed under a lock
+    let pipelined: Vec<(&Room, &str)> = prepared
+        .iter()
+        .filter_map(|p| p.as_ref().ok())
+        .filter(|new_room| new_room.room.slug.is_none())
+        .map(|new_room| (&new_room.room, new_room.creator_hash.as_str()))
+        .collect();
+    let pipeline_error = if pipelined.is_empty() {
+        None
+    } else {
+        let grace = state.config.creator_key_grace_seconds;
+        let written = state.room_repo.create_rooms(&pipelined, grace).await;
+        written.err().map(|e| e.status_and_message().1)
+    };
+
+    let mut results = Vec::with_capacity(prepared.len());
+    for (index, prepared) in prepared.into_iter().enumerate() {
+        let outcome = match prepared {
+            Ok(new_room) if new_room.room.slug.is_some() => {
+                persist_room(&state, &new_room).await.map(|()| new_room)
+            }
+            Ok(new_room) => match &pipeline_error {
+                Some(message) => Err(AppError::RedisError(message.clone())),
+                None => Ok(new_room),
+            },
+            Err(e) => Err(e),
+        };
+        results.push(batch_result(index, outcome.map(created_room_response)));
+    }
+
+    let failed = results.iter().filter(|r| r.error.is_some()).count();
+    tracing::info!(requested = results.len(), failed, "Room batch created");
+
+    Ok(Json(results))
+}
+
+/// Refuse a batch larger than `max` rooms
+pub fn ensure_batch_size(len: usize, max: usize) -> Result<()> {
+    if len > max {
+        return Err(AppError::BadRequest(format!(
+            "Batch has {} rooms, at most {} are allowed",
+            len, max
+        )));
+    }
+    Ok(())
+}
+
+/// Report one batch item: the room, or the error a single create would have returned
+pub fn batch_result(index: usize, outcome: Result<CreateRoomResponse>) -> BatchCreateRoomResult {
+    match outcome {
+        Ok(room) => BatchCreateRoomResult {
+            index,
+            room: Some(room),
+            error: None,
+        },
+        Err(e) => {
+            let (status, error) = e.status_and_message();
+            BatchCreateRoomResult {
+                index,
+                room: None,
+                error: Some(BatchItemError {
+                    error,
+                    code: status.as_u16(),
+                }),
+            }
+        }
+    }
+}
+
+/// A validated room, not yet written, with its creator key (returned once)
+struct NewRoom {
+    room: Room,
+    creator_key: String,
+    creator_hash: String,
+}
+
+/// Validate a create request and build the room it describes
+fn prepare_room(
+    state: &AppState,
+    request: CreateRoomRequest,
+    created_by: Option<String>,
+) -> Result<NewRoom> {
     if request.name.is_empty() {
         return Err(AppError::BadRequest("Room name is required".to_string()));
     }
-    if request.name.len() > 100 {
-        return Err(AppError::BadRequest(
-            "Room name must be at most 100 characters".to_string(),
-        ));
-    }
+    TextLimits::from_config(&state.config).check(TextField::RoomName, &request.name)?;
+
+    let slug = match request.slug.as_deref() {
+        Some(raw) if state.config.room_slugs_enabled => Some(normalize_slug(raw)?),
+        Some(_) => {
+            return Err(AppError::BadRequest(
+                "Room slugs are not enabled".to_string(),
+            ))
+        }
+        None => None,
+    };
+    let allowed_codecs = normalize_codec_list(&request.allowed_codecs)?;
 
-    let room = Room::new(
+    let mut room = Room::new(
         request.name,
         request
             .max_publishers
             .min(state.config.max_publishers_per_room),
-        if request.ttl_seconds > 0 {
-            request.ttl_seconds
-        } else {
-            state.config.room_ttl_seconds
-        },
+        0,
     );
+    // A new room has its whole lifetime ahead of it
+    let rules = RoomTtlRules::from_config(&state.config);
+    room.ttl_seconds = rules
+        .ttl_for(&room, request.ttl_seconds, room.created_at)
+        .unwrap_or(rules.max_lifetime);
+
+    room.slug = slug;
+    room.created_by = created_by;
+    room.allowed_codecs = allowed_codecs;
+    room.moderator_preemption = request.moderator_preemption;
+    room.require_unique_display = request.require_unique_display;
+    room.end_on_host_leave = request.end_on_host_leave;
 
     // creator_key (host-only), returned once
-    let creator_key = gen_creator_key();
+    let creator_key = generate_creator_key();
     let creator_hash = hash_code(&state.config.invite_code_salt, creator_key.trim());
 
-    state.room_repo.create_room(&room).await?;
-    state
-        .room_repo
-        .set_creator_key_hash(&room.room_id, &creator_hash, room.ttl_seconds)
-        .await?;
+    Ok(NewRoom {
+        room,
+        creator_key,
+        creator_hash,
+    })
+}
+
+/// Write a room and its creator key hash
+async fn persist_room(state: &AppState, new_room: &NewRoom) -> Result<()> {
+    let room = &new_room.room;
+
+    // Reserve the slug first so a collision fails before anything is written
+    let persist = async {
+        if let Some(slug) = &room.slug {
+            if !state
+                .room_repo
+                .claim_slug(slug, &room.room_id, room.ttl_seconds)
+                .await?
+            {
+                return Err(AppError::Conflict(format!("Slug '{}' is already taken", slug)));
+            }
+        }
 
-    tracing::info!(room_id = %room.room_id, name = %room.name, "Room created");
+        state.room_repo.create_room(room).await?;
+        state
+            .room_repo
+            .set_creator_key_hash(
+                &room.room_id,
+                &new_room.creator_hash,
+                room.ttl_seconds + state.config.creator_key_grace_seconds,
+            )
+            .await
+    };
+
+    // Slugged creates run under the slug's lock so instances can't interleave them
+    match &room.slug {
+        Some(slug) => {
+            state
+                .room_repo
+                .with_lock(&format!("slug:{}", slug), persist)
+                .await
+        }
+        None => persist.await,
+    }
+}
 
-    Ok(Json(CreateRoomResponse {
+fn created_room_response(new_room: NewRoom) -> CreateRoomResponse {
+    let room = new_room.room;
+    CreateRoomResponse {
         room_id: room.room_id,
         name: room.name,
         created_at: room.created_at,
         max_publishers: room.max_publishers,
         ttl_seconds: room.ttl_seconds,
-        creator_key,
-    }))
+        slug: room.slug,
+        allowed_codecs: room.allowed_codecs,
+        moderator_preemption: room.moderator_preemption,
+        require_unique_display: room.require_unique_display,
+        end_on_host_leave: room.end_on_host_leave,
+        creator_key: new_room.creator_key,
+    }
 }
 
 #[derive(serde::Deserialize)]
@@ -127,24 +805,50 @@ struct ListRoomsQuery {
     limit: Option<usize>,
 }
 
+#[derive(serde::Deserialize)]
+struct ListInvitationsQuery {
+    offset: Option<usize>,
+    limit: Option<usize>,
+}
+
+/// Offset and size of an invitation page: `limit` defaults to and is capped
+/// at `max_page_size` (at least 1)
+pub fn invitation_page(
+    offset: Option<usize>,
+    limit: Option<usize>,
+    max_page_size: usize,
+) -> (usize, usize) {
+    let max_page_size = max_page_size.max(1);
+    let limit = limit.unwrap_or(max_page_size).clamp(1, max_page_size);
+    (offset.unwrap_or(0), limit)
+}
+
+/// Private deployments turn the listing off so rooms are only reachable by
+/// id; it then answers like an unknown route, before Redis is touched
+pub fn ensure_listing_enabled(enabled: bool) -> Result<()> {
+    if !enabled {
+        return Err(AppError::NotFound("Not found".to_string()));
+    }
+    Ok(())
+}
+
 /// GET /api/v1/rooms - List recent rooms
 async fn list_rooms(
     State(state): State<AppState>,
-    Query(query): Query<ListRoomsQuery>,
-) -> Result<Json<Vec<crate::models::RoomInfo>>> {
+    AppQuery(query): AppQuery<ListRoomsQuery>,
+) -> Result<Json<Vec<crate::models::RoomSummary>>> {
+    ensure_listing_enabled(state.config.enable_room_listing)?;
+
     let limit = query.limit.unwrap_or(20).min(100);
     let rooms = state.room_repo.list_rooms(limit).await?;
     Ok(Json(rooms))
 }
 
-/// GET /api/v1/rooms/:room_id - Get room information
+/// GET /api/v1/rooms/:room_id - Get room information (room_id or slug)
 async fn get_room(
     State(state): State<AppState>,
-    Path(room_id): Path<String>,
+    RoomId(room_id): RoomId,
 ) -> Result<Json<crate::models::RoomInfo>> {
-    Uuid::parse_str(&room_id)
-        .map_err(|_| AppError::BadRequest("Invalid room ID format".to_string()))?;
-
     let room_info = state
         .room_repo
         .get_room_info(&room_id)
@@ -154,196 +858,816 @@ async fn get_room(
     Ok(Json(room_info))
 }
 
-/// POST /api/v1/rooms/:room_id/join - Option B join:
+/// GET /api/v1/rooms/:room_id/config - The room's effective settings, for
+/// support triage (host via `X-Creator-Key`, or a co-host's bearer JWT)
+async fn get_room_config(
+    State(state): State<AppState>,
+    RoomId(room_id): RoomId,
+    headers: HeaderMap,
+) -> Result<Json<RoomConfigResponse>> {
+    let room = state
+        .room_repo
+        .get_room(&room_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Room {} not found", room_id)))?;
+
+    let creator_key = headers
+        .get(CREATOR_KEY_HEADER)
+        .and_then(|v| v.to_str().ok());
+    require_moderator(&state, &room_id, creator_key, &headers).await?;
+
+    let config = &state.config;
+    Ok(Json(RoomConfigResponse {
+        expires_in: state.room_repo.room_ttl(&room_id).await?,
+        locked: state.room_repo.is_locked(&room_id).await?,
+        room_id: room.room_id,
+        name: room.name,
+        slug: room.slug,
+        created_by: room.created_by,
+        created_at: room.created_at,
+        max_publishers: room.max_publishers,
+        max_publishers_per_room: config.max_publishers_per_room,
+        ttl_seconds: room.ttl_seconds,
+        max_room_lifetime_seconds: config.max_room_lifetime_seconds,
+        allowed_codecs: room.allowed_codecs,
+        codec_preference: config.sdp_codec_preference.clone(),
+        moderator_preemption: room.moderator_preemption,
+        require_unique_display: room.require_unique_display,
+        end_on_host_leave: room.end_on_host_leave,
+        max_subscribed_feeds: config.max_subscribed_feeds,
+        max_subscribers_per_feed: config.max_subscribers_per_feed,
+        max_invites_per_room: config.max_invites_per_room,
+    }))
+}
+
+/// A magic link a join may redeem, as `(invite_token, nonce, expires_at)`.
+/// Links stop working as soon as the feature is turned off.
+pub fn redeemable_magic_link(enabled: bool, link: MagicLink) -> Result<(String, String, i64)> {
+    if !enabled {
+        return Err(AppError::BadRequest(
+            "Magic links are not enabled".to_string(),
+        ));
+    }
+    match link {
+        MagicLink::Valid {
+            invite_token,
+            nonce,
+            expires_at,
+        } => Ok((invite_token, nonce, expires_at)),
+        MagicLink::Expired => Err(AppError::BadRequest(
+            "Magic link has expired, use the invitation link and code".to_string(),
+        )),
+        MagicLink::Invalid => Err(AppError::BadRequest("Invalid magic link".to_string())),
+    }
+}
+
+/// POST /api/v1/rooms/:room_id/join - Option B join (room_id or slug):
 /// - Host: creator_key
-/// - Guest: invite_token + invite_code
+/// - Guest: invite_token + invite_code, or the magic_token of an email link
 async fn join_room(
     State(state): State<AppState>,
-    Path(room_id): Path<String>,
-    Json(request): Json<JoinRequest>,
+    RoomId(room_id): RoomId,
+    headers: HeaderMap,
+    AppJson(request): AppJson<JoinRequest>,
 ) -> Result<Json<JoinResponse>> {
-    Uuid::parse_str(&room_id)
-        .map_err(|_| AppError::BadRequest("Invalid room ID format".to_string()))?;
+    ensure_accepting(state.in_maintenance().await?)?;
+    let client_version = headers
+        .get(CLIENT_VERSION_HEADER)
+        .and_then(|v| v.to_str().ok());
+    ensure_client_version(client_version, state.config.min_client_version)?;
+
+    let display = sanitize_display(
+        &request.display,
+        &DisplayNameRules::from_config(&state.config),
+    )?;
+    let fingerprint = normalize_fingerprint(request.fingerprint.as_deref())?;
 
-    let display = request.display.trim();
-    if display.is_empty() {
-        return Err(AppError::BadRequest("Display name is required".to_string()));
+    // Check room exists
+    let room = state
+        .room_repo
+        .get_room(&room_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Room {} not found", room_id)))?;
+
+    // Fail early, before an invite is consumed; the join below re-checks under the lock
+    if room.require_unique_display {
+        let members = state.room_repo.get_member_infos(&room_id).await?;
+        ensure_display_available(&display, "", &members)?;
     }
-    if display.len() > 100 {
-        return Err(AppError::BadRequest(
-            "Display name must be at most 100 characters".to_string(),
+
+    let user_id = Uuid::new_v4().to_string();
+
+    // 1) Host flow (creator key)
+    let (is_host, observer, magic_nonce) = if let Some(creator_key) = request
+        .creator_key
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        verify_creator_key(&state, &room_id, creator_key).await?;
+        ensure_capacity(&state, &room, true).await?;
+        check_media_capacity(&state, true)?;
+
+        // Registered once the join is sure to go through, under the room lock
+        // so a concurrent transfer either sees this host (and demotes it) or
+        // already refuses the old key
+        state
+            .room_repo
+            .with_room_lock(&room_id, async {
+                verify_creator_key(&state, &room_id, creator_key).await?;
+                state.room_repo.add_host(&room_id, &user_id).await
+            })
+            .await?;
+
+        // host join: no consume
+        (true, false, None)
+    } else {
+        // 2) Guest flow: invite_token + invite_code. Banned clients and, in a
+        // locked room, guests are turned away before their invite is consumed;
+        // co-hosts (bearer JWT) get through the lock.
+        let fingerprints: Vec<String> = fingerprint.iter().cloned().collect();
+        if state
+            .room_repo
+            .is_banned(&room_id, &ban_identities(None, &fingerprints))
+            .await?
+        {
+            return Err(AppError::Forbidden(
+                "You are banned from this room".to_string(),
+            ));
+        }
+        if state.room_repo.is_locked(&room_id).await? {
+            ensure_unlocked(true, bearer_is_cohost(&state, &room_id, &headers).await?)?;
+        }
+
+        // A magic link stands in for the token and the code
+        let magic_link = request
+            .magic_token
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|magic_token| {
+                let now = chrono::Utc::now().timestamp();
+                let link = verify_magic_link(&state.config.invite_code_salt, magic_token, now);
+                redeemable_magic_link(state.config.magic_link_ttl_seconds > 0, link)
+            })
+            .transpose()?;
+
+        let invite_token = match &magic_link {
+            Some((invite_token, _, _)) => invite_token.as_str(),
+            None => request
+                .invite_token
+                .as_deref()
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .ok_or_else(|| AppError::BadRequest("Invite token is required".to_string()))?,
+        };
+
+        if check_invite_token(&state, invite_token)?.is_some_and(|signed| signed != room_id) {
+            return Err(AppError::BadRequest(
+                "Invitation does not match this room".to_string(),
+            ));
+        }
+
+        let invitation = state
+            .room_repo
+            .get_invitation(invite_token)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Invitation not found or expired".to_string()))?;
+
+        if invitation.room_id != room_id {
+            return Err(AppError::BadRequest(
+                "Invitation does not match this room".to_string(),
+            ));
+        }
+        if !invitation.is_valid() {
+            state.record_invite(InviteStage::Expired);
+            return Err(AppError::BadRequest(
+                "Invitation is expired or has reached maximum uses".to_string(),
+            ));
+        }
+
+        // The magic link's signature proves the email was received; without
+        // one the code must match (normalize user input, then hash it)
+        if magic_link.is_none() {
+            let invite_code_raw = request
+                .invite_code
+                .as_deref()
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .ok_or_else(|| AppError::BadRequest("Invitation code is required".to_string()))?;
+            let salt = &state.config.invite_code_salt;
+            if !invite_code_matches(salt, invite_code_raw, &invitation.code_hash) {
+                state.record_invite(InviteStage::FailedCode);
+                return Err(AppError::BadRequest("Invalid invitation code".to_string()));
+            }
+        }
+
+        // Observers never publish, so they don't take a seat
+        if !invitation.observer {
+            ensure_capacity(&state, &room, false).await?;
+        }
+        check_media_capacity(&state, !invitation.observer)?;
+
+        // A magic link is good for one join. Claimed before the invite is
+        // consumed so two racing redemptions can't both get through; a join
+        // that fails after this gives the link back.
+        let magic_nonce = match &magic_link {
+            Some((_, nonce, expires_at)) => {
+                let ttl_seconds = (expires_at - chrono::Utc::now().timestamp()).max(1) as u64;
+                if !state.room_repo.claim_magic_link(nonce, ttl_seconds).await? {
+                    return Err(AppError::BadRequest(
+                        "Magic link has already been used".to_string(),
+                    ));
+                }
+                Some(nonce.clone())
+            }
+            None => None,
+        };
+
+        // Consume only after verification
+        let ok = match state.room_repo.use_invitation(invite_token).await {
+            Ok(ok) => ok,
+            Err(e) => {
+                release_magic_link(&state, magic_nonce.as_deref()).await;
+                return Err(e);
+            }
+        };
+        if !ok {
+            release_magic_link(&state, magic_nonce.as_deref()).await;
+            state.record_invite(InviteStage::Expired);
+            return Err(AppError::BadRequest(
+                "Invitation is expired or has reached maximum uses".to_string(),
+            ));
+        }
+        state.record_invite(InviteStage::Redeemed);
+
+        (false, invitation.observer, magic_nonce)
+    };
+
+    // Generate JWT (hosts get longer-lived tokens than guests)
+    let (token, expires_in) = if is_host {
+        state.auth.generate_host_token(&user_id, &room_id, &display)?
+    } else {
+        state
+            .auth
+            .generate_guest_token(&user_id, &room_id, &display, observer)?
+    };
+
+    if let Err(e) = join_member_checked(&state, &room_id, &user_id, &display, observer).await {
+        release_magic_link(&state, magic_nonce.as_deref()).await;
+        return Err(e);
+    }
+    if let Some(fingerprint) = &fingerprint {
+        state
+            .room_repo
+            .set_member_fingerprint(&room_id, &user_id, fingerprint, room.ttl_seconds)
+            .await?;
+    }
+
+    let ws_url = format!(
+        "ws://{}:{}/ws?room_id={}&token={}",
+        state.config.server_host, state.config.server_port, room_id, token
+    );
+
+    let ice_servers = state.ice_servers(&user_id);
+    let network_hint = request.network_hint.as_deref().and_then(NetworkHint::parse);
+    let (ice_servers, ice_transport_policy) = match network_hint {
+        Some(hint) => {
+            let (servers, policy) = hint.tailor(ice_servers);
+            (servers, Some(policy.to_string()))
+        }
+        None => (ice_servers, None),
+    };
+
+    Ok(Json(JoinResponse {
+        room_id,
+        user_id,
+        ws_url,
+        token,
+        ice_servers,
+        ice_transport_policy,
+        expires_in,
+        participants: vec![],
+    }))
+}
+
+/// Give a claimed magic link back after the join it was claimed for failed
+async fn release_magic_link(state: &AppState, nonce: Option<&str>) {
+    let Some(nonce) = nonce else {
+        return;
+    };
+    if let Err(e) = state.room_repo.release_magic_link(nonce).await {
+        tracing::warn!(error = %e, "Failed to release magic link");
+    }
+}
+
+/// GET /api/v1/rooms/:room_id/ice - Fresh ICE servers (JWT-gated) so long meetings
+/// can re-fetch expiring TURN credentials before an ICE restart
+async fn get_ice_servers(
+    State(state): State<AppState>,
+    RoomId(room_id): RoomId,
+    headers: HeaderMap,
+) -> Result<Json<Vec<IceServer>>> {
+    let claims = state.auth.extract_bearer(&headers)?;
+    if claims.room_id != room_id {
+        return Err(AppError::Unauthorized(
+            "Token room_id does not match".to_string(),
         ));
     }
 
-    // Check room exists
+    Ok(Json(state.ice_servers(&claims.sub)))
+}
+
+/// POST /api/v1/rooms/:room_id/leave
+async fn leave_room(
+    State(_state): State<AppState>,
+    RoomId(_room_id): RoomId,
+) -> Result<Json<serde_json::Value>> {
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+/// Replace the room's creator_key with a fresh one, returned once. Call under
+/// the room lock after `verify_creator_key`; the compare-and-swap also fails
+/// a concurrent swap that got there first.
+async fn swap_creator_key(state: &AppState, room_id: &str, current_key: &str) -> Result<String> {
+    let pepper = &state.config.invite_code_salt;
+    let current_hash = hash_code(pepper, current_key.trim());
+    let creator_key = generate_creator_key();
+    let new_hash = hash_code(pepper, &creator_key);
+
+    if !state
+        .room_repo
+        .rotate_creator_key_hash(room_id, &current_hash, &new_hash)
+        .await?
+    {
+        return Err(AppError::BadRequest("Invalid creator key".to_string()));
+    }
+    Ok(creator_key)
+}
+
+/// POST /api/v1/rooms/:room_id/rotate-key - Replace a leaked creator_key (host only).
+/// The new key is returned once; the old one stops working immediately.
+async fn rotate_creator_key(
+    State(state): State<AppState>,
+    RoomId(room_id): RoomId,
+    AppJson(request): AppJson<RotateKeyRequest>,
+) -> Result<Json<RotateKeyResponse>> {
+    let (creator_key, demoted) = state
+        .room_repo
+        .with_room_lock(&room_id, async {
+            verify_creator_key(&state, &room_id, &request.creator_key).await?;
+            let creator_key = swap_creator_key(&state, &room_id, &request.creator_key).await?;
+
+            let demoted = if request.revoke_cohosts {
+                state.room_repo.clear_cohosts(&room_id).await?
+            } else {
+                Vec::new()
+            };
+            Ok((creator_key, demoted))
+        })
+        .await?;
+
+    for user_id in demoted {
+        broadcast_role_changed(&state, &room_id, &user_id, "participant")?;
+    }
+
+    tracing::info!(
+        room_id = %room_id,
+        revoke_cohosts = request.revoke_cohosts,
+        "Creator key rotated"
+    );
+
+    Ok(Json(RotateKeyResponse {
+        room_id,
+        creator_key,
+    }))
+}
+
+/// POST /api/v1/rooms/:room_id/transfer-host - Hand the room to a new host.
+/// The creator_key is replaced under the room lock, so the outgoing host's key
+/// stops working at once; the new key is returned once, for the new host.
+/// Everyone who joined with the old key loses host powers on their live
+/// connections too.
+async fn transfer_host(
+    State(state): State<AppState>,
+    RoomId(room_id): RoomId,
+    AppJson(request): AppJson<TransferHostRequest>,
+) -> Result<Json<TransferHostResponse>> {
+    let new_host = request
+        .user_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty());
+
+    let (creator_key, demoted) = state
+        .room_repo
+        .with_room_lock(&room_id, async {
+            verify_creator_key(&state, &room_id, &request.creator_key).await?;
+
+            if let Some(user_id) = new_host {
+                if !state.room_repo.is_member(&room_id, user_id).await? {
+                    return Err(AppError::NotFound(format!(
+                        "User {} is not in this room",
+                        user_id
+                    )));
+                }
+            }
+
+            let creator_key = swap_creator_key(&state, &room_id, &request.creator_key).await?;
+            let demoted = state.room_repo.clear_hosts(&room_id).await?;
+            Ok((creator_key, demoted))
+        })
+        .await?;
+
+    let connections = state.connections.get_room(&room_id);
+    for user_id in demoted {
+        if let Some(connections) = &connections {
+            connections.set_user_host(&user_id, false);
+        }
+        broadcast_role_changed(&state, &room_id, &user_id, "participant")?;
+    }
+
+    let msg = SignalingMessage::new(
+        msg_types::HOST_TRANSFERRED,
+        serde_json::to_value(HostTransferredPayload {
+            room_id: room_id.clone(),
+            user_id: new_host.map(str::to_string),
+        })?,
+    );
+    state.connections.broadcast_to_room(&room_id, msg, None);
+
+    tracing::info!(
+        room_id = %room_id,
+        new_host = ?new_host,
+        "Host transferred"
+    );
+
+    Ok(Json(TransferHostResponse {
+        room_id,
+        creator_key,
+    }))
+}
+
+/// POST /api/v1/rooms/:room_id/announce - Push a banner to everyone in the room (host only)
+async fn announce(
+    State(state): State<AppState>,
+    RoomId(room_id): RoomId,
+    AppJson(request): AppJson<AnnounceRequest>,
+) -> Result<Json<Announcement>> {
+    verify_creator_key(&state, &room_id, &request.creator_key).await?;
+
+    let room = state
+        .room_repo
+        .get_room(&room_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Room {} not found", room_id)))?;
+
+    let announcement = Announcement {
+        room_id: room_id.clone(),
+        text: sanitize_announcement(&request.text, &TextLimits::from_config(&state.config))?,
+        level: request.level,
+        sent_at: chrono::Utc::now().timestamp(),
+    };
+
+    // Persist first so anyone joining during the broadcast still gets it
+    state
+        .room_repo
+        .set_announcement(&announcement, room.ttl_seconds)
+        .await?;
+
+    let msg = SignalingMessage::new(msg_types::ANNOUNCEMENT, serde_json::to_value(&announcement)?);
+    state.connections.broadcast_to_room(&room_id, msg, None);
+
+    tracing::info!(room_id = %room_id, level = ?announcement.level, "Announcement sent");
+
+    Ok(Json(announcement))
+}
+
+/// POST /api/v1/rooms/:room_id/lock - Stop admitting guests (host only)
+async fn lock_room(
+    State(state): State<AppState>,
+    RoomId(room_id): RoomId,
+    AppJson(request): AppJson<LockRoomRequest>,
+) -> Result<Json<LockRoomResponse>> {
+    set_room_lock(&state, room_id, &request.creator_key, true).await
+}
+
+/// POST /api/v1/rooms/:room_id/unlock - Admit guests again (host only)
+async fn unlock_room(
+    State(state): State<AppState>,
+    RoomId(room_id): RoomId,
+    AppJson(request): AppJson<LockRoomRequest>,
+) -> Result<Json<LockRoomResponse>> {
+    set_room_lock(&state, room_id, &request.creator_key, false).await
+}
+
+async fn set_room_lock(
+    state: &AppState,
+    room_id: String,
+    creator_key: &str,
+    locked: bool,
+) -> Result<Json<LockRoomResponse>> {
+    state
+        .room_repo
+        .with_room_lock(&room_id, async {
+            verify_creator_key(state, &room_id, creator_key).await?;
+
+            let room = state
+                .room_repo
+                .get_room(&room_id)
+                .await?
+                .ok_or_else(|| AppError::NotFound(format!("Room {} not found", room_id)))?;
+
+            state
+                .room_repo
+                .set_locked(&room_id, locked, room.ttl_seconds)
+                .await
+        })
+        .await?;
+
+    let msg = SignalingMessage::new(
+        if locked {
+            msg_types::ROOM_LOCKED
+        } else {
+            msg_types::ROOM_UNLOCKED
+        },
+        serde_json::to_value(RoomLockPayload {
+            room_id: room_id.clone(),
+        })?,
+    );
+    state.connections.broadcast_to_room(&room_id, msg, None);
+
+    tracing::info!(room_id = %room_id, locked, "Room lock changed");
+
+    Ok(Json(LockRoomResponse { room_id, locked }))
+}
+
+/// POST /api/v1/rooms/:room_id/cohosts - Promote a participant to co-host (host only)
+async fn add_cohost(
+    State(state): State<AppState>,
+    RoomId(room_id): RoomId,
+    AppJson(request): AppJson<CohostRequest>,
+) -> Result<Json<CohostResponse>> {
+    verify_creator_key(&state, &room_id, &request.creator_key).await?;
+
+    let user_id = request.user_id.trim();
+    if !state.room_repo.is_member(&room_id, user_id).await? {
+        return Err(AppError::NotFound(format!(
+            "User {} is not in room {}",
+            user_id, room_id
+        )));
+    }
+
+    state.room_repo.add_cohost(&room_id, user_id).await?;
+    broadcast_role_changed(&state, &room_id, user_id, "cohost")?;
+
+    tracing::info!(room_id = %room_id, user_id = %user_id, "Co-host promoted");
+
+    Ok(Json(CohostResponse {
+        room_id,
+        user_id: user_id.to_string(),
+        role: "cohost".to_string(),
+    }))
+}
+
+/// DELETE /api/v1/rooms/:room_id/cohosts/:user_id - Revoke a co-host (moderators)
+async fn revoke_cohost(
+    State(state): State<AppState>,
+    RoomId(room_id): RoomId,
+    AppPath((_, user_id)): AppPath<(String, String)>,
+    headers: HeaderMap,
+    AppJson(request): AppJson<ModeratorRequest>,
+) -> Result<Json<CohostResponse>> {
+    require_moderator(&state, &room_id, request.creator_key.as_deref(), &headers).await?;
+
+    if !state.room_repo.remove_cohost(&room_id, &user_id).await? {
+        return Err(AppError::NotFound(format!(
+            "User {} is not a co-host",
+            user_id
+        )));
+    }
+
+    broadcast_role_changed(&state, &room_id, &user_id, "participant")?;
+
+    tracing::info!(room_id = %room_id, user_id = %user_id, "Co-host revoked");
+
+    Ok(Json(CohostResponse {
+        room_id,
+        user_id,
+        role: "participant".to_string(),
+    }))
+}
+
+/// POST /api/v1/rooms/:room_id/ban - Ban a participant and/or a client
+/// fingerprint (moderators). A banned participant is disconnected and loses
+/// any co-host role; the ban lasts `duration_seconds` or the room's lifetime.
+async fn ban(
+    State(state): State<AppState>,
+    RoomId(room_id): RoomId,
+    headers: HeaderMap,
+    AppJson(request): AppJson<BanRequest>,
+) -> Result<Json<BanResponse>> {
+    require_moderator(&state, &room_id, request.creator_key.as_deref(), &headers).await?;
+
     let room = state
         .room_repo
         .get_room(&room_id)
         .await?
         .ok_or_else(|| AppError::NotFound(format!("Room {} not found", room_id)))?;
 
-    // Capacity check
-    let member_count = state.room_repo.get_member_count(&room_id).await?;
-    if member_count >= room.max_publishers as usize {
-        return Err(AppError::RoomFull);
-    }
-
-    // 1) Host flow (creator key)
-    if let Some(creator_key) = request
-        .creator_key
+    let user_id = request
+        .user_id
         .as_deref()
         .map(str::trim)
-        .filter(|s| !s.is_empty())
-    {
-        let expected = state
-            .room_repo
-            .get_creator_key_hash(&room_id)
-            .await?
-            .ok_or_else(|| AppError::BadRequest("Access denied".to_string()))?;
-
-        let got = hash_code(&state.config.invite_code_salt, creator_key);
-        if got != expected {
-            return Err(AppError::BadRequest("Invalid creator key".to_string()));
-        }
-
-        // host join: no consume
-    } else {
-        // 2) Guest flow: invite_token + invite_code
-        let invite_token = request
-            .invite_token
-            .as_deref()
-            .map(str::trim)
-            .filter(|s| !s.is_empty())
-            .ok_or_else(|| AppError::BadRequest("Invite token is required".to_string()))?;
-
-        let invite_code_raw = request
-            .invite_code
-            .as_deref()
-            .map(str::trim)
-            .filter(|s| !s.is_empty())
-            .ok_or_else(|| AppError::BadRequest("Invitation code is required".to_string()))?;
-
-        let invitation = state
-            .room_repo
-            .get_invitation(invite_token)
-            .await?
-            .ok_or_else(|| AppError::NotFound("Invitation not found or expired".to_string()))?;
+        .filter(|u| !u.is_empty());
+    let identities =
+        requested_ban_identities(&state, &room_id, user_id, request.fingerprint.as_deref()).await?;
+
+    let expires_at = request
+        .duration_seconds
+        .filter(|d| *d > 0)
+        .map(|d| chrono::Utc::now() + chrono::Duration::seconds(d as i64));
+    state
+        .room_repo
+        .add_bans(
+            &room_id,
+            &identities,
+            expires_at.map(|t| t.timestamp()),
+            room.ttl_seconds,
+        )
+        .await?;
 
-        if invitation.room_id != room_id {
-            return Err(AppError::BadRequest(
-                "Invitation does not match this room".to_string(),
-            ));
-        }
-        if !invitation.is_valid() {
-            return Err(AppError::BadRequest(
-                "Invitation is expired or has reached maximum uses".to_string(),
-            ));
+    if let Some(user_id) = user_id {
+        if state.room_repo.remove_cohost(&room_id, user_id).await? {
+            broadcast_role_changed(&state, &room_id, user_id, "participant")?;
         }
-
-        // Normalize user input, then hash normalized form
-        let normalized = normalize_invite_code(invite_code_raw);
-        let got = hash_code(&state.config.invite_code_salt, &normalized);
-
-        if got != invitation.code_hash {
-            return Err(AppError::BadRequest("Invalid invitation code".to_string()));
+        let clients = state
+            .connections
+            .get_room(&room_id)
+            .map(|connections| connections.take_user_clients(user_id))
+            .unwrap_or_default();
+        let payload = serde_json::to_value(BannedPayload {
+            room_id: room_id.clone(),
+            expires_at,
+        })?;
+        for client in &clients {
+            let _ = client.send(SignalingMessage::new(msg_types::BANNED, payload.clone()));
+            client.close();
         }
 
-        // Consume only after verification
-        let ok = state.room_repo.use_invitation(invite_token).await?;
-        if !ok {
-            return Err(AppError::BadRequest(
-                "Invitation is expired or has reached maximum uses".to_string(),
-            ));
+        // The seat frees now, whether or not the user ever connected. Closed
+        // sockets announce the leave from their own cleanup; a member who only
+        // joined over REST is announced here.
+        let was_member = state.room_repo.is_member(&room_id, user_id).await?;
+        state.room_repo.leave_member(&room_id, user_id).await?;
+        if was_member && clients.is_empty() {
+            let msg = SignalingMessage::new(
+                msg_types::MEMBER_LEFT,
+                serde_json::to_value(MemberLeftPayload {
+                    user_id: user_id.to_string(),
+                    room_id: room_id.clone(),
+                })?,
+            );
+            state.connections.broadcast_to_room(&room_id, msg, None);
         }
     }
 
-    // Generate user id + JWT
-    let user_id = Uuid::new_v4().to_string();
-    let token = state.auth.generate_token(&user_id, &room_id, display)?;
-
-    state.room_repo.add_member(&room_id, &user_id).await?;
+    tracing::info!(room_id = %room_id, user_id = ?user_id, entries = identities.len(), "Ban added");
 
-    let ws_url = format!(
-        "ws://{}:{}/ws?room_id={}&token={}",
-        state.config.server_host, state.config.server_port, room_id, token
-    );
+    Ok(Json(BanResponse {
+        room_id,
+        user_id: user_id.map(str::to_string),
+        entries: identities.len(),
+        expires_at,
+    }))
+}
 
-    let mut ice_servers = vec![IceServer {
-        urls: vec![state.config.stun_server.clone()],
-        username: None,
-        credential: None,
-    }];
+/// DELETE /api/v1/rooms/:room_id/ban - Lift the bans of a participant and/or a
+/// client fingerprint (moderators)
+async fn unban(
+    State(state): State<AppState>,
+    RoomId(room_id): RoomId,
+    headers: HeaderMap,
+    AppJson(request): AppJson<UnbanRequest>,
+) -> Result<Json<BanResponse>> {
+    require_moderator(&state, &room_id, request.creator_key.as_deref(), &headers).await?;
+
+    let user_id = request
+        .user_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|u| !u.is_empty());
+    let identities =
+        requested_ban_identities(&state, &room_id, user_id, request.fingerprint.as_deref()).await?;
 
-    if let Some(turn_server) = &state.config.turn_server {
-        ice_servers.push(IceServer {
-            urls: vec![turn_server.clone()],
-            username: state.config.turn_username.clone(),
-            credential: state.config.turn_credential.clone(),
-        });
+    let removed = state.room_repo.remove_bans(&room_id, &identities).await?;
+    if removed == 0 {
+        return Err(AppError::NotFound("No matching ban".to_string()));
     }
 
-    Ok(Json(JoinResponse {
+    tracing::info!(room_id = %room_id, user_id = ?user_id, removed, "Ban lifted");
+
+    Ok(Json(BanResponse {
         room_id,
-        user_id,
-        ws_url,
-        token,
-        ice_servers,
-        expires_in: state.config.jwt_expiry_seconds,
-        participants: vec![],
+        user_id: user_id.map(str::to_string),
+        entries: removed,
+        expires_at: None,
     }))
 }
 
-/// POST /api/v1/rooms/:room_id/leave
-async fn leave_room(
-    State(_state): State<AppState>,
-    Path(room_id): Path<String>,
-) -> Result<Json<serde_json::Value>> {
-    Uuid::parse_str(&room_id)
-        .map_err(|_| AppError::BadRequest("Invalid room ID format".to_string()))?;
+/// Notify everyone in the room that a participant's role changed
+fn broadcast_role_changed(state: &AppState, room_id: &str, user_id: &str, role: &str) -> Result<()> {
+    let msg = SignalingMessage::new(
+        msg_types::ROLE_CHANGED,
+        serde_json::to_value(RoleChangedPayload {
+            room_id: room_id.to_string(),
+            user_id: user_id.to_string(),
+            role: role.to_string(),
+        })?,
+    );
 
-    Ok(Json(serde_json::json!({ "success": true })))
+    state.connections.broadcast_to_room(room_id, msg, None);
+    Ok(())
 }
 
 /// Create a publisher info entry
-pub fn create_publisher_info(user_id: &str, feed_id: &str, display: &str) -> PublisherInfo {
+pub fn create_publisher_info(
+    user_id: &str,
+    feed_id: &str,
+    display: &str,
+    has_audio: bool,
+    has_video: bool,
+    moderator: bool,
+) -> PublisherInfo {
     PublisherInfo {
         feed_id: feed_id.to_string(),
         user_id: user_id.to_string(),
         display: display.to_string(),
         joined_at: chrono::Utc::now(),
+        has_audio,
+        has_video,
+        moderator,
     }
 }
 
+/// Publisher to demote when a moderator publishes into a full room: the
+/// guest that has been publishing the longest
+pub fn preemption_victim(publishers: &[PublisherInfo]) -> Option<&PublisherInfo> {
+    publishers
+        .iter()
+        .filter(|p| !p.moderator)
+        .min_by_key(|p| p.joined_at)
+}
+
+/// Feed to spotlight: None clears it, otherwise it must be published right now
+pub fn spotlight_feed(
+    requested: Option<&str>,
+    publishers: &[PublisherInfo],
+) -> Result<Option<String>> {
+    let Some(feed_id) = requested.map(str::trim).filter(|f| !f.is_empty()) else {
+        return Ok(None);
+    };
+    if !publishers.iter().any(|p| p.feed_id == feed_id) {
+        return Err(AppError::NotFound(format!("Feed {} is not published", feed_id)));
+    }
+    Ok(Some(feed_id.to_string()))
+}
+
 /// POST /api/v1/rooms/:room_id/invite
 async fn create_invitation(
     State(state): State<AppState>,
-    Path(room_id): Path<String>,
-    Json(request): Json<CreateInvitationRequest>,
+    RoomId(room_id): RoomId,
+    AppJson(request): AppJson<CreateInvitationRequest>,
 ) -> Result<Json<CreateInvitationResponse>> {
-    Uuid::parse_str(&room_id)
-        .map_err(|_| AppError::BadRequest("Invalid room ID format".to_string()))?;
-
     state
         .room_repo
         .get_room(&room_id)
         .await?
         .ok_or_else(|| AppError::NotFound(format!("Room {} not found", room_id)))?;
 
+    let live = state.room_repo.prune_room_invites(&room_id).await?;
+    ensure_invite_capacity(live, state.config.max_invites_per_room)?;
+
     // Generate code + store normalized hash (important!)
     let code = gen_invite_code();
     let normalized = normalize_invite_code(&code);
     let code_hash = hash_code(&state.config.invite_code_salt, &normalized);
 
-    let invitation = RoomInvitation::new_with_code_hash(
+    let max_uses = InviteUsePolicy::from_config(&state.config).resolve(request.max_uses)?;
+
+    let mut invitation = RoomInvitation::new_with_code_hash(
+        sign_invite_token(&state.config.invite_code_salt, &room_id),
         room_id.clone(),
         "system".to_string(),
         request.ttl_seconds,
-        request.max_uses,
+        max_uses,
         None,
         code_hash,
     );
+    invitation.observer = request.observer;
 
     state.room_repo.create_invitation(&invitation).await?;
+    state.record_invite(InviteStage::Created);
 
     let invite_url = format!(
         "{}/invite/{}",
@@ -359,72 +1683,220 @@ async fn create_invitation(
         room_id,
         expires_at: invitation.expires_at,
         max_uses: invitation.max_uses,
+        observer: invitation.observer,
         invite_url,
     }))
 }
 
-/// GET /api/v1/rooms/:room_id/invites
+/// GET /api/v1/rooms/:room_id/invites?offset=&limit= - One page of the
+/// room's invitations, ordered by token
 async fn list_invitations(
     State(state): State<AppState>,
-    Path(room_id): Path<String>,
+    RoomId(room_id): RoomId,
+    AppQuery(query): AppQuery<ListInvitationsQuery>,
 ) -> Result<Json<Vec<RoomInvitation>>> {
-    Uuid::parse_str(&room_id)
-        .map_err(|_| AppError::BadRequest("Invalid room ID format".to_string()))?;
-
     state
         .room_repo
         .get_room(&room_id)
         .await?
         .ok_or_else(|| AppError::NotFound(format!("Room {} not found", room_id)))?;
 
-    let invitations = state.room_repo.get_room_invitations(&room_id).await?;
+    let (offset, limit) = invitation_page(
+        query.offset,
+        query.limit,
+        state.config.invite_list_max_page_size,
+    );
+    let invitations = state
+        .room_repo
+        .get_room_invitations(&room_id, offset, limit)
+        .await?;
     Ok(Json(invitations))
 }
 
-/// GET /api/v1/rooms/invite/:token
-async fn get_invitation(
+/// GET /api/v1/rooms/:room_id/invites/count - Number of live invitations,
+/// without fetching them
+async fn count_invitations(
     State(state): State<AppState>,
-    Path(token): Path<String>,
-) -> Result<Json<InvitationInfo>> {
-    let invitation = state
+    RoomId(room_id): RoomId,
+) -> Result<Json<InvitationCountResponse>> {
+    state
         .room_repo
-        .get_invitation(&token)
+        .get_room(&room_id)
         .await?
-        .ok_or_else(|| AppError::NotFound("Invitation not found or expired".to_string()))?;
+        .ok_or_else(|| AppError::NotFound(format!("Room {} not found", room_id)))?;
 
-    let is_valid = invitation.is_valid();
+    let count = state.room_repo.prune_room_invites(&room_id).await?;
+    Ok(Json(InvitationCountResponse { room_id, count }))
+}
+
+/// PATCH /api/v1/rooms/:room_id - Keep a room alive longer (host only). The
+/// TTL restarts from now, within the same limits as at creation; past
+/// `max_room_lifetime_seconds` the room is left to expire.
+async fn update_room(
+    State(state): State<AppState>,
+    RoomId(room_id): RoomId,
+    AppJson(request): AppJson<UpdateRoomRequest>,
+) -> Result<Json<UpdateRoomResponse>> {
+    verify_creator_key(&state, &room_id, &request.creator_key).await?;
 
     let room = state
         .room_repo
-        .get_room(&invitation.room_id)
+        .get_room(&room_id)
         .await?
-        .ok_or_else(|| AppError::NotFound("Room no longer exists".to_string()))?;
+        .ok_or_else(|| AppError::NotFound(format!("Room {} not found", room_id)))?;
+
+    let ttl_seconds = RoomTtlRules::from_config(&state.config)
+        .ttl_for(&room, request.ttl_seconds, chrono::Utc::now())
+        .ok_or_else(|| AppError::Conflict("Room has reached its maximum lifetime".to_string()))?;
+    state
+        .room_repo
+        .refresh_room_ttl(&room, ttl_seconds, state.config.creator_key_grace_seconds)
+        .await?;
+
+    tracing::info!(room_id = %room_id, ttl_seconds, "Room TTL refreshed");
+
+    Ok(Json(UpdateRoomResponse {
+        room_id,
+        ttl_seconds,
+    }))
+}
+
+/// PATCH /api/v1/rooms/:room_id/invite/:token - Extend an invitation's lifetime
+/// or uses without re-sending it (host only)
+async fn update_invitation(
+    State(state): State<AppState>,
+    RoomId(room_id): RoomId,
+    AppPath((_, token)): AppPath<(String, String)>,
+    AppJson(request): AppJson<UpdateInvitationRequest>,
+) -> Result<Json<InvitationInfo>> {
+    verify_creator_key(&state, &room_id, &request.creator_key).await?;
+
+    let room = state
+        .room_repo
+        .get_room(&room_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Room {} not found", room_id)))?;
+
+    let mut invitation = bound_invitation(&state, &token, Some(&room_id)).await?;
+
+    if let Some(ttl_seconds) = request.ttl_seconds {
+        if ttl_seconds == 0 {
+            return Err(AppError::BadRequest(
+                "ttl_seconds must be at least 1".to_string(),
+            ));
+        }
+        invitation.expires_at = chrono::Utc::now() + chrono::Duration::seconds(ttl_seconds as i64);
+    }
+
+    if let Some(max_uses) = request.max_uses {
+        if max_uses < invitation.uses {
+            return Err(AppError::BadRequest(format!(
+                "max_uses cannot be lower than the {} uses already made",
+                invitation.uses
+            )));
+        }
+        invitation.max_uses = InviteUsePolicy::from_config(&state.config).resolve(Some(max_uses))?;
+    }
+
+    state.room_repo.save_invitation(&invitation).await?;
+
+    tracing::info!(
+        room_id = %room_id,
+        expires_at = %invitation.expires_at,
+        max_uses = ?invitation.max_uses,
+        "Invitation updated"
+    );
 
     Ok(Json(InvitationInfo {
+        is_valid: invitation.is_valid(),
         token: invitation.token,
         room_id: invitation.room_id,
         room_name: room.name,
         expires_at: invitation.expires_at,
-        is_valid,
     }))
 }
 
-/// POST /api/v1/rooms/invite/:token/use
+/// POST /api/v1/rooms/:room_id/invite/:token/rotate-code - Replace a leaked
+/// invitation code without re-sharing the link
+async fn rotate_invite_code(
+    State(state): State<AppState>,
+    RoomId(room_id): RoomId,
+    AppPath((_, token)): AppPath<(String, String)>,
+    AppJson(request): AppJson<RotateInviteCodeRequest>,
+) -> Result<Json<RotateInviteCodeResponse>> {
+    verify_creator_key(&state, &room_id, &request.creator_key).await?;
+
+    let mut invitation = bound_invitation(&state, &token, Some(&room_id)).await?;
+
+    // Only the hash is stored; the old code stops matching as soon as this is saved
+    let code = gen_invite_code();
+    invitation.code_hash = hash_code(&state.config.invite_code_salt, &normalize_invite_code(&code));
+
+    // Same expiry, so the TTL is preserved
+    state.room_repo.save_invitation(&invitation).await?;
+
+    tracing::info!(room_id = %room_id, "Invitation code rotated");
+
+    Ok(Json(RotateInviteCodeResponse {
+        token: invitation.token,
+        room_id: invitation.room_id,
+        invite_code: code,
+        expires_at: invitation.expires_at,
+    }))
+}
+
+/// GET /api/v1/rooms/invite/:token
+async fn get_invitation(
+    State(state): State<AppState>,
+    AppPath(token): AppPath<String>,
+) -> Result<Json<InvitationInfo>> {
+    ensure_roomless_invites_allowed(state.config.invite_room_bound_only)?;
+    let info = invitation_info(&state, &token, None).await?;
+    record_invitation_view(&state, &token).await?;
+    Ok(Json(info))
+}
+
+/// GET /api/v1/rooms/:room_id/invite/:token
+async fn get_room_invitation(
+    State(state): State<AppState>,
+    RoomId(room_id): RoomId,
+    AppPath((_, token)): AppPath<(String, String)>,
+) -> Result<Json<InvitationInfo>> {
+    let info = invitation_info(&state, &token, Some(&room_id)).await?;
+    record_invitation_view(&state, &token).await?;
+    Ok(Json(info))
+}
+
+/// POST /api/v1/rooms/invite/:token/use - Check that an invitation can still
+/// be redeemed. Nothing is consumed: a use is only counted when a join
+/// redeems it with its code, so opening or probing a link can't exhaust it.
 async fn use_invitation(
     State(state): State<AppState>,
-    Path(token): Path<String>,
+    AppPath(token): AppPath<String>,
 ) -> Result<Json<InvitationInfo>> {
-    let invitation = state
-        .room_repo
-        .get_invitation(&token)
-        .await?
-        .ok_or_else(|| AppError::NotFound("Invitation not found or expired".to_string()))?;
+    ensure_roomless_invites_allowed(state.config.invite_room_bound_only)?;
+    let info = usable_invitation(&state, &token, None).await?;
+    Ok(Json(info))
+}
 
-    if !invitation.is_valid() {
-        return Err(AppError::BadRequest(
-            "Invitation is expired or has reached maximum uses".to_string(),
-        ));
-    }
+/// POST /api/v1/rooms/:room_id/invite/:token/use - `use_invitation` for the
+/// room the route names
+async fn use_room_invitation(
+    State(state): State<AppState>,
+    RoomId(room_id): RoomId,
+    AppPath((_, token)): AppPath<(String, String)>,
+) -> Result<Json<InvitationInfo>> {
+    let info = usable_invitation(&state, &token, Some(&room_id)).await?;
+    Ok(Json(info))
+}
+
+async fn invitation_info(
+    state: &AppState,
+    token: &str,
+    route_room: Option<&str>,
+) -> Result<InvitationInfo> {
+    let invitation = bound_invitation(state, token, route_room).await?;
+    let is_valid = invitation.is_valid();
 
     let room = state
         .room_repo
@@ -432,26 +1904,49 @@ async fn use_invitation(
         .await?
         .ok_or_else(|| AppError::NotFound("Room no longer exists".to_string()))?;
 
-    state.room_repo.use_invitation(&token).await?;
-
-    Ok(Json(InvitationInfo {
+    Ok(InvitationInfo {
         token: invitation.token,
         room_id: invitation.room_id,
         room_name: room.name,
         expires_at: invitation.expires_at,
-        is_valid: true,
-    }))
+        is_valid,
+    })
+}
+
+/// Funnel `viewed` stage; the first view is also stamped on the invitation
+async fn record_invitation_view(state: &AppState, token: &str) -> Result<()> {
+    if !state.config.metrics_enabled {
+        return Ok(());
+    }
+    state.record_invite(InviteStage::Viewed);
+    state.room_repo.mark_invitation_viewed(token).await
+}
+
+async fn usable_invitation(
+    state: &AppState,
+    token: &str,
+    route_room: Option<&str>,
+) -> Result<InvitationInfo> {
+    let info = invitation_info(state, token, route_room).await?;
+    if !info.is_valid {
+        state.record_invite(InviteStage::Expired);
+        return Err(AppError::BadRequest(
+            "Invitation is expired or has reached maximum uses".to_string(),
+        ));
+    }
+    Ok(info)
 }
 
 /// POST /api/v1/rooms/{room_id}/invite-email
 /// sends invite link + code and stores hash in Redis
 async fn send_invite_email(
     State(state): State<AppState>,
-    Path(room_id): Path<String>,
-    Json(request): Json<InviteEmailRequest>,
+    RoomId(room_id): RoomId,
+    AppJson(request): AppJson<InviteEmailRequest>,
 ) -> Result<Json<InviteEmailResponse>> {
-    Uuid::parse_str(&room_id)
-        .map_err(|_| AppError::BadRequest("Invalid room ID format".to_string()))?;
+    let mailer = state.mailer.clone().ok_or_else(|| {
+        AppError::NotImplemented("Email is not configured on this server".to_string())
+    })?;
 
     let room = state
         .room_repo
@@ -459,6 +1954,18 @@ async fn send_invite_email(
         .await?
         .ok_or_else(|| AppError::NotFound("Room not found".to_string()))?;
 
+    // Checked before the invitation is stored so a rejected request leaves nothing behind
+    let limits = TextLimits::from_config(&state.config);
+    if let Some(subject) = &request.subject {
+        limits.check(TextField::InviteSubject, subject)?;
+    }
+    if let Some(message) = &request.message {
+        limits.check(TextField::InviteMessage, message.trim())?;
+    }
+
+    let live = state.room_repo.prune_room_invites(&room_id).await?;
+    ensure_invite_capacity(live, state.config.max_invites_per_room)?;
+
     let ttl_seconds = request.ttl_seconds.unwrap_or(86400);
 
     // generate code + store normalized hash
@@ -466,25 +1973,30 @@ async fn send_invite_email(
     let normalized = normalize_invite_code(&code);
     let code_hash = hash_code(&state.config.invite_code_salt, &normalized);
 
-    let invitation = RoomInvitation::new_with_code_hash(
+    let max_uses = InviteUsePolicy::from_config(&state.config).resolve(request.max_uses)?;
+
+    let mut invitation = RoomInvitation::new_with_code_hash(
+        sign_invite_token(&state.config.invite_code_salt, &room_id),
         room_id.clone(),
         "system".to_string(),
         ttl_seconds,
-        request.max_uses,
+        max_uses,
         None,
         code_hash,
     );
+    invitation.observer = request.observer;
 
     state.room_repo.create_invitation(&invitation).await?;
+    state.record_invite(InviteStage::Created);
 
+    let frontend_host = state
+        .config
+        .frontend_host
+        .as_deref()
+        .unwrap_or("http://localhost:3000");
     let invite_url = format!(
         "{}/room/{}/lobby?token={}",
-        state.config
-            .frontend_host
-            .as_deref()
-            .unwrap_or("http://localhost:3000"),
-        room_id,
-        invitation.token
+        frontend_host, room_id, invitation.token
     );
 
     let subject = request
@@ -505,15 +2017,353 @@ async fn send_invite_email(
         room.name, invite_url, code
     ));
 
-    state
-        .mailer
-        .send_invite(request.emails.clone(), subject, text)
-        .await?;
+    // Each recipient gets their own single-use one-click link, so the email
+    // goes out once per recipient; without links, one email covers them all.
+    // Once one email is out, later failures are reported per recipient
+    // rather than failing a request that partly went through.
+    let magic_ttl = state.config.magic_link_ttl_seconds;
+    let mut failed = Vec::new();
+    let mut first_error = None;
+    if magic_ttl > 0 {
+        let now = chrono::Utc::now().timestamp();
+        let expires_at = (now + magic_ttl as i64).min(invitation.expires_at.timestamp());
+        for email in &request.emails {
+            let salt = &state.config.invite_code_salt;
+            let magic_token = sign_magic_link(salt, &invitation.token, expires_at);
+            let magic_url = format!(
+                "{}/room/{}/lobby?magic={}",
+                frontend_host, room_id, magic_token
+            );
+            let text = format!(
+                "{}\nOne-click link (single use, expires in {} minutes):\n{}\n",
+                text,
+                magic_ttl.div_ceil(60),
+                magic_url
+            );
+            if let Err(e) = mailer
+                .send_invite(vec![email.clone()], subject.clone(), text)
+                .await
+            {
+                tracing::warn!(room_id = %room_id, error = %e, "Failed to send invite email");
+                failed.push(email.clone());
+                first_error.get_or_insert(e);
+            }
+        }
+        // Nothing went out: fail as a single email would have
+        if let Some(e) = first_error.filter(|_| failed.len() == request.emails.len()) {
+            return Err(e);
+        }
+    } else {
+        mailer
+            .send_invite(request.emails.clone(), subject, text)
+            .await?;
+    }
 
     Ok(Json(InviteEmailResponse {
-        sent: request.emails.len() as u32,
+        sent: (request.emails.len() - failed.len()) as u32,
+        failed,
         token: invitation.token,
         invite_url,
         room_id,
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invitation_bound_to_its_room() {
+        let invitation = RoomInvitation::new_with_code_hash(
+            "token".to_string(),
+            "room-a".to_string(),
+            "host".to_string(),
+            3600,
+            None,
+            None,
+            String::new(),
+        );
+
+        let (a, b) = (Some("room-a"), Some("room-b"));
+
+        assert!(invitation_bound_to(&invitation, None, None));
+        assert!(invitation_bound_to(&invitation, a, a));
+        // Used from another room's route, or a token signed for another room
+        assert!(!invitation_bound_to(&invitation, b, None));
+        assert!(!invitation_bound_to(&invitation, None, b));
+        assert!(!invitation_bound_to(&invitation, a, b));
+
+        assert!(ensure_roomless_invites_allowed(false).is_ok());
+        assert!(matches!(
+            ensure_roomless_invites_allowed(true),
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_magic_link_join_and_expiry() {
+        let secret = "test-salt";
+        let invite = sign_invite_token(secret, "room-a");
+        let magic = sign_magic_link(secret, &invite, 1_000);
+
+        // A live link names the invitation it stands for
+        let link = verify_magic_link(secret, &magic, 999);
+        let (invite_token, _, expires_at) = redeemable_magic_link(true, link).unwrap();
+        assert_eq!(invite_token, invite);
+        assert_eq!(expires_at, 1_000);
+
+        let expired = verify_magic_link(secret, &magic, 1_000);
+        assert!(matches!(
+            redeemable_magic_link(true, expired),
+            Err(AppError::BadRequest(msg)) if msg.contains("expired")
+        ));
+        // Turning the feature off disables links already sent
+        let disabled = verify_magic_link(secret, &magic, 999);
+        assert!(redeemable_magic_link(false, disabled).is_err());
+        // The plain invite token is not a magic link
+        let plain = verify_magic_link(secret, &invite, 999);
+        assert!(redeemable_magic_link(true, plain).is_err());
+    }
+
+    #[test]
+    fn test_full_room_admits_only_members_and_observers() {
+        assert!(seat_available(false, false, 3, 4));
+        // Connecting to a full room without having joined it
+        assert!(!seat_available(false, false, 4, 4));
+        assert!(!seat_available(false, false, 5, 4));
+        // Already joined over REST: the seat is theirs
+        assert!(seat_available(true, false, 4, 4));
+        assert!(seat_available(false, true, 4, 4));
+    }
+
+    #[test]
+    fn test_locked_room_admits_host_and_cohosts() {
+        assert!(ensure_unlocked(true, true).is_ok());
+        assert!(ensure_unlocked(false, false).is_ok());
+    }
+
+    #[test]
+    fn test_join_refused_when_media_at_capacity() {
+        assert!(ensure_media_capacity(100, 0, 100, 0, true).is_ok());
+        assert!(ensure_media_capacity(9, 10, 4, 5, true).is_ok());
+        assert!(matches!(
+            ensure_media_capacity(10, 10, 0, 5, false),
+            Err(AppError::ServiceUnavailable(_))
+        ));
+        assert!(matches!(
+            ensure_media_capacity(0, 10, 5, 5, true),
+            Err(AppError::ServiceUnavailable(_))
+        ));
+        // Observers don't publish, so a full publisher budget doesn't stop them
+        assert!(ensure_media_capacity(0, 10, 5, 5, false).is_ok());
+    }
+
+    #[test]
+    fn test_ban_identities() {
+        let fingerprints = vec!["abc".to_string(), " ".to_string(), "abc".to_string()];
+        assert_eq!(
+            ban_identities(Some(" user-1 "), &fingerprints),
+            vec!["user:user-1".to_string(), "fp:abc".to_string()]
+        );
+        assert_eq!(
+            ban_identities(None, &fingerprints),
+            vec!["fp:abc".to_string()]
+        );
+        assert!(ban_identities(Some(""), &[]).is_empty());
+    }
+
+    #[test]
+    fn test_room_ref_parsing() {
+        let id = "6f1c2a9e-3b4d-4c5e-8f70-123456789abc";
+        assert_eq!(
+            parse_room_ref(id, false).unwrap(),
+            RoomRef::Id(id.to_string())
+        );
+        assert_eq!(
+            parse_room_ref("Team-Standup", true).unwrap(),
+            RoomRef::Slug("team-standup".to_string())
+        );
+
+        // Slugs only count when enabled; garbage never does
+        assert!(matches!(
+            parse_room_ref("team-standup", false),
+            Err(AppError::InvalidRoomId)
+        ));
+        for garbage in ["", "not a room", "../etc", "api"] {
+            assert!(matches!(
+                parse_room_ref(garbage, true),
+                Err(AppError::InvalidRoomId)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_subscription_limit() {
+        assert!(ensure_subscription_limit(8, 8).is_ok());
+        assert!(matches!(
+            ensure_subscription_limit(9, 8),
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(ensure_subscription_limit(500, 0).is_ok());
+    }
+
+    #[test]
+    fn test_effective_room_ttl() {
+        // Zero falls back to the configured default
+        assert_eq!(effective_room_ttl(0, 7200, 0), 7200);
+        assert_eq!(effective_room_ttl(0, 3600, 86400), 3600);
+        // Requests above the maximum are clamped, below it kept
+        assert_eq!(effective_room_ttl(100_000, 7200, 86400), 86400);
+        assert_eq!(effective_room_ttl(600, 7200, 86400), 600);
+        // No maximum configured
+        assert_eq!(effective_room_ttl(100_000, 7200, 0), 100_000);
+        // A default above the maximum is clamped too
+        assert_eq!(effective_room_ttl(0, 7200, 3600), 3600);
+    }
+
+    #[test]
+    fn test_room_ttl_rules_share_create_and_refresh_limits() {
+        let rules = RoomTtlRules {
+            default_ttl: 3600,
+            max_ttl: 7200,
+            max_lifetime: 10_000,
+        };
+        let room = Room::new("ttl".to_string(), 4, 0);
+        let at = |secs| room.created_at + chrono::Duration::seconds(secs);
+
+        // At creation: the default, the per-request maximum, the lifetime
+        assert_eq!(rules.ttl_for(&room, 0, at(0)), Some(3600));
+        assert_eq!(rules.ttl_for(&room, 100_000, at(0)), Some(7200));
+        let short_lived = RoomTtlRules {
+            max_lifetime: 600,
+            ..rules
+        };
+        assert_eq!(short_lived.ttl_for(&room, 0, at(0)), Some(600));
+
+        // Later refreshes only get what is left of the lifetime
+        assert_eq!(rules.ttl_for(&room, 7200, at(5000)), Some(5000));
+        assert_eq!(rules.ttl_for(&room, 0, at(9999)), Some(1));
+        assert_eq!(rules.ttl_for(&room, 0, at(10_000)), None);
+    }
+
+    fn member(user_id: &str, display: &str) -> MemberInfo {
+        MemberInfo {
+            user_id: user_id.to_string(),
+            display: display.to_string(),
+            joined_at: 0,
+            color: String::new(),
+            observer: false,
+        }
+    }
+
+    #[test]
+    fn test_duplicate_display_is_rejected_with_suggestion() {
+        let members = vec![member("u1", "Alice"), member("u2", "Alice 2")];
+
+        let err = ensure_display_available("alice", "u3", &members).unwrap_err();
+        match err {
+            AppError::Conflict(msg) => assert!(msg.contains("'alice 3'"), "{}", msg),
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert!(ensure_display_available("Bob", "u3", &members).is_ok());
+        // A member keeps (or re-sends) their own name
+        assert!(ensure_display_available("Alice", "u1", &members).is_ok());
+    }
+
+    #[test]
+    fn test_token_room_matches_alias() {
+        let uuid = "0b3a4c52-6a4e-4f0e-9a53-3f8f1a0c2d11";
+
+        assert!(token_room_matches(uuid, uuid, None));
+        // Connecting through the slug of the token's room
+        assert!(token_room_matches(uuid, "daily-standup", Some(uuid)));
+        // A slug pointing at another room, or one that didn't resolve
+        let other = "9f1c7e2a-1b2d-4c3e-8f4a-5b6c7d8e9f00";
+        assert!(!token_room_matches(uuid, "other-room", Some(other)));
+        assert!(!token_room_matches(uuid, "daily-standup", None));
+    }
+
+    #[test]
+    fn test_observer_publish_is_refused() {
+        assert!(ensure_can_publish(false).is_ok());
+        assert!(matches!(
+            ensure_can_publish(true),
+            Err(AppError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn test_room_listing_can_be_disabled() {
+        assert!(ensure_listing_enabled(true).is_ok());
+        assert!(matches!(
+            ensure_listing_enabled(false),
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_invitation_page_is_capped() {
+        assert_eq!(invitation_page(None, None, 100), (0, 100));
+        assert_eq!(invitation_page(Some(200), Some(20), 100), (200, 20));
+        assert_eq!(invitation_page(None, Some(5_000), 100), (0, 100));
+        assert_eq!(invitation_page(None, Some(0), 100), (0, 1));
+        // A zero cap still serves one at a time rather than nothing
+        assert_eq!(invitation_page(None, None, 0), (0, 1));
+    }
+
+    #[test]
+    fn test_room_batch_reports_items_separately() {
+        assert!(ensure_batch_size(100, 100).is_ok());
+        assert!(matches!(
+            ensure_batch_size(101, 100),
+            Err(AppError::BadRequest(_))
+        ));
+
+        let taken = AppError::Conflict("Slug 'x' is already taken".to_string());
+        let failed = batch_result(3, Err(taken));
+        assert_eq!(failed.index, 3);
+        assert!(failed.room.is_none());
+        let error = failed.error.unwrap();
+        assert_eq!(error.code, 409);
+        assert_eq!(error.error, "Slug 'x' is already taken");
+    }
+
+    #[test]
+    fn test_invite_capacity_is_bounded() {
+        assert!(ensure_invite_capacity(499, 500).is_ok());
+        assert!(matches!(
+            ensure_invite_capacity(500, 500),
+            Err(AppError::Conflict(_))
+        ));
+        assert!(ensure_invite_capacity(10_000, 0).is_ok());
+    }
+
+    #[test]
+    fn test_preemption_demotes_oldest_guest() {
+        let mut host = create_publisher_info("host", "f-host", "Host", true, true, true);
+        let mut early = create_publisher_info("early", "f-early", "Early", true, true, false);
+        let late = create_publisher_info("late", "f-late", "Late", true, true, false);
+        host.joined_at = late.joined_at - chrono::Duration::seconds(20);
+        early.joined_at = late.joined_at - chrono::Duration::seconds(10);
+
+        let publishers = vec![late.clone(), host.clone(), early];
+        assert_eq!(preemption_victim(&publishers).unwrap().user_id, "early");
+
+        // Only moderators publishing: nobody to demote
+        assert!(preemption_victim(&[host]).is_none());
+    }
+
+    #[test]
+    fn test_spotlight_targets_a_published_feed() {
+        let publishers = vec![create_publisher_info("u1", "f1", "Alice", true, true, false)];
+
+        let pinned = spotlight_feed(Some(" f1 "), &publishers).unwrap();
+        assert_eq!(pinned.as_deref(), Some("f1"));
+        assert_eq!(spotlight_feed(None, &publishers).unwrap(), None);
+        assert_eq!(spotlight_feed(Some(""), &publishers).unwrap(), None);
+        assert!(matches!(
+            spotlight_feed(Some("gone"), &publishers),
+            Err(AppError::NotFound(_))
+        ));
+    }
+}
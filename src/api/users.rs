@@ -0,0 +1,23 @@
+use axum::{extract::State, http::HeaderMap, routing::get, Json, Router};
+
+use crate::error::Result;
+use crate::models::RoomInfo;
+use crate::state::AppState;
+
+/// User routes
+pub fn user_routes() -> Router<AppState> {
+    Router::new().route("/me/rooms", get(get_my_rooms))
+}
+
+/// GET /api/v1/users/me/rooms - Rooms the bearer's user is a member of
+/// (for "rejoin your meeting" prompts)
+async fn get_my_rooms(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<RoomInfo>>> {
+    let claims = state.auth.extract_bearer(&headers)?;
+
+    let rooms = state.room_repo.get_user_rooms(&claims.sub).await?;
+
+    Ok(Json(rooms))
+}
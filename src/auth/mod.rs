@@ -1,5 +1,10 @@
+use axum::http::{header::AUTHORIZATION, HeaderMap};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use chrono::Utc;
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use hmac::{Hmac, Mac};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::Deserialize;
+use sha1::Sha1;
 
 use crate::config::Config;
 use crate::error::{AppError, Result};
@@ -11,6 +16,16 @@ pub struct AuthService {
     encoding_key: EncodingKey,
     decoding_key: DecodingKey,
     expiry_seconds: u64,
+    host_expiry_seconds: u64,
+    guest_expiry_seconds: u64,
+    /// Verifies tokens from the upstream account system (room creation)
+    creator_decoding_key: Option<DecodingKey>,
+}
+
+/// Claims we rely on in upstream account tokens
+#[derive(Debug, Deserialize)]
+struct UpstreamClaims {
+    sub: String,
 }
 
 impl AuthService {
@@ -19,18 +34,84 @@ impl AuthService {
             encoding_key: EncodingKey::from_secret(config.jwt_secret.as_bytes()),
             decoding_key: DecodingKey::from_secret(config.jwt_secret.as_bytes()),
             expiry_seconds: config.jwt_expiry_seconds,
+            host_expiry_seconds: config.jwt_host_expiry_seconds,
+            guest_expiry_seconds: config.jwt_guest_expiry_seconds,
+            // PEM validity is checked when the config is loaded
+            creator_decoding_key: config
+                .creator_auth_public_key
+                .as_deref()
+                .and_then(|pem| DecodingKey::from_rsa_pem(pem.as_bytes()).ok()),
         }
     }
 
     /// Generate a JWT token for a user joining a room
     pub fn generate_token(&self, user_id: &str, room_id: &str, display: &str) -> Result<String> {
+        self.generate_token_with_expiry(
+            user_id,
+            room_id,
+            display,
+            false,
+            false,
+            self.expiry_seconds,
+        )
+    }
+
+    /// Generate a token for the host join flow; returns the token and its lifetime in seconds
+    pub fn generate_host_token(
+        &self,
+        user_id: &str,
+        room_id: &str,
+        display: &str,
+    ) -> Result<(String, u64)> {
+        let token = self.generate_token_with_expiry(
+            user_id,
+            room_id,
+            display,
+            true,
+            false,
+            self.host_expiry_seconds,
+        )?;
+        Ok((token, self.host_expiry_seconds))
+    }
+
+    /// Generate a token for the guest join flow; returns the token and its lifetime in seconds.
+    /// `observer` comes from the invitation and keeps the guest from publishing.
+    pub fn generate_guest_token(
+        &self,
+        user_id: &str,
+        room_id: &str,
+        display: &str,
+        observer: bool,
+    ) -> Result<(String, u64)> {
+        let token = self.generate_token_with_expiry(
+            user_id,
+            room_id,
+            display,
+            false,
+            observer,
+            self.guest_expiry_seconds,
+        )?;
+        Ok((token, self.guest_expiry_seconds))
+    }
+
+    fn generate_token_with_expiry(
+        &self,
+        user_id: &str,
+        room_id: &str,
+        display: &str,
+        host: bool,
+        observer: bool,
+        expiry_seconds: u64,
+    ) -> Result<String> {
         let now = Utc::now().timestamp();
-        let exp = now + self.expiry_seconds as i64;
+        let exp = now + expiry_seconds as i64;
 
         let claims = Claims {
             sub: user_id.to_string(),
             room_id: room_id.to_string(),
             display: display.to_string(),
+            host,
+            observer,
             iat: now,
             exp,
         };
@@ -63,6 +144,52 @@ impl AuthService {
 
         self.validate_token(token)
     }
+
+    /// Extract and validate a token from an `Authorization: Bearer xxx` header
+    pub fn extract_bearer(&self, headers: &HeaderMap) -> Result<Claims> {
+        let token = bearer_token(headers)?;
+        self.validate_token(token)
+    }
+
+    /// Verify an upstream RS256 `Authorization: Bearer` token and return its subject
+    pub fn verify_creator(&self, headers: &HeaderMap) -> Result<String> {
+        let key = self.creator_decoding_key.as_ref().ok_or_else(|| {
+            AppError::InternalError("Creator authentication is not configured".to_string())
+        })?;
+
+        let token = bearer_token(headers)?;
+        let token_data = decode::<UpstreamClaims>(token, key, &Validation::new(Algorithm::RS256))
+            .map_err(|e| AppError::Unauthorized(format!("Invalid token: {}", e)))?;
+
+        if token_data.claims.sub.is_empty() {
+            return Err(AppError::Unauthorized("Token has no subject".to_string()));
+        }
+
+        Ok(token_data.claims.sub)
+    }
+}
+
+fn bearer_token(headers: &HeaderMap) -> Result<&str> {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| AppError::Unauthorized("Bearer token required".to_string()))
+}
+
+/// Mint TURN REST API credentials (draft-uberti-behave-turn-rest):
+/// username is `<expiry unix ts>:<user_id>`, credential is base64(HMAC-SHA1(secret, username))
+pub fn turn_rest_credentials(secret: &str, user_id: &str, expires_at: i64) -> (String, String) {
+    let username = format!("{}:{}", expires_at, user_id);
+
+    let mut mac =
+        Hmac::<Sha1>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(username.as_bytes());
+    let credential = BASE64.encode(mac.finalize().into_bytes());
+
+    (username, credential)
 }
 
 #[cfg(test)]
@@ -73,20 +200,84 @@ mod tests {
         Config {
             server_host: "localhost".to_string(),
             server_port: 8080,
+            server_region: None,
+            max_request_body_bytes: 262144,
+            shutdown_drain_timeout_seconds: 10,
             redis_url: "redis://localhost".to_string(),
+            redis_pool_max_size: 0,
+            redis_connect_timeout_ms: 2000,
+            redis_wait_timeout_ms: 5000,
             jwt_secret: "test-secret-key".to_string(),
             jwt_expiry_seconds: 900,
+            jwt_host_expiry_seconds: 86400,
+            jwt_guest_expiry_seconds: 600,
+            token_expiry_warning_seconds: 60,
+            create_requires_auth: false,
+            creator_auth_public_key: None,
             room_ttl_seconds: 7200,
+            max_room_ttl_seconds: 0,
+            max_room_lifetime_seconds: 0,
+            creator_key_grace_seconds: 300,
+            room_closing_warning_seconds: 300,
+            unjoined_room_timeout_seconds: 0,
             max_publishers_per_room: 50,
+            max_room_batch_size: 100,
+            room_slugs_enabled: false,
+            enable_room_listing: true,
             stun_server: "stun:stun.l.google.com:19302".to_string(),
             turn_server: None,
             turn_username: None,
             turn_credential: None,
+            turn_secret: None,
+            turn_credential_ttl_seconds: 86400,
+            ice_candidate_filters: vec![],
+            turn_health_check_interval_seconds: 0,
+            turn_hide_unreachable: false,
+            media_trace_packets: false,
+            log_sdp: false,
+            enable_debug_signaling: false,
+            opus_red: false,
+            sdp_codec_preference: vec![],
+            sdp_codec_strip: vec![],
+            max_sdp_bytes: 65536,
+            max_subscribed_feeds: 0,
+            max_subscribers_per_feed: 0,
+            max_connections: 0,
+            max_publishers_total: 0,
+            viewer_count_interval_seconds: 5,
+            keyframe_interval_seconds: 0,
+            keyframe_min_interval_ms: 1000,
+            publisher_inactivity_timeout_seconds: 0,
+            publisher_reconnect_grace_ms: 0,
+            host_leave_grace_seconds: 30,
+            publisher_event_batch_ms: 0,
+            media_reaper_interval_seconds: 60,
+            publisher_reconcile_interval_seconds: 60,
+            duplicate_session_policy: crate::ws::DuplicateSessionPolicy::Replace,
+            min_client_version: None,
+            admin_api_key: None,
+            maintenance_mode: false,
+            metrics_enabled: false,
             frontend_host: Some("localhost".to_string()),
             frontend_port: Some(3000),
+            cors_allowed_origins: vec![],
             mail_from: Some("noreply@truegather.test".to_string()),
             resend_api_key: Some("test_resend_key".to_string()),
             invite_code_salt: "test-salt".to_string(),
+            default_invite_max_uses: None,
+            max_invite_max_uses: None,
+            max_invites_per_room: 500,
+            invite_reaper_interval_seconds: 3600,
+            invite_list_max_page_size: 100,
+            invite_room_bound_only: false,
+            magic_link_ttl_seconds: 0,
+            room_name_max_length: 100,
+            invite_subject_max_length: 200,
+            invite_message_max_length: 2000,
+            announcement_max_length: 500,
+            display_name_min_length: 1,
+            display_name_max_length: 100,
+            display_name_blocklist: vec![],
         }
     }
 
@@ -106,6 +297,45 @@ mod tests {
         assert_eq!(claims.display, "Alice");
     }
 
+    #[test]
+    fn test_host_and_guest_token_expiry() {
+        let config = test_config();
+        let auth = AuthService::new(&config);
+
+        let (host_token, host_expiry) = auth
+            .generate_host_token("host", "room-456", "Host")
+            .expect("Should generate host token");
+        let (guest_token, guest_expiry) = auth
+            .generate_guest_token("guest", "room-456", "Guest", false)
+            .expect("Should generate guest token");
+
+        assert_eq!(host_expiry, 86400);
+        assert_eq!(guest_expiry, 600);
+
+        let host = auth.validate_token(&host_token).expect("Should validate");
+        let guest = auth.validate_token(&guest_token).expect("Should validate");
+        assert!(host.host);
+        assert!(!guest.host);
+        assert_eq!(host.exp - host.iat, 86400);
+        assert_eq!(guest.exp - guest.iat, 600);
+    }
+
+    #[test]
+    fn test_observer_guest_token() {
+        let config = test_config();
+        let auth = AuthService::new(&config);
+
+        let (token, _) = auth
+            .generate_guest_token("watcher", "room-456", "Lobby screen", true)
+            .expect("Should generate guest token");
+        let (host_token, _) = auth
+            .generate_host_token("host", "room-456", "Host")
+            .expect("Should generate host token");
+
+        assert!(auth.validate_token(&token).expect("Should validate").observer);
+        assert!(!auth.validate_token(&host_token).expect("Should validate").observer);
+    }
+
     #[test]
     fn test_extract_from_query() {
         let config = test_config();
@@ -124,6 +354,25 @@ mod tests {
         assert_eq!(claims.room_id, "room-456");
     }
 
+    #[test]
+    fn test_turn_rest_credentials() {
+        let (username, credential) = turn_rest_credentials("turn-secret", "user-123", 1700000000);
+
+        assert_eq!(username, "1700000000:user-123");
+        assert_eq!(credential, "irIm3TOSIN8jJAMwpkmK5IJPAlU=");
+    }
+
+    #[test]
+    fn test_verify_creator_without_key_is_rejected() {
+        let config = test_config();
+        let auth = AuthService::new(&config);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, "Bearer anything".parse().unwrap());
+
+        assert!(auth.verify_creator(&headers).is_err());
+    }
+
     #[test]
     fn test_invalid_token() {
         let config = test_config();
@@ -1,25 +1,151 @@
 use std::env;
 
+use crate::media::ice::CandidateFilter;
+use crate::validation::ClientVersion;
+use crate::ws::DuplicateSessionPolicy;
+
 /// App config loaded from environment variables (.env)
 #[derive(Debug, Clone)]
 pub struct Config {
     pub server_host: String,
     pub server_port: u16,
+    /// Region tag (e.g. `eu-west`) reported with the instance id in `/health`,
+    /// `joined` and `X-Served-By`, for multi-region deployments
+    pub server_region: Option<String>,
+    /// Largest REST request body accepted, in bytes
+    pub max_request_body_bytes: usize,
+    /// Upper bound on the media drain after the server stops accepting
+    /// connections, in seconds
+    pub shutdown_drain_timeout_seconds: u64,
     pub redis_url: String,
+    /// Most pooled Redis connections (0 = deadpool's default, 4 per CPU)
+    pub redis_pool_max_size: usize,
+    /// Limit on opening a Redis connection, in milliseconds (0 = none)
+    pub redis_connect_timeout_ms: u64,
+    /// Limit on waiting for a free pooled connection, in milliseconds (0 = none)
+    pub redis_wait_timeout_ms: u64,
 
     // JWT
     pub jwt_secret: String,
     pub jwt_expiry_seconds: u64,
+    /// Lifetime of tokens issued through the host (creator_key) join flow
+    pub jwt_host_expiry_seconds: u64,
+    /// Lifetime of tokens issued through the guest (invitation) join flow
+    pub jwt_guest_expiry_seconds: u64,
+    /// Send `token_expiring` over the WebSocket this long before the
+    /// connection's token expires, in seconds (0 = off)
+    pub token_expiry_warning_seconds: u64,
+
+    // Room creation
+    /// Require an upstream RS256 bearer token on `POST /rooms`
+    pub create_requires_auth: bool,
+    /// PEM public key verifying upstream tokens (required with `create_requires_auth`)
+    pub creator_auth_public_key: Option<String>,
 
     // Rooms
+    /// TTL of a room created without one
     pub room_ttl_seconds: u64,
+    /// Longest TTL a room may be created with; longer requests are clamped (0 = none)
+    pub max_room_ttl_seconds: u64,
+    /// Absolute cap on a room's life from creation, across TTL refreshes (0 = none)
+    pub max_room_lifetime_seconds: u64,
+    /// How long the creator key hash outlives the room, so the host can still
+    /// clean up or moderate around the room's expiry
+    pub creator_key_grace_seconds: u64,
+    /// Warn connected clients with `room_closing` this long before the room
+    /// expires, in seconds (0 = no warning)
+    pub room_closing_warning_seconds: u64,
+    /// Delete a room nobody has joined this long after creation, ahead of
+    /// its TTL, in seconds (0 = never)
+    pub unjoined_room_timeout_seconds: u64,
     pub max_publishers_per_room: u32,
+    /// Most rooms one `POST /rooms/batch` may create
+    pub max_room_batch_size: usize,
+    /// Allow rooms to be created with a unique human-readable slug
+    pub room_slugs_enabled: bool,
+    /// Serve `GET /api/v1/rooms`; off, rooms are only reachable by id or slug
+    pub enable_room_listing: bool,
 
     // ICE
     pub stun_server: String,
     pub turn_server: Option<String>,
     pub turn_username: Option<String>,
     pub turn_credential: Option<String>,
+    /// Shared secret for TURN REST API credentials (coturn `static-auth-secret`).
+    /// When set, short-lived credentials are minted per user instead of the static ones.
+    pub turn_secret: Option<String>,
+    pub turn_credential_ttl_seconds: u64,
+    /// ICE candidates dropped before they reach webrtc-rs, from clients and
+    /// in server SDPs (`mdns`, `ipv4`, `ipv6`, `udp`, `tcp`, a candidate type or a CIDR)
+    pub ice_candidate_filters: Vec<String>,
+    /// How often the TURN server is probed, in seconds (0 = never)
+    pub turn_health_check_interval_seconds: u64,
+    /// Stop advertising the TURN server while its last probe failed
+    pub turn_hide_unreachable: bool,
+
+    // Media
+    /// Log per-packet forwarding errors at trace level (very noisy)
+    pub media_trace_packets: bool,
+    /// Log offers/answers (ICE credentials masked) at debug level, for incident debugging
+    pub log_sdp: bool,
+    /// Answer `debug_session` with the server's view of the connection (development only)
+    pub enable_debug_signaling: bool,
+    /// Negotiate Opus RED (redundant audio) with peers that support it. Off by
+    /// default: subscribers that can't decode RED can't play a RED publisher.
+    pub opus_red: bool,
+    /// Codec names moved to the front of generated SDPs, in order (empty = leave as is)
+    pub sdp_codec_preference: Vec<String>,
+    /// Codec names removed from generated SDPs
+    pub sdp_codec_strip: Vec<String>,
+    /// Largest client SDP accepted, in bytes
+    pub max_sdp_bytes: usize,
+    /// Most feeds one subscriber connection may receive (0 = unlimited)
+    pub max_subscribed_feeds: usize,
+    /// Most subscribers one feed may have (0 = unlimited)
+    pub max_subscribers_per_feed: usize,
+    /// Joins are refused (503) once this instance has this many WebSocket
+    /// connections (0 = unlimited)
+    pub max_connections: usize,
+    /// Publishing joins are refused (503) once this instance's gateway has
+    /// this many publishers; observers still get in (0 = unlimited)
+    pub max_publishers_total: usize,
+    /// How often feeds whose subscriber count changed get a `viewer_count`
+    /// event, in seconds (0 = never)
+    pub viewer_count_interval_seconds: u64,
+    /// Periodic keyframe request to every publisher, in seconds (0 = off)
+    pub keyframe_interval_seconds: u64,
+    /// Minimum gap between keyframe requests to the same feed, in milliseconds
+    pub keyframe_min_interval_ms: u64,
+    /// Remove publishers that sent no RTP for this long, in seconds (0 = never,
+    /// the default: a publisher who muted or paused every track is silent too)
+    pub publisher_inactivity_timeout_seconds: u64,
+    /// Delay before a disconnected publisher is announced as gone, in
+    /// milliseconds; republishing within it keeps the feed (0 = immediate)
+    pub publisher_reconnect_grace_ms: u64,
+    /// How long a room created with `end_on_host_leave` waits for its host to
+    /// reconnect before ending, in seconds
+    pub host_leave_grace_seconds: u64,
+    /// Coalesce publisher_joined/left into one publishers_changed per room over
+    /// this window, in milliseconds (0 = send each event on its own)
+    pub publisher_event_batch_ms: u64,
+    /// How often orphaned media rooms are swept, in seconds
+    pub media_reaper_interval_seconds: u64,
+    /// How often Redis publisher entries are reconciled with the media
+    /// gateway, in seconds (0 = never)
+    pub publisher_reconcile_interval_seconds: u64,
+    /// Handling of a second connection by the same user to the same room
+    pub duplicate_session_policy: DuplicateSessionPolicy,
+    /// Oldest client version allowed to join or open a WebSocket (None = any)
+    pub min_client_version: Option<ClientVersion>,
+
+    // Admin
+    /// Key expected in `X-Admin-Key` for `/api/v1/admin/*` (unset = admin API disabled)
+    pub admin_api_key: Option<String>,
+    /// Refuse new rooms and joins while running meetings continue (can also be
+    /// switched on at runtime through the admin API)
+    pub maintenance_mode: bool,
+    /// Serve `/metrics` and count invitation funnel stages
+    pub metrics_enabled: bool,
 
     // Mail
     pub mail_from: Option<String>,
@@ -28,53 +154,322 @@ pub struct Config {
     // Frontend base
     pub frontend_host: Option<String>,
     pub frontend_port: Option<u16>,
+    /// Browser origins allowed to call the API and open WebSockets
+    /// (empty = any, for development)
+    pub cors_allowed_origins: Vec<String>,
 
     // ✅ Pepper/salt used to hash invitation codes + creator keys
     // IMPORTANT: if you change this, all existing invites become invalid.
     pub invite_code_salt: String,
+
+    // Invitations
+    /// `max_uses` given to invites that don't request one (None = unlimited)
+    pub default_invite_max_uses: Option<u32>,
+    /// Upper bound on `max_uses`; when set, unlimited invites are not issued
+    pub max_invite_max_uses: Option<u32>,
+    /// Live invitations a room may have at once (0 = unlimited)
+    pub max_invites_per_room: usize,
+    /// How often expired invitation references are pruned, in seconds (0 = never)
+    pub invite_reaper_interval_seconds: u64,
+    /// Most invitations `GET /rooms/:id/invites` returns per page
+    pub invite_list_max_page_size: usize,
+    /// Only serve invitations through routes naming their room; the room-less
+    /// `/rooms/invite/:token` routes answer 404
+    pub invite_room_bound_only: bool,
+    /// Lifetime of the single-use one-click links added to invitation emails,
+    /// in seconds (0 = no links, guests type the code)
+    pub magic_link_ttl_seconds: u64,
+
+    // Free-text limits, in characters
+    pub room_name_max_length: usize,
+    pub invite_subject_max_length: usize,
+    pub invite_message_max_length: usize,
+    pub announcement_max_length: usize,
+
+    // Display names
+    pub display_name_min_length: usize,
+    pub display_name_max_length: usize,
+    pub display_name_blocklist: Vec<String>,
 }
 
 impl Config {
     pub fn from_env() -> Result<Self, ConfigError> {
         dotenvy::dotenv().ok();
 
+        let jwt_expiry_seconds = env::var("JWT_EXPIRY_SECONDS")
+            .unwrap_or_else(|_| "900".to_string())
+            .parse()
+            .unwrap_or(900);
+
+        let create_requires_auth = env_bool("CREATE_REQUIRES_AUTH", false);
+        // Single-line env values carry the PEM with literal "\n" separators
+        let creator_auth_public_key = env::var("CREATOR_AUTH_PUBLIC_KEY")
+            .ok()
+            .map(|pem| pem.replace("\\n", "\n"));
+        match &creator_auth_public_key {
+            Some(pem) if jsonwebtoken::DecodingKey::from_rsa_pem(pem.as_bytes()).is_err() => {
+                return Err(ConfigError::InvalidCreatorAuthKey);
+            }
+            None if create_requires_auth => return Err(ConfigError::MissingCreatorAuthKey),
+            _ => {}
+        }
+
+        let ice_candidate_filters = env_list("ICE_CANDIDATE_FILTERS");
+        CandidateFilter::parse_list(&ice_candidate_filters)
+            .map_err(ConfigError::InvalidIceCandidateFilter)?;
+
+        let duplicate_session_policy = match env::var("DUPLICATE_SESSION_POLICY") {
+            Ok(raw) => DuplicateSessionPolicy::parse(&raw)
+                .ok_or(ConfigError::InvalidDuplicateSessionPolicy(raw))?,
+            Err(_) => DuplicateSessionPolicy::default(),
+        };
+
+        let min_client_version = match env::var("MIN_CLIENT_VERSION") {
+            Ok(raw) if !raw.trim().is_empty() => {
+                let version = ClientVersion::parse(&raw);
+                Some(version.ok_or(ConfigError::InvalidMinClientVersion(raw))?)
+            }
+            _ => None,
+        };
+
+        let server_region = match env::var("SERVER_REGION") {
+            Ok(raw) if !raw.trim().is_empty() => {
+                let region = raw.trim();
+                let valid = region
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+                if !valid {
+                    return Err(ConfigError::InvalidServerRegion(raw));
+                }
+                Some(region.to_string())
+            }
+            _ => None,
+        };
+
         Ok(Config {
             server_host: env::var("SERVER_HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
             server_port: env::var("SERVER_PORT")
                 .unwrap_or_else(|_| "8080".to_string())
                 .parse()
                 .map_err(|_| ConfigError::InvalidPort)?,
+            server_region,
+            max_request_body_bytes: env::var("MAX_REQUEST_BODY_BYTES")
+                .unwrap_or_else(|_| "262144".to_string())
+                .parse()
+                .unwrap_or(262144),
+            shutdown_drain_timeout_seconds: env::var("SHUTDOWN_DRAIN_TIMEOUT_SECONDS")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()
+                .unwrap_or(10),
 
             redis_url: env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379".to_string()),
+            redis_pool_max_size: env::var("REDIS_POOL_MAX_SIZE")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
+            redis_connect_timeout_ms: env::var("REDIS_CONNECT_TIMEOUT_MS")
+                .unwrap_or_else(|_| "2000".to_string())
+                .parse()
+                .unwrap_or(2000),
+            redis_wait_timeout_ms: env::var("REDIS_WAIT_TIMEOUT_MS")
+                .unwrap_or_else(|_| "5000".to_string())
+                .parse()
+                .unwrap_or(5000),
 
             jwt_secret: env::var("JWT_SECRET").map_err(|_| ConfigError::MissingJwtSecret)?,
-            jwt_expiry_seconds: env::var("JWT_EXPIRY_SECONDS")
-                .unwrap_or_else(|_| "900".to_string())
+            jwt_expiry_seconds,
+            jwt_host_expiry_seconds: env::var("JWT_HOST_EXPIRY_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(jwt_expiry_seconds),
+
+            create_requires_auth,
+            creator_auth_public_key,
+            jwt_guest_expiry_seconds: env::var("JWT_GUEST_EXPIRY_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(jwt_expiry_seconds),
+            token_expiry_warning_seconds: env::var("TOKEN_EXPIRY_WARNING_SECONDS")
+                .unwrap_or_else(|_| "60".to_string())
                 .parse()
-                .unwrap_or(900),
+                .unwrap_or(60),
 
             room_ttl_seconds: env::var("ROOM_TTL_SECONDS")
                 .unwrap_or_else(|_| "7200".to_string())
                 .parse()
                 .unwrap_or(7200),
+            max_room_ttl_seconds: env::var("MAX_ROOM_TTL_SECONDS")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
+            max_room_lifetime_seconds: env::var("MAX_ROOM_LIFETIME_SECONDS")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
+            creator_key_grace_seconds: env::var("CREATOR_KEY_GRACE_SECONDS")
+                .unwrap_or_else(|_| "300".to_string())
+                .parse()
+                .unwrap_or(300),
+            room_closing_warning_seconds: env::var("ROOM_CLOSING_WARNING_SECONDS")
+                .unwrap_or_else(|_| "300".to_string())
+                .parse()
+                .unwrap_or(300),
+            unjoined_room_timeout_seconds: env::var("UNJOINED_ROOM_TIMEOUT_SECONDS")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
 
             max_publishers_per_room: env::var("MAX_PUBLISHERS_PER_ROOM")
                 .unwrap_or_else(|_| "50".to_string())
                 .parse()
                 .unwrap_or(50),
+            max_room_batch_size: env::var("MAX_ROOM_BATCH_SIZE")
+                .unwrap_or_else(|_| "100".to_string())
+                .parse()
+                .unwrap_or(100),
+            room_slugs_enabled: env_bool("ROOM_SLUGS_ENABLED", false),
+            enable_room_listing: env_bool("ENABLE_ROOM_LISTING", true),
 
             stun_server: env::var("STUN_SERVER").unwrap_or_else(|_| "stun:stun.l.google.com:19302".to_string()),
             turn_server: env::var("TURN_SERVER").ok(),
             turn_username: env::var("TURN_USERNAME").ok(),
             turn_credential: env::var("TURN_CREDENTIAL").ok(),
+            turn_secret: env::var("TURN_SECRET").ok(),
+            turn_credential_ttl_seconds: env::var("TURN_CREDENTIAL_TTL_SECONDS")
+                .unwrap_or_else(|_| "86400".to_string())
+                .parse()
+                .unwrap_or(86400),
+            ice_candidate_filters,
+            turn_health_check_interval_seconds: env::var("TURN_HEALTH_CHECK_INTERVAL_SECONDS")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
+            turn_hide_unreachable: env_bool("TURN_HIDE_UNREACHABLE", false),
+
+            media_trace_packets: env_bool("MEDIA_TRACE_PACKETS", false),
+            log_sdp: env_bool("LOG_SDP", false),
+            enable_debug_signaling: env_bool("ENABLE_DEBUG_SIGNALING", false),
+            opus_red: env_bool("OPUS_RED", false),
+            sdp_codec_preference: env_list("SDP_CODEC_PREFERENCE"),
+            sdp_codec_strip: env_list("SDP_CODEC_STRIP"),
+            max_subscribed_feeds: env::var("MAX_SUBSCRIBED_FEEDS")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
+            max_subscribers_per_feed: env::var("MAX_SUBSCRIBERS_PER_FEED")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
+            max_connections: env::var("MAX_CONNECTIONS")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
+            max_publishers_total: env::var("MAX_PUBLISHERS_TOTAL")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
+            viewer_count_interval_seconds: env::var("VIEWER_COUNT_INTERVAL_SECONDS")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .unwrap_or(5),
+            max_sdp_bytes: env::var("MAX_SDP_BYTES")
+                .unwrap_or_else(|_| "65536".to_string())
+                .parse()
+                .unwrap_or(65536),
+            keyframe_interval_seconds: env::var("KEYFRAME_INTERVAL_SECONDS")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
+            keyframe_min_interval_ms: env::var("KEYFRAME_MIN_INTERVAL_MS")
+                .unwrap_or_else(|_| "1000".to_string())
+                .parse()
+                .unwrap_or(1000),
+            publisher_inactivity_timeout_seconds: env::var("PUBLISHER_INACTIVITY_TIMEOUT_SECONDS")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
+            publisher_reconnect_grace_ms: env::var("PUBLISHER_RECONNECT_GRACE_MS")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
+            host_leave_grace_seconds: env::var("HOST_LEAVE_GRACE_SECONDS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .unwrap_or(30),
+            publisher_event_batch_ms: env::var("PUBLISHER_EVENT_BATCH_MS")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
+            media_reaper_interval_seconds: env::var("MEDIA_REAPER_INTERVAL_SECONDS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .unwrap_or(60),
+            publisher_reconcile_interval_seconds: env::var("PUBLISHER_RECONCILE_INTERVAL_SECONDS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .unwrap_or(60),
+            duplicate_session_policy,
+            min_client_version,
+
+            admin_api_key: env::var("ADMIN_API_KEY").ok().filter(|k| !k.trim().is_empty()),
+            maintenance_mode: env_bool("MAINTENANCE_MODE", false),
+            metrics_enabled: env_bool("METRICS_ENABLED", false),
 
             mail_from: env::var("MAIL_FROM").ok(),
             resend_api_key: env::var("RESEND_API_KEY").ok(),
 
             frontend_host: env::var("FRONTEND_HOST").ok(),
             frontend_port: env::var("FRONTEND_PORT").ok().and_then(|p| p.parse().ok()),
+            cors_allowed_origins: env_list("CORS_ALLOWED_ORIGINS"),
 
             invite_code_salt: env::var("INVITE_CODE_SALT").map_err(|_| ConfigError::MissingInviteCodeSalt)?,
+
+            default_invite_max_uses: env::var("DEFAULT_INVITE_MAX_USES").ok().and_then(|v| v.parse().ok()),
+            max_invite_max_uses: env::var("MAX_INVITE_MAX_USES").ok().and_then(|v| v.parse().ok()),
+            max_invites_per_room: env::var("MAX_INVITES_PER_ROOM")
+                .unwrap_or_else(|_| "500".to_string())
+                .parse()
+                .unwrap_or(500),
+            invite_reaper_interval_seconds: env::var("INVITE_REAPER_INTERVAL_SECONDS")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()
+                .unwrap_or(3600),
+            invite_list_max_page_size: env::var("INVITE_LIST_MAX_PAGE_SIZE")
+                .unwrap_or_else(|_| "100".to_string())
+                .parse()
+                .unwrap_or(100),
+            invite_room_bound_only: env_bool("INVITE_ROOM_BOUND_ONLY", false),
+            magic_link_ttl_seconds: env::var("MAGIC_LINK_TTL_SECONDS")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
+
+            room_name_max_length: env::var("ROOM_NAME_MAX_LENGTH")
+                .unwrap_or_else(|_| "100".to_string())
+                .parse()
+                .unwrap_or(100),
+            invite_subject_max_length: env::var("INVITE_SUBJECT_MAX_LENGTH")
+                .unwrap_or_else(|_| "200".to_string())
+                .parse()
+                .unwrap_or(200),
+            invite_message_max_length: env::var("INVITE_MESSAGE_MAX_LENGTH")
+                .unwrap_or_else(|_| "2000".to_string())
+                .parse()
+                .unwrap_or(2000),
+            announcement_max_length: env::var("ANNOUNCEMENT_MAX_LENGTH")
+                .unwrap_or_else(|_| "500".to_string())
+                .parse()
+                .unwrap_or(500),
+
+            display_name_min_length: env::var("DISPLAY_NAME_MIN_LENGTH")
+                .unwrap_or_else(|_| "1".to_string())
+                .parse()
+                .unwrap_or(1),
+            display_name_max_length: env::var("DISPLAY_NAME_MAX_LENGTH")
+                .unwrap_or_else(|_| "100".to_string())
+                .parse()
+                .unwrap_or(100),
+            display_name_blocklist: env_list("DISPLAY_NAME_BLOCKLIST"),
         })
     }
 
@@ -83,6 +478,25 @@ impl Config {
     }
 }
 
+/// Parse a boolean env var ("true"/"1"/"yes"/"on", case-insensitive)
+fn env_bool(key: &str, default: bool) -> bool {
+    env::var(key)
+        .map(|v| matches!(v.trim().to_lowercase().as_str(), "true" | "1" | "yes" | "on"))
+        .unwrap_or(default)
+}
+
+/// Parse a comma-separated env var into a list (empty entries dropped)
+fn env_list(key: &str) -> Vec<String> {
+    env::var(key)
+        .map(|v| {
+            v.split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ConfigError {
     #[error("Invalid server port")]
@@ -91,4 +505,16 @@ pub enum ConfigError {
     MissingJwtSecret,
     #[error("INVITE_CODE_SALT environment variable is required")]
     MissingInviteCodeSalt,
+    #[error("CREATOR_AUTH_PUBLIC_KEY is required when CREATE_REQUIRES_AUTH is enabled")]
+    MissingCreatorAuthKey,
+    #[error("CREATOR_AUTH_PUBLIC_KEY is not a valid RSA public key (PEM)")]
+    InvalidCreatorAuthKey,
+    #[error("Invalid ICE_CANDIDATE_FILTERS entry: {0}")]
+    InvalidIceCandidateFilter(String),
+    #[error("Invalid DUPLICATE_SESSION_POLICY (expected replace or allow): {0}")]
+    InvalidDuplicateSessionPolicy(String),
+    #[error("Invalid MIN_CLIENT_VERSION (expected major.minor.patch): {0}")]
+    InvalidMinClientVersion(String),
+    #[error("Invalid SERVER_REGION (letters, digits, '-', '_' and '.' only): {0}")]
+    InvalidServerRegion(String),
 }
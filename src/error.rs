@@ -1,3 +1,4 @@
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::Json;
@@ -11,6 +12,9 @@ pub enum AppError {
     #[error("Unauthorized: {0}")]
     Unauthorized(String),
 
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
     #[error("Bad request: {0}")]
     BadRequest(String),
 
@@ -26,22 +30,71 @@ pub enum AppError {
     #[error("Room is full")]
     RoomFull,
 
+    #[error("Room is locked")]
+    RoomLocked,
+
+    #[error("Invalid room ID format")]
+    InvalidRoomId,
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
     #[error("JWT error: {0}")]
     JwtError(String),
+
+    #[error("Service unavailable: {0}")]
+    ServiceUnavailable(String),
+
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+
+    #[error("Unsupported media type: {0}")]
+    UnsupportedMediaType(String),
+
+    #[error("Unprocessable entity: {0}")]
+    UnprocessableEntity(String),
+
+    #[error("Not implemented: {0}")]
+    NotImplemented(String),
+
+    #[error("Upgrade required: {0}")]
+    UpgradeRequired(String),
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let (status, error_message) = match &self {
+impl AppError {
+    /// HTTP status and the message shown to the client
+    pub fn status_and_message(&self) -> (StatusCode, String) {
+        match self {
             AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg.clone()),
             AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg.clone()),
+            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg.clone()),
             AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg.clone()),
             AppError::InternalError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg.clone()),
             AppError::RedisError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg.clone()),
             AppError::WebRtcError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg.clone()),
             AppError::RoomFull => (StatusCode::CONFLICT, "Room is full".to_string()),
+            AppError::RoomLocked => (StatusCode::LOCKED, "Room is locked".to_string()),
+            AppError::InvalidRoomId => (
+                StatusCode::BAD_REQUEST,
+                "Invalid room ID format".to_string(),
+            ),
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, msg.clone()),
             AppError::JwtError(msg) => (StatusCode::UNAUTHORIZED, msg.clone()),
-        };
+            AppError::ServiceUnavailable(msg) => (StatusCode::SERVICE_UNAVAILABLE, msg.clone()),
+            AppError::PayloadTooLarge(msg) => (StatusCode::PAYLOAD_TOO_LARGE, msg.clone()),
+            AppError::UnsupportedMediaType(msg) => {
+                (StatusCode::UNSUPPORTED_MEDIA_TYPE, msg.clone())
+            }
+            AppError::UnprocessableEntity(msg) => (StatusCode::UNPROCESSABLE_ENTITY, msg.clone()),
+            AppError::NotImplemented(msg) => (StatusCode::NOT_IMPLEMENTED, msg.clone()),
+            AppError::UpgradeRequired(msg) => (StatusCode::UPGRADE_REQUIRED, msg.clone()),
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let (status, error_message) = self.status_and_message();
 
         let body = Json(json!({
             "error": error_message,
@@ -52,6 +105,48 @@ impl IntoResponse for AppError {
     }
 }
 
+impl From<JsonRejection> for AppError {
+    fn from(rejection: JsonRejection) -> Self {
+        // Keep axum's distinction: 413 over the `DefaultBodyLimit`, 415 without
+        // a JSON content type, 422 for valid JSON of the wrong shape
+        match rejection.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => AppError::PayloadTooLarge(rejection.body_text()),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE => {
+                AppError::UnsupportedMediaType(rejection.body_text())
+            }
+            StatusCode::UNPROCESSABLE_ENTITY => {
+                AppError::UnprocessableEntity(rejection.body_text())
+            }
+            _ => AppError::BadRequest(rejection.body_text()),
+        }
+    }
+}
+
+impl From<QueryRejection> for AppError {
+    fn from(rejection: QueryRejection) -> Self {
+        AppError::BadRequest(rejection.body_text())
+    }
+}
+
+impl From<PathRejection> for AppError {
+    fn from(rejection: PathRejection) -> Self {
+        AppError::BadRequest(rejection.body_text())
+    }
+}
+
+/// Body for panics caught by `CatchPanicLayer`: same shape as `AppError`,
+/// without leaking the panic message to clients
+pub fn panic_response(panic: Box<dyn std::any::Any + Send + 'static>) -> Response {
+    let detail = panic
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| panic.downcast_ref::<&str>().copied())
+        .unwrap_or("unknown panic");
+    tracing::error!(panic = %detail, "Handler panicked");
+
+    AppError::InternalError("Internal server error".to_string()).into_response()
+}
+
 impl From<redis::RedisError> for AppError {
     fn from(err: redis::RedisError) -> Self {
         AppError::RedisError(err.to_string())
@@ -60,7 +155,14 @@ impl From<redis::RedisError> for AppError {
 
 impl From<deadpool_redis::PoolError> for AppError {
     fn from(err: deadpool_redis::PoolError) -> Self {
-        AppError::RedisError(err.to_string())
+        match err {
+            // Pool exhausted or Redis too slow to connect: fail fast as 503
+            deadpool_redis::PoolError::Timeout(kind) => {
+                tracing::warn!(timeout = ?kind, "Timed out getting a Redis connection");
+                AppError::ServiceUnavailable("Redis is unavailable, try again later".to_string())
+            }
+            err => AppError::RedisError(err.to_string()),
+        }
     }
 }
 
@@ -4,9 +4,15 @@ pub mod config;
 pub mod error;
 pub mod mail;
 pub mod media;
+pub mod metrics;
 pub mod models;
 pub mod redis;
+pub mod security;
+pub mod selfcheck;
 pub mod state;
+pub mod tasks;
+pub mod turn;
+pub mod validation;
 pub mod ws;
 
 pub use config::Config;
@@ -20,4 +20,9 @@ impl Mailer {
     pub async fn send_invite(&self, to: Vec<String>, subject: String, text: String) -> Result<()> {
         self.inner.send(to, subject, text).await
     }
+
+    /// Check the provider credentials without sending anything
+    pub async fn verify_credentials(&self) -> Result<()> {
+        self.inner.verify_credentials().await
+    }
 }
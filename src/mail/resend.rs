@@ -57,4 +57,25 @@ impl ResendMailer {
 
         Ok(())
     }
+
+    /// Dry run: list domains. A sending-only key is refused there as
+    /// `restricted_api_key`, which still proves the key is valid.
+    pub async fn verify_credentials(&self) -> Result<()> {
+        let res = self
+            .client
+            .get("https://api.resend.com/domains")
+            .bearer_auth(&self.api_key)
+            .send()
+            .await
+            .map_err(|e| AppError::BadRequest(format!("Resend unreachable: {}", e)))?;
+
+        if res.status().is_success() {
+            return Ok(());
+        }
+        let body = res.text().await.unwrap_or_default();
+        if body.contains("restricted_api_key") {
+            return Ok(());
+        }
+        Err(AppError::BadRequest(format!("Resend API error: {}", body)))
+    }
 }
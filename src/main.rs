@@ -1,19 +1,25 @@
 use std::net::SocketAddr;
+use std::time::Duration;
 
+use axum::http::{HeaderName, HeaderValue};
 use axum::Router;
 use tokio::net::TcpListener;
 use tokio::signal;
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::catch_panic::CatchPanicLayer;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
 use truegather_backend::api;
 use truegather_backend::auth::AuthService;
 use truegather_backend::config::Config;
+use truegather_backend::error::panic_response;
 use truegather_backend::mail::Mailer;
 use truegather_backend::media::MediaGateway;
 use truegather_backend::redis::{create_pool, RoomRepository};
+use truegather_backend::selfcheck;
 use truegather_backend::state::AppState;
+use truegather_backend::tasks;
 use truegather_backend::ws::ws_routes;
 
 #[tokio::main]
@@ -32,6 +38,19 @@ async fn main() -> anyhow::Result<()> {
 
     tracing::info!("Starting TrueGather Backend...");
 
+    // `--check`: validate config and dependencies, print a report and exit
+    if std::env::args().skip(1).any(|arg| arg == "--check") {
+        let report = match Config::from_env() {
+            Ok(config) => selfcheck::run(&config).await,
+            Err(e) => {
+                println!("FAIL  config     {}\nFAILED", e);
+                std::process::exit(1);
+            }
+        };
+        println!("{}", report);
+        std::process::exit(if report.passed() { 0 } else { 1 });
+    }
+
     // Load configuration
     let config = Config::from_env()?;
     tracing::info!(
@@ -62,18 +81,54 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("Media gateway initialized");
 
     // Create application state
-    let mailer = Mailer::new_from_env()?;
+    // Email invites are optional; without a mailer the rest of the server works
+    let mailer = match Mailer::new_from_env() {
+        Ok(mailer) => Some(mailer),
+        Err(e) => {
+            tracing::warn!(error = %e, "Mailer not configured, email invites are disabled");
+            None
+        }
+    };
     let state = AppState::new(config.clone(), auth, room_repo, media_gateway, mailer);
 
+    // Background maintenance
+    tasks::spawn_media_reaper(state.clone());
+    tasks::spawn_keyframe_ticker(state.clone());
+    tasks::spawn_publisher_reaper(state.clone());
+    tasks::spawn_turn_health_check(state.clone());
+    tasks::spawn_invite_reaper(state.clone());
+    tasks::spawn_room_closing_warner(state.clone());
+    tasks::spawn_unjoined_room_reaper(state.clone());
+    tasks::spawn_publisher_reconciler(state.clone());
+    tasks::spawn_viewer_count_broadcaster(state.clone());
+
+    let media_gateway = state.media_gateway.clone();
+
+    // Any origin until an allowlist is configured (development)
+    let cors_origin = if config.cors_allowed_origins.is_empty() {
+        AllowOrigin::any()
+    } else {
+        let origins = config
+            .cors_allowed_origins
+            .iter()
+            .filter_map(|o| HeaderValue::from_str(o).ok());
+        AllowOrigin::list(origins)
+    };
+
     // Build router
+    let served_by = state.served_by();
     let app = Router::new()
         .merge(api::create_router(state.clone()))
         .merge(ws_routes().with_state(state))
+        .fallback(api::extract::not_found)
+        .layer(CatchPanicLayer::custom(panic_response))
+        .layer(api::served_by_layer(&served_by))
         .layer(
             CorsLayer::new()
-                .allow_origin(Any)
+                .allow_origin(cors_origin)
                 .allow_methods(Any)
-                .allow_headers(Any),
+                .allow_headers(Any)
+                .expose_headers([HeaderName::from_static(api::SERVED_BY_HEADER)]),
         )
         .layer(TraceLayer::new_for_http());
 
@@ -88,6 +143,22 @@ async fn main() -> anyhow::Result<()> {
         .with_graceful_shutdown(shutdown_signal())
         .await?;
 
+    // Drain media once no new connections come in
+    let drain_timeout = Duration::from_secs(config.shutdown_drain_timeout_seconds);
+    match tokio::time::timeout(drain_timeout, media_gateway.shutdown()).await {
+        Ok(summary) => tracing::info!(
+            rooms = summary.rooms,
+            publishers = summary.publishers,
+            subscribers = summary.subscribers,
+            forwarders = summary.forwarders,
+            "Media drained"
+        ),
+        Err(_) => tracing::warn!(
+            timeout_seconds = config.shutdown_drain_timeout_seconds,
+            "Media drain timed out, exiting anyway"
+        ),
+    }
+
     tracing::info!("Server shutdown complete");
 
     Ok(())
@@ -1,41 +1,133 @@
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, RwLock};
 
 use dashmap::DashMap;
+use tracing::Instrument;
 use webrtc::api::interceptor_registry::register_default_interceptors;
-use webrtc::api::media_engine::{MediaEngine, MIME_TYPE_OPUS, MIME_TYPE_VP8};
+use webrtc::api::media_engine::{MediaEngine, MIME_TYPE_H264, MIME_TYPE_OPUS, MIME_TYPE_VP8};
 use webrtc::api::setting_engine::SettingEngine;
 use webrtc::api::APIBuilder;
 use webrtc::ice_transport::ice_candidate::RTCIceCandidateInit;
 use webrtc::ice_transport::ice_server::RTCIceServer;
 use webrtc::interceptor::registry::Registry;
 use webrtc::peer_connection::configuration::RTCConfiguration;
+use webrtc::peer_connection::peer_connection_state::RTCPeerConnectionState;
 use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
 use webrtc::peer_connection::RTCPeerConnection;
+use webrtc::rtcp::packet::Packet as RtcpPacket;
+use webrtc::rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication;
 use webrtc::rtp_transceiver::rtp_codec::{
     RTCRtpCodecCapability, RTCRtpCodecParameters, RTPCodecType,
 };
-use webrtc::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
 use webrtc::track::track_local::TrackLocal;
 
 use crate::config::Config;
 use crate::error::{AppError, Result};
+use crate::media::ice::{self, CandidateFilter};
+use crate::media::sdp;
 use crate::media::track_forwarder::TrackForwarder;
+use crate::ws::{msg_types, ClientHandle, MediaStatePayload, PeerStatePayload, SignalingMessage};
 
-/// Publisher session holding the peer connection and tracks
+/// Publisher session holding the peer connection and tracks.
+/// Tracks arrive asynchronously through `on_track`, so the forwarder list and
+/// media flags are shared with that callback.
 pub struct PublisherSession {
     pub peer_connection: Arc<RTCPeerConnection>,
     pub user_id: String,
     pub feed_id: String,
-    pub local_tracks: Vec<Arc<TrackLocalStaticRTP>>,
-    pub forwarders: Vec<Arc<TrackForwarder>>,
+    pub forwarders: Arc<RwLock<Vec<Arc<TrackForwarder>>>>,
+    /// Number of tracks received so far, for subscribers waiting on them
+    pub tracks_received: watch::Receiver<usize>,
+    /// Set once the first audio track arrives
+    pub has_audio: Arc<AtomicBool>,
+    /// Set once the first video track arrives
+    pub has_video: Arc<AtomicBool>,
+    /// When a keyframe was last requested from this feed (rate limiting)
+    pub last_keyframe_request: Mutex<Option<Instant>>,
+    /// Tracks the publish offer sends (a screen share may carry system audio
+    /// next to the screen and mic); subscribers wait for all of them
+    pub expected_tracks: usize,
+    /// The answer as sent to the client; `get_candidates` serves only
+    /// candidates missing from it
+    pub sent_sdp: String,
+    pub created_at: Instant,
 }
 
+impl PublisherSession {
+    /// The feed's forwarders once every track announced in the offer has
+    /// arrived (or `FEED_TRACKS_WAIT` has passed), so a subscriber attaches
+    /// all of them together instead of only those that raced ahead. The
+    /// future borrows nothing from the session: await it with no guard held.
+    fn settled_forwarders(
+        &self,
+    ) -> impl Future<Output = Vec<Arc<TrackForwarder>>> + Send + 'static {
+        let forwarders = self.forwarders.clone();
+        let expected = self.expected_tracks;
+        let mut received = self.tracks_received.clone();
+        async move {
+            let all_arrived = received.wait_for(|count| *count >= expected);
+            let _ = tokio::time::timeout(FEED_TRACKS_WAIT, all_arrived).await;
+            let forwarders = forwarders.read().await.clone();
+            forwarders
+        }
+    }
+}
+
+/// Refuse a new subscriber for a feed already sent to `max` clients (0 = unlimited)
+pub fn ensure_feed_capacity(feed_id: &str, subscribers: usize, max: usize) -> Result<()> {
+    if max > 0 && subscribers >= max {
+        return Err(AppError::Conflict(format!(
+            "Feed {} already has the maximum of {} subscribers",
+            feed_id, max
+        )));
+    }
+    Ok(())
+}
+
+/// Longest a subscribe waits for the remaining tracks of a just-published feed
+const FEED_TRACKS_WAIT: Duration = Duration::from_secs(2);
+
+/// A publisher that stopped sending media, see `MediaGateway::inactive_publishers`
+#[derive(Debug, Clone)]
+pub struct InactivePublisher {
+    pub room_id: String,
+    pub user_id: String,
+    pub feed_id: String,
+    pub idle: Duration,
+}
+
+/// RED (RFC 2198) redundancy wrapper; webrtc-rs has no constant for it
+const MIME_TYPE_RED: &str = "audio/red";
+
+/// Longest SDP written to the logs when `log_sdp` is on, in bytes
+const SDP_LOG_MAX_BYTES: usize = 16 * 1024;
+
+/// Most candidates buffered for a subscriber that hasn't answered yet
+pub const MAX_PENDING_CANDIDATES: usize = 64;
+
 /// Subscriber session holding the peer connection
 pub struct SubscriberSession {
     pub peer_connection: Arc<RTCPeerConnection>,
     pub user_id: String,
+    /// Connection the subscribe came from (None when created without one)
+    pub conn_id: Option<String>,
     pub subscribed_feeds: Vec<String>,
+    /// feed_id -> mids of its `m=` sections; from the client's answer when it
+    /// reports them, otherwise derived from subscribe order
+    pub feed_mids: HashMap<String, Vec<String>>,
+    /// The client's ICE ufrag from its answer; trickled candidates naming
+    /// another one belong to a connection this one replaced
+    pub remote_ufrag: Option<String>,
+    /// The offer as sent to the client; `get_candidates` serves only
+    /// candidates missing from it
+    pub sent_sdp: String,
+    /// Candidates trickled before the client's answer, applied once it is set
+    /// (at most `MAX_PENDING_CANDIDATES`)
+    pub pending_candidates: Vec<RTCIceCandidateInit>,
 }
 
 /// Room media state
@@ -64,58 +156,245 @@ pub struct MediaGateway {
     rooms: DashMap<String, Arc<RoomMedia>>,
     ice_servers: Vec<RTCIceServer>,
     api: Arc<webrtc::api::API>,
+    trace_packets: bool,
+    log_sdp: bool,
+    candidate_filters: Vec<CandidateFilter>,
+    codec_preference: Vec<String>,
+    codec_strip: Vec<String>,
+    keyframe_min_interval: Duration,
+    /// Most subscribers a single feed may be sent to (0 = unlimited)
+    max_subscribers_per_feed: usize,
+    /// Most publishers across rooms (0 = unlimited)
+    max_publishers_total: usize,
+    /// Publishers still negotiating, counted against `max_publishers_total`
+    publishers_creating: AtomicUsize,
 }
 
-impl MediaGateway {
-    pub fn new(config: &Config) -> Result<Self> {
-        // Configure media engine
-        let mut media_engine = MediaEngine::default();
+/// A publisher counted in `publishers_creating` until dropped
+struct CreatingPublisher<'a>(&'a AtomicUsize);
+
+impl Drop for CreatingPublisher<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// What `MediaGateway::shutdown` tore down
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ShutdownSummary {
+    pub rooms: usize,
+    pub publishers: usize,
+    pub subscribers: usize,
+    pub forwarders: usize,
+}
+
+/// Tells a client about its server-side peer connection's state changes
+/// (`media_state`), skipping repeats of the last state sent. `New` and
+/// `Closed` are not sent: the server closes connections itself on
+/// unpublish, re-subscribe and leave, and says so through other messages.
+struct MediaStateNotifier {
+    client: ClientHandle,
+    role: &'static str,
+    last: Mutex<Option<RTCPeerConnectionState>>,
+}
+
+impl MediaStateNotifier {
+    fn new(client: ClientHandle, role: &'static str) -> Self {
+        Self {
+            client,
+            role,
+            last: Mutex::new(None),
+        }
+    }
+
+    /// Whether `state` should reach the client; records it as the last one sent
+    fn should_send(&self, state: RTCPeerConnectionState) -> bool {
+        if matches!(
+            state,
+            RTCPeerConnectionState::Unspecified
+                | RTCPeerConnectionState::New
+                | RTCPeerConnectionState::Closed
+        ) {
+            return false;
+        }
+        let mut last = self.last.lock().unwrap();
+        if *last == Some(state) {
+            return false;
+        }
+        *last = Some(state);
+        true
+    }
+
+    fn notify(&self, state: RTCPeerConnectionState) {
+        if !self.should_send(state) {
+            return;
+        }
+        let payload = MediaStatePayload {
+            role: self.role.to_string(),
+            state: state.to_string(),
+        };
+        if let Ok(payload) = serde_json::to_value(payload) {
+            let _ = self
+                .client
+                .send(SignalingMessage::new(msg_types::MEDIA_STATE, payload));
+        }
+    }
+}
 
-        // Register audio codec (Opus)
-        media_engine.register_codec(
+fn peer_state(pc: &RTCPeerConnection) -> PeerStatePayload {
+    PeerStatePayload {
+        connection_state: pc.connection_state().to_string(),
+        ice_connection_state: pc.ice_connection_state().to_string(),
+    }
+}
+
+/// Hands out RTP payload types: a codec keeps its usual one when it's free,
+/// otherwise it takes the next free dynamic type (96-127, then 35-63)
+#[derive(Default)]
+struct PayloadTypes {
+    used: HashSet<u8>,
+}
+
+impl PayloadTypes {
+    fn allocate(&mut self, preferred: u8) -> Result<u8> {
+        let pt = std::iter::once(preferred)
+            .chain(96..=127)
+            .chain(35..=63)
+            .find(|pt| !self.used.contains(pt))
+            .ok_or_else(|| AppError::WebRtcError("No free RTP payload type".to_string()))?;
+        self.used.insert(pt);
+        Ok(pt)
+    }
+}
+
+/// Codecs the SFU negotiates, in registration (preference) order
+fn codec_set(opus_red: bool) -> Result<Vec<(RTCRtpCodecParameters, RTPCodecType)>> {
+    let mut payload_types = PayloadTypes::default();
+    let opus_pt = payload_types.allocate(111)?;
+    let vp8_pt = payload_types.allocate(96)?;
+    let h264_pt = payload_types.allocate(102)?;
+    let mut codecs = Vec::new();
+
+    // Opus RED (RFC 2198): each packet also carries the previous frames, so
+    // single losses are recovered at the cost of extra bitrate. Registered
+    // before plain Opus to be preferred by peers that support it; forwarders
+    // relay the RED payload untouched.
+    if opus_red {
+        codecs.push((
             RTCRtpCodecParameters {
                 capability: RTCRtpCodecCapability {
-                    mime_type: MIME_TYPE_OPUS.to_owned(),
+                    mime_type: MIME_TYPE_RED.to_owned(),
                     clock_rate: 48000,
                     channels: 2,
-                    sdp_fmtp_line: "minptime=10;useinbandfec=1".to_owned(),
+                    sdp_fmtp_line: format!("{0}/{0}", opus_pt),
                     rtcp_feedback: vec![],
                 },
-                payload_type: 111,
+                payload_type: payload_types.allocate(63)?,
                 ..Default::default()
             },
             RTPCodecType::Audio,
-        )?;
+        ));
+    }
 
-        // Register video codec (VP8)
-        media_engine.register_codec(
-            RTCRtpCodecParameters {
-                capability: RTCRtpCodecCapability {
-                    mime_type: MIME_TYPE_VP8.to_owned(),
-                    clock_rate: 90000,
-                    channels: 0,
-                    sdp_fmtp_line: String::new(),
-                    rtcp_feedback: vec![],
-                },
-                payload_type: 96,
-                ..Default::default()
+    // Audio codec (Opus)
+    codecs.push((
+        RTCRtpCodecParameters {
+            capability: RTCRtpCodecCapability {
+                mime_type: MIME_TYPE_OPUS.to_owned(),
+                clock_rate: 48000,
+                channels: 2,
+                sdp_fmtp_line: "minptime=10;useinbandfec=1".to_owned(),
+                rtcp_feedback: vec![],
             },
-            RTPCodecType::Video,
-        )?;
+            payload_type: opus_pt,
+            ..Default::default()
+        },
+        RTPCodecType::Audio,
+    ));
+
+    // Video codec (VP8)
+    codecs.push((
+        RTCRtpCodecParameters {
+            capability: RTCRtpCodecCapability {
+                mime_type: MIME_TYPE_VP8.to_owned(),
+                clock_rate: 90000,
+                channels: 0,
+                sdp_fmtp_line: String::new(),
+                rtcp_feedback: vec![],
+            },
+            payload_type: vp8_pt,
+            ..Default::default()
+        },
+        RTPCodecType::Video,
+    ));
+
+    // H264 (constrained baseline, packetization mode 1), after VP8: used when
+    // a publisher sends it or a subscriber's `preferred_codecs` ranks it first
+    codecs.push((
+        RTCRtpCodecParameters {
+            capability: RTCRtpCodecCapability {
+                mime_type: MIME_TYPE_H264.to_owned(),
+                clock_rate: 90000,
+                channels: 0,
+                sdp_fmtp_line:
+                    "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f"
+                        .to_owned(),
+                rtcp_feedback: vec![],
+            },
+            payload_type: h264_pt,
+            ..Default::default()
+        },
+        RTPCodecType::Video,
+    ));
+
+    Ok(codecs)
+}
+
+/// Refuse to start with two codecs sharing a payload type: peers would
+/// negotiate one and silently mis-decode the other
+fn ensure_unique_payload_types(codecs: &[(RTCRtpCodecParameters, RTPCodecType)]) -> Result<()> {
+    let mut seen: HashMap<u8, &str> = HashMap::new();
+    for (codec, _) in codecs {
+        let mime_type = codec.capability.mime_type.as_str();
+        if let Some(other) = seen.insert(codec.payload_type, mime_type) {
+            return Err(AppError::WebRtcError(format!(
+                "Payload type {} registered for both {} and {}",
+                codec.payload_type, other, mime_type
+            )));
+        }
+    }
+    Ok(())
+}
 
-        // Create interceptor registry
-        let mut registry = Registry::new();
-        registry = register_default_interceptors(registry, &mut media_engine)?;
+/// Build the webrtc API with the codecs the SFU negotiates
+fn build_api(opus_red: bool) -> Result<webrtc::api::API> {
+    // Configure media engine
+    let mut media_engine = MediaEngine::default();
 
-        // Create setting engine
-        let setting_engine = SettingEngine::default();
+    let codecs = codec_set(opus_red)?;
+    ensure_unique_payload_types(&codecs)?;
+    for (codec, kind) in codecs {
+        media_engine.register_codec(codec, kind)?;
+    }
 
-        // Build API
-        let api = APIBuilder::new()
-            .with_media_engine(media_engine)
-            .with_interceptor_registry(registry)
-            .with_setting_engine(setting_engine)
-            .build();
+    // Create interceptor registry
+    let mut registry = Registry::new();
+    registry = register_default_interceptors(registry, &mut media_engine)?;
+
+    // Create setting engine
+    let setting_engine = SettingEngine::default();
+
+    // Build API
+    Ok(APIBuilder::new()
+        .with_media_engine(media_engine)
+        .with_interceptor_registry(registry)
+        .with_setting_engine(setting_engine)
+        .build())
+}
+
+impl MediaGateway {
+    pub fn new(config: &Config) -> Result<Self> {
+        let api = build_api(config.opus_red)?;
 
         // Configure ICE servers
         let mut ice_servers = vec![RTCIceServer {
@@ -136,6 +415,16 @@ impl MediaGateway {
             rooms: DashMap::new(),
             ice_servers,
             api: Arc::new(api),
+            trace_packets: config.media_trace_packets,
+            log_sdp: config.log_sdp,
+            candidate_filters: CandidateFilter::parse_list(&config.ice_candidate_filters)
+                .map_err(|f| AppError::InternalError(format!("Invalid ICE candidate filter: {}", f)))?,
+            codec_preference: config.sdp_codec_preference.clone(),
+            codec_strip: config.sdp_codec_strip.clone(),
+            keyframe_min_interval: Duration::from_millis(config.keyframe_min_interval_ms),
+            max_subscribers_per_feed: config.max_subscribers_per_feed,
+            max_publishers_total: config.max_publishers_total,
+            publishers_creating: AtomicUsize::new(0),
         })
     }
 
@@ -160,6 +449,49 @@ impl MediaGateway {
         }
     }
 
+    /// Apply the configured codec ordering, then the room's codec allowlist, to
+    /// an SDP we hand out (no-op by default). The local description is left as
+    /// negotiated; the remote side picks from the munged list, which is what
+    /// steers the codec actually used.
+    /// `preferred_codecs` (a subscriber's) rank ahead of `SDP_CODEC_PREFERENCE`
+    fn munge_sdp(
+        &self,
+        description: String,
+        allowed_codecs: &[String],
+        preferred_codecs: &[String],
+    ) -> String {
+        let prefer = sdp::merge_codec_preferences(preferred_codecs, &self.codec_preference);
+        let description = if prefer.is_empty() && self.codec_strip.is_empty() {
+            description
+        } else {
+            sdp::munge_codecs(&description, &prefer, &self.codec_strip)
+        };
+        let description = sdp::restrict_codecs(&description, allowed_codecs);
+        ice::filter_sdp_candidates(&self.candidate_filters, &description)
+    }
+
+    /// Whether a client candidate is dropped by the configured filters
+    fn candidate_filtered(&self, room_id: &str, user_id: &str, candidate: &str) -> bool {
+        let filtered = ice::is_filtered(&self.candidate_filters, candidate);
+        if filtered {
+            tracing::debug!(room_id = %room_id, user_id = %user_id, candidate = %candidate, "Dropped filtered ICE candidate");
+        }
+        filtered
+    }
+
+    /// Log an offer/answer under `span` when `log_sdp` is enabled
+    fn log_sdp(&self, span: &tracing::Span, kind: &str, description: &str) {
+        if self.log_sdp {
+            span.in_scope(|| {
+                tracing::debug!(
+                    sdp_kind = kind,
+                    sdp = %sdp::redact_for_log(description, SDP_LOG_MAX_BYTES),
+                    "SDP"
+                )
+            });
+        }
+    }
+
     /// Create a new publisher peer connection
     pub async fn create_publisher(
         &self,
@@ -167,77 +499,99 @@ impl MediaGateway {
         user_id: &str,
         feed_id: &str,
         offer_sdp: &str,
+        allowed_codecs: &[String],
+        client: Option<ClientHandle>,
     ) -> Result<String> {
+        let _creating = self.reserve_publisher(room_id, user_id)?;
         let room = self.get_or_create_room(room_id);
 
+        // Every log emitted for this publisher (callbacks, forwarders) inherits this context
+        let span = tracing::info_span!(
+            "publisher",
+            room_id = %room_id,
+            user_id = %user_id,
+            feed_id = %feed_id
+        );
+
         // Create peer connection
         let peer_connection = Arc::new(self.api.new_peer_connection(self.create_config()).await?);
 
         // Set up track handling
-        let local_tracks: Arc<RwLock<Vec<Arc<TrackLocalStaticRTP>>>> =
-            Arc::new(RwLock::new(Vec::new()));
         let forwarders: Arc<RwLock<Vec<Arc<TrackForwarder>>>> = Arc::new(RwLock::new(Vec::new()));
+        let (tracks_tx, tracks_received) = watch::channel(0usize);
+        let tracks_tx = Arc::new(tracks_tx);
+        let has_audio = Arc::new(AtomicBool::new(false));
+        let has_video = Arc::new(AtomicBool::new(false));
 
-        let local_tracks_clone = local_tracks.clone();
         let forwarders_clone = forwarders.clone();
+        let has_audio_clone = has_audio.clone();
+        let has_video_clone = has_video.clone();
         let room_clone = room.clone();
         let feed_id_clone = feed_id.to_string();
+        let span_clone = span.clone();
+        let trace_packets = self.trace_packets;
 
         // Handle incoming tracks from publisher
         peer_connection.on_track(Box::new(move |track, _receiver, _transceiver| {
-            let local_tracks = local_tracks_clone.clone();
             let forwarders = forwarders_clone.clone();
+            let tracks_tx = tracks_tx.clone();
+            let has_audio = has_audio_clone.clone();
+            let has_video = has_video_clone.clone();
             let _room = room_clone.clone();
             let feed_id = feed_id_clone.clone();
+            let span = span_clone.clone();
 
             Box::pin(async move {
                 tracing::info!(
-                    feed_id = %feed_id,
                     kind = ?track.kind(),
                     codec = %track.codec().capability.mime_type,
                     "Received track from publisher"
                 );
 
-                // Create local track for forwarding
-                let codec = track.codec();
-                let local_track = Arc::new(TrackLocalStaticRTP::new(
-                    codec.capability.clone(),
-                    format!("{}-{}", feed_id, track.kind()),
-                    format!("truegather-{}", feed_id),
-                ));
-
-                // Create forwarder
-                let forwarder = Arc::new(TrackForwarder::new(track.clone(), local_track.clone()));
-
-                // Store tracks
-                {
-                    let mut tracks = local_tracks.write().await;
-                    tracks.push(local_track);
+                match track.kind() {
+                    RTPCodecType::Audio => has_audio.store(true, Ordering::SeqCst),
+                    RTPCodecType::Video => has_video.store(true, Ordering::SeqCst),
+                    _ => {}
                 }
 
-                {
+                // Create forwarder (subscribers attach their own local tracks to it)
+                let forwarder = {
                     let mut fwds = forwarders.write().await;
+                    let ordinal = fwds.iter().filter(|f| f.kind() == track.kind()).count();
+                    let forwarder = Arc::new(TrackForwarder::new(
+                        track.clone(),
+                        &feed_id,
+                        ordinal,
+                        span.clone(),
+                        trace_packets,
+                    ));
                     fwds.push(forwarder.clone());
-                }
+                    tracks_tx.send_replace(fwds.len());
+                    forwarder
+                };
 
                 // Start forwarding
                 forwarder.start().await;
-                tracing::info!(feed_id = %feed_id, kind = ?track.kind(), "Forwarder started for publisher track")
-            })
+                tracing::info!(kind = ?track.kind(), "Forwarder started for publisher track")
+            }
+            .instrument(span))
         }));
 
         // Handle ICE connection state changes
-        let user_id_log = user_id.to_string();
+        let span_state = span.clone();
+        let notifier = client.map(|client| MediaStateNotifier::new(client, "publisher"));
         peer_connection.on_peer_connection_state_change(Box::new(move |state| {
-            tracing::info!(
-                user_id = %user_id_log,
-                state = ?state,
-                "Publisher peer connection state changed"
-            );
+            span_state.in_scope(|| {
+                tracing::info!(state = ?state, "Publisher peer connection state changed");
+            });
+            if let Some(notifier) = &notifier {
+                notifier.notify(state);
+            }
             Box::pin(async {})
         }));
 
         // Set remote description (offer from client)
+        self.log_sdp(&span, "publisher_offer", offer_sdp);
         let offer = RTCSessionDescription::offer(offer_sdp.to_string())?;
         peer_connection.set_remote_description(offer).await?;
 
@@ -257,26 +611,131 @@ impl MediaGateway {
             .await
             .ok_or_else(|| AppError::WebRtcError("No local description".to_string()))?;
 
+        let answer_sdp = self.munge_sdp(local_desc.sdp, allowed_codecs, &[]);
+
         // Store publisher session
         let session = PublisherSession {
             peer_connection: peer_connection.clone(),
             user_id: user_id.to_string(),
             feed_id: feed_id.to_string(),
-            local_tracks: local_tracks.read().await.clone(),
-            forwarders: forwarders.read().await.clone(),
+            forwarders,
+            tracks_received,
+            has_audio,
+            has_video,
+            last_keyframe_request: Mutex::new(None),
+            expected_tracks: sdp::sending_track_count(&sdp::restrict_codecs(offer_sdp, allowed_codecs)),
+            sent_sdp: answer_sdp.clone(),
+            created_at: Instant::now(),
         };
 
-        room.publishers
+        // A publisher resuming within the reconnection grace period replaces
+        // the session its previous connection left behind
+        let replaced = room
+            .publishers
             .insert(user_id.to_string(), Arc::new(RwLock::new(session)));
+        if let Some(previous) = replaced {
+            Self::close_publisher(&previous).await;
+        }
 
-        tracing::info!(
-            room_id = %room_id,
-            user_id = %user_id,
-            feed_id = %feed_id,
-            "Publisher peer connection created"
-        );
+        span.in_scope(|| tracing::info!("Publisher peer connection created"));
 
-        Ok(local_desc.sdp)
+        self.log_sdp(&span, "publisher_answer", &answer_sdp);
+        Ok(answer_sdp)
+    }
+
+    /// Refuse a new publisher past `max_publishers_total`. Publishers still
+    /// negotiating count too, so concurrent offers can't overshoot; one
+    /// replacing the user's own session adds nothing and always gets through.
+    fn reserve_publisher(
+        &self,
+        room_id: &str,
+        user_id: &str,
+    ) -> Result<Option<CreatingPublisher<'_>>> {
+        let replacing = self
+            .rooms
+            .get(room_id)
+            .is_some_and(|room| room.publishers.contains_key(user_id));
+        if self.max_publishers_total == 0 || replacing {
+            return Ok(None);
+        }
+        // Counted before the sessions are, so a publisher moving from
+        // negotiating to stored is never missed
+        let creating = self.publishers_creating.fetch_add(1, Ordering::SeqCst);
+        let reserved = CreatingPublisher(&self.publishers_creating);
+        let (publishers, _) = self.session_totals();
+        if publishers + creating >= self.max_publishers_total {
+            return Err(AppError::ServiceUnavailable(
+                "Server at capacity: no publisher slot left, please retry later".to_string(),
+            ));
+        }
+        Ok(Some(reserved))
+    }
+
+    /// Server candidates of the user's publisher (`subscriber == false`) or
+    /// subscriber connection that the sent SDP doesn't carry, each with the
+    /// feed its section belongs to. Gathering completes before the SDP goes
+    /// out, so this is normally empty; it is the one-shot path for clients
+    /// that can't take trickled `remote_candidate`s.
+    pub async fn unsent_candidates(
+        &self,
+        room_id: &str,
+        user_id: &str,
+        subscriber: bool,
+    ) -> Result<Vec<(String, sdp::SdpCandidate)>> {
+        let room = self
+            .rooms
+            .get(room_id)
+            .ok_or_else(|| AppError::NotFound("Room not found".to_string()))?;
+
+        if subscriber {
+            let session = room
+                .subscribers
+                .get(user_id)
+                .map(|e| e.value().clone())
+                .ok_or_else(|| AppError::NotFound("Subscriber not found".to_string()))?;
+            let session = session.read().await;
+            let current = self.current_local_sdp(&session.peer_connection).await;
+            Ok(sdp::missing_candidates(&current, &session.sent_sdp)
+                .into_iter()
+                .map(|candidate| {
+                    let feed_id = candidate
+                        .sdp_mid
+                        .as_ref()
+                        .and_then(|mid| {
+                            session
+                                .feed_mids
+                                .iter()
+                                .find(|(_, mids)| mids.contains(mid))
+                                .map(|(feed_id, _)| feed_id.clone())
+                        })
+                        .unwrap_or_default();
+                    (feed_id, candidate)
+                })
+                .collect())
+        } else {
+            let session = room
+                .publishers
+                .get(user_id)
+                .map(|e| e.value().clone())
+                .ok_or_else(|| AppError::NotFound("Publisher not found".to_string()))?;
+            let session = session.read().await;
+            let current = self.current_local_sdp(&session.peer_connection).await;
+            Ok(sdp::missing_candidates(&current, &session.sent_sdp)
+                .into_iter()
+                .map(|candidate| (session.feed_id.clone(), candidate))
+                .collect())
+        }
+    }
+
+    /// Local description of a connection with the configured candidate
+    /// filters applied (empty before one is set)
+    async fn current_local_sdp(&self, peer_connection: &RTCPeerConnection) -> String {
+        let description = peer_connection
+            .local_description()
+            .await
+            .map(|d| d.sdp)
+            .unwrap_or_default();
+        ice::filter_sdp_candidates(&self.candidate_filters, &description)
     }
 
     /// Add ICE candidate to publisher peer connection
@@ -288,6 +747,9 @@ impl MediaGateway {
         sdp_mid: Option<&str>,
         sdp_mline_index: Option<u16>,
     ) -> Result<()> {
+        if self.candidate_filtered(room_id, user_id, candidate) {
+            return Ok(());
+        }
         if let Some(room) = self.rooms.get(room_id) {
             if let Some(session) = room.publishers.get(user_id) {
                 let session = session.read().await;
@@ -312,48 +774,104 @@ impl MediaGateway {
         room_id: &str,
         user_id: &str,
         feed_ids: &[String],
+        allowed_codecs: &[String],
+        preferred_codecs: &[String],
+        client: Option<ClientHandle>,
     ) -> Result<String> {
         let room = self
             .rooms
             .get(room_id)
+            .map(|r| r.clone())
             .ok_or_else(|| AppError::NotFound("Room not found".to_string()))?;
 
-        // Create peer connection
-        let peer_connection = Arc::new(self.api.new_peer_connection(self.create_config()).await?);
+        let span = tracing::info_span!(
+            "subscriber",
+            room_id = %room_id,
+            user_id = %user_id,
+            feeds = ?feed_ids
+        );
 
-        // Add tracks from requested publishers
+        // The requested feeds that are published, each with all its tracks
+        let publishers: Vec<Arc<RwLock<PublisherSession>>> =
+            room.publishers.iter().map(|e| e.value().clone()).collect();
+        let mut feeds = Vec::with_capacity(feed_ids.len());
         for feed_id in feed_ids {
             // Find publisher by feed_id
-            for entry in room.publishers.iter() {
-                let session = entry.value().read().await;
-                if session.feed_id == *feed_id {
-                    // Add all local tracks from this publisher
-                    for track in &session.local_tracks {
-                        let rtp_sender = peer_connection
-                            .add_track(Arc::clone(track) as Arc<dyn TrackLocal + Send + Sync>)
-                            .await?;
-
-                        // Handle RTCP packets (for stats, etc.)
-                        tokio::spawn(async move {
-                            let mut rtcp_buf = vec![0u8; 1500];
-                            while let Ok((_, _)) = rtp_sender.read(&mut rtcp_buf).await {
-                                // Process RTCP if needed
-                            }
-                        });
-                    }
+            for publisher in &publishers {
+                let settled = {
+                    let session = publisher.read().await;
+                    (session.feed_id == *feed_id).then(|| session.settled_forwarders())
+                };
+                if let Some(settled) = settled {
+                    feeds.push((feed_id.clone(), publisher.clone(), settled.await));
                     break;
                 }
             }
         }
 
+        // Refuse a full feed before touching the previous subscription, whose
+        // own slots don't count since this subscribe replaces it
+        for (feed_id, publisher, _) in &feeds {
+            let subscribers = Self::subscribers_of(&publisher.read().await, Some(user_id)).await;
+            ensure_feed_capacity(feed_id, subscribers, self.max_subscribers_per_feed)?;
+        }
+
+        // Create peer connection
+        let peer_connection = Arc::new(self.api.new_peer_connection(self.create_config()).await?);
+
+        // A re-subscribe replaces the previous connection's tracks
+        Self::detach_subscriber(&room, user_id).await;
+
+        // Add tracks from requested publishers, counting them per feed to map
+        // the offer's sections back to feeds
+        let mut feed_tracks: Vec<(String, usize)> = Vec::with_capacity(feeds.len());
+        for (feed_id, publisher, forwarders) in feeds {
+            // Checked again under the session's write lock, so subscribes
+            // racing for a feed's last slot can't both take it
+            let session = publisher.write().await;
+            let subscribers = Self::subscribers_of(&session, None).await;
+            let max = self.max_subscribers_per_feed;
+            if let Err(e) = ensure_feed_capacity(&feed_id, subscribers, max) {
+                drop(session);
+                Self::detach_subscriber(&room, user_id).await;
+                let _ = peer_connection.close().await;
+                return Err(e);
+            }
+
+            // Attach a dedicated local track per publisher track, all of the
+            // feed's at once; nothing is forwarded to them until the
+            // subscriber answers
+            feed_tracks.push((feed_id, forwarders.len()));
+            for forwarder in forwarders.iter() {
+                let track = forwarder.add_target(user_id).await;
+                let rtp_sender = peer_connection
+                    .add_track(track as Arc<dyn TrackLocal + Send + Sync>)
+                    .await?;
+
+                // Handle RTCP packets (for stats, etc.)
+                tokio::spawn(
+                    async move {
+                        let mut rtcp_buf = vec![0u8; 1500];
+                        while let Ok((_, _)) = rtp_sender.read(&mut rtcp_buf).await {
+                            // Process RTCP if needed
+                        }
+                    }
+                    .instrument(span.clone()),
+                );
+            }
+        }
+
         // Handle ICE connection state changes
-        let user_id_log = user_id.to_string();
+        let span_state = span.clone();
+        let conn_id = client.as_ref().map(|client| client.conn_id.clone());
+        let notifier = client.map(|client| MediaStateNotifier::new(client, "subscriber"));
         peer_connection.on_peer_connection_state_change(Box::new(move |state| {
-            tracing::info!(
-                user_id = %user_id_log,
-                state = ?state,
-                "Subscriber peer connection state changed"
-            );
+            span_state.in_scope(|| {
+                tracing::info!(state = ?state, "Subscriber peer connection state changed");
+            });
+            if let Some(notifier) = &notifier {
+                notifier.notify(state);
+            }
             Box::pin(async {})
         }));
 
@@ -361,7 +879,9 @@ impl MediaGateway {
         let offer = peer_connection.create_offer(None).await?;
         peer_connection.set_local_description(offer.clone()).await?;
 
-        // Wait for ICE gathering
+        // Wait for ICE gathering: server candidates are embedded in the offer
+        // rather than trickled, so the client never needs a `remote_candidate`
+        // for this connection
         let mut gather_complete = peer_connection.gathering_complete_promise().await;
         let _ = gather_complete.recv().await;
 
@@ -371,65 +891,230 @@ impl MediaGateway {
             .await
             .ok_or_else(|| AppError::WebRtcError("No local description".to_string()))?;
 
+        let feed_mids = sdp::feed_mids_in_order(&feed_tracks, &sdp::section_mids(&local_desc.sdp));
+        let offer_sdp = self.munge_sdp(local_desc.sdp, allowed_codecs, preferred_codecs);
+
         // Store subscriber session
         let session = SubscriberSession {
             peer_connection,
             user_id: user_id.to_string(),
+            conn_id,
             subscribed_feeds: feed_ids.to_vec(),
+            feed_mids,
+            remote_ufrag: None,
+            sent_sdp: offer_sdp.clone(),
+            pending_candidates: Vec::new(),
         };
 
         room.subscribers
             .insert(user_id.to_string(), Arc::new(RwLock::new(session)));
 
-        tracing::info!(
-            room_id = %room_id,
-            user_id = %user_id,
-            feeds = ?feed_ids,
-            "Subscriber peer connection created"
-        );
+        span.in_scope(|| tracing::info!("Subscriber peer connection created"));
 
-        Ok(local_desc.sdp)
+        self.log_sdp(&span, "subscriber_offer", &offer_sdp);
+        Ok(offer_sdp)
     }
 
-    /// Set subscriber answer
+    /// Set subscriber answer, then start forwarding to the subscriber and ask
+    /// the publishers for a keyframe so video starts without waiting for one.
+    /// `reported_mids` are the client's (feed_id, mid) pairs; entries naming a
+    /// feed it didn't subscribe to or a mid absent from the answer are ignored.
     pub async fn set_subscriber_answer(
         &self,
         room_id: &str,
         user_id: &str,
         answer_sdp: &str,
+        reported_mids: &[(String, String)],
     ) -> Result<()> {
-        if let Some(room) = self.rooms.get(room_id) {
-            if let Some(session) = room.subscribers.get(user_id) {
-                let session = session.read().await;
-                let answer = RTCSessionDescription::answer(answer_sdp.to_string())?;
-                session
-                    .peer_connection
-                    .set_remote_description(answer)
-                    .await?;
+        let Some(room) = self.rooms.get(room_id).map(|r| r.clone()) else {
+            return Ok(());
+        };
+        let Some(session) = room.subscribers.get(user_id).map(|s| s.clone()) else {
+            return Ok(());
+        };
+
+        let span = tracing::debug_span!("subscriber", room_id = %room_id, user_id = %user_id);
+        self.log_sdp(&span, "subscriber_answer", answer_sdp);
+
+        let mut session = session.write().await;
+        let answer = RTCSessionDescription::answer(answer_sdp.to_string())?;
+        session
+            .peer_connection
+            .set_remote_description(answer)
+            .await?;
+        session.remote_ufrag = sdp::ice_ufrag(answer_sdp).map(str::to_string);
+
+        for candidate in std::mem::take(&mut session.pending_candidates) {
+            if let (Some(expected), Some(ufrag)) =
+                (&session.remote_ufrag, sdp::candidate_ufrag(&candidate.candidate))
+            {
+                if ufrag != expected {
+                    continue;
+                }
+            }
+            if let Err(e) = session.peer_connection.add_ice_candidate(candidate).await {
+                tracing::warn!(room_id = %room_id, user_id = %user_id, error = %e, "Failed to apply buffered ICE candidate");
             }
         }
+
+        if !reported_mids.is_empty() {
+            let answer_mids = sdp::section_mids(answer_sdp);
+            let mut reported: HashMap<String, Vec<String>> = HashMap::new();
+            for (feed_id, mid) in reported_mids {
+                if session.subscribed_feeds.contains(feed_id) && answer_mids.contains(mid) {
+                    reported.entry(feed_id.clone()).or_default().push(mid.clone());
+                } else {
+                    tracing::debug!(room_id = %room_id, user_id = %user_id, feed_id = %feed_id, mid = %mid, "Ignoring unknown feed mid");
+                }
+            }
+            session.feed_mids.extend(reported);
+        }
+
+        for entry in room.publishers.iter() {
+            let publisher = entry.value().read().await;
+            if !session.subscribed_feeds.contains(&publisher.feed_id) {
+                continue;
+            }
+
+            let mut video_ready = false;
+            for forwarder in publisher.forwarders.read().await.iter() {
+                if forwarder.set_ready(user_id).await && forwarder.kind() == RTPCodecType::Video {
+                    video_ready = true;
+                }
+            }
+            if video_ready {
+                self.request_keyframe_from(&publisher).await;
+            }
+        }
+
+        tracing::debug!(room_id = %room_id, user_id = %user_id, "Subscriber ready, forwarding started");
         Ok(())
     }
 
-    /// Add ICE candidate to subscriber peer connection
+    /// Ask the publisher of `feed_id` for a keyframe (e.g. a subscriber saw a freeze).
+    /// Returns false if the feed is unknown or the request was rate-limited.
+    pub async fn request_keyframe(&self, room_id: &str, feed_id: &str) -> bool {
+        let Some(room) = self.rooms.get(room_id).map(|r| r.clone()) else {
+            return false;
+        };
+
+        for entry in room.publishers.iter() {
+            let publisher = entry.value().read().await;
+            if publisher.feed_id == feed_id {
+                return self.request_keyframe_from(&publisher).await;
+            }
+        }
+        false
+    }
+
+    /// Periodic freeze recovery: request a keyframe from every publisher that
+    /// has at least one subscriber attached
+    pub async fn request_keyframes_all(&self) {
+        let rooms: Vec<Arc<RoomMedia>> = self.rooms.iter().map(|r| r.value().clone()).collect();
+        for room in rooms {
+            for entry in room.publishers.iter() {
+                let publisher = entry.value().read().await;
+                let mut watched = false;
+                for forwarder in publisher.forwarders.read().await.iter() {
+                    if forwarder.kind() == RTPCodecType::Video && forwarder.target_count(None).await > 0 {
+                        watched = true;
+                    }
+                }
+                if watched {
+                    self.request_keyframe_from(&publisher).await;
+                }
+            }
+        }
+    }
+
+    /// Send a PLI for each of the publisher's video tracks, at most once per
+    /// `keyframe_min_interval` so subscribers can't trigger a PLI storm
+    async fn request_keyframe_from(&self, publisher: &PublisherSession) -> bool {
+        {
+            let mut last = publisher
+                .last_keyframe_request
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            if last.is_some_and(|at| now.duration_since(at) < self.keyframe_min_interval) {
+                return false;
+            }
+            *last = Some(now);
+        }
+
+        for forwarder in publisher.forwarders.read().await.iter() {
+            if forwarder.kind() != RTPCodecType::Video {
+                continue;
+            }
+            let pli: Box<dyn RtcpPacket + Send + Sync> = Box::new(PictureLossIndication {
+                sender_ssrc: 0,
+                media_ssrc: forwarder.ssrc(),
+            });
+            if let Err(e) = publisher.peer_connection.write_rtcp(&[pli]).await {
+                tracing::debug!(
+                    user_id = %publisher.user_id,
+                    error = %e,
+                    "Failed to send keyframe request"
+                );
+            }
+        }
+        true
+    }
+
+    /// Remove a subscriber's targets from every publisher forwarder in the room
+    async fn detach_subscriber(room: &RoomMedia, user_id: &str) {
+        for entry in room.publishers.iter() {
+            let publisher = entry.value().read().await;
+            for forwarder in publisher.forwarders.read().await.iter() {
+                forwarder.remove_target(user_id).await;
+            }
+        }
+    }
+
+    /// Add ICE candidate to subscriber peer connection.
+    ///
+    /// Each user has a single subscriber peer connection carrying every
+    /// subscribed feed over one BUNDLE transport, so candidates are keyed by
+    /// user only; there is no per-feed transport to pick.
     pub async fn add_ice_candidate_subscriber(
         &self,
         room_id: &str,
         user_id: &str,
-        _feed_id: &str,
         candidate: &str,
         sdp_mid: Option<&str>,
         sdp_mline_index: Option<u16>,
     ) -> Result<()> {
+        if self.candidate_filtered(room_id, user_id, candidate) {
+            return Ok(());
+        }
         if let Some(room) = self.rooms.get(room_id) {
             if let Some(session) = room.subscribers.get(user_id) {
-                let session = session.read().await;
+                let mut session = session.write().await;
+                if let (Some(expected), Some(ufrag)) =
+                    (&session.remote_ufrag, sdp::candidate_ufrag(candidate))
+                {
+                    if ufrag != expected {
+                        tracing::debug!(room_id = %room_id, user_id = %user_id, "Dropping candidate of a replaced subscriber connection");
+                        return Ok(());
+                    }
+                }
                 let ice_candidate = RTCIceCandidateInit {
                     candidate: candidate.to_string(),
                     sdp_mid: sdp_mid.map(|s| s.to_string()),
                     sdp_mline_index,
                     ..Default::default()
                 };
+                // The client may trickle before its answer reaches us
+                if session.peer_connection.remote_description().await.is_none() {
+                    if session.pending_candidates.len() >= MAX_PENDING_CANDIDATES {
+                        return Err(AppError::BadRequest(format!(
+                            "At most {} ICE candidates may be sent before the subscribe answer",
+                            MAX_PENDING_CANDIDATES
+                        )));
+                    }
+                    session.pending_candidates.push(ice_candidate);
+                    return Ok(());
+                }
                 session
                     .peer_connection
                     .add_ice_candidate(ice_candidate)
@@ -439,19 +1124,54 @@ impl MediaGateway {
         Ok(())
     }
 
-    /// Remove a publisher
-    pub async fn remove_publisher(&self, room_id: &str, user_id: &str) {
-        if let Some(room) = self.rooms.get(room_id) {
-            if let Some((_, session)) = room.publishers.remove(user_id) {
-                let session = session.read().await;
+    /// Whether the user currently has a publisher session in the room
+    pub fn has_publisher(&self, room_id: &str, user_id: &str) -> bool {
+        self.rooms
+            .get(room_id)
+            .is_some_and(|room| room.publishers.contains_key(user_id))
+    }
 
-                // Stop forwarders
-                for forwarder in &session.forwarders {
-                    forwarder.stop().await;
+    /// Publishers whose tracks have all been silent for at least `timeout`.
+    /// A muted track (`enabled = false` on the client) keeps sending RTP, so
+    /// only feeds that stopped sending entirely (or never produced a track) qualify.
+    pub async fn inactive_publishers(&self, timeout: Duration) -> Vec<InactivePublisher> {
+        let rooms: Vec<(String, Arc<RoomMedia>)> = self
+            .rooms
+            .iter()
+            .map(|r| (r.key().clone(), r.value().clone()))
+            .collect();
+
+        let mut inactive = Vec::new();
+        for (room_id, room) in rooms {
+            for entry in room.publishers.iter() {
+                let publisher = entry.value().read().await;
+                let forwarders = publisher.forwarders.read().await;
+                let idle = forwarders
+                    .iter()
+                    .map(|f| f.idle_for())
+                    .min()
+                    .unwrap_or_else(|| publisher.created_at.elapsed());
+
+                if idle >= timeout {
+                    inactive.push(InactivePublisher {
+                        room_id: room_id.clone(),
+                        user_id: publisher.user_id.clone(),
+                        feed_id: publisher.feed_id.clone(),
+                        idle,
+                    });
                 }
+            }
+        }
+        inactive
+    }
 
-                // Close peer connection
-                let _ = session.peer_connection.close().await;
+    /// Remove a publisher, returns false if there was none
+    pub async fn remove_publisher(&self, room_id: &str, user_id: &str) -> bool {
+        let mut removed = false;
+        if let Some(room) = self.rooms.get(room_id) {
+            if let Some((_, session)) = room.publishers.remove(user_id) {
+                removed = true;
+                Self::close_publisher(&session).await;
 
                 tracing::info!(
                     room_id = %room_id,
@@ -460,24 +1180,64 @@ impl MediaGateway {
                 );
             }
         }
+        removed
     }
 
-    /// Remove a subscriber
-    pub async fn remove_subscriber(&self, room_id: &str, user_id: &str, _feed_id: &str) {
-        if let Some(room) = self.rooms.get(room_id) {
-            if let Some((_, session)) = room.subscribers.remove(user_id) {
-                let session = session.read().await;
-
-                // Close peer connection
-                let _ = session.peer_connection.close().await;
+    /// Stop a publisher's forwarders, then close its peer connection
+    async fn close_publisher(session: &RwLock<PublisherSession>) {
+        let session = session.read().await;
+        for forwarder in session.forwarders.read().await.iter() {
+            forwarder.stop().await;
+        }
+        let _ = session.peer_connection.close().await;
+    }
 
-                tracing::info!(
-                    room_id = %room_id,
-                    user_id = %user_id,
-                    "Subscriber removed"
-                );
+    /// Remove a subscriber. With `conn_id`, only if the subscribe came from
+    /// that connection: another connection of the same user may have
+    /// subscribed since, replacing it.
+    pub async fn remove_subscriber(&self, room_id: &str, user_id: &str, conn_id: Option<&str>) {
+        let Some(room) = self.rooms.get(room_id).map(|r| r.clone()) else {
+            return;
+        };
+        let Some(current) = room.subscribers.get(user_id).map(|s| s.clone()) else {
+            return;
+        };
+        if let Some(conn_id) = conn_id {
+            let owner = current.read().await.conn_id.clone();
+            if owner.is_some_and(|owner| owner != conn_id) {
+                return;
             }
         }
+
+        if let Some((_, session)) = room
+            .subscribers
+            .remove_if(user_id, |_, session| Arc::ptr_eq(session, &current))
+        {
+            let session = session.read().await;
+
+            Self::detach_subscriber(&room, user_id).await;
+
+            // Close peer connection
+            let _ = session.peer_connection.close().await;
+
+            tracing::info!(
+                room_id = %room_id,
+                user_id = %user_id,
+                "Subscriber removed"
+            );
+        }
+    }
+
+    /// Feed `user_id` currently publishes, if any
+    pub async fn publisher_feed_id(&self, room_id: &str, user_id: &str) -> Option<String> {
+        let session = self
+            .rooms
+            .get(room_id)?
+            .publishers
+            .get(user_id)
+            .map(|s| s.clone())?;
+        let feed_id = session.read().await.feed_id.clone();
+        Some(feed_id)
     }
 
     /// Clean up a room
@@ -486,7 +1246,7 @@ impl MediaGateway {
             // Close all publisher connections
             for entry in room.publishers.iter() {
                 let session = entry.value().read().await;
-                for forwarder in &session.forwarders {
+                for forwarder in session.forwarders.read().await.iter() {
                     forwarder.stop().await;
                 }
                 let _ = session.peer_connection.close().await;
@@ -502,6 +1262,172 @@ impl MediaGateway {
         }
     }
 
+    /// Tear down all media on server shutdown: every forwarder is stopped
+    /// before any peer connection closes, so no forwarding task is left
+    /// writing into a closed transport. Rooms are removed as they drain.
+    pub async fn shutdown(&self) -> ShutdownSummary {
+        let mut summary = ShutdownSummary::default();
+        let rooms: Vec<Arc<RoomMedia>> = self
+            .room_ids()
+            .iter()
+            .filter_map(|room_id| self.rooms.remove(room_id).map(|(_, room)| room))
+            .collect();
+
+        for room in &rooms {
+            for entry in room.publishers.iter() {
+                let session = entry.value().read().await;
+                for forwarder in session.forwarders.read().await.iter() {
+                    forwarder.stop().await;
+                    summary.forwarders += 1;
+                }
+            }
+        }
+
+        for room in &rooms {
+            for entry in room.publishers.iter() {
+                let _ = entry.value().read().await.peer_connection.close().await;
+                summary.publishers += 1;
+            }
+            for entry in room.subscribers.iter() {
+                let _ = entry.value().read().await.peer_connection.close().await;
+                summary.subscribers += 1;
+            }
+        }
+
+        summary.rooms = rooms.len();
+        summary
+    }
+
+    /// Ids of every room that currently has media state
+    pub fn room_ids(&self) -> Vec<String> {
+        self.rooms.iter().map(|entry| entry.key().clone()).collect()
+    }
+
+    /// (user_id, feed_id) of every publisher the room has media for
+    pub async fn publisher_feeds(&self, room_id: &str) -> Vec<(String, String)> {
+        let Some(room) = self.rooms.get(room_id).map(|r| r.clone()) else {
+            return Vec::new();
+        };
+
+        let mut feeds = Vec::new();
+        for entry in room.publishers.iter() {
+            let publisher = entry.value().read().await;
+            feeds.push((publisher.user_id.clone(), publisher.feed_id.clone()));
+        }
+        feeds
+    }
+
+    /// Number of rooms with media state
+    pub fn room_count(&self) -> usize {
+        self.rooms.len()
+    }
+
+    /// Whether every peer connection in the room has reached a terminal state
+    /// (closed or failed). A room with no sessions at all is also considered dead.
+    pub async fn is_room_dead(&self, room_id: &str) -> bool {
+        let Some(room) = self.rooms.get(room_id).map(|r| r.clone()) else {
+            return false;
+        };
+
+        let mut peer_connections = Vec::new();
+        for entry in room.publishers.iter() {
+            peer_connections.push(entry.value().read().await.peer_connection.clone());
+        }
+        for entry in room.subscribers.iter() {
+            peer_connections.push(entry.value().read().await.peer_connection.clone());
+        }
+
+        peer_connections.iter().all(|pc| {
+            matches!(
+                pc.connection_state(),
+                RTCPeerConnectionState::Closed | RTCPeerConnectionState::Failed
+            )
+        })
+    }
+
+    /// Media kinds received so far from a publisher, as `(has_audio, has_video)`
+    pub async fn publisher_media_kinds(&self, room_id: &str, user_id: &str) -> Option<(bool, bool)> {
+        let session = self.rooms.get(room_id)?.publishers.get(user_id)?.clone();
+        let session = session.read().await;
+        Some((
+            session.has_audio.load(Ordering::SeqCst),
+            session.has_video.load(Ordering::SeqCst),
+        ))
+    }
+
+    /// Connection and ICE states of a user's publisher peer connection
+    pub async fn publisher_peer_state(
+        &self,
+        room_id: &str,
+        user_id: &str,
+    ) -> Option<PeerStatePayload> {
+        let session = self.rooms.get(room_id)?.publishers.get(user_id)?.clone();
+        let session = session.read().await;
+        Some(peer_state(&session.peer_connection))
+    }
+
+    /// Connection and ICE states of a user's subscriber peer connection
+    pub async fn subscriber_peer_state(
+        &self,
+        room_id: &str,
+        user_id: &str,
+    ) -> Option<PeerStatePayload> {
+        let session = self.rooms.get(room_id)?.subscribers.get(user_id)?.clone();
+        let session = session.read().await;
+        Some(peer_state(&session.peer_connection))
+    }
+
+    /// Subscribers a feed is currently sent to. Every subscriber attaches to
+    /// all of the feed's forwarders, so the busiest forwarder has them all.
+    pub async fn feed_subscriber_count(&self, room_id: &str, feed_id: &str) -> usize {
+        let Some(room) = self.rooms.get(room_id).map(|r| r.clone()) else {
+            return 0;
+        };
+        for entry in room.publishers.iter() {
+            let session = entry.value().read().await;
+            if session.feed_id == feed_id {
+                return Self::subscribers_of(&session, None).await;
+            }
+        }
+        0
+    }
+
+    /// Subscriber count of every feed in a room, as `(feed_id, subscribers)`
+    pub async fn feed_subscriber_counts(&self, room_id: &str) -> Vec<(String, usize)> {
+        let Some(room) = self.rooms.get(room_id).map(|r| r.clone()) else {
+            return Vec::new();
+        };
+        let mut counts = Vec::with_capacity(room.publishers.len());
+        for entry in room.publishers.iter() {
+            let session = entry.value().read().await;
+            let subscribers = Self::subscribers_of(&session, None).await;
+            counts.push((session.feed_id.clone(), subscribers));
+        }
+        counts
+    }
+
+    /// Highest subscriber count of any single feed, across rooms
+    pub async fn busiest_feed_subscribers(&self) -> usize {
+        let rooms: Vec<Arc<RoomMedia>> = self.rooms.iter().map(|r| r.value().clone()).collect();
+        let mut busiest = 0;
+        for room in rooms {
+            for entry in room.publishers.iter() {
+                let subscribers = Self::subscribers_of(&entry.value().read().await, None).await;
+                busiest = busiest.max(subscribers);
+            }
+        }
+        busiest
+    }
+
+    /// Subscribers a feed is sent to, leaving out `except` if given
+    async fn subscribers_of(session: &PublisherSession, except: Option<&str>) -> usize {
+        let mut subscribers = 0;
+        for forwarder in session.forwarders.read().await.iter() {
+            subscribers = subscribers.max(forwarder.target_count(except).await);
+        }
+        subscribers
+    }
+
     /// Get publisher count in a room
     pub fn get_publisher_count(&self, room_id: &str) -> usize {
         self.rooms
@@ -518,17 +1444,35 @@ impl MediaGateway {
             .unwrap_or(0)
     }
 
-    /// List publishers for debugging: returns vec of (user_id, feed_id, track_count, forwarder_count)
+    /// Publisher and subscriber sessions across all rooms, as `(publishers, subscribers)`
+    pub fn session_totals(&self) -> (usize, usize) {
+        self.rooms.iter().fold((0, 0), |(publishers, subscribers), room| {
+            (
+                publishers + room.publishers.len(),
+                subscribers + room.subscribers.len(),
+            )
+        })
+    }
+
+    /// List publishers for debugging: returns vec of (user_id, feed_id, track_count, forwarder_count, target_count)
     pub async fn list_publishers(&self, room_id: &str) -> Vec<serde_json::Value> {
         let mut out = Vec::new();
         if let Some(room) = self.rooms.get(room_id) {
             for entry in room.publishers.iter() {
                 let session = entry.value().read().await;
+                let forwarders = session.forwarders.read().await;
+                let mut target_count = 0;
+                for forwarder in forwarders.iter() {
+                    target_count += forwarder.target_count(None).await;
+                }
                 out.push(serde_json::json!({
                     "user_id": session.user_id,
                     "feed_id": session.feed_id,
-                    "track_count": session.local_tracks.len(),
-                    "forwarder_count": session.forwarders.len()
+                    "track_count": forwarders.len(),
+                    "forwarder_count": forwarders.len(),
+                    "target_count": target_count,
+                    "has_audio": session.has_audio.load(Ordering::SeqCst),
+                    "has_video": session.has_video.load(Ordering::SeqCst)
                 }));
             }
         }
@@ -551,3 +1495,466 @@ impl MediaGateway {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_gateway() -> MediaGateway {
+        MediaGateway {
+            rooms: DashMap::new(),
+            ice_servers: Vec::new(),
+            api: Arc::new(build_api(false).unwrap()),
+            trace_packets: false,
+            log_sdp: false,
+            candidate_filters: Vec::new(),
+            codec_preference: Vec::new(),
+            codec_strip: Vec::new(),
+            keyframe_min_interval: Duration::ZERO,
+            max_subscribers_per_feed: 0,
+            max_publishers_total: 0,
+            publishers_creating: AtomicUsize::new(0),
+        }
+    }
+
+    fn publisher_session(
+        gateway: &MediaGateway,
+        room_id: &str,
+        user_id: &str,
+    ) -> Arc<RwLock<PublisherSession>> {
+        let room = gateway.rooms.get(room_id).unwrap();
+        let session = room.publishers.get(user_id).unwrap();
+        session.clone()
+    }
+
+    async fn audio_offer(api: &webrtc::api::API) -> String {
+        let pc = api
+            .new_peer_connection(RTCConfiguration::default())
+            .await
+            .unwrap();
+        pc.add_transceiver_from_kind(RTPCodecType::Audio, None)
+            .await
+            .unwrap();
+        let offer = pc.create_offer(None).await.unwrap().sdp;
+        pc.close().await.unwrap();
+        offer
+    }
+
+    #[test]
+    fn test_codec_payload_types_unique() {
+        for opus_red in [false, true] {
+            let codecs = codec_set(opus_red).unwrap();
+            ensure_unique_payload_types(&codecs).unwrap();
+        }
+
+        // RED points at Opus's payload type
+        let codecs = codec_set(true).unwrap();
+        let opus = codecs
+            .iter()
+            .find(|(c, _)| c.capability.mime_type == MIME_TYPE_OPUS)
+            .unwrap();
+        assert_eq!(codecs[0].0.capability.mime_type, MIME_TYPE_RED);
+        assert_eq!(codecs[0].0.capability.sdp_fmtp_line, "111/111");
+        assert_eq!(opus.0.payload_type, 111);
+
+        let mut clash = codecs.clone();
+        clash[1].0.payload_type = clash[0].0.payload_type;
+        assert!(ensure_unique_payload_types(&clash).is_err());
+    }
+
+    #[test]
+    fn test_feed_capacity() {
+        assert!(ensure_feed_capacity("feed", 99, 100).is_ok());
+        assert!(matches!(
+            ensure_feed_capacity("feed", 100, 100),
+            Err(AppError::Conflict(_))
+        ));
+        // Unlimited
+        assert!(ensure_feed_capacity("feed", 10_000, 0).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_subscriber_preference_reorders_a_real_offer() {
+        let gateway = test_gateway();
+        let pc = gateway
+            .api
+            .new_peer_connection(RTCConfiguration::default())
+            .await
+            .unwrap();
+        pc.add_transceiver_from_kind(RTPCodecType::Video, None)
+            .await
+            .unwrap();
+        let offer = pc.create_offer(None).await.unwrap().sdp;
+        pc.close().await.unwrap();
+
+        // VP8 leads as registered; a subscriber preferring H264 gets it first
+        let first_video_codec = |sdp: &str| {
+            let m_line = sdp.lines().find(|l| l.starts_with("m=video")).unwrap();
+            let pt = m_line.split(' ').nth(3).unwrap().to_string();
+            let prefix = format!("a=rtpmap:{} ", pt);
+            let rtpmap = sdp.lines().find(|l| l.starts_with(&prefix)).unwrap();
+            rtpmap[prefix.len()..].to_string()
+        };
+        assert_eq!(first_video_codec(&offer), "VP8/90000");
+        let munged = gateway.munge_sdp(offer, &[], &["H264".to_string()]);
+        assert_eq!(first_video_codec(&munged), "H264/90000");
+    }
+
+    #[test]
+    fn test_payload_type_falls_back_when_taken() {
+        let mut payload_types = PayloadTypes::default();
+        assert_eq!(payload_types.allocate(96).unwrap(), 96);
+        assert_eq!(payload_types.allocate(96).unwrap(), 97);
+        assert_eq!(payload_types.allocate(111).unwrap(), 111);
+    }
+
+    #[test]
+    fn test_media_state_skips_repeats() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let client = ClientHandle::new(
+            "c".to_string(),
+            "u".to_string(),
+            "r".to_string(),
+            "u".to_string(),
+            false,
+            false,
+            tx,
+        );
+        let notifier = MediaStateNotifier::new(client, "publisher");
+
+        notifier.notify(RTCPeerConnectionState::New);
+        notifier.notify(RTCPeerConnectionState::Connected);
+        notifier.notify(RTCPeerConnectionState::Connected);
+        notifier.notify(RTCPeerConnectionState::Failed);
+        notifier.notify(RTCPeerConnectionState::Closed);
+
+        let mut states = Vec::new();
+        while let Ok(msg) = rx.try_recv() {
+            assert_eq!(msg.msg_type, msg_types::MEDIA_STATE);
+            assert_eq!(msg.payload["role"], "publisher");
+            states.push(msg.payload["state"].as_str().unwrap().to_string());
+        }
+        assert_eq!(states, vec!["connected", "failed"]);
+    }
+
+    #[tokio::test]
+    async fn test_peer_state_reports_connection_and_ice() {
+        let pc = build_api(false)
+            .unwrap()
+            .new_peer_connection(RTCConfiguration::default())
+            .await
+            .unwrap();
+        let expected = PeerStatePayload {
+            connection_state: "new".to_string(),
+            ice_connection_state: "new".to_string(),
+        };
+        assert_eq!(peer_state(&pc), expected);
+
+        pc.close().await.unwrap();
+        assert_eq!(peer_state(&pc).connection_state, "closed");
+    }
+
+    #[tokio::test]
+    async fn test_opus_red_registered_only_when_enabled() {
+        assert!(!audio_offer(&build_api(false).unwrap()).await.contains("red/48000"));
+
+        let offer = audio_offer(&build_api(true).unwrap()).await;
+        assert!(offer.contains("a=rtpmap:63 red/48000/2"));
+        assert!(offer.contains("a=fmtp:63 111/111"));
+    }
+
+    #[tokio::test]
+    async fn test_red_capable_offer_negotiates_red() {
+        let api = build_api(true).unwrap();
+        let offer = audio_offer(&api).await;
+
+        let pc = api
+            .new_peer_connection(RTCConfiguration::default())
+            .await
+            .unwrap();
+        pc.set_remote_description(RTCSessionDescription::offer(offer).unwrap())
+            .await
+            .unwrap();
+        let answer = pc.create_answer(None).await.unwrap().sdp;
+        pc.close().await.unwrap();
+
+        assert!(answer.contains("red/48000/2"));
+        assert!(answer.contains("opus/48000/2"));
+    }
+
+    #[tokio::test]
+    async fn test_republish_replaces_and_closes_the_previous_session() {
+        let gateway = test_gateway();
+        let offer = audio_offer(&gateway.api).await;
+        gateway
+            .create_publisher("room", "user", "feed-1", &offer, &[], None)
+            .await
+            .unwrap();
+        let previous = publisher_session(&gateway, "room", "user");
+        let previous_pc = previous.read().await.peer_connection.clone();
+
+        let offer = audio_offer(&gateway.api).await;
+        gateway
+            .create_publisher("room", "user", "feed-1", &offer, &[], None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            previous_pc.connection_state(),
+            RTCPeerConnectionState::Closed
+        );
+        let current = publisher_session(&gateway, "room", "user");
+        assert!(!Arc::ptr_eq(&previous, &current));
+        assert_eq!(current.read().await.feed_id, "feed-1");
+    }
+
+    /// Publish an Opus + VP8 feed from a loopback client that keeps sending,
+    /// so its tracks reach the gateway with their first packets. Abort the
+    /// task and close the connection when done.
+    async fn publish_live_feed(
+        gateway: &MediaGateway,
+        user_id: &str,
+        feed_id: &str,
+    ) -> (RTCPeerConnection, tokio::task::JoinHandle<()>) {
+        use webrtc::media::Sample;
+        use webrtc::track::track_local::track_local_static_sample::TrackLocalStaticSample;
+
+        let client = gateway
+            .api
+            .new_peer_connection(RTCConfiguration::default())
+            .await
+            .unwrap();
+        let local_track = |mime_type: &str, id: &str| {
+            let capability = RTCRtpCodecCapability {
+                mime_type: mime_type.to_string(),
+                ..Default::default()
+            };
+            Arc::new(TrackLocalStaticSample::new(
+                capability,
+                id.to_string(),
+                "stream".to_string(),
+            ))
+        };
+        let tracks = [
+            local_track(MIME_TYPE_OPUS, "audio"),
+            local_track(MIME_TYPE_VP8, "video"),
+        ];
+        for track in &tracks {
+            client
+                .add_track(track.clone() as Arc<dyn TrackLocal + Send + Sync>)
+                .await
+                .unwrap();
+        }
+
+        let offer = client.create_offer(None).await.unwrap();
+        let mut gathered = client.gathering_complete_promise().await;
+        client.set_local_description(offer).await.unwrap();
+        let _ = gathered.recv().await;
+        let offer = client.local_description().await.unwrap().sdp;
+        let answer = gateway
+            .create_publisher("room", user_id, feed_id, &offer, &[], None)
+            .await
+            .unwrap();
+        client
+            .set_remote_description(RTCSessionDescription::answer(answer).unwrap())
+            .await
+            .unwrap();
+
+        let sending = tokio::spawn(async move {
+            loop {
+                for track in &tracks {
+                    let sample = Sample {
+                        data: vec![0u8; 16].into(),
+                        duration: Duration::from_millis(20),
+                        ..Default::default()
+                    };
+                    let _ = track.write_sample(&sample).await;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        });
+        (client, sending)
+    }
+
+    #[tokio::test]
+    async fn test_subscriber_gets_every_track_of_a_fresh_feed() {
+        let gateway = test_gateway();
+        let (client, sending) = publish_live_feed(&gateway, "alice", "feed-a").await;
+
+        // Subscribing straight away waits for both tracks
+        let offer = gateway
+            .create_subscriber("room", "bob", &["feed-a".to_string()], &[], &[], None)
+            .await
+            .unwrap();
+        sending.abort();
+        client.close().await.unwrap();
+
+        assert!(offer.contains("m=audio"));
+        assert!(offer.contains("m=video"));
+        let room = gateway.rooms.get("room").unwrap().clone();
+        let subscriber = room.subscribers.get("bob").unwrap().clone();
+        assert_eq!(subscriber.read().await.feed_mids["feed-a"].len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_feed_cap_holds_for_concurrent_subscribes() {
+        let mut gateway = test_gateway();
+        gateway.max_subscribers_per_feed = 1;
+        let (client, sending) = publish_live_feed(&gateway, "alice", "feed-a").await;
+
+        // Both see a free slot at first; only one may take it
+        let feeds = ["feed-a".to_string()];
+        let (bob, carol) = tokio::join!(
+            gateway.create_subscriber("room", "bob", &feeds, &[], &[], None),
+            gateway.create_subscriber("room", "carol", &feeds, &[], &[], None),
+        );
+        let (holder, refused) = match (bob, carol) {
+            (Ok(_), refused) => ("bob", refused),
+            (refused, Ok(_)) => ("carol", refused),
+            (Err(bob), Err(carol)) => panic!("Both refused: {} / {}", bob, carol),
+        };
+        assert!(matches!(refused, Err(AppError::Conflict(_))));
+        assert_eq!(gateway.feed_subscriber_count("room", "feed-a").await, 1);
+
+        // Re-subscribing replaces the holder's subscription, so its own slot
+        // doesn't count against it
+        gateway
+            .create_subscriber("room", holder, &feeds, &[], &[], None)
+            .await
+            .unwrap();
+        assert_eq!(gateway.feed_subscriber_count("room", "feed-a").await, 1);
+
+        sending.abort();
+        client.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_feed_subscriber_counts_follow_subscribes() {
+        let gateway = test_gateway();
+        let (client, sending) = publish_live_feed(&gateway, "alice", "feed-a").await;
+        let count = |n: usize| vec![("feed-a".to_string(), n)];
+        assert_eq!(gateway.feed_subscriber_counts("room").await, count(0));
+
+        let feeds = ["feed-a".to_string()];
+        for user_id in ["bob", "carol"] {
+            gateway
+                .create_subscriber("room", user_id, &feeds, &[], &[], None)
+                .await
+                .unwrap();
+        }
+        assert_eq!(gateway.feed_subscriber_counts("room").await, count(2));
+
+        gateway.remove_subscriber("room", "bob", None).await;
+        assert_eq!(gateway.feed_subscriber_counts("room").await, count(1));
+
+        sending.abort();
+        client.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_publishers_are_capped_across_rooms() {
+        let mut gateway = test_gateway();
+        gateway.max_publishers_total = 1;
+        let (client, sending) = publish_live_feed(&gateway, "alice", "feed-a").await;
+
+        let offer = audio_offer(&gateway.api).await;
+        let refused = gateway
+            .create_publisher("other-room", "bob", "feed-b", &offer, &[], None)
+            .await;
+        assert!(matches!(refused, Err(AppError::ServiceUnavailable(_))));
+        // Republishing replaces the session and takes no new slot
+        gateway
+            .create_publisher("room", "alice", "feed-a", &offer, &[], None)
+            .await
+            .unwrap();
+
+        sending.abort();
+        client.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_candidates_trickled_before_the_answer_apply_after_it() {
+        use webrtc::stats::StatsReportType;
+
+        let gateway = test_gateway();
+        let (client, sending) = publish_live_feed(&gateway, "alice", "feed-a").await;
+        let offer = gateway
+            .create_subscriber("room", "bob", &["feed-a".to_string()], &[], &[], None)
+            .await
+            .unwrap();
+
+        // The candidate arrives before the answer and is held back
+        let candidate = "candidate:1 1 udp 2130706431 192.0.2.1 50000 typ host";
+        gateway
+            .add_ice_candidate_subscriber("room", "bob", candidate, Some("0"), Some(0))
+            .await
+            .unwrap();
+        let room = gateway.rooms.get("room").unwrap().clone();
+        let subscriber = room.subscribers.get("bob").unwrap().clone();
+        assert_eq!(subscriber.read().await.pending_candidates.len(), 1);
+
+        let viewer = gateway
+            .api
+            .new_peer_connection(RTCConfiguration::default())
+            .await
+            .unwrap();
+        viewer
+            .set_remote_description(RTCSessionDescription::offer(offer).unwrap())
+            .await
+            .unwrap();
+        let answer = viewer.create_answer(None).await.unwrap();
+        viewer.set_local_description(answer.clone()).await.unwrap();
+        gateway
+            .set_subscriber_answer("room", "bob", &answer.sdp, &[])
+            .await
+            .unwrap();
+        assert!(subscriber.read().await.pending_candidates.is_empty());
+
+        // The ICE agent takes remote candidates asynchronously
+        let peer_connection = subscriber.read().await.peer_connection.clone();
+        let mut applied = false;
+        for _ in 0..50 {
+            let stats = peer_connection.get_stats().await;
+            applied = stats.reports.values().any(
+                |report| matches!(report, StatsReportType::RemoteCandidate(c) if c.port == 50000),
+            );
+            if applied {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(applied);
+
+        sending.abort();
+        viewer.close().await.unwrap();
+        client.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_candidates_before_the_answer_are_capped() {
+        let gateway = test_gateway();
+        let (client, sending) = publish_live_feed(&gateway, "alice", "feed-a").await;
+        gateway
+            .create_subscriber("room", "bob", &["feed-a".to_string()], &[], &[], None)
+            .await
+            .unwrap();
+
+        for port in 0..MAX_PENDING_CANDIDATES {
+            let candidate = format!(
+                "candidate:1 1 udp 2130706431 192.0.2.1 {} typ host",
+                50000 + port
+            );
+            gateway
+                .add_ice_candidate_subscriber("room", "bob", &candidate, Some("0"), Some(0))
+                .await
+                .unwrap();
+        }
+        let candidate = "candidate:1 1 udp 2130706431 192.0.2.1 49999 typ host";
+        let refused = gateway
+            .add_ice_candidate_subscriber("room", "bob", candidate, Some("0"), Some(0))
+            .await;
+        assert!(matches!(refused, Err(AppError::BadRequest(_))));
+
+        sending.abort();
+        client.close().await.unwrap();
+    }
+}
@@ -0,0 +1,210 @@
+//! ICE candidate filtering for networks where some candidate kinds only slow
+//! connectivity checks down (mDNS `.local` hosts, IPv6, specific ranges)
+
+use std::net::IpAddr;
+
+/// One configured filter; a candidate matching any filter is dropped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CandidateFilter {
+    /// mDNS-obfuscated host candidates (`<uuid>.local`)
+    Mdns,
+    Ipv4,
+    Ipv6,
+    /// Transport, `udp` or `tcp`
+    Transport(String),
+    /// Candidate type: `host`, `srflx`, `prflx` or `relay`
+    Type(String),
+    /// Address range, e.g. `10.0.0.0/8`
+    Cidr(IpAddr, u8),
+}
+
+impl CandidateFilter {
+    /// Parse a filter: `mdns`, `ipv4`, `ipv6`, `udp`, `tcp`, `host`, `srflx`,
+    /// `prflx`, `relay` or a CIDR range
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim().to_ascii_lowercase();
+        match raw.as_str() {
+            "mdns" => Some(Self::Mdns),
+            "ipv4" => Some(Self::Ipv4),
+            "ipv6" => Some(Self::Ipv6),
+            "udp" | "tcp" => Some(Self::Transport(raw)),
+            "host" | "srflx" | "prflx" | "relay" => Some(Self::Type(raw)),
+            _ => {
+                let (addr, prefix) = raw.split_once('/')?;
+                let addr: IpAddr = addr.parse().ok()?;
+                let prefix: u8 = prefix.parse().ok()?;
+                let max = if addr.is_ipv4() { 32 } else { 128 };
+                (prefix <= max).then_some(Self::Cidr(addr, prefix))
+            }
+        }
+    }
+
+    /// Parse every filter, returning the first invalid entry on failure
+    pub fn parse_list(raw: &[String]) -> Result<Vec<Self>, String> {
+        raw.iter()
+            .map(|f| Self::parse(f).ok_or_else(|| f.clone()))
+            .collect()
+    }
+
+    fn matches(&self, candidate: &Candidate<'_>) -> bool {
+        let ip = candidate.address.parse::<IpAddr>().ok();
+        match self {
+            Self::Mdns => candidate.address.to_ascii_lowercase().ends_with(".local"),
+            Self::Ipv4 => ip.is_some_and(|ip| ip.is_ipv4()),
+            Self::Ipv6 => ip.is_some_and(|ip| ip.is_ipv6()),
+            Self::Transport(t) => candidate.transport.eq_ignore_ascii_case(t),
+            Self::Type(t) => candidate.typ.eq_ignore_ascii_case(t),
+            Self::Cidr(net, prefix) => ip.is_some_and(|ip| in_range(ip, *net, *prefix)),
+        }
+    }
+}
+
+/// The fields of an ICE candidate line the filters look at
+struct Candidate<'a> {
+    transport: &'a str,
+    address: &'a str,
+    typ: &'a str,
+}
+
+/// Parse `[a=]candidate:<foundation> <component> <transport> <priority> <address> <port> typ <type> ...`
+fn parse_candidate(line: &str) -> Option<Candidate<'_>> {
+    let line = line.trim();
+    let line = line.strip_prefix("a=").unwrap_or(line);
+    let mut parts = line.strip_prefix("candidate:")?.split_whitespace();
+
+    let _foundation = parts.next()?;
+    let _component = parts.next()?;
+    let transport = parts.next()?;
+    let _priority = parts.next()?;
+    let address = parts.next()?;
+    let _port = parts.next()?;
+    if parts.next()? != "typ" {
+        return None;
+    }
+    let typ = parts.next()?;
+
+    Some(Candidate {
+        transport,
+        address,
+        typ,
+    })
+}
+
+fn in_range(ip: IpAddr, net: IpAddr, prefix: u8) -> bool {
+    match (ip, net) {
+        (IpAddr::V4(ip), IpAddr::V4(net)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(net) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(net)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(net) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Whether a candidate should be dropped. Lines that don't parse as candidates
+/// (e.g. the empty end-of-candidates marker) are never dropped.
+pub fn is_filtered(filters: &[CandidateFilter], candidate: &str) -> bool {
+    if filters.is_empty() {
+        return false;
+    }
+    parse_candidate(candidate).is_some_and(|c| filters.iter().any(|f| f.matches(&c)))
+}
+
+/// Remove filtered `a=candidate` lines from an SDP
+pub fn filter_sdp_candidates(filters: &[CandidateFilter], sdp: &str) -> String {
+    if filters.is_empty() {
+        return sdp.to_string();
+    }
+    let newline = if sdp.contains("\r\n") { "\r\n" } else { "\n" };
+
+    let mut out = String::with_capacity(sdp.len());
+    for line in sdp.lines() {
+        if line.starts_with("a=candidate:") && is_filtered(filters, line) {
+            tracing::debug!(candidate = %line, "Dropped filtered ICE candidate");
+            continue;
+        }
+        out.push_str(line);
+        out.push_str(newline);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MDNS: &str =
+        "candidate:1 1 udp 2122260223 9b36e1a8-1b0c-4f9d-9a8f-0b0c4f9d9a8f.local 54321 typ host";
+    const V6: &str = "candidate:2 1 udp 2122262783 2001:db8::1 54322 typ host generation 0";
+    const PRIVATE: &str = "a=candidate:3 1 udp 2122194687 10.1.2.3 54323 typ host";
+    const RELAY_TCP: &str =
+        "candidate:4 1 tcp 41885439 203.0.113.7 443 typ relay raddr 0.0.0.0 rport 0";
+
+    fn filters(raw: &[&str]) -> Vec<CandidateFilter> {
+        CandidateFilter::parse_list(&raw.iter().map(|s| s.to_string()).collect::<Vec<_>>()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_filters() {
+        assert_eq!(
+            filters(&["mDNS", " ipv6 ", "tcp", "relay", "10.0.0.0/8"]),
+            vec![
+                CandidateFilter::Mdns,
+                CandidateFilter::Ipv6,
+                CandidateFilter::Transport("tcp".to_string()),
+                CandidateFilter::Type("relay".to_string()),
+                CandidateFilter::Cidr("10.0.0.0".parse().unwrap(), 8),
+            ]
+        );
+        assert_eq!(
+            CandidateFilter::parse_list(&["ipv6".to_string(), "10.0.0.0/33".to_string()]),
+            Err("10.0.0.0/33".to_string())
+        );
+        assert!(CandidateFilter::parse("sctp").is_none());
+    }
+
+    #[test]
+    fn test_no_filters_pass_everything() {
+        for c in [MDNS, V6, PRIVATE, RELAY_TCP] {
+            assert!(!is_filtered(&[], c));
+        }
+    }
+
+    #[test]
+    fn test_filters_match_their_candidates_only() {
+        let mdns = filters(&["mdns"]);
+        assert!(is_filtered(&mdns, MDNS));
+        assert!(!is_filtered(&mdns, V6));
+
+        let v6 = filters(&["ipv6"]);
+        assert!(is_filtered(&v6, V6));
+        assert!(!is_filtered(&v6, PRIVATE));
+
+        let range = filters(&["10.0.0.0/8", "2001:db8::/32"]);
+        assert!(is_filtered(&range, PRIVATE));
+        assert!(is_filtered(&range, V6));
+        assert!(!is_filtered(&range, RELAY_TCP));
+
+        let tcp_relay = filters(&["tcp"]);
+        assert!(is_filtered(&tcp_relay, RELAY_TCP));
+        assert!(!is_filtered(&filters(&["srflx"]), RELAY_TCP));
+
+        // End-of-candidates marker is left alone
+        assert!(!is_filtered(&mdns, ""));
+    }
+
+    #[test]
+    fn test_filter_sdp_candidates() {
+        let sdp = format!(
+            "v=0\r\nm=audio 9 UDP/TLS/RTP/SAVPF 111\r\na=mid:0\r\na={}\r\n{}\r\n",
+            MDNS, PRIVATE
+        );
+        let filtered = filter_sdp_candidates(&filters(&["mdns"]), &sdp);
+        assert!(!filtered.contains(".local"));
+        assert!(filtered.contains("10.1.2.3"));
+        assert!(filtered.contains("a=mid:0\r\n"));
+    }
+}
@@ -1,4 +1,6 @@
 pub mod gateway;
+pub mod ice;
+pub mod sdp;
 pub mod track_forwarder;
 
 pub use gateway::*;
@@ -0,0 +1,658 @@
+//! Small SDP helpers operating on `m=` sections without a full parser
+
+use std::collections::HashMap;
+
+/// One `m=` section of an SDP, borrowed from the original text
+struct MediaSection<'a> {
+    kind: &'a str,
+    port: &'a str,
+    lines: Vec<&'a str>,
+}
+
+/// Split an SDP into its media sections (session-level lines are skipped)
+fn media_sections(sdp: &str) -> Vec<MediaSection<'_>> {
+    let mut sections: Vec<MediaSection<'_>> = Vec::new();
+
+    for line in sdp.lines() {
+        if let Some(rest) = line.strip_prefix("m=") {
+            let mut parts = rest.split_whitespace();
+            sections.push(MediaSection {
+                kind: parts.next().unwrap_or(""),
+                port: parts.next().unwrap_or(""),
+                lines: vec![line],
+            });
+        } else if let Some(section) = sections.last_mut() {
+            section.lines.push(line);
+        }
+    }
+
+    sections
+}
+
+/// Which media kinds an SDP actually sends, as `(has_audio, has_video)`.
+/// A section counts unless its port is 0 (rejected) or its direction is
+/// `recvonly`/`inactive`.
+pub fn sending_media_kinds(sdp: &str) -> (bool, bool) {
+    let mut has_audio = false;
+    let mut has_video = false;
+
+    for section in media_sections(sdp) {
+        let sends = section.port != "0"
+            && !section
+                .lines
+                .iter()
+                .any(|l| *l == "a=recvonly" || *l == "a=inactive");
+
+        match section.kind {
+            "audio" => has_audio |= sends,
+            "video" => has_video |= sends,
+            _ => {}
+        }
+    }
+
+    (has_audio, has_video)
+}
+
+/// Why an SDP can't be forwarded by the gateway, which relies on every
+/// audio/video section sharing one transport: each must carry `a=rtcp-mux`
+/// and, when there are several media sections, all of them must be listed in
+/// an `a=group:BUNDLE` line. Rejected sections (port 0 without
+/// `a=bundle-only`) are ignored. `None` when the SDP is fine.
+pub fn transport_problem(sdp: &str) -> Option<String> {
+    let bundle: Vec<&str> = sdp
+        .lines()
+        .take_while(|l| !l.starts_with("m="))
+        .find_map(|l| l.strip_prefix("a=group:BUNDLE"))
+        .map(|mids| mids.split_whitespace().collect())
+        .unwrap_or_default();
+
+    let sections = media_sections(sdp);
+    let active: Vec<&MediaSection<'_>> = sections
+        .iter()
+        .filter(|s| s.port != "0" || s.lines.contains(&"a=bundle-only"))
+        .collect();
+
+    for section in &active {
+        if matches!(section.kind, "audio" | "video") && !section.lines.contains(&"a=rtcp-mux") {
+            return Some(format!("{} section without a=rtcp-mux", section.kind));
+        }
+    }
+
+    if active.len() > 1 {
+        for section in &active {
+            let mid = section.lines.iter().find_map(|l| l.strip_prefix("a=mid:"));
+            if !mid.is_some_and(|mid| bundle.contains(&mid.trim())) {
+                return Some(format!("{} section not in a BUNDLE group", section.kind));
+            }
+        }
+    }
+
+    None
+}
+
+/// Number of audio/video tracks an offer sends (same rules as
+/// [`sending_media_kinds`]), e.g. 3 for camera + mic + screen-share system audio
+pub fn sending_track_count(sdp: &str) -> usize {
+    media_sections(sdp)
+        .iter()
+        .filter(|section| matches!(section.kind, "audio" | "video"))
+        .filter(|section| {
+            section.port != "0"
+                && !section
+                    .lines
+                    .iter()
+                    .any(|l| *l == "a=recvonly" || *l == "a=inactive")
+        })
+        .count()
+}
+
+/// SDP made fit for logs: ICE credentials are masked and the text is cut to
+/// at most `max_bytes` (on a char boundary) with a marker saying how much was dropped
+pub fn redact_for_log(sdp: &str, max_bytes: usize) -> String {
+    let redacted: Vec<String> = sdp
+        .lines()
+        .map(|line| {
+            let line = line.trim_end_matches('\r');
+            for prefix in ["a=ice-pwd:", "a=ice-ufrag:"] {
+                if line.starts_with(prefix) {
+                    return format!("{}<redacted>", prefix);
+                }
+            }
+            line.to_string()
+        })
+        .collect();
+    let mut out = redacted.join("\n");
+
+    if out.len() > max_bytes {
+        let mut cut = max_bytes;
+        while !out.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        let dropped = out.len() - cut;
+        out.truncate(cut);
+        out.push_str(&format!("\n...[{} bytes truncated]", dropped));
+    }
+    out
+}
+
+/// `a=mid` of every media section that has one, in SDP order
+pub fn section_mids(sdp: &str) -> Vec<String> {
+    media_sections(sdp)
+        .iter()
+        .filter_map(|section| {
+            section
+                .lines
+                .iter()
+                .find_map(|l| l.strip_prefix("a=mid:"))
+                .map(|mid| mid.trim().to_string())
+        })
+        .collect()
+}
+
+/// ICE username fragment (`a=ice-ufrag`) of a description; with BUNDLE every
+/// section shares the first one
+pub fn ice_ufrag(sdp: &str) -> Option<&str> {
+    sdp.lines()
+        .find_map(|l| l.trim().strip_prefix("a=ice-ufrag:"))
+        .map(str::trim)
+}
+
+/// Username fragment a trickled candidate line names (`... ufrag <ufrag>`), if any
+pub fn candidate_ufrag(candidate: &str) -> Option<&str> {
+    let mut tokens = candidate.split_whitespace();
+    tokens.find(|t| *t == "ufrag")?;
+    tokens.next()
+}
+
+/// An `a=candidate` line of a description and the section it belongs to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SdpCandidate {
+    /// The line without its `a=` prefix, as it is trickled (`candidate:...`)
+    pub candidate: String,
+    pub sdp_mid: Option<String>,
+    pub sdp_mline_index: u16,
+}
+
+/// Every `a=candidate` line of a description, in SDP order
+pub fn candidates(sdp: &str) -> Vec<SdpCandidate> {
+    let mut out = Vec::new();
+    for (index, section) in media_sections(sdp).iter().enumerate() {
+        let mid = section
+            .lines
+            .iter()
+            .find_map(|l| l.strip_prefix("a=mid:"))
+            .map(|mid| mid.trim().to_string());
+        for line in &section.lines {
+            if let Some(candidate) = line.trim().strip_prefix("a=") {
+                if candidate.starts_with("candidate:") {
+                    out.push(SdpCandidate {
+                        candidate: candidate.to_string(),
+                        sdp_mid: mid.clone(),
+                        sdp_mline_index: index as u16,
+                    });
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Candidates of `current` that `sent` doesn't carry in the same section
+pub fn missing_candidates(current: &str, sent: &str) -> Vec<SdpCandidate> {
+    let sent = candidates(sent);
+    candidates(current)
+        .into_iter()
+        .filter(|c| !sent.contains(c))
+        .collect()
+}
+
+/// Map feeds to the mids of their sections assuming sections were added in
+/// subscribe order, `tracks` per feed (the fallback when the client reports
+/// no mapping). Sections beyond the known tracks are left unmapped.
+pub fn feed_mids_in_order(feeds: &[(String, usize)], mids: &[String]) -> HashMap<String, Vec<String>> {
+    let mut mids = mids.iter();
+    feeds
+        .iter()
+        .map(|(feed_id, tracks)| {
+            let feed_mids: Vec<String> = mids.by_ref().take(*tracks).cloned().collect();
+            (feed_id.clone(), feed_mids)
+        })
+        .collect()
+}
+
+/// Reorder (and optionally strip) codecs in every audio/video `m=` section.
+///
+/// Payloads whose codec name appears in `prefer` move to the front in that
+/// order; everything else keeps its relative order. RTX payloads rank with
+/// the codec they repair. Codecs listed in `strip` are removed together with
+/// their `rtpmap`/`fmtp`/`rtcp-fb` lines and any RTX payload pointing at them,
+/// unless that would leave a section without payloads. Names are compared
+/// case-insensitively; with both lists empty the SDP is returned untouched.
+pub fn munge_codecs(sdp: &str, prefer: &[String], strip: &[String]) -> String {
+    if prefer.is_empty() && strip.is_empty() {
+        return sdp.to_string();
+    }
+
+    rewrite_sections(sdp, |section| munge_section(section, prefer, strip))
+}
+
+/// A subscriber's codec preference ahead of the server's: `client` names
+/// first, then the remaining `server` ones (compared case-insensitively).
+/// Names absent from the SDP are simply ignored by `munge_codecs`.
+pub fn merge_codec_preferences(client: &[String], server: &[String]) -> Vec<String> {
+    let mut merged = client.to_vec();
+    for name in server {
+        if !merged.iter().any(|c| c.eq_ignore_ascii_case(name)) {
+            merged.push(name.clone());
+        }
+    }
+    merged
+}
+
+/// Keep only the `allowed` codecs (plus RTX payloads repairing them) in every
+/// audio/video `m=` section. A section left without any allowed codec is
+/// rejected (port 0) instead of emptied. Names are compared
+/// case-insensitively; with `allowed` empty the SDP is returned untouched.
+pub fn restrict_codecs(sdp: &str, allowed: &[String]) -> String {
+    if allowed.is_empty() {
+        return sdp.to_string();
+    }
+
+    rewrite_sections(sdp, |section| restrict_section(section, allowed))
+}
+
+/// Whether any audio/video section of the SDP offers one of the `allowed` codecs
+pub fn has_allowed_codec(sdp: &str, allowed: &[String]) -> bool {
+    media_sections(sdp)
+        .iter()
+        .filter(|section| matches!(section.kind, "audio" | "video"))
+        .flat_map(|section| payload_names(&section.lines[1..]).0.into_values())
+        .any(|name| allowed.iter().any(|c| c.eq_ignore_ascii_case(name)))
+}
+
+/// Apply `rewrite` to each `m=` section (session-level lines pass through),
+/// preserving the SDP's line endings
+fn rewrite_sections<F>(sdp: &str, mut rewrite: F) -> String
+where
+    F: FnMut(&[&str]) -> Vec<String>,
+{
+    let eol = if sdp.contains("\r\n") { "\r\n" } else { "\n" };
+    let lines: Vec<&str> = sdp.lines().collect();
+    let mut out: Vec<String> = Vec::with_capacity(lines.len());
+
+    let mut start = lines
+        .iter()
+        .position(|l| l.starts_with("m="))
+        .unwrap_or(lines.len());
+    out.extend(lines[..start].iter().map(|l| l.to_string()));
+
+    while start < lines.len() {
+        let end = lines[start + 1..]
+            .iter()
+            .position(|l| l.starts_with("m="))
+            .map(|i| start + 1 + i)
+            .unwrap_or(lines.len());
+        out.extend(rewrite(&lines[start..end]));
+        start = end;
+    }
+
+    let mut munged = out.join(eol);
+    if sdp.ends_with('\n') {
+        munged.push_str(eol);
+    }
+    munged
+}
+
+/// Split `a=<attr>:<pt> <rest>` into `(pt, rest)`
+fn attr_payload<'a>(line: &'a str, prefix: &str) -> Option<(&'a str, &'a str)> {
+    line.strip_prefix(prefix)?.split_once(' ')
+}
+
+/// Payload type -> value, borrowed from the SDP
+type PayloadMap<'a> = HashMap<&'a str, &'a str>;
+
+/// Codec name per payload type and, for RTX payloads, the payload they repair
+fn payload_names<'a>(lines: &[&'a str]) -> (PayloadMap<'a>, PayloadMap<'a>) {
+    let mut names = PayloadMap::new();
+    let mut apt = PayloadMap::new();
+    for line in lines {
+        if let Some((pt, rest)) = attr_payload(line, "a=rtpmap:") {
+            names.insert(pt, rest.split('/').next().unwrap_or(""));
+        } else if let Some((pt, rest)) = attr_payload(line, "a=fmtp:") {
+            if let Some(target) = rest.split(';').find_map(|p| p.trim().strip_prefix("apt=")) {
+                apt.insert(pt, target);
+            }
+        }
+    }
+    (names, apt)
+}
+
+/// Rebuild a section with payloads `kept`, dropping per-payload attributes of the others
+fn rebuild_section<'a>(lines: &[&str], mut m_parts: Vec<&'a str>, kept: &[&'a str]) -> Vec<String> {
+    let mut out = Vec::with_capacity(lines.len());
+    m_parts.extend(kept);
+    out.push(m_parts.join(" "));
+
+    for line in &lines[1..] {
+        let removed = ["a=rtpmap:", "a=fmtp:", "a=rtcp-fb:"].iter().any(|prefix| {
+            attr_payload(line, prefix).is_some_and(|(pt, _)| pt != "*" && !kept.contains(&pt))
+        });
+        if !removed {
+            out.push(line.to_string());
+        }
+    }
+
+    out
+}
+
+fn munge_section(lines: &[&str], prefer: &[String], strip: &[String]) -> Vec<String> {
+    let unchanged = || lines.iter().map(|l| l.to_string()).collect();
+
+    let mut m_parts: Vec<&str> = lines[0].split_whitespace().collect();
+    if m_parts.len() < 4 || !matches!(m_parts[0], "m=audio" | "m=video") {
+        return unchanged();
+    }
+    let payloads = m_parts.split_off(3);
+    let (names, apt) = payload_names(&lines[1..]);
+
+    let listed = |list: &[String], pt: &str| -> Option<usize> {
+        let name = names.get(pt)?;
+        list.iter().position(|c| c.eq_ignore_ascii_case(name))
+    };
+    let dropped = |pt: &str| {
+        listed(strip, pt).is_some() || apt.get(pt).is_some_and(|&a| listed(strip, a).is_some())
+    };
+
+    let mut kept: Vec<&str> = payloads
+        .iter()
+        .copied()
+        .filter(|&pt| !dropped(pt))
+        .collect();
+    if kept.is_empty() {
+        return unchanged();
+    }
+    kept.sort_by_key(|pt| {
+        let primary = apt.get(pt).copied().unwrap_or(*pt);
+        listed(prefer, primary).unwrap_or(prefer.len())
+    });
+
+    rebuild_section(lines, m_parts, &kept)
+}
+
+fn restrict_section(lines: &[&str], allowed: &[String]) -> Vec<String> {
+    let mut m_parts: Vec<&str> = lines[0].split_whitespace().collect();
+    if m_parts.len() < 4 || !matches!(m_parts[0], "m=audio" | "m=video") {
+        return lines.iter().map(|l| l.to_string()).collect();
+    }
+    let payloads = m_parts.split_off(3);
+    let (names, apt) = payload_names(&lines[1..]);
+
+    let is_allowed = |pt: &str| {
+        names
+            .get(pt)
+            .is_some_and(|name| allowed.iter().any(|c| c.eq_ignore_ascii_case(name)))
+    };
+    let kept: Vec<&str> = payloads
+        .iter()
+        .copied()
+        .filter(|&pt| is_allowed(pt) || apt.get(pt).is_some_and(|&a| is_allowed(a)))
+        .collect();
+
+    if kept.is_empty() {
+        // A rejected section keeps its format list, only the port changes
+        m_parts[1] = "0";
+        return rebuild_section(lines, m_parts, &payloads);
+    }
+
+    rebuild_section(lines, m_parts, &kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AUDIO_VIDEO: &str = "v=0\r\n\
+        o=- 1 2 IN IP4 127.0.0.1\r\n\
+        s=-\r\n\
+        t=0 0\r\n\
+        m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+        a=sendrecv\r\n\
+        a=rtpmap:111 opus/48000/2\r\n\
+        m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+        a=sendonly\r\n\
+        a=rtpmap:96 VP8/90000\r\n";
+
+    #[test]
+    fn test_audio_and_video() {
+        assert_eq!(sending_media_kinds(AUDIO_VIDEO), (true, true));
+    }
+
+    #[test]
+    fn test_missing_candidates_are_those_not_sent() {
+        let sent = "v=0\r\n\
+            m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+            a=mid:0\r\n\
+            a=candidate:1 1 udp 2130706431 10.0.0.1 5000 typ host\r\n\
+            m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+            a=mid:1\r\n";
+        let current = "v=0\r\n\
+            m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+            a=mid:0\r\n\
+            a=candidate:1 1 udp 2130706431 10.0.0.1 5000 typ host\r\n\
+            a=candidate:2 1 udp 1694498815 203.0.113.7 5000 typ srflx raddr 10.0.0.1 rport 5000\r\n\
+            m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+            a=mid:1\r\n\
+            a=candidate:1 1 udp 2130706431 10.0.0.1 5000 typ host\r\n";
+
+        assert!(missing_candidates(sent, sent).is_empty());
+        let missing = missing_candidates(current, sent);
+        assert_eq!(missing.len(), 2);
+        assert!(missing[0].candidate.starts_with("candidate:2 "));
+        assert_eq!(missing[0].sdp_mid.as_deref(), Some("0"));
+        assert_eq!(missing[0].sdp_mline_index, 0);
+        assert_eq!(missing[1].sdp_mid.as_deref(), Some("1"));
+        assert_eq!(missing[1].sdp_mline_index, 1);
+    }
+
+    #[test]
+    fn test_audio_only() {
+        let sdp = "v=0\r\nm=audio 9 UDP/TLS/RTP/SAVPF 111\r\na=sendonly\r\n";
+        assert_eq!(sending_media_kinds(sdp), (true, false));
+    }
+
+    #[test]
+    fn test_screen_share_with_system_audio_counts_every_track() {
+        let sdp = "v=0\r\n\
+            m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+            a=sendonly\r\n\
+            m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+            a=sendonly\r\n\
+            m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+            a=sendonly\r\n\
+            m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+            a=recvonly\r\n";
+        assert_eq!(sending_track_count(sdp), 3);
+        assert_eq!(sending_media_kinds(sdp), (true, true));
+        assert_eq!(sending_track_count(AUDIO_VIDEO), 2);
+    }
+
+    #[test]
+    fn test_recvonly_and_rejected_sections_do_not_count() {
+        let sdp = "v=0\n\
+            m=audio 9 UDP/TLS/RTP/SAVPF 111\n\
+            a=sendrecv\n\
+            m=video 9 UDP/TLS/RTP/SAVPF 96\n\
+            a=recvonly\n\
+            m=video 0 UDP/TLS/RTP/SAVPF 97\n\
+            a=sendonly\n";
+        assert_eq!(sending_media_kinds(sdp), (true, false));
+    }
+
+    const BUNDLED: &str = "v=0\r\n\
+        s=-\r\n\
+        a=group:BUNDLE 0 1\r\n\
+        m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+        a=mid:0\r\n\
+        a=rtcp-mux\r\n\
+        m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+        a=mid:1\r\n\
+        a=rtcp-mux\r\n";
+
+    #[test]
+    fn test_bundled_muxed_sdp_is_accepted() {
+        assert_eq!(transport_problem(BUNDLED), None);
+        // A single section needs no BUNDLE group
+        let single = "v=0\r\nm=audio 9 UDP/TLS/RTP/SAVPF 111\r\na=mid:0\r\na=rtcp-mux\r\n";
+        assert_eq!(transport_problem(single), None);
+    }
+
+    #[test]
+    fn test_non_muxed_or_unbundled_sdp_is_rejected() {
+        let no_mux = BUNDLED.replacen("a=rtcp-mux\r\n", "", 1);
+        assert_eq!(
+            transport_problem(&no_mux).as_deref(),
+            Some("audio section without a=rtcp-mux")
+        );
+
+        let no_bundle = BUNDLED.replace("a=group:BUNDLE 0 1\r\n", "");
+        assert!(transport_problem(&no_bundle).is_some());
+
+        let partial = BUNDLED.replace("BUNDLE 0 1", "BUNDLE 0");
+        assert_eq!(
+            transport_problem(&partial).as_deref(),
+            Some("video section not in a BUNDLE group")
+        );
+
+        // A rejected section is out of the picture
+        let rejected = partial.replace("m=video 9", "m=video 0");
+        assert_eq!(transport_problem(&rejected), None);
+    }
+
+    const VIDEO_CODECS: &str = "v=0\r\n\
+        s=-\r\n\
+        m=video 9 UDP/TLS/RTP/SAVPF 102 103 96 97\r\n\
+        a=mid:0\r\n\
+        a=rtpmap:102 H264/90000\r\n\
+        a=rtcp-fb:102 nack pli\r\n\
+        a=fmtp:102 profile-level-id=42e01f;packetization-mode=1\r\n\
+        a=rtpmap:103 rtx/90000\r\n\
+        a=fmtp:103 apt=102\r\n\
+        a=rtpmap:96 VP8/90000\r\n\
+        a=rtcp-fb:96 nack pli\r\n\
+        a=rtpmap:97 rtx/90000\r\n\
+        a=fmtp:97 apt=96\r\n";
+
+    fn codecs(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_munge_without_preferences_is_identity() {
+        assert_eq!(munge_codecs(VIDEO_CODECS, &[], &[]), VIDEO_CODECS);
+    }
+
+    #[test]
+    fn test_munge_reorders_with_rtx_following_primary() {
+        let sdp = munge_codecs(VIDEO_CODECS, &codecs(&["vp8"]), &[]);
+        assert!(sdp.contains("m=video 9 UDP/TLS/RTP/SAVPF 96 97 102 103\r\n"));
+        assert!(sdp.contains("a=rtpmap:102 H264/90000\r\n"));
+        assert!(sdp.ends_with("\r\n"));
+    }
+
+    #[test]
+    fn test_subscriber_preference_beats_server_default() {
+        let prefer = merge_codec_preferences(&codecs(&["h264"]), &codecs(&["VP8"]));
+        assert_eq!(prefer, ["h264", "VP8"]);
+        let sdp = munge_codecs(VIDEO_CODECS, &prefer, &[]);
+        assert!(sdp.contains("m=video 9 UDP/TLS/RTP/SAVPF 102 103 96 97\r\n"));
+
+        // An unsupported preference falls back to the server's order
+        let prefer = merge_codec_preferences(&codecs(&["AV1"]), &codecs(&["VP8"]));
+        let sdp = munge_codecs(VIDEO_CODECS, &prefer, &[]);
+        assert!(sdp.contains("m=video 9 UDP/TLS/RTP/SAVPF 96 97 102 103\r\n"));
+    }
+
+    #[test]
+    fn test_munge_strips_codec_and_its_rtx() {
+        let sdp = munge_codecs(VIDEO_CODECS, &[], &codecs(&["H264"]));
+        assert!(sdp.contains("m=video 9 UDP/TLS/RTP/SAVPF 96 97\r\n"));
+        assert!(!sdp.contains(":102 "));
+        assert!(!sdp.contains(":103 "));
+        assert!(sdp.contains("a=mid:0\r\n"));
+        assert!(sdp.contains("a=fmtp:97 apt=96\r\n"));
+    }
+
+    #[test]
+    fn test_munge_never_empties_a_section() {
+        let sdp = munge_codecs(VIDEO_CODECS, &[], &codecs(&["H264", "VP8"]));
+        assert_eq!(sdp, VIDEO_CODECS);
+    }
+
+    #[test]
+    fn test_restrict_keeps_allowed_codec_and_its_rtx() {
+        let sdp = restrict_codecs(VIDEO_CODECS, &codecs(&["vp8"]));
+        assert!(sdp.contains("m=video 9 UDP/TLS/RTP/SAVPF 96 97\r\n"));
+        assert!(!sdp.contains(":102 "));
+        assert!(sdp.contains("a=rtcp-fb:96 nack pli\r\n"));
+        assert!(sdp.contains("a=fmtp:97 apt=96\r\n"));
+    }
+
+    #[test]
+    fn test_restrict_rejects_section_without_allowed_codec() {
+        let sdp = restrict_codecs(AUDIO_VIDEO, &codecs(&["opus"]));
+        assert!(sdp.contains("m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n"));
+        assert!(sdp.contains("m=video 0 UDP/TLS/RTP/SAVPF 96\r\n"));
+        assert_eq!(sending_media_kinds(&sdp), (true, false));
+    }
+
+    #[test]
+    fn test_section_mids_and_order_fallback() {
+        let sdp = "v=0\r\n\
+            a=group:BUNDLE 0 1 2\r\n\
+            m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+            a=mid:0\r\n\
+            m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+            a=mid:1\r\n\
+            m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+            a=mid:2\r\n";
+        let mids = section_mids(sdp);
+        assert_eq!(mids, ["0", "1", "2"]);
+
+        let feeds = [("cam".to_string(), 2), ("mic".to_string(), 1)];
+        let map = feed_mids_in_order(&feeds, &mids);
+        assert_eq!(map["cam"], ["0", "1"]);
+        assert_eq!(map["mic"], ["2"]);
+    }
+
+    #[test]
+    fn test_ice_ufrag_of_answer_and_candidate() {
+        let sdp = "v=0\r\na=ice-ufrag:abcd\r\na=ice-pwd:s3cr3t\r\nm=audio 9 UDP/TLS/RTP/SAVPF 111\r\n";
+        assert_eq!(ice_ufrag(sdp), Some("abcd"));
+        assert_eq!(ice_ufrag("v=0\r\n"), None);
+
+        let candidate = "candidate:1 1 udp 2122260223 192.168.1.2 54321 typ host generation 0 ufrag abcd network-id 1";
+        assert_eq!(candidate_ufrag(candidate), Some("abcd"));
+        let bare = "candidate:1 1 udp 2122260223 192.168.1.2 54321 typ host";
+        assert_eq!(candidate_ufrag(bare), None);
+    }
+
+    #[test]
+    fn test_redact_for_log() {
+        let sdp = "v=0\r\na=ice-ufrag:abcd\r\na=ice-pwd:s3cr3t\r\nm=audio 9 UDP/TLS/RTP/SAVPF 111\r\n";
+        let logged = redact_for_log(sdp, 1024);
+        assert!(!logged.contains("s3cr3t"));
+        assert!(!logged.contains("abcd"));
+        assert!(logged.contains("a=ice-pwd:<redacted>"));
+        assert!(logged.contains("m=audio 9"));
+
+        let cut = redact_for_log("v=0\nééééé", 6);
+        assert!(cut.starts_with("v=0\né"));
+        assert!(cut.ends_with("bytes truncated]"));
+    }
+
+    #[test]
+    fn test_has_allowed_codec() {
+        assert!(has_allowed_codec(VIDEO_CODECS, &codecs(&["h264"])));
+        assert!(!has_allowed_codec(VIDEO_CODECS, &codecs(&["opus"])));
+    }
+}
@@ -1,26 +1,142 @@
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
+use chrono::Utc;
+use tokio::sync::RwLock;
+use tracing::Instrument;
+use webrtc::rtp_transceiver::rtp_codec::{RTCRtpCodecCapability, RTPCodecType};
 use webrtc::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
 use webrtc::track::track_local::TrackLocalWriter;
 use webrtc::track::track_remote::TrackRemote;
 
-/// Track forwarder - reads RTP from remote track and writes to local track
+/// One subscriber's copy of a publisher track
+pub struct ForwardTarget {
+    pub subscriber_id: String,
+    pub track: Arc<TrackLocalStaticRTP>,
+    /// Set once the subscriber has applied our offer; packets are held back until then
+    ready: AtomicBool,
+}
+
+impl ForwardTarget {
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::SeqCst)
+    }
+}
+
+/// Track id of a feed's `ordinal`-th (0-based) track of `kind`. The first
+/// keeps the plain `<feed>-<kind>` form; extra tracks of the same kind (system
+/// audio next to the mic during a screen share) get a suffix so ids stay
+/// unique within the feed's stream.
+pub fn forwarder_track_id(feed_id: &str, kind: RTPCodecType, ordinal: usize) -> String {
+    if ordinal == 0 {
+        format!("{}-{}", feed_id, kind)
+    } else {
+        format!("{}-{}-{}", feed_id, kind, ordinal)
+    }
+}
+
+/// Track forwarder - reads RTP from a publisher's remote track and fans it
+/// out to a local track per subscriber
 pub struct TrackForwarder {
     remote_track: Arc<TrackRemote>,
-    local_track: Arc<TrackLocalStaticRTP>,
-    running: AtomicBool,
+    codec: RTCRtpCodecCapability,
+    track_id: String,
+    stream_id: String,
+    targets: Arc<RwLock<Vec<Arc<ForwardTarget>>>>,
+    running: Arc<AtomicBool>,
+    /// Unix millis of the last RTP packet read from the publisher
+    last_rtp_ms: Arc<AtomicU64>,
+    /// Publisher session span, entered by the forwarding task
+    span: tracing::Span,
+    /// Log per-packet errors (very noisy, off unless `MEDIA_TRACE_PACKETS` is set)
+    trace_packets: bool,
 }
 
 impl TrackForwarder {
-    pub fn new(remote_track: Arc<TrackRemote>, local_track: Arc<TrackLocalStaticRTP>) -> Self {
+    /// `ordinal` counts the feed's earlier tracks of the same kind. All of a
+    /// feed's tracks share one stream id so subscribers play them in sync.
+    pub fn new(
+        remote_track: Arc<TrackRemote>,
+        feed_id: &str,
+        ordinal: usize,
+        span: tracing::Span,
+        trace_packets: bool,
+    ) -> Self {
         Self {
+            codec: remote_track.codec().capability,
+            track_id: forwarder_track_id(feed_id, remote_track.kind(), ordinal),
+            stream_id: format!("truegather-{}", feed_id),
             remote_track,
-            local_track,
-            running: AtomicBool::new(false),
+            targets: Arc::new(RwLock::new(Vec::new())),
+            running: Arc::new(AtomicBool::new(false)),
+            last_rtp_ms: Arc::new(AtomicU64::new(now_ms())),
+            span,
+            trace_packets,
         }
     }
 
+    pub fn kind(&self) -> RTPCodecType {
+        self.remote_track.kind()
+    }
+
+    /// SSRC of the publisher's stream (target of keyframe requests)
+    pub fn ssrc(&self) -> u32 {
+        self.remote_track.ssrc()
+    }
+
+    /// Create a (not yet ready) local track for a subscriber, replacing any previous one
+    pub async fn add_target(&self, subscriber_id: &str) -> Arc<TrackLocalStaticRTP> {
+        let track = Arc::new(TrackLocalStaticRTP::new(
+            self.codec.clone(),
+            self.track_id.clone(),
+            self.stream_id.clone(),
+        ));
+
+        let mut targets = self.targets.write().await;
+        targets.retain(|t| t.subscriber_id != subscriber_id);
+        targets.push(Arc::new(ForwardTarget {
+            subscriber_id: subscriber_id.to_string(),
+            track: track.clone(),
+            ready: AtomicBool::new(false),
+        }));
+
+        track
+    }
+
+    /// Stop forwarding to a subscriber
+    pub async fn remove_target(&self, subscriber_id: &str) {
+        self.targets
+            .write()
+            .await
+            .retain(|t| t.subscriber_id != subscriber_id);
+    }
+
+    /// Start sending packets to a subscriber. Returns true if it was waiting.
+    pub async fn set_ready(&self, subscriber_id: &str) -> bool {
+        self.targets
+            .read()
+            .await
+            .iter()
+            .filter(|t| t.subscriber_id == subscriber_id)
+            .any(|t| !t.ready.swap(true, Ordering::SeqCst))
+    }
+
+    /// Time since the publisher last sent RTP on this track (or since the track arrived)
+    pub fn idle_for(&self) -> Duration {
+        Duration::from_millis(now_ms().saturating_sub(self.last_rtp_ms.load(Ordering::Relaxed)))
+    }
+
+    /// Number of subscribers currently attached, leaving out `except` if given
+    pub async fn target_count(&self, except: Option<&str>) -> usize {
+        self.targets
+            .read()
+            .await
+            .iter()
+            .filter(|t| except != Some(t.subscriber_id.as_str()))
+            .count()
+    }
+
     /// Start forwarding RTP packets
     pub async fn start(&self) {
         if self.running.swap(true, Ordering::SeqCst) {
@@ -28,33 +144,49 @@ impl TrackForwarder {
         }
 
         let remote_track = self.remote_track.clone();
-        let local_track = self.local_track.clone();
-        let running = Arc::new(AtomicBool::new(true));
-        let running_clone = running.clone();
-
-        tokio::spawn(async move {
-            while running_clone.load(Ordering::SeqCst) {
-                // Read RTP packet from remote track
-                match remote_track.read_rtp().await {
-                    Ok((rtp_packet, _attributes)) => {
-                        // Write RTP packet to local track for forwarding
-                        if let Err(e) = local_track.write_rtp(&rtp_packet).await {
-                            tracing::trace!(error = %e, "Error writing RTP to local track");
-                            // Don't break on write errors, just continue
+        let targets = self.targets.clone();
+        let running = self.running.clone();
+        let last_rtp_ms = self.last_rtp_ms.clone();
+        let trace_packets = self.trace_packets;
+        let kind = remote_track.kind();
+
+        tokio::spawn(
+            async move {
+                while running.load(Ordering::SeqCst) {
+                    // Read RTP packet from remote track
+                    match remote_track.read_rtp().await {
+                        Ok((rtp_packet, _attributes)) => {
+                            last_rtp_ms.store(now_ms(), Ordering::Relaxed);
+
+                            // Write RTP packet to every subscriber that is ready for it;
+                            // the payload (RED-wrapped Opus included) is relayed as is
+                            for target in targets.read().await.iter().filter(|t| t.is_ready()) {
+                                if let Err(e) = target.track.write_rtp(&rtp_packet).await {
+                                    if trace_packets {
+                                        tracing::trace!(
+                                            error = %e,
+                                            subscriber_id = %target.subscriber_id,
+                                            "Error writing RTP to local track"
+                                        );
+                                    }
+                                    // Don't break on write errors, just continue
+                                }
+                            }
                         }
-                    }
-                    Err(e) => {
-                        // Check if it's just a timeout or if we should stop
-                        if running_clone.load(Ordering::SeqCst) {
-                            tracing::trace!(error = %e, "Error reading RTP from remote track");
+                        Err(e) => {
+                            // Check if it's just a timeout or if we should stop
+                            if running.load(Ordering::SeqCst) {
+                                tracing::debug!(error = %e, "Error reading RTP from remote track");
+                            }
+                            break;
                         }
-                        break;
                     }
                 }
-            }
 
-            tracing::debug!("Track forwarder stopped");
-        });
+                tracing::debug!(kind = ?kind, "Track forwarder stopped");
+            }
+            .instrument(self.span.clone()),
+        );
     }
 
     /// Stop forwarding
@@ -67,3 +199,23 @@ impl TrackForwarder {
         self.running.load(Ordering::SeqCst)
     }
 }
+
+fn now_ms() -> u64 {
+    Utc::now().timestamp_millis().max(0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_track_ids_unique_within_feed() {
+        let mic = forwarder_track_id("feed", RTPCodecType::Audio, 0);
+        let system_audio = forwarder_track_id("feed", RTPCodecType::Audio, 1);
+        let screen = forwarder_track_id("feed", RTPCodecType::Video, 0);
+
+        assert_eq!(mic, "feed-audio");
+        assert_eq!(screen, "feed-video");
+        assert_eq!(system_audio, "feed-audio-1");
+    }
+}
@@ -0,0 +1,101 @@
+//! Counters served at `/metrics` (Prometheus text format) with `METRICS_ENABLED`.
+//!
+//! Counts are per process: each instance reports its own and the scraper sums them.
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Stage an invitation reaches on its way to a join
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InviteStage {
+    /// Issued through `/invite` or `/invite-email`
+    Created,
+    /// Looked up by a guest (`GET .../invite/:token`)
+    Viewed,
+    /// Consumed by a guest join
+    Redeemed,
+    /// Join refused because the typed code was wrong
+    FailedCode,
+    /// Join or `use` refused because the invite expired or ran out of uses
+    Expired,
+}
+
+impl InviteStage {
+    pub const ALL: [InviteStage; 5] = [
+        InviteStage::Created,
+        InviteStage::Viewed,
+        InviteStage::Redeemed,
+        InviteStage::FailedCode,
+        InviteStage::Expired,
+    ];
+
+    /// `stage` label value
+    pub fn as_str(self) -> &'static str {
+        match self {
+            InviteStage::Created => "created",
+            InviteStage::Viewed => "viewed",
+            InviteStage::Redeemed => "redeemed",
+            InviteStage::FailedCode => "failed_code",
+            InviteStage::Expired => "expired",
+        }
+    }
+}
+
+/// Invitation conversion funnel: one counter per [`InviteStage`]
+#[derive(Debug, Default)]
+pub struct InviteFunnel {
+    counts: [AtomicU64; 5],
+}
+
+impl InviteFunnel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, stage: InviteStage) {
+        self.counts[stage as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count(&self, stage: InviteStage) -> u64 {
+        self.counts[stage as usize].load(Ordering::Relaxed)
+    }
+
+    /// Prometheus exposition of the funnel, every stage listed even at 0
+    pub fn render(&self) -> String {
+        let mut out = String::from(
+            "# HELP truegather_invitations_total Invitations reaching each funnel stage\n\
+             # TYPE truegather_invitations_total counter\n",
+        );
+        for stage in InviteStage::ALL {
+            let _ = writeln!(
+                out,
+                "truegather_invitations_total{{stage=\"{}\"}} {}",
+                stage.as_str(),
+                self.count(stage)
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invite_funnel_render() {
+        let funnel = InviteFunnel::new();
+        funnel.record(InviteStage::Created);
+        funnel.record(InviteStage::Created);
+        funnel.record(InviteStage::Viewed);
+        funnel.record(InviteStage::FailedCode);
+
+        let text = funnel.render();
+        assert!(text.contains("# TYPE truegather_invitations_total counter"));
+        assert!(text.contains("truegather_invitations_total{stage=\"created\"} 2\n"));
+        assert!(text.contains("truegather_invitations_total{stage=\"viewed\"} 1\n"));
+        assert!(text.contains("truegather_invitations_total{stage=\"redeemed\"} 0\n"));
+        assert!(text.contains("truegather_invitations_total{stage=\"failed_code\"} 1\n"));
+        assert!(text.contains("truegather_invitations_total{stage=\"expired\"} 0\n"));
+    }
+}
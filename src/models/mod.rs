@@ -7,15 +7,41 @@ pub mod user;
 pub use room::{
     Room,
     RoomInfo,
+    RoomSummary,
     PublisherInfo,
+    sort_publishers_by_join_time,
     RoomStatus,
     CreateRoomRequest,
     CreateRoomResponse,
+    UpdateRoomRequest,
+    UpdateRoomResponse,
+    BatchCreateRoomResult,
+    BatchItemError,
     JoinRequest, // ✅ Option B join request (invite_token+invite_code OR creator_key)
+    CohostRequest,
+    CohostResponse,
+    ModeratorRequest,
+    BanRequest,
+    UnbanRequest,
+    BanResponse,
+    RotateKeyRequest,
+    RotateKeyResponse,
+    TransferHostRequest,
+    TransferHostResponse,
+    LockRoomRequest,
+    LockRoomResponse,
+    RoomConfigResponse,
+    Announcement,
+    AnnouncementLevel,
+    AnnounceRequest,
     RoomInvitation,
     CreateInvitationRequest,
     CreateInvitationResponse,
+    UpdateInvitationRequest,
+    RotateInviteCodeRequest,
+    RotateInviteCodeResponse,
     InvitationInfo,
+    InvitationCountResponse,
     InviteEmailRequest,
     InviteEmailResponse,
 };
@@ -27,12 +53,17 @@ pub use room::{
 pub use user::{
     // ✅ Auth / WS
     Claims,
+    WhoAmIResponse,
     WsSession,
 
     // ✅ Join REST response structures
     JoinResponse,
     IceServer,
+    NetworkHint,
 
     // ✅ If you renamed the "user join" request to avoid collision
     UserJoinRequest,
+
+    // Consistent per-participant tile color
+    participant_color,
 };
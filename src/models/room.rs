@@ -9,6 +9,26 @@ pub struct Room {
     pub created_at: DateTime<Utc>,
     pub max_publishers: u32,
     pub ttl_seconds: u64,
+    /// Human-readable alias (`slug:<slug>` -> room_id), unique while the room lives
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    /// Upstream account subject that created the room (when creation requires auth)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    /// Codecs media may use in this room (empty = whatever the gateway negotiates)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_codecs: Vec<String>,
+    /// At the publisher cap, let the host and co-hosts publish by demoting
+    /// the oldest guest publisher
+    #[serde(default)]
+    pub moderator_preemption: bool,
+    /// Refuse a display name another member of the room already uses
+    #[serde(default)]
+    pub require_unique_display: bool,
+    /// End the room (media, Redis state, every connection) once the host has
+    /// disconnected and not come back within the grace period
+    #[serde(default)]
+    pub end_on_host_leave: bool,
 }
 
 impl Room {
@@ -19,7 +39,39 @@ impl Room {
             created_at: Utc::now(),
             max_publishers,
             ttl_seconds,
+            slug: None,
+            created_by: None,
+            allowed_codecs: Vec::new(),
+            moderator_preemption: false,
+            require_unique_display: false,
+            end_on_host_leave: false,
+        }
+    }
+
+    /// Whether a room nobody has ever joined has sat empty for `timeout`
+    /// seconds since creation (0 = never), so it can go before its TTL.
+    /// Once activated, only the TTL ends it.
+    pub fn unjoined_expired(
+        &self,
+        activated: bool,
+        members: usize,
+        timeout: u64,
+        now: DateTime<Utc>,
+    ) -> bool {
+        let age = (now - self.created_at).num_seconds();
+        timeout > 0 && !activated && members == 0 && age >= timeout as i64
+    }
+
+    /// TTL an extension to `requested_ttl` seconds may actually get so the room
+    /// never outlives `created_at + max_lifetime` (0 = no cap). `None` once the
+    /// lifetime is used up.
+    pub fn capped_ttl(&self, requested_ttl: u64, max_lifetime: u64, now: DateTime<Utc>) -> Option<u64> {
+        if max_lifetime == 0 {
+            return Some(requested_ttl);
         }
+        let deadline = self.created_at + chrono::Duration::seconds(max_lifetime as i64);
+        let remaining = (deadline - now).num_seconds();
+        (remaining > 0).then(|| requested_ttl.min(remaining as u64))
     }
 }
 
@@ -27,10 +79,43 @@ impl Room {
 pub struct RoomInfo {
     pub room_id: String,
     pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_codecs: Vec<String>,
+    #[serde(default)]
+    pub moderator_preemption: bool,
+    #[serde(default)]
+    pub require_unique_display: bool,
+    #[serde(default)]
+    pub end_on_host_leave: bool,
     pub participants: Vec<String>,
     pub publishers: Vec<PublisherInfo>,
     pub status: RoomStatus,
     pub participants_count: usize,
+    /// Members holding a seat (observers don't), out of `max_publishers`
+    pub seats_taken: usize,
+    pub max_publishers: u32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Room entry in listings: counts only, no member or publisher lists
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomSummary {
+    pub room_id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    pub status: RoomStatus,
+    pub participants_count: usize,
+    pub publishers_count: usize,
+    /// Members holding a seat (observers don't), out of `max_publishers`
+    pub seats_taken: usize,
+    pub max_publishers: u32,
     pub created_at: DateTime<Utc>,
 }
 
@@ -40,6 +125,23 @@ pub struct PublisherInfo {
     pub user_id: String,
     pub display: String,
     pub joined_at: DateTime<Utc>,
+    #[serde(default)]
+    pub has_audio: bool,
+    #[serde(default)]
+    pub has_video: bool,
+    /// Published by the host or a co-host (never demoted by preemption)
+    #[serde(default)]
+    pub moderator: bool,
+}
+
+/// Order publishers by when they started publishing (feed id breaks ties),
+/// so every client lays out tiles the same way
+pub fn sort_publishers_by_join_time(publishers: &mut [PublisherInfo]) {
+    publishers.sort_by(|a, b| {
+        a.joined_at
+            .cmp(&b.joined_at)
+            .then_with(|| a.feed_id.cmp(&b.feed_id))
+    });
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -47,6 +149,22 @@ pub struct PublisherInfo {
 pub enum RoomStatus {
     Active,
     Inactive,
+    /// Every seat is taken: guests are turned away until someone leaves
+    Full,
+}
+
+impl RoomStatus {
+    /// Status from the room's occupancy; a room at capacity is `Full` even
+    /// when all it holds are seats (observers alone never fill it)
+    pub fn from_occupancy(members: usize, seats_taken: usize, max_publishers: u32) -> Self {
+        if seats_taken >= max_publishers as usize {
+            RoomStatus::Full
+        } else if members == 0 {
+            RoomStatus::Inactive
+        } else {
+            RoomStatus::Active
+        }
+    }
 }
 
 /// Request to create a room
@@ -55,16 +173,46 @@ pub struct CreateRoomRequest {
     pub name: String,
     #[serde(default = "default_max_publishers")]
     pub max_publishers: u32,
-    #[serde(default = "default_ttl")]
+    /// 0 (or absent) takes the deployment's `ROOM_TTL_SECONDS`
+    #[serde(default)]
     pub ttl_seconds: u64,
+    /// Optional unique alias usable in place of the room_id (e.g. `daily-standup`)
+    #[serde(default)]
+    pub slug: Option<String>,
+    /// Restrict media to these codecs, e.g. `["opus"]` for an audio-only room
+    #[serde(default)]
+    pub allowed_codecs: Vec<String>,
+    /// Hosts/co-hosts may take a full room's oldest guest publisher slot
+    #[serde(default)]
+    pub moderator_preemption: bool,
+    /// Two members may not share a display name
+    #[serde(default)]
+    pub require_unique_display: bool,
+    /// End the room when the host leaves (1:1 or host-led meetings)
+    #[serde(default)]
+    pub end_on_host_leave: bool,
 }
 
 fn default_max_publishers() -> u32 {
     50
 }
 
-fn default_ttl() -> u64 {
-    7200
+/// One item of a batch create, in request order: the created room, or why
+/// that item failed
+#[derive(Debug, Serialize)]
+pub struct BatchCreateRoomResult {
+    pub index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub room: Option<CreateRoomResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<BatchItemError>,
+}
+
+/// Same shape as an error response body
+#[derive(Debug, Serialize)]
+pub struct BatchItemError {
+    pub error: String,
+    pub code: u16,
 }
 
 /// Response after creating a room
@@ -75,11 +223,34 @@ pub struct CreateRoomResponse {
     pub created_at: DateTime<Utc>,
     pub max_publishers: u32,
     pub ttl_seconds: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allowed_codecs: Vec<String>,
+    pub moderator_preemption: bool,
+    pub require_unique_display: bool,
+    pub end_on_host_leave: bool,
 
     /// creator_key returned ONLY once (host device)
     pub creator_key: String,
 }
 
+/// Keep a room alive longer (host only)
+#[derive(Debug, Deserialize)]
+pub struct UpdateRoomRequest {
+    pub creator_key: String,
+    /// New TTL from now (0 = the deployment default)
+    #[serde(default)]
+    pub ttl_seconds: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UpdateRoomResponse {
+    pub room_id: String,
+    /// TTL applied, after the deployment's limits
+    pub ttl_seconds: u64,
+}
+
 /// ✅ Join request for Option B (the only one rooms API uses)
 /// - Guest flow: invite_token + invite_code
 /// - Host flow: creator_key
@@ -96,13 +267,199 @@ pub struct JoinRequest {
     #[serde(default)]
     pub invite_code: Option<String>,
 
+    /// Guest flow (one-click email link): replaces invite_token + invite_code
+    #[serde(default)]
+    pub magic_token: Option<String>,
+
     /// Host flow (creator key stored on host device)
     #[serde(default)]
     pub creator_key: Option<String>,
+
+    /// Stable client identifier (e.g. a hash kept in local storage); user
+    /// ids are minted per join, so bans that should outlive a rejoin use it
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+
+    /// `cellular`, `wifi` or `wired`: tailors the order of `ice_servers` and
+    /// adds an `ice_transport_policy` suggestion (unknown values are ignored)
+    #[serde(default)]
+    pub network_hint: Option<String>,
+}
+
+/// Request to promote a participant to co-host (host only)
+#[derive(Debug, Deserialize)]
+pub struct CohostRequest {
+    pub creator_key: String,
+    pub user_id: String,
+}
+
+/// Credentials for moderation endpoints: the host's creator_key in the body,
+/// or a co-host's JWT in the `Authorization: Bearer` header
+#[derive(Debug, Default, Deserialize)]
+pub struct ModeratorRequest {
+    #[serde(default)]
+    pub creator_key: Option<String>,
+}
+
+/// Response after a co-host change
+#[derive(Debug, Serialize)]
+pub struct CohostResponse {
+    pub room_id: String,
+    pub user_id: String,
+    pub role: String,
+}
+
+/// Ban a participant and/or a client fingerprint (moderators). Banning a
+/// participant also bans the fingerprint they joined with, if any.
+#[derive(Debug, Deserialize)]
+pub struct BanRequest {
+    #[serde(default)]
+    pub creator_key: Option<String>,
+    #[serde(default)]
+    pub user_id: Option<String>,
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+    /// Length of the ban (None or 0 = as long as the room lives)
+    #[serde(default)]
+    pub duration_seconds: Option<u64>,
+}
+
+/// Lift the bans of a participant and/or a client fingerprint (moderators)
+#[derive(Debug, Deserialize)]
+pub struct UnbanRequest {
+    #[serde(default)]
+    pub creator_key: Option<String>,
+    #[serde(default)]
+    pub user_id: Option<String>,
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+}
+
+/// Response after a ban or unban
+#[derive(Debug, Serialize)]
+pub struct BanResponse {
+    pub room_id: String,
+    pub user_id: Option<String>,
+    /// Ban entries added or removed (the user id and fingerprints)
+    pub entries: usize,
+    /// When the ban ends (None = with the room; always None for an unban)
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Request to rotate a room's creator_key
+#[derive(Debug, Deserialize)]
+pub struct RotateKeyRequest {
+    pub creator_key: String,
+    /// Also demote every co-host (their delegation came from the old key holder)
+    #[serde(default)]
+    pub revoke_cohosts: bool,
+}
+
+/// Response after rotation (new creator_key returned once)
+#[derive(Debug, Serialize)]
+pub struct RotateKeyResponse {
+    pub room_id: String,
+    pub creator_key: String,
+}
+
+/// Hand the room over to a new host: the creator_key is replaced and the new
+/// one goes to whoever takes over
+#[derive(Debug, Deserialize)]
+pub struct TransferHostRequest {
+    pub creator_key: String,
+    /// Member taking over, announced to the room in `host_transferred`
+    #[serde(default)]
+    pub user_id: Option<String>,
+}
+
+/// Response after a transfer (new creator_key returned once)
+#[derive(Debug, Serialize)]
+pub struct TransferHostResponse {
+    pub room_id: String,
+    pub creator_key: String,
+}
+
+/// Request to lock/unlock a room (host only)
+#[derive(Debug, Deserialize)]
+pub struct LockRoomRequest {
+    pub creator_key: String,
+}
+
+/// Lock state after a lock/unlock call
+#[derive(Debug, Serialize)]
+pub struct LockRoomResponse {
+    pub room_id: String,
+    pub locked: bool,
+}
+
+/// A room's effective settings: its own flags resolved against the
+/// deployment's limits (`GET /rooms/:id/config`, moderators only)
+#[derive(Debug, Serialize)]
+pub struct RoomConfigResponse {
+    pub room_id: String,
+    pub name: String,
+    pub slug: Option<String>,
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Participant (and publisher) cap of this room
+    pub max_publishers: u32,
+    /// Deployment-wide cap the room's value was clamped to
+    pub max_publishers_per_room: u32,
+    pub ttl_seconds: u64,
+    /// Seconds left before the room expires, if it still has a TTL
+    pub expires_in: Option<u64>,
+    /// Hard cap on the room's life from creation (0 = none)
+    pub max_room_lifetime_seconds: u64,
+    pub locked: bool,
+    /// Codecs media may use (empty = whatever the gateway negotiates)
+    pub allowed_codecs: Vec<String>,
+    /// Server-side codec order applied to every offer/answer
+    pub codec_preference: Vec<String>,
+    pub moderator_preemption: bool,
+    pub require_unique_display: bool,
+    pub end_on_host_leave: bool,
+    /// Feeds one subscriber may receive at once (0 = unlimited)
+    pub max_subscribed_feeds: usize,
+    /// Subscribers one feed may be sent to at once (0 = unlimited)
+    pub max_subscribers_per_feed: usize,
+    /// Live invitations the room may hold (0 = unlimited)
+    pub max_invites_per_room: usize,
+}
+
+/// Severity of a host announcement (drives the client's banner style)
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnouncementLevel {
+    #[default]
+    Info,
+    Warning,
+    Critical,
+}
+
+/// Host-to-room banner; the latest one is kept for late joiners
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Announcement {
+    pub room_id: String,
+    pub text: String,
+    pub level: AnnouncementLevel,
+    pub sent_at: i64,
+}
+
+/// Request to announce something to the whole room (host only)
+#[derive(Debug, Deserialize)]
+pub struct AnnounceRequest {
+    pub creator_key: String,
+    pub text: String,
+    #[serde(default)]
+    pub level: AnnouncementLevel,
 }
 
 /// Room invitation stored in Redis
+///
+/// Decoding goes through [`StoredInvitation`] so invites written by older
+/// versions still load; see `From<StoredInvitation>` for how they are upgraded.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "StoredInvitation")]
 pub struct RoomInvitation {
     pub token: String,
     pub room_id: String,
@@ -117,11 +474,21 @@ pub struct RoomInvitation {
 
     /// ✅ hash of the code that guest must type (never store raw code)
     pub code_hash: String,
+
+    /// Guests joining through this invite watch only (observer role)
+    pub observer: bool,
+
+    /// Funnel timestamps for analytics; `first_viewed_at` is only stamped
+    /// with `METRICS_ENABLED`
+    pub first_viewed_at: Option<DateTime<Utc>>,
+    pub first_redeemed_at: Option<DateTime<Utc>>,
 }
 
 impl RoomInvitation {
-    /// Create a new invitation storing the code hash (Option B)
+    /// Create a new invitation storing the code hash (Option B).
+    /// `token` is issued by `security::sign_invite_token`.
     pub fn new_with_code_hash(
+        token: String,
         room_id: String,
         created_by: String,
         ttl_seconds: u64,
@@ -131,7 +498,7 @@ impl RoomInvitation {
     ) -> Self {
         let now = Utc::now();
         Self {
-            token: Self::generate_token(),
+            token,
             room_id,
             created_by,
             created_at: now,
@@ -140,25 +507,20 @@ impl RoomInvitation {
             uses: 0,
             email,
             code_hash,
+            observer: false,
+            first_viewed_at: None,
+            first_redeemed_at: None,
         }
     }
 
-    fn generate_token() -> String {
-        use rand::Rng;
-        const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
-        let mut rng = rand::rng();
-        (0..24)
-            .map(|_| {
-                let idx = rng.random_range(0..CHARSET.len());
-                CHARSET[idx] as char
-            })
-            .collect()
-    }
-
     /// Invite is valid if:
+    /// - it has a code hash (legacy invites without one are never valid)
     /// - not expired
     /// - max_uses not reached (if max_uses exists)
     pub fn is_valid(&self) -> bool {
+        if self.code_hash.is_empty() {
+            return false;
+        }
         let now = Utc::now();
         if now > self.expires_at {
             return false;
@@ -172,23 +534,112 @@ impl RoomInvitation {
     }
 }
 
+/// Every invitation shape ever written to Redis, with everything but the
+/// identifiers optional
+#[derive(Deserialize)]
+struct StoredInvitation {
+    token: String,
+    room_id: String,
+    #[serde(default)]
+    created_by: Option<String>,
+    #[serde(default)]
+    created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    expires_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    max_uses: Option<u32>,
+    #[serde(default, alias = "used_count")]
+    uses: Option<u32>,
+    #[serde(default)]
+    email: Option<String>,
+    #[serde(default)]
+    code_hash: Option<String>,
+    #[serde(default)]
+    observer: bool,
+    #[serde(default)]
+    first_viewed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    first_redeemed_at: Option<DateTime<Utc>>,
+    /// Per-invite salt from before the server-wide pepper
+    #[serde(default)]
+    code_salt: Option<String>,
+}
+
+impl From<StoredInvitation> for RoomInvitation {
+    /// Upgrade a stored invite. Anything that cannot be checked safely (no
+    /// expiry, no code hash, or a hash made with a legacy per-invite salt)
+    /// loads as an invalid invite instead of failing the whole decode.
+    fn from(stored: StoredInvitation) -> Self {
+        let expires_at = stored.expires_at.unwrap_or_default();
+        let code_hash = match stored.code_salt.as_deref() {
+            Some(salt) if !salt.is_empty() => String::new(),
+            _ => stored.code_hash.unwrap_or_default(),
+        };
+
+        Self {
+            token: stored.token,
+            room_id: stored.room_id,
+            created_by: stored.created_by.unwrap_or_default(),
+            created_at: stored.created_at.unwrap_or(expires_at),
+            expires_at,
+            max_uses: stored.max_uses,
+            uses: stored.uses.unwrap_or(0),
+            email: stored.email,
+            code_hash,
+            observer: stored.observer,
+            first_viewed_at: stored.first_viewed_at,
+            first_redeemed_at: stored.first_redeemed_at,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CreateInvitationRequest {
     #[serde(default = "default_invitation_ttl")]
     pub ttl_seconds: u64,
     pub max_uses: Option<u32>,
+    /// Guests joining with this invite may watch but not publish
+    #[serde(default)]
+    pub observer: bool,
 }
 
 fn default_invitation_ttl() -> u64 {
     86400
 }
 
+/// Extend an existing invitation (host only); omitted fields are left as is
+#[derive(Debug, Deserialize)]
+pub struct UpdateInvitationRequest {
+    pub creator_key: String,
+    /// New lifetime counted from now
+    #[serde(default)]
+    pub ttl_seconds: Option<u64>,
+    #[serde(default)]
+    pub max_uses: Option<u32>,
+}
+
+/// Regenerate an invitation's code, keeping its token (host only)
+#[derive(Debug, Deserialize)]
+pub struct RotateInviteCodeRequest {
+    pub creator_key: String,
+}
+
+/// The new plaintext code is returned only in this response
+#[derive(Debug, Serialize)]
+pub struct RotateInviteCodeResponse {
+    pub token: String,
+    pub room_id: String,
+    pub invite_code: String,
+    pub expires_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct CreateInvitationResponse {
     pub token: String,
     pub room_id: String,
     pub expires_at: DateTime<Utc>,
     pub max_uses: Option<u32>,
+    pub observer: bool,
     pub invite_url: String,
 }
 
@@ -201,6 +652,14 @@ pub struct InvitationInfo {
     pub is_valid: bool,
 }
 
+/// GET /rooms/:id/invites/count response
+#[derive(Debug, Serialize)]
+pub struct InvitationCountResponse {
+    pub room_id: String,
+    /// Invitations that haven't expired
+    pub count: usize,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct InviteEmailRequest {
     pub emails: Vec<String>,
@@ -212,12 +671,161 @@ pub struct InviteEmailRequest {
     pub subject: Option<String>,
     #[serde(default)]
     pub message: Option<String>,
+    /// Guests joining with this invite may watch but not publish
+    #[serde(default)]
+    pub observer: bool,
 }
 
 #[derive(Debug, Serialize)]
 pub struct InviteEmailResponse {
     pub sent: u32,
+    /// Recipients whose email could not be sent; the others got theirs
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failed: Vec<String>,
     pub token: String,
     pub invite_url: String,
     pub room_id: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_room_ttl_capped_at_max_lifetime() {
+        let room = Room::new("cap".to_string(), 4, 3600);
+        let at = |secs: i64| room.created_at + chrono::Duration::seconds(secs);
+
+        // No cap configured
+        assert_eq!(room.capped_ttl(3600, 0, at(100_000)), Some(3600));
+        // Plenty of lifetime left
+        assert_eq!(room.capped_ttl(3600, 7200, at(0)), Some(3600));
+        // Only the rest of the lifetime is granted
+        assert_eq!(room.capped_ttl(3600, 7200, at(5400)), Some(1800));
+        assert_eq!(room.capped_ttl(3600, 7200, at(7199)), Some(1));
+        // Boundary and beyond: no more extensions
+        assert_eq!(room.capped_ttl(3600, 7200, at(7200)), None);
+        assert_eq!(room.capped_ttl(3600, 7200, at(9000)), None);
+    }
+
+    #[test]
+    fn test_unjoined_room_expires_after_timeout() {
+        let room = Room::new("speculative".to_string(), 4, 7200);
+        let at = |secs: i64| room.created_at + chrono::Duration::seconds(secs);
+
+        assert!(!room.unjoined_expired(false, 0, 600, at(599)));
+        assert!(room.unjoined_expired(false, 0, 600, at(600)));
+        // Disabled
+        assert!(!room.unjoined_expired(false, 0, 0, at(100_000)));
+        // Joined once (or someone is in it): the TTL rules apply
+        assert!(!room.unjoined_expired(true, 0, 600, at(600)));
+        assert!(!room.unjoined_expired(false, 1, 600, at(600)));
+    }
+
+    #[test]
+    fn test_room_at_capacity_is_full() {
+        assert_eq!(RoomStatus::from_occupancy(0, 0, 4), RoomStatus::Inactive);
+        assert_eq!(RoomStatus::from_occupancy(3, 3, 4), RoomStatus::Active);
+        assert_eq!(RoomStatus::from_occupancy(4, 4, 4), RoomStatus::Full);
+        // Observers don't take seats
+        assert_eq!(RoomStatus::from_occupancy(6, 3, 4), RoomStatus::Active);
+        assert_eq!(
+            serde_json::to_value(RoomStatus::Full).unwrap(),
+            serde_json::json!("full")
+        );
+    }
+
+    fn publisher(feed_id: &str, joined_at: DateTime<Utc>) -> PublisherInfo {
+        PublisherInfo {
+            feed_id: feed_id.to_string(),
+            user_id: feed_id.to_string(),
+            display: feed_id.to_string(),
+            joined_at,
+            has_audio: true,
+            has_video: true,
+            moderator: false,
+        }
+    }
+
+    #[test]
+    fn test_publishers_sorted_by_join_time() {
+        let t0 = Utc::now();
+        let later = |s: i64| t0 + chrono::Duration::seconds(s);
+        let mut publishers = vec![
+            publisher("c", later(20)),
+            publisher("b", later(5)),
+            publisher("a", later(20)),
+            publisher("d", t0),
+        ];
+
+        sort_publishers_by_join_time(&mut publishers);
+
+        let order: Vec<&str> = publishers.iter().map(|p| p.feed_id.as_str()).collect();
+        assert_eq!(order, ["d", "b", "a", "c"]);
+    }
+
+    #[test]
+    fn test_display_names_not_unique_by_default() {
+        assert!(!Room::new("open".to_string(), 4, 60).require_unique_display);
+
+        // Rooms stored before the setting existed
+        let stored = serde_json::json!({
+            "room_id": "room",
+            "name": "old",
+            "created_at": Utc::now(),
+            "max_publishers": 4,
+            "ttl_seconds": 60,
+        });
+        let room: Room = serde_json::from_value(stored).unwrap();
+        assert!(!room.require_unique_display);
+    }
+
+    #[test]
+    fn test_current_invitation_round_trips() {
+        let invitation = RoomInvitation::new_with_code_hash(
+            "tok".to_string(),
+            "room".to_string(),
+            "host".to_string(),
+            60,
+            Some(3),
+            None,
+            "abc".to_string(),
+        );
+        let json = serde_json::to_string(&invitation).unwrap();
+        let decoded: RoomInvitation = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.code_hash, "abc");
+        assert_eq!(decoded.max_uses, Some(3));
+        assert!(decoded.is_valid());
+    }
+
+    #[test]
+    fn test_legacy_used_count_and_missing_email_are_upgraded() {
+        let json = r#"{"token":"tok","room_id":"room","created_by":"host",
+            "created_at":"2024-01-01T00:00:00Z","expires_at":"2999-01-01T00:00:00Z",
+            "max_uses":2,"used_count":2,"code_hash":"abc"}"#;
+        let invitation: RoomInvitation = serde_json::from_str(json).unwrap();
+        assert_eq!(invitation.uses, 2);
+        assert_eq!(invitation.email, None);
+        assert!(!invitation.is_valid());
+    }
+
+    #[test]
+    fn test_legacy_invites_without_security_fields_load_as_invalid() {
+        // No code hash at all
+        let json = r#"{"token":"a","room_id":"room","expires_at":"2999-01-01T00:00:00Z"}"#;
+        let invitation: RoomInvitation = serde_json::from_str(json).unwrap();
+        assert_eq!(invitation.uses, 0);
+        assert!(!invitation.is_valid());
+
+        // Hash made with a per-invite salt we can no longer verify
+        let json = r#"{"token":"b","room_id":"room","expires_at":"2999-01-01T00:00:00Z",
+            "code_salt":"s4lt","code_hash":"abc"}"#;
+        let invitation: RoomInvitation = serde_json::from_str(json).unwrap();
+        assert!(!invitation.is_valid());
+
+        // No expiry
+        let json = r#"{"token":"c","room_id":"room","code_hash":"abc"}"#;
+        let invitation: RoomInvitation = serde_json::from_str(json).unwrap();
+        assert!(!invitation.is_valid());
+    }
+}
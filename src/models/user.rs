@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 /// Request to join a room
 #[derive(Debug, Deserialize)]
@@ -17,6 +18,39 @@ pub struct MemberInfo {
     pub user_id: String,
     pub display: String,
     pub joined_at: i64,
+    /// See [`participant_color`]; derived on read, not stored
+    #[serde(default)]
+    pub color: String,
+    /// Watch-only participant (never publishes)
+    #[serde(default)]
+    pub observer: bool,
+}
+
+/// Tile/avatar color for a participant, identical on every client.
+///
+/// Scheme: hue = first two bytes of SHA-256(user_id) as a big-endian u16,
+/// modulo 360; saturation 65%, lightness 45%; returned as `#rrggbb`.
+pub fn participant_color(user_id: &str) -> String {
+    let digest = Sha256::digest(user_id.as_bytes());
+    let hue = u16::from_be_bytes([digest[0], digest[1]]) % 360;
+    let (r, g, b) = hsl_to_rgb(hue as f64, 0.65, 0.45);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn hsl_to_rgb(h: f64, s: f64, l: f64) -> (u8, u8, u8) {
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let x = c * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
+    let m = l - c / 2.0;
+    let (r, g, b) = match h as u16 {
+        0..=59 => (c, x, 0.0),
+        60..=119 => (x, c, 0.0),
+        120..=179 => (0.0, c, x),
+        180..=239 => (0.0, x, c),
+        240..=299 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let channel = |v: f64| ((v + m) * 255.0).round() as u8;
+    (channel(r), channel(g), channel(b))
 }
 
 /// Response after joining a room
@@ -27,6 +61,10 @@ pub struct JoinResponse {
     pub ws_url: String,
     pub token: String,
     pub ice_servers: Vec<IceServer>,
+    /// Suggested `iceTransportPolicy` for the client's `network_hint`
+    /// ("relay" or "all"); absent when no hint was given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ice_transport_policy: Option<String>,
     pub expires_in: u64,
     pub participants: Vec<MemberInfo>,
 }
@@ -41,6 +79,57 @@ pub struct IceServer {
     pub credential: Option<String>,
 }
 
+impl IceServer {
+    fn is_turn(&self) -> bool {
+        self.urls
+            .iter()
+            .any(|u| u.starts_with("turn:") || u.starts_with("turns:"))
+    }
+}
+
+/// Network the client says it is on (`network_hint` at join); advisory only
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkHint {
+    Cellular,
+    Wifi,
+    Wired,
+}
+
+impl NetworkHint {
+    /// Case-insensitive; unknown hints are ignored rather than rejected
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "cellular" => Some(NetworkHint::Cellular),
+            "wifi" => Some(NetworkHint::Wifi),
+            "wired" => Some(NetworkHint::Wired),
+            _ => None,
+        }
+    }
+
+    /// Order the advertised ICE servers for this network and suggest a
+    /// transport policy. Cellular goes relay-first (TURN servers first, and
+    /// "relay" when there is one); wifi and wired keep STUN first so host and
+    /// srflx candidates are tried directly.
+    pub fn tailor(self, mut servers: Vec<IceServer>) -> (Vec<IceServer>, &'static str) {
+        match self {
+            NetworkHint::Cellular => {
+                // Stable sort: TURN servers move ahead, each group keeps its order
+                servers.sort_by_key(|s| !s.is_turn());
+                let policy = if servers.iter().any(IceServer::is_turn) {
+                    "relay"
+                } else {
+                    "all"
+                };
+                (servers, policy)
+            }
+            NetworkHint::Wifi | NetworkHint::Wired => {
+                servers.sort_by_key(IceServer::is_turn);
+                (servers, "all")
+            }
+        }
+    }
+}
+
 /// WebSocket session info stored in Redis
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WsSession {
@@ -57,6 +146,110 @@ pub struct Claims {
     pub sub: String, // user_id
     pub room_id: String,
     pub display: String,
+    /// Issued through the host (creator_key) join flow
+    #[serde(default)]
+    pub host: bool,
+    /// Watch-only participant: may subscribe but never publish
+    #[serde(default)]
+    pub observer: bool,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+/// GET /auth/whoami response: the caller's decoded token
+#[derive(Debug, Serialize)]
+pub struct WhoAmIResponse {
+    pub sub: String,
+    pub room_id: String,
+    pub display: String,
+    pub host: bool,
+    pub observer: bool,
     pub iat: i64,
     pub exp: i64,
+    /// Seconds until the token expires (0 once it has)
+    pub expires_in: i64,
+}
+
+impl WhoAmIResponse {
+    pub fn new(claims: Claims, now: i64) -> Self {
+        Self {
+            expires_in: (claims.exp - now).max(0),
+            sub: claims.sub,
+            room_id: claims.room_id,
+            display: claims.display,
+            host: claims.host,
+            observer: claims.observer,
+            iat: claims.iat,
+            exp: claims.exp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_whoami_reports_remaining_lifetime() {
+        let claims = Claims {
+            sub: "user-1".to_string(),
+            room_id: "room-1".to_string(),
+            display: "Alice".to_string(),
+            host: true,
+            observer: false,
+            iat: 1_000,
+            exp: 1_900,
+        };
+        assert_eq!(WhoAmIResponse::new(claims.clone(), 1_300).expires_in, 600);
+        assert_eq!(WhoAmIResponse::new(claims, 2_000).expires_in, 0);
+    }
+
+    #[test]
+    fn test_participant_color_is_stable_hex() {
+        let color = participant_color("user-123");
+        assert_eq!(color, participant_color("user-123"));
+        assert_eq!(color.len(), 7);
+        assert!(color.starts_with('#'));
+        assert!(color[1..].chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(participant_color("user-123"), participant_color("user-456"));
+    }
+
+    fn server(url: &str) -> IceServer {
+        IceServer {
+            urls: vec![url.to_string()],
+            username: None,
+            credential: None,
+        }
+    }
+
+    #[test]
+    fn test_network_hint_orders_ice_servers() {
+        let servers = vec![
+            server("stun:stun.example.com:3478"),
+            server("turn:turn.example.com:3478"),
+        ];
+        let first = |servers: &[IceServer]| servers[0].urls[0].clone();
+
+        let (cellular, policy) = NetworkHint::Cellular.tailor(servers.clone());
+        assert_eq!(first(&cellular), "turn:turn.example.com:3478");
+        assert_eq!(policy, "relay");
+
+        let (wired, policy) = NetworkHint::Wired.tailor(servers.clone());
+        assert_eq!(first(&wired), "stun:stun.example.com:3478");
+        assert_eq!(policy, "all");
+
+        // Relay-only makes no sense without a TURN server to relay through
+        let (_, policy) = NetworkHint::Cellular.tailor(vec![server("stun:stun.example.com")]);
+        assert_eq!(policy, "all");
+
+        assert_eq!(NetworkHint::parse(" WiFi "), Some(NetworkHint::Wifi));
+        assert_eq!(NetworkHint::parse("satellite"), None);
+    }
+
+    #[test]
+    fn test_hsl_primaries() {
+        assert_eq!(hsl_to_rgb(0.0, 1.0, 0.5), (255, 0, 0));
+        assert_eq!(hsl_to_rgb(120.0, 1.0, 0.5), (0, 255, 0));
+        assert_eq!(hsl_to_rgb(240.0, 1.0, 0.5), (0, 0, 255));
+    }
 }
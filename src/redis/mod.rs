@@ -2,17 +2,67 @@ pub mod room_repository;
 
 pub use room_repository::*;
 
-use deadpool_redis::{Config as RedisConfig, Pool, Runtime};
+use std::time::Duration;
+
+use deadpool_redis::{Config as RedisConfig, Pool, PoolConfig, Runtime, Timeouts};
 
 use crate::config::Config;
 use crate::error::{AppError, Result};
 
 /// Create a Redis connection pool
 pub fn create_pool(config: &Config) -> Result<Pool> {
-    let redis_config = RedisConfig::from_url(&config.redis_url);
+    build_pool(
+        &config.redis_url,
+        config.redis_pool_max_size,
+        config.redis_connect_timeout_ms,
+        config.redis_wait_timeout_ms,
+    )
+}
+
+/// Pool of at most `max_size` connections (0 = deadpool's default). Opening
+/// a connection is bounded by `connect_timeout_ms` and waiting for a free one
+/// by `wait_timeout_ms`, so a slow Redis fails requests instead of hanging
+/// them (0 = no limit).
+pub fn build_pool(
+    url: &str,
+    max_size: usize,
+    connect_timeout_ms: u64,
+    wait_timeout_ms: u64,
+) -> Result<Pool> {
+    let limit = |ms: u64| (ms > 0).then(|| Duration::from_millis(ms));
+
+    let mut pool_config = if max_size > 0 {
+        PoolConfig::new(max_size)
+    } else {
+        PoolConfig::default()
+    };
+    pool_config.timeouts = Timeouts {
+        wait: limit(wait_timeout_ms),
+        create: limit(connect_timeout_ms),
+        recycle: limit(connect_timeout_ms),
+    };
+
+    let mut redis_config = RedisConfig::from_url(url);
+    redis_config.pool = Some(pool_config);
     let pool = redis_config
         .create_pool(Some(Runtime::Tokio1))
         .map_err(|e| AppError::RedisError(format!("Failed to create Redis pool: {}", e)))?;
 
     Ok(pool)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_honors_configured_max_size() {
+        // Building the pool opens no connection, so no Redis is needed
+        let pool = build_pool("redis://127.0.0.1:6379", 7, 2000, 5000).unwrap();
+        assert_eq!(pool.status().max_size, 7);
+
+        // 0 keeps deadpool's default
+        let pool = build_pool("redis://127.0.0.1:6379", 0, 0, 0).unwrap();
+        assert!(pool.status().max_size > 0);
+    }
+}
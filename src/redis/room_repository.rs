@@ -1,9 +1,22 @@
-use chrono::Utc;
+use std::future::Future;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
 use deadpool_redis::Pool;
 use redis::AsyncCommands;
 
 use crate::error::{AppError, Result};
-use crate::models::{PublisherInfo, Room, RoomInfo, RoomInvitation, RoomStatus, WsSession};
+use crate::models::{
+    Announcement, PublisherInfo, Room, RoomInfo, RoomInvitation, RoomStatus, RoomSummary, WsSession,
+};
+
+/// Lifetime of a mutation lock; short so a crashed holder can't wedge a room
+const LOCK_TTL_MS: u64 = 5_000;
+/// How long to wait for a contended lock before giving up
+const LOCK_WAIT: Duration = Duration::from_millis(1_000);
+const LOCK_RETRY: Duration = Duration::from_millis(25);
+/// Keys SCAN is asked to look at per call
+const SCAN_COUNT: usize = 500;
 
 /// Room repository for Redis operations
 #[derive(Clone)]
@@ -35,6 +48,35 @@ impl RoomRepository {
         Ok(())
     }
 
+    /// Create several rooms and their creator key hashes in one pipeline (not
+    /// atomic). Each hash outlives its room by `creator_key_grace_seconds`.
+    pub async fn create_rooms(
+        &self,
+        rooms: &[(&Room, &str)],
+        creator_key_grace_seconds: u64,
+    ) -> Result<()> {
+        let mut conn = self.pool.get().await?;
+        let mut pipe = redis::pipe();
+
+        for (room, creator_hash) in rooms {
+            let json = serde_json::to_string(room)?;
+            pipe.cmd("SETEX")
+                .arg(format!("room:{}", room.room_id))
+                .arg(room.ttl_seconds as i64)
+                .arg(json)
+                .ignore();
+            pipe.cmd("SETEX")
+                .arg(format!("room:{}:creator_key_hash", room.room_id))
+                .arg((room.ttl_seconds + creator_key_grace_seconds) as i64)
+                .arg(*creator_hash)
+                .ignore();
+        }
+        pipe.query_async::<()>(&mut *conn).await?;
+
+        tracing::info!(count = rooms.len(), "Rooms created");
+        Ok(())
+    }
+
     /// Get room by ID
     pub async fn get_room(&self, room_id: &str) -> Result<Option<Room>> {
         let mut conn = self.pool.get().await?;
@@ -60,33 +102,78 @@ impl RoomRepository {
 
         let members = self.get_members(room_id).await?;
         let publishers = self.get_publishers(room_id).await?;
+        let seats_taken = self
+            .get_member_infos(room_id)
+            .await?
+            .iter()
+            .filter(|member| !member.observer)
+            .count();
 
-        let status = if members.is_empty() {
-            RoomStatus::Inactive
-        } else {
-            RoomStatus::Active
-        };
+        let status = RoomStatus::from_occupancy(members.len(), seats_taken, room.max_publishers);
 
         Ok(Some(RoomInfo {
             room_id: room.room_id,
             name: room.name,
+            slug: room.slug,
+            created_by: room.created_by,
+            allowed_codecs: room.allowed_codecs,
+            moderator_preemption: room.moderator_preemption,
+            require_unique_display: room.require_unique_display,
+            end_on_host_leave: room.end_on_host_leave,
             participants_count: members.len(),
             participants: members,
             publishers,
             status,
+            seats_taken,
+            max_publishers: room.max_publishers,
             created_at: room.created_at,
         }))
     }
 
-    /// List recent rooms (MVP)
-    pub async fn list_rooms(&self, limit: usize) -> Result<Vec<RoomInfo>> {
+    /// Room with member/publisher counts only (`SCARD`/`HLEN`), for listings
+    pub async fn get_room_summary(&self, room_id: &str) -> Result<Option<RoomSummary>> {
+        let room = match self.get_room(room_id).await? {
+            Some(r) => r,
+            None => return Ok(None),
+        };
+
         let mut conn = self.pool.get().await?;
+        let (participants_count, publishers_count): (usize, usize) = redis::pipe()
+            .scard(format!("room:{}:members", room_id))
+            .hlen(format!("room:{}:publishers", room_id))
+            .query_async(&mut *conn)
+            .await?;
+
+        let seats_taken = self
+            .get_member_infos(room_id)
+            .await?
+            .iter()
+            .filter(|member| !member.observer)
+            .count();
+
+        let status =
+            RoomStatus::from_occupancy(participants_count, seats_taken, room.max_publishers);
+
+        Ok(Some(RoomSummary {
+            room_id: room.room_id,
+            name: room.name,
+            slug: room.slug,
+            created_by: room.created_by,
+            status,
+            participants_count,
+            publishers_count,
+            seats_taken,
+            max_publishers: room.max_publishers,
+            created_at: room.created_at,
+        }))
+    }
 
-        // Get all keys room:*
-        let keys: Vec<String> = conn.keys("room:*").await?;
+    /// Ids of every live room
+    pub async fn room_ids(&self) -> Result<Vec<String>> {
+        let keys = self.scan_keys("room:*").await?;
 
         // Keep only exact keys: room:<uuid>
-        let mut room_ids: Vec<String> = keys
+        Ok(keys
             .into_iter()
             .filter_map(|k| {
                 let parts: Vec<&str> = k.split(':').collect();
@@ -96,13 +183,18 @@ impl RoomRepository {
                     None
                 }
             })
-            .collect();
+            .collect())
+    }
 
-        let mut infos: Vec<RoomInfo> = Vec::new();
+    /// List recent rooms (MVP)
+    pub async fn list_rooms(&self, limit: usize) -> Result<Vec<RoomSummary>> {
+        let mut room_ids = self.room_ids().await?;
 
-        // Fetch RoomInfo for each id
+        let mut infos: Vec<RoomSummary> = Vec::new();
+
+        // Fetch the (counts-only) summary for each id
         for room_id in room_ids.drain(..) {
-            if let Some(info) = self.get_room_info(&room_id).await? {
+            if let Some(info) = self.get_room_summary(&room_id).await? {
                 infos.push(info);
             }
         }
@@ -118,13 +210,27 @@ impl RoomRepository {
 
     /// Delete a room
     pub async fn delete_room(&self, room_id: &str) -> Result<()> {
+        let slug = self.get_room(room_id).await?.and_then(|r| r.slug);
         let mut conn = self.pool.get().await?;
 
-        let keys = vec![
+        let mut keys = vec![
             format!("room:{}", room_id),
             format!("room:{}:members", room_id),
+            format!("room:{}:members_info", room_id),
             format!("room:{}:publishers", room_id),
+            format!("room:{}:cohosts", room_id),
+            format!("room:{}:hosts", room_id),
+            format!("room:{}:locked", room_id),
+            format!("room:{}:announcement", room_id),
+            format!("room:{}:spotlight", room_id),
+            format!("room:{}:bans", room_id),
+            format!("room:{}:fingerprints", room_id),
+            format!("room:{}:activated", room_id),
+            format!("room:{}:creator_key_hash", room_id),
         ];
+        if let Some(slug) = slug {
+            keys.push(format!("slug:{}", slug));
+        }
 
         redis::cmd("DEL")
             .arg(&keys)
@@ -135,15 +241,36 @@ impl RoomRepository {
         Ok(())
     }
 
-    /// Refresh room TTL
-    pub async fn refresh_room_ttl(&self, room_id: &str, ttl_seconds: u64) -> Result<()> {
+    /// Set the TTL of `room` and its keys to `ttl_seconds` (see
+    /// `RoomTtlRules::ttl_for` for the limits). The creator key hash keeps its
+    /// `creator_key_grace_seconds` lead.
+    pub async fn refresh_room_ttl(
+        &self,
+        room: &Room,
+        ttl_seconds: u64,
+        creator_key_grace_seconds: u64,
+    ) -> Result<()> {
+        let room_id = &room.room_id;
+        let slug = room.slug.as_ref();
         let mut conn = self.pool.get().await?;
 
-        let keys = vec![
+        let mut keys = vec![
             format!("room:{}", room_id),
             format!("room:{}:members", room_id),
+            format!("room:{}:members_info", room_id),
             format!("room:{}:publishers", room_id),
+            format!("room:{}:cohosts", room_id),
+            format!("room:{}:hosts", room_id),
+            format!("room:{}:locked", room_id),
+            format!("room:{}:announcement", room_id),
+            format!("room:{}:spotlight", room_id),
+            format!("room:{}:bans", room_id),
+            format!("room:{}:fingerprints", room_id),
+            format!("room:{}:activated", room_id),
         ];
+        if let Some(slug) = slug {
+            keys.push(format!("slug:{}", slug));
+        }
 
         for key in keys {
             redis::cmd("EXPIRE")
@@ -152,18 +279,158 @@ impl RoomRepository {
                 .query_async::<()>(&mut *conn)
                 .await?;
         }
+        redis::cmd("EXPIRE")
+            .arg(format!("room:{}:creator_key_hash", room_id))
+            .arg((ttl_seconds + creator_key_grace_seconds) as i64)
+            .query_async::<()>(&mut *conn)
+            .await?;
 
         Ok(())
     }
 
+    /// Seconds until the room expires (`None` if it is gone or never expires)
+    pub async fn room_ttl(&self, room_id: &str) -> Result<Option<u64>> {
+        let mut conn = self.pool.get().await?;
+        let ttl: i64 = conn.ttl(format!("room:{}", room_id)).await?;
+        Ok((ttl >= 0).then_some(ttl as u64))
+    }
+
+    // ==================== Slug Operations ====================
+
+    /// Reserve `slug` for a room (SET NX), returns false if it is already taken
+    pub async fn claim_slug(&self, slug: &str, room_id: &str, ttl_seconds: u64) -> Result<bool> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("slug:{}", slug);
+
+        let claimed: Option<String> = redis::cmd("SET")
+            .arg(&key)
+            .arg(room_id)
+            .arg("NX")
+            .arg("EX")
+            .arg(ttl_seconds as i64)
+            .query_async(&mut *conn)
+            .await?;
+
+        Ok(claimed.is_some())
+    }
+
+    /// Resolve a slug to its room_id
+    pub async fn resolve_slug(&self, slug: &str) -> Result<Option<String>> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("slug:{}", slug);
+
+        let room_id: Option<String> = conn.get(&key).await?;
+        Ok(room_id)
+    }
+
     // ==================== Member Operations ====================
 
+    /// Register a member in both the `:members` set and the `:members_info` hash
+    /// in one transaction, so the two views can't drift apart. The first join
+    /// also marks the room activated (see `is_activated`).
+    pub async fn join_member(
+        &self,
+        room_id: &str,
+        user_id: &str,
+        display: &str,
+        observer: bool,
+    ) -> Result<()> {
+        let ttl = self.get_room(room_id).await?.map(|room| room.ttl_seconds as i64);
+        let mut conn = self.pool.get().await?;
+        let members_key = format!("room:{}:members", room_id);
+        let info_key = format!("room:{}:members_info", room_id);
+        let user_rooms_key = format!("user:{}:rooms", user_id);
+        let activated_key = format!("room:{}:activated", room_id);
+
+        let info = serde_json::json!({
+            "user_id": user_id,
+            "display": display,
+            "joined_at": chrono::Utc::now().timestamp(),
+            "observer": observer
+        });
+
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .sadd(&members_key, user_id)
+            .ignore()
+            .hset(&info_key, user_id, info.to_string())
+            .ignore()
+            .sadd(&user_rooms_key, room_id)
+            .ignore()
+            .cmd("SET")
+            .arg(&activated_key)
+            .arg(chrono::Utc::now().timestamp())
+            .arg("NX")
+            .ignore();
+        if let Some(ttl) = ttl {
+            pipe.expire(&members_key, ttl)
+                .ignore()
+                .expire(&info_key, ttl)
+                .ignore()
+                .expire(&activated_key, ttl)
+                .ignore();
+        }
+        pipe.query_async::<()>(&mut *conn).await?;
+        if let Some(ttl) = ttl {
+            self.extend_ttl(&user_rooms_key, ttl).await?;
+        }
+
+        tracing::debug!(room_id = %room_id, user_id = %user_id, "Member joined");
+        Ok(())
+    }
+
+    /// Make `key` live at least `ttl` more seconds without ever shortening it.
+    /// `user:<id>:rooms` indexes rooms with different TTLs, so joining a
+    /// short-lived room must not expire the entries of longer-lived ones.
+    /// (`EXPIRE ... GT` would do, but needs Redis 7.)
+    async fn extend_ttl(&self, key: &str, ttl: i64) -> Result<()> {
+        let mut conn = self.pool.get().await?;
+
+        let script = redis::Script::new(
+            r"
+            local current = redis.call('TTL', KEYS[1])
+            if current == -1 or (current >= 0 and current < tonumber(ARGV[1])) then
+                return redis.call('EXPIRE', KEYS[1], ARGV[1])
+            end
+            return 0
+            ",
+        );
+        script
+            .key(key)
+            .arg(ttl)
+            .invoke_async::<()>(&mut *conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Remove a member from both the `:members` set and the `:members_info` hash
+    pub async fn leave_member(&self, room_id: &str, user_id: &str) -> Result<()> {
+        let mut conn = self.pool.get().await?;
+
+        redis::pipe()
+            .atomic()
+            .srem(format!("room:{}:members", room_id), user_id)
+            .ignore()
+            .hdel(format!("room:{}:members_info", room_id), user_id)
+            .ignore()
+            .srem(format!("user:{}:rooms", user_id), room_id)
+            .ignore()
+            .query_async::<()>(&mut *conn)
+            .await?;
+
+        tracing::debug!(room_id = %room_id, user_id = %user_id, "Member left");
+        Ok(())
+    }
+
     /// Add a member to a room
     pub async fn add_member(&self, room_id: &str, user_id: &str) -> Result<()> {
         let mut conn = self.pool.get().await?;
         let key = format!("room:{}:members", room_id);
 
+        let user_rooms_key = format!("user:{}:rooms", user_id);
+
         conn.sadd::<_, _, ()>(&key, user_id).await?;
+        conn.sadd::<_, _, ()>(&user_rooms_key, room_id).await?;
 
         // Set TTL if room exists
         if let Some(room) = self.get_room(room_id).await? {
@@ -172,6 +439,8 @@ impl RoomRepository {
                 .arg(room.ttl_seconds as i64)
                 .query_async::<()>(&mut *conn)
                 .await?;
+            self.extend_ttl(&user_rooms_key, room.ttl_seconds as i64)
+                .await?;
         }
 
         tracing::debug!(room_id = %room_id, user_id = %user_id, "Member added");
@@ -179,14 +448,21 @@ impl RoomRepository {
     }
 
     /// Set member info (display name and joined_at) in a hash for persistence
-    pub async fn set_member_info(&self, room_id: &str, user_id: &str, display: &str) -> Result<()> {
+    pub async fn set_member_info(
+        &self,
+        room_id: &str,
+        user_id: &str,
+        display: &str,
+        observer: bool,
+    ) -> Result<()> {
         let mut conn = self.pool.get().await?;
         let key = format!("room:{}:members_info", room_id);
 
         let info = serde_json::json!({
             "user_id": user_id,
             "display": display,
-            "joined_at": chrono::Utc::now().timestamp()
+            "joined_at": chrono::Utc::now().timestamp(),
+            "observer": observer
         });
 
         conn.hset::<_, _, _, ()>(&key, user_id, info.to_string()).await?;
@@ -204,6 +480,34 @@ impl RoomRepository {
         Ok(())
     }
 
+    /// Change a member's display name, keeping when they joined (and the
+    /// display on their publisher entry, if any). Returns false if the member
+    /// has no info in the room.
+    pub async fn rename_member(&self, room_id: &str, user_id: &str, display: &str) -> Result<bool> {
+        let mut conn = self.pool.get().await?;
+        let info_key = format!("room:{}:members_info", room_id);
+        let publishers_key = format!("room:{}:publishers", room_id);
+
+        let Some(json): Option<String> = conn.hget(&info_key, user_id).await? else {
+            return Ok(false);
+        };
+        let mut info: serde_json::Value = serde_json::from_str(&json)?;
+        info["display"] = serde_json::Value::from(display);
+        conn.hset::<_, _, _, ()>(&info_key, user_id, info.to_string())
+            .await?;
+
+        let publisher: Option<String> = conn.hget(&publishers_key, user_id).await?;
+        if let Some(json) = publisher {
+            let mut publisher: PublisherInfo = serde_json::from_str(&json)?;
+            publisher.display = display.to_string();
+            let json = serde_json::to_string(&publisher)?;
+            conn.hset::<_, _, _, ()>(&publishers_key, user_id, json).await?;
+        }
+
+        tracing::debug!(room_id = %room_id, user_id = %user_id, "Member renamed");
+        Ok(true)
+    }
+
     /// Remove member info from the hash
     pub async fn remove_member_info(&self, room_id: &str, user_id: &str) -> Result<()> {
         let mut conn = self.pool.get().await?;
@@ -234,6 +538,10 @@ impl RoomRepository {
         let members: Vec<crate::models::user::MemberInfo> = data
             .into_iter()
             .filter_map(|(_, json)| serde_json::from_str(&json).ok())
+            .map(|mut m: crate::models::user::MemberInfo| {
+                m.color = crate::models::user::participant_color(&m.user_id);
+                m
+            })
             .collect();
 
         Ok(members)
@@ -245,11 +553,36 @@ impl RoomRepository {
         let key = format!("room:{}:members", room_id);
 
         conn.srem::<_, _, ()>(&key, user_id).await?;
+        conn.srem::<_, _, ()>(format!("user:{}:rooms", user_id), room_id)
+            .await?;
 
         tracing::debug!(room_id = %room_id, user_id = %user_id, "Member removed");
         Ok(())
     }
 
+    /// Rooms `user_id` is currently a member of. The `user:<id>:rooms` index is
+    /// not touched when a room expires or is deleted, so entries whose room is
+    /// gone or no longer lists the user are dropped here.
+    pub async fn get_user_rooms(&self, user_id: &str) -> Result<Vec<RoomInfo>> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("user:{}:rooms", user_id);
+
+        let room_ids: Vec<String> = conn.smembers(&key).await?;
+
+        let mut rooms = Vec::with_capacity(room_ids.len());
+        for room_id in room_ids {
+            match self.get_room_info(&room_id).await? {
+                Some(info) if info.participants.iter().any(|m| m == user_id) => rooms.push(info),
+                _ => {
+                    conn.srem::<_, _, ()>(&key, &room_id).await?;
+                    tracing::debug!(room_id = %room_id, user_id = %user_id, "Dropped stale user room entry");
+                }
+            }
+        }
+
+        Ok(rooms)
+    }
+
     /// Get member count
     pub async fn get_member_count(&self, room_id: &str) -> Result<usize> {
         let mut conn = self.pool.get().await?;
@@ -259,6 +592,13 @@ impl RoomRepository {
         Ok(count)
     }
 
+    /// Whether anyone has ever joined the room
+    pub async fn is_activated(&self, room_id: &str) -> Result<bool> {
+        let mut conn = self.pool.get().await?;
+        let activated: bool = conn.exists(format!("room:{}:activated", room_id)).await?;
+        Ok(activated)
+    }
+
     /// Check if user is a member
     pub async fn is_member(&self, room_id: &str, user_id: &str) -> Result<bool> {
         let mut conn = self.pool.get().await?;
@@ -404,6 +744,129 @@ impl RoomRepository {
         Ok(())
     }
 
+    // ==================== Announcements ====================
+
+    /// Store a room's latest announcement (replaces the previous one)
+    pub async fn set_announcement(&self, announcement: &Announcement, ttl_seconds: u64) -> Result<()> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("room:{}:announcement", announcement.room_id);
+        let json = serde_json::to_string(announcement)?;
+
+        conn.set_ex::<_, _, ()>(&key, json, ttl_seconds).await?;
+        Ok(())
+    }
+
+    /// Latest announcement of a room, if any
+    pub async fn get_announcement(&self, room_id: &str) -> Result<Option<Announcement>> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("room:{}:announcement", room_id);
+
+        let json: Option<String> = conn.get(&key).await?;
+        Ok(json.map(|data| serde_json::from_str(&data)).transpose()?)
+    }
+
+    // ==================== Layout ====================
+
+    /// Store the room's spotlighted feed, or clear it with None
+    pub async fn set_spotlight(
+        &self,
+        room_id: &str,
+        feed_id: Option<&str>,
+        ttl_seconds: u64,
+    ) -> Result<()> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("room:{}:spotlight", room_id);
+
+        match feed_id {
+            Some(feed_id) => conn.set_ex::<_, _, ()>(&key, feed_id, ttl_seconds).await?,
+            None => conn.del::<_, ()>(&key).await?,
+        }
+        Ok(())
+    }
+
+    /// Feed currently spotlighted in a room, if any
+    pub async fn get_spotlight(&self, room_id: &str) -> Result<Option<String>> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("room:{}:spotlight", room_id);
+
+        Ok(conn.get(&key).await?)
+    }
+
+    // ==================== Distributed Locks ====================
+
+    /// Try once to take `lock:<resource>` (SET NX PX). Returns the holder token on success.
+    pub async fn try_lock(&self, resource: &str) -> Result<Option<String>> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("lock:{}", resource);
+        let token = uuid::Uuid::new_v4().to_string();
+
+        let acquired: Option<String> = redis::cmd("SET")
+            .arg(&key)
+            .arg(&token)
+            .arg("NX")
+            .arg("PX")
+            .arg(LOCK_TTL_MS)
+            .query_async(&mut *conn)
+            .await?;
+
+        Ok(acquired.map(|_| token))
+    }
+
+    /// Release a lock only if `token` still holds it (it may have expired and been retaken)
+    pub async fn unlock(&self, resource: &str, token: &str) -> Result<bool> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("lock:{}", resource);
+
+        let script = redis::Script::new(
+            r"
+            if redis.call('GET', KEYS[1]) == ARGV[1] then
+                return redis.call('DEL', KEYS[1])
+            end
+            return 0
+            ",
+        );
+        let released: i32 = script.key(&key).arg(token).invoke_async(&mut *conn).await?;
+
+        Ok(released == 1)
+    }
+
+    /// Run `fut` while holding `lock:<resource>`, waiting briefly if another
+    /// instance holds it. Fails with `Conflict` if the lock stays busy.
+    pub async fn with_lock<T, F>(&self, resource: &str, fut: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let deadline = tokio::time::Instant::now() + LOCK_WAIT;
+        let token = loop {
+            if let Some(token) = self.try_lock(resource).await? {
+                break token;
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(AppError::Conflict(
+                    "Resource is busy, please retry".to_string(),
+                ));
+            }
+            tokio::time::sleep(LOCK_RETRY).await;
+        };
+
+        let result = fut.await;
+
+        if let Err(e) = self.unlock(resource, &token).await {
+            // The lock expires on its own; just make the miss visible
+            tracing::warn!(resource = %resource, error = %e, "Failed to release lock");
+        }
+
+        result
+    }
+
+    /// Serialize mutations of one room across instances
+    pub async fn with_room_lock<T, F>(&self, room_id: &str, fut: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        self.with_lock(&format!("room:{}", room_id), fut).await
+    }
+
     // ==================== Health Check ====================
 
     /// Check Redis connection health
@@ -418,6 +881,27 @@ impl RoomRepository {
         Ok(pong == "PONG")
     }
 
+    // ==================== Maintenance ====================
+
+    /// Turn the runtime maintenance flag on or off (shared by all instances)
+    pub async fn set_maintenance(&self, enabled: bool) -> Result<()> {
+        let mut conn = self.pool.get().await?;
+
+        if enabled {
+            conn.set::<_, _, ()>("maintenance", "1").await?;
+        } else {
+            conn.del::<_, ()>("maintenance").await?;
+        }
+        Ok(())
+    }
+
+    /// Whether the runtime maintenance flag is set
+    pub async fn is_maintenance(&self) -> Result<bool> {
+        let mut conn = self.pool.get().await?;
+        let enabled: bool = conn.exists("maintenance").await?;
+        Ok(enabled)
+    }
+
     // ==================== Creator Key (host access) ====================
 
     pub async fn set_creator_key_hash(
@@ -439,6 +923,14 @@ impl RoomRepository {
         Ok(())
     }
 
+    /// Seconds until the creator key hash expires (`None` if it is gone)
+    pub async fn creator_key_ttl(&self, room_id: &str) -> Result<Option<u64>> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("room:{}:creator_key_hash", room_id);
+        let ttl: i64 = conn.ttl(&key).await?;
+        Ok((ttl >= 0).then_some(ttl as u64))
+    }
+
     pub async fn get_creator_key_hash(&self, room_id: &str) -> Result<Option<String>> {
         let mut conn = self.pool.get().await?;
         let key = format!("room:{}:creator_key_hash", room_id);
@@ -447,22 +939,284 @@ impl RoomRepository {
         Ok(v)
     }
 
-    // ==================== Invitation Operations ====================
+    /// Replace the creator key hash only if it still equals `current_hash`,
+    /// keeping the key's TTL. Returns false if it changed or expired meanwhile.
+    pub async fn rotate_creator_key_hash(
+        &self,
+        room_id: &str,
+        current_hash: &str,
+        new_hash: &str,
+    ) -> Result<bool> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("room:{}:creator_key_hash", room_id);
 
-    /// Create a room invitation
-    pub async fn create_invitation(&self, invitation: &RoomInvitation) -> Result<()> {
+        let script = redis::Script::new(
+            r"
+            if redis.call('GET', KEYS[1]) == ARGV[1] then
+                redis.call('SET', KEYS[1], ARGV[2], 'KEEPTTL')
+                return 1
+            end
+            return 0
+            ",
+        );
+        let swapped: i32 = script
+            .key(&key)
+            .arg(current_hash)
+            .arg(new_hash)
+            .invoke_async(&mut *conn)
+            .await?;
+
+        Ok(swapped == 1)
+    }
+
+    // ==================== Host Operations ====================
+
+    /// Record a user who joined with the creator_key
+    pub async fn add_host(&self, room_id: &str, user_id: &str) -> Result<()> {
         let mut conn = self.pool.get().await?;
-        let key = format!("invite:{}", invitation.token);
-        let json = serde_json::to_string(invitation)?;
+        let key = format!("room:{}:hosts", room_id);
 
-        let ttl = (invitation.expires_at - Utc::now()).num_seconds().max(1) as i64;
+        conn.sadd::<_, _, ()>(&key, user_id).await?;
 
-        redis::cmd("SETEX")
+        // Set TTL if room exists
+        if let Some(room) = self.get_room(room_id).await? {
+            redis::cmd("EXPIRE")
+                .arg(&key)
+                .arg(room.ttl_seconds as i64)
+                .query_async::<()>(&mut *conn)
+                .await?;
+        }
+
+        tracing::debug!(room_id = %room_id, user_id = %user_id, "Host added");
+        Ok(())
+    }
+
+    /// Forget every host (the creator_key changed hands), returning their user ids
+    pub async fn clear_hosts(&self, room_id: &str) -> Result<Vec<String>> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("room:{}:hosts", room_id);
+
+        let hosts: Vec<String> = conn.smembers(&key).await?;
+        conn.del::<_, ()>(&key).await?;
+
+        tracing::debug!(room_id = %room_id, count = hosts.len(), "Hosts cleared");
+        Ok(hosts)
+    }
+
+    /// Whether a host is currently a member of the room, on any instance
+    pub async fn has_host_member(&self, room_id: &str) -> Result<bool> {
+        let mut conn = self.pool.get().await?;
+
+        let hosts: Vec<String> = conn
+            .sinter(&[
+                format!("room:{}:hosts", room_id),
+                format!("room:{}:members", room_id),
+            ])
+            .await?;
+        Ok(!hosts.is_empty())
+    }
+
+    /// Check if user joined with the room's current creator_key. Host tokens
+    /// outlive a host transfer, so this is what decides host powers.
+    pub async fn is_host(&self, room_id: &str, user_id: &str) -> Result<bool> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("room:{}:hosts", room_id);
+
+        let is_host: bool = conn.sismember(&key, user_id).await?;
+        Ok(is_host)
+    }
+
+    // ==================== Co-host Operations ====================
+
+    /// Promote a user to co-host
+    pub async fn add_cohost(&self, room_id: &str, user_id: &str) -> Result<()> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("room:{}:cohosts", room_id);
+
+        conn.sadd::<_, _, ()>(&key, user_id).await?;
+
+        // Set TTL if room exists
+        if let Some(room) = self.get_room(room_id).await? {
+            redis::cmd("EXPIRE")
+                .arg(&key)
+                .arg(room.ttl_seconds as i64)
+                .query_async::<()>(&mut *conn)
+                .await?;
+        }
+
+        tracing::debug!(room_id = %room_id, user_id = %user_id, "Co-host added");
+        Ok(())
+    }
+
+    /// Revoke a co-host, returns false if the user was not a co-host
+    pub async fn remove_cohost(&self, room_id: &str, user_id: &str) -> Result<bool> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("room:{}:cohosts", room_id);
+
+        let removed: usize = conn.srem(&key, user_id).await?;
+
+        tracing::debug!(room_id = %room_id, user_id = %user_id, "Co-host removed");
+        Ok(removed > 0)
+    }
+
+    /// Demote every co-host, returning the user ids that were removed
+    pub async fn clear_cohosts(&self, room_id: &str) -> Result<Vec<String>> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("room:{}:cohosts", room_id);
+
+        let cohosts: Vec<String> = conn.smembers(&key).await?;
+        conn.del::<_, ()>(&key).await?;
+
+        tracing::debug!(room_id = %room_id, count = cohosts.len(), "Co-hosts cleared");
+        Ok(cohosts)
+    }
+
+    /// Check if user is a co-host
+    pub async fn is_cohost(&self, room_id: &str, user_id: &str) -> Result<bool> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("room:{}:cohosts", room_id);
+
+        let is_cohost: bool = conn.sismember(&key, user_id).await?;
+        Ok(is_cohost)
+    }
+
+    // ==================== Bans ====================
+
+    /// Ban identities (`user:<id>`, `fp:<fingerprint>`) until `expires_at`
+    /// (unix seconds, None = for as long as the room lives). The set is a
+    /// sorted set scored by expiry so each entry can lapse on its own.
+    pub async fn add_bans(
+        &self,
+        room_id: &str,
+        identities: &[String],
+        expires_at: Option<i64>,
+        ttl_seconds: u64,
+    ) -> Result<()> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("room:{}:bans", room_id);
+        let score = expires_at.map_or_else(|| "+inf".to_string(), |t| t.to_string());
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for identity in identities {
+            pipe.cmd("ZADD").arg(&key).arg(&score).arg(identity).ignore();
+        }
+        pipe.expire(&key, ttl_seconds as i64).ignore();
+        pipe.query_async::<()>(&mut *conn).await?;
+
+        tracing::debug!(room_id = %room_id, count = identities.len(), "Bans added");
+        Ok(())
+    }
+
+    /// Lift bans, returns how many entries were removed
+    pub async fn remove_bans(&self, room_id: &str, identities: &[String]) -> Result<usize> {
+        if identities.is_empty() {
+            return Ok(0);
+        }
+        let mut conn = self.pool.get().await?;
+        let key = format!("room:{}:bans", room_id);
+
+        let removed: usize = conn.zrem(&key, identities).await?;
+
+        tracing::debug!(room_id = %room_id, removed, "Bans removed");
+        Ok(removed)
+    }
+
+    /// Whether any of the identities is under a ban that hasn't lapsed
+    pub async fn is_banned(&self, room_id: &str, identities: &[String]) -> Result<bool> {
+        if identities.is_empty() {
+            return Ok(false);
+        }
+        let mut conn = self.pool.get().await?;
+        let key = format!("room:{}:bans", room_id);
+
+        // Drop lapsed bans first so a plain membership test is enough
+        let now = Utc::now().timestamp();
+        redis::cmd("ZREMRANGEBYSCORE")
             .arg(&key)
-            .arg(ttl)
-            .arg(&json)
+            .arg("-inf")
+            .arg(format!("({}", now))
+            .query_async::<()>(&mut *conn)
+            .await?;
+
+        for identity in identities {
+            let score: Option<String> = conn.zscore(&key, identity).await?;
+            if score.is_some() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Remember the fingerprint a member joined with, so banning the member
+    /// also bans it
+    pub async fn set_member_fingerprint(
+        &self,
+        room_id: &str,
+        user_id: &str,
+        fingerprint: &str,
+        ttl_seconds: u64,
+    ) -> Result<()> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("room:{}:fingerprints", room_id);
+
+        redis::pipe()
+            .atomic()
+            .hset(&key, user_id, fingerprint)
+            .ignore()
+            .expire(&key, ttl_seconds as i64)
+            .ignore()
             .query_async::<()>(&mut *conn)
             .await?;
+        Ok(())
+    }
+
+    /// Fingerprint a member joined with, if they sent one
+    pub async fn get_member_fingerprint(
+        &self,
+        room_id: &str,
+        user_id: &str,
+    ) -> Result<Option<String>> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("room:{}:fingerprints", room_id);
+
+        let fingerprint: Option<String> = conn.hget(&key, user_id).await?;
+        Ok(fingerprint)
+    }
+
+    // ==================== Room Lock ====================
+
+    /// Lock or unlock a room (locked rooms only admit the host and co-hosts)
+    pub async fn set_locked(&self, room_id: &str, locked: bool, ttl_seconds: u64) -> Result<()> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("room:{}:locked", room_id);
+
+        if locked {
+            conn.set_ex::<_, _, ()>(&key, "1", ttl_seconds).await?;
+        } else {
+            conn.del::<_, ()>(&key).await?;
+        }
+
+        tracing::debug!(room_id = %room_id, locked, "Room lock updated");
+        Ok(())
+    }
+
+    /// Check if a room is locked
+    pub async fn is_locked(&self, room_id: &str) -> Result<bool> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("room:{}:locked", room_id);
+
+        let locked: bool = conn.exists(&key).await?;
+        Ok(locked)
+    }
+
+    // ==================== Invitation Operations ====================
+
+    /// Create a room invitation
+    pub async fn create_invitation(&self, invitation: &RoomInvitation) -> Result<()> {
+        self.save_invitation(invitation).await?;
+
+        let mut conn = self.pool.get().await?;
 
         // Also add to room's invitation set for tracking
         let room_invites_key = format!("room:{}:invites", invitation.room_id);
@@ -477,18 +1231,38 @@ impl RoomRepository {
         Ok(())
     }
 
-    /// Get an invitation by token
+    /// Write an invitation back, expiring it at its `expires_at`
+    pub async fn save_invitation(&self, invitation: &RoomInvitation) -> Result<()> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("invite:{}", invitation.token);
+        let json = serde_json::to_string(invitation)?;
+
+        let ttl = (invitation.expires_at - Utc::now()).num_seconds().max(1) as i64;
+
+        redis::cmd("SETEX")
+            .arg(&key)
+            .arg(ttl)
+            .arg(&json)
+            .query_async::<()>(&mut *conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get an invitation by token, with its view stamp
     pub async fn get_invitation(&self, token: &str) -> Result<Option<RoomInvitation>> {
         let mut conn = self.pool.get().await?;
         let key = format!("invite:{}", token);
+        let viewed_key = format!("invite:{}:viewed", token);
 
-        let json: Option<String> = conn.get(&key).await?;
+        let (json, viewed): (Option<String>, Option<String>) = redis::cmd("MGET")
+            .arg(&key)
+            .arg(&viewed_key)
+            .query_async(&mut *conn)
+            .await?;
 
         match json {
-            Some(data) => {
-                let invitation: RoomInvitation = serde_json::from_str(&data)?;
-                Ok(Some(invitation))
-            }
+            Some(data) => Ok(Some(with_view_stamp(serde_json::from_str(&data)?, viewed))),
             None => Ok(None),
         }
     }
@@ -505,22 +1279,60 @@ impl RoomRepository {
         }
 
         invitation.uses += 1;
+        invitation.first_redeemed_at.get_or_insert_with(Utc::now);
+        self.save_invitation(&invitation).await?;
 
+        tracing::debug!(token = %token, uses = %invitation.uses, "Invitation used");
+        Ok(true)
+    }
+
+    /// Stamp the first time a guest looked an invitation up. The stamp has
+    /// its own key (SET NX, expiring with the invitation) so a view never
+    /// rewrites the invitation under a concurrent join or delete.
+    pub async fn mark_invitation_viewed(&self, token: &str) -> Result<()> {
         let mut conn = self.pool.get().await?;
         let key = format!("invite:{}", token);
-        let json = serde_json::to_string(&invitation)?;
 
-        let ttl = (invitation.expires_at - Utc::now()).num_seconds().max(1) as i64;
+        // -2: revoked or expired, nothing to stamp. Invitations are always
+        // written with an expiry; should one lack it, the stamp still gets one.
+        let ttl: i64 = conn.ttl(&key).await?;
+        if ttl == -2 {
+            return Ok(());
+        }
 
-        redis::cmd("SETEX")
-            .arg(&key)
-            .arg(ttl)
-            .arg(&json)
+        redis::cmd("SET")
+            .arg(format!("invite:{}:viewed", token))
+            .arg(Utc::now().to_rfc3339())
+            .arg("NX")
+            .arg("EX")
+            .arg(if ttl > 0 { ttl } else { 86400 })
             .query_async::<()>(&mut *conn)
             .await?;
+        Ok(())
+    }
 
-        tracing::debug!(token = %token, uses = %invitation.uses, "Invitation used");
-        Ok(true)
+    /// Redeem a magic link (SET NX until it expires); false if it was already used
+    pub async fn claim_magic_link(&self, nonce: &str, ttl_seconds: u64) -> Result<bool> {
+        let mut conn = self.pool.get().await?;
+        let key = format!("magic:{}", nonce);
+
+        let claimed: Option<String> = redis::cmd("SET")
+            .arg(&key)
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(ttl_seconds.max(1) as i64)
+            .query_async(&mut *conn)
+            .await?;
+
+        Ok(claimed.is_some())
+    }
+
+    /// Make a claimed magic link redeemable again
+    pub async fn release_magic_link(&self, nonce: &str) -> Result<()> {
+        let mut conn = self.pool.get().await?;
+        conn.del::<_, ()>(format!("magic:{}", nonce)).await?;
+        Ok(())
     }
 
     /// Delete an invitation
@@ -532,8 +1344,9 @@ impl RoomRepository {
 
         let mut conn = self.pool.get().await?;
         let key = format!("invite:{}", token);
+        let viewed_key = format!("invite:{}:viewed", token);
 
-        conn.del::<_, ()>(&key).await?;
+        conn.del::<_, ()>(&[&key, &viewed_key]).await?;
 
         // Remove from room's invitation set
         let room_invites_key = format!("room:{}:invites", invitation.room_id);
@@ -543,23 +1356,141 @@ impl RoomRepository {
         Ok(())
     }
 
-    /// Get all invitations for a room
-    pub async fn get_room_invitations(&self, room_id: &str) -> Result<Vec<RoomInvitation>> {
+    /// Drop references to invitations whose `invite:<token>` key has expired
+    /// from a room's tracking set; returns how many live invitations remain.
+    /// An emptied set disappears with its last member.
+    pub async fn prune_room_invites(&self, room_id: &str) -> Result<usize> {
         let mut conn = self.pool.get().await?;
         let room_invites_key = format!("room:{}:invites", room_id);
 
         let tokens: Vec<String> = conn.smembers(&room_invites_key).await?;
+        if tokens.is_empty() {
+            return Ok(0);
+        }
+
+        let mut pipe = redis::pipe();
+        for token in &tokens {
+            pipe.exists(format!("invite:{}", token));
+        }
+        let exists: Vec<bool> = pipe.query_async(&mut *conn).await?;
+
+        let expired: Vec<&String> = tokens
+            .iter()
+            .zip(&exists)
+            .filter(|(_, exists)| !**exists)
+            .map(|(token, _)| token)
+            .collect();
+        if !expired.is_empty() {
+            conn.srem::<_, _, ()>(&room_invites_key, &expired).await?;
+            tracing::debug!(room_id = %room_id, pruned = expired.len(), "Pruned expired invitations");
+        }
 
-        let mut invitations = Vec::new();
-        for token in tokens {
-            if let Some(invitation) = self.get_invitation(&token).await? {
-                invitations.push(invitation);
-            } else {
-                // Clean up expired invitation reference
-                conn.srem::<_, _, ()>(&room_invites_key, &token).await?;
+        Ok(tokens.len() - expired.len())
+    }
+
+    /// Keys matching `pattern`, walked with SCAN so a large keyspace doesn't
+    /// stall Redis the way KEYS does
+    async fn scan_keys(&self, pattern: &str) -> Result<Vec<String>> {
+        let mut conn = self.pool.get().await?;
+
+        let mut keys = Vec::new();
+        let mut cursor: u64 = 0;
+        loop {
+            let (next, batch): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(pattern)
+                .arg("COUNT")
+                .arg(SCAN_COUNT)
+                .query_async(&mut *conn)
+                .await?;
+            keys.extend(batch);
+            if next == 0 {
+                break;
             }
+            cursor = next;
+        }
+
+        // SCAN may return a key more than once
+        keys.sort_unstable();
+        keys.dedup();
+        Ok(keys)
+    }
+
+    /// Rooms that have an invitation tracking set (including rooms that have
+    /// expired while some of their invitations are still live)
+    pub async fn invite_set_room_ids(&self) -> Result<Vec<String>> {
+        let keys = self.scan_keys("room:*:invites").await?;
+
+        Ok(keys
+            .into_iter()
+            .filter_map(|k| {
+                k.strip_prefix("room:")
+                    .and_then(|k| k.strip_suffix(":invites"))
+                    .map(str::to_string)
+            })
+            .collect())
+    }
+
+    /// One page of a room's invitations, ordered by token so pages stay
+    /// stable: `limit` tokens from `offset`, fetched in a single round trip.
+    /// Expired references met on the page are dropped from the set (the page
+    /// comes back short rather than being refilled).
+    pub async fn get_room_invitations(
+        &self,
+        room_id: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<RoomInvitation>> {
+        let mut conn = self.pool.get().await?;
+        let room_invites_key = format!("room:{}:invites", room_id);
+
+        let tokens: Vec<String> = redis::cmd("SORT")
+            .arg(&room_invites_key)
+            .arg("ALPHA")
+            .arg("LIMIT")
+            .arg(offset)
+            .arg(limit)
+            .query_async(&mut *conn)
+            .await?;
+        if tokens.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Each invitation and its view stamp, side by side
+        let keys: Vec<String> = tokens
+            .iter()
+            .flat_map(|t| [format!("invite:{}", t), format!("invite:{}:viewed", t)])
+            .collect();
+        let values: Vec<Option<String>> = conn.mget(&keys).await?;
+
+        let mut invitations = Vec::with_capacity(tokens.len());
+        let mut expired: Vec<&String> = Vec::new();
+        for (token, pair) in tokens.iter().zip(values.chunks(2)) {
+            match &pair[0] {
+                Some(data) => {
+                    let viewed = pair[1].clone();
+                    invitations.push(with_view_stamp(serde_json::from_str(data)?, viewed));
+                }
+                None => expired.push(token),
+            }
+        }
+        if !expired.is_empty() {
+            // Clean up expired invitation references
+            conn.srem::<_, _, ()>(&room_invites_key, &expired).await?;
         }
 
         Ok(invitations)
     }
 }
+
+/// Fill in `first_viewed_at` from the invitation's `invite:<token>:viewed`
+/// key; older invitations carry it in the blob itself
+fn with_view_stamp(mut invitation: RoomInvitation, viewed: Option<String>) -> RoomInvitation {
+    if invitation.first_viewed_at.is_none() {
+        invitation.first_viewed_at = viewed
+            .and_then(|v| DateTime::parse_from_rfc3339(&v).ok())
+            .map(|v| v.with_timezone(&Utc));
+    }
+    invitation
+}
@@ -0,0 +1,296 @@
+//! Secrets and signed tokens handed out to clients
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Outcome of checking an invite token without touching Redis
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InviteToken {
+    /// Signature checks out; the token was issued for `room_id`
+    Signed { room_id: String },
+    /// Pre-signing plain token, only Redis can tell whether it exists
+    Legacy,
+    /// Tampered or garbage, reject without a lookup
+    Invalid,
+}
+
+/// Outcome of checking a magic link token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MagicLink {
+    /// Signed by us and still live: stands for `invite_token` plus its code.
+    /// `nonce` names this link so it can only be redeemed once.
+    Valid {
+        invite_token: String,
+        nonce: String,
+        expires_at: i64,
+    },
+    /// Genuine but past its expiry
+    Expired,
+    /// Tampered or garbage
+    Invalid,
+}
+
+/// Generates host-only creator key (stored locally on creator device)
+pub fn generate_creator_key() -> String {
+    use rand::Rng;
+    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+    let mut rng = rand::rng();
+    (0..32)
+        .map(|_| {
+            let idx = rng.random_range(0..CHARSET.len());
+            CHARSET[idx] as char
+        })
+        .collect()
+}
+
+/// HMAC over `payload`, domain-separated by `purpose` so a token of one kind
+/// never verifies as another
+fn token_mac(secret: &str, purpose: &[u8], payload: &str) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(purpose);
+    mac.update(payload.as_bytes());
+    mac
+}
+
+fn invite_mac(secret: &str, payload: &str) -> HmacSha256 {
+    token_mac(secret, b"invite-token:", payload)
+}
+
+fn magic_link_mac(secret: &str, payload: &str) -> HmacSha256 {
+    token_mac(secret, b"magic-link:", payload)
+}
+
+fn token_nonce() -> String {
+    use rand::Rng;
+    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+    let mut rng = rand::rng();
+    (0..16)
+        .map(|_| CHARSET[rng.random_range(0..CHARSET.len())] as char)
+        .collect()
+}
+
+/// Issue an invite token bound to `room_id`: `base64url(room_id:nonce).base64url(hmac)`
+pub fn sign_invite_token(secret: &str, room_id: &str) -> String {
+    let payload = URL_SAFE_NO_PAD.encode(format!("{}:{}", room_id, token_nonce()));
+    let sig = URL_SAFE_NO_PAD.encode(invite_mac(secret, &payload).finalize().into_bytes());
+    format!("{}.{}", payload, sig)
+}
+
+/// Issue a one-click link token for `invite_token`, valid until `expires_at`
+/// (unix seconds): `base64url(invite_token:expires_at:nonce).base64url(hmac)`
+pub fn sign_magic_link(secret: &str, invite_token: &str, expires_at: i64) -> String {
+    let payload = format!("{}:{}:{}", invite_token, expires_at, token_nonce());
+    let payload = URL_SAFE_NO_PAD.encode(payload);
+    let sig = URL_SAFE_NO_PAD.encode(magic_link_mac(secret, &payload).finalize().into_bytes());
+    format!("{}.{}", payload, sig)
+}
+
+/// Check a magic link's signature (constant-time compare) and expiry at `now`
+pub fn verify_magic_link(secret: &str, token: &str, now: i64) -> MagicLink {
+    let Some((payload, sig)) = token.split_once('.') else {
+        return MagicLink::Invalid;
+    };
+    let Ok(sig) = URL_SAFE_NO_PAD.decode(sig) else {
+        return MagicLink::Invalid;
+    };
+    if magic_link_mac(secret, payload).verify_slice(&sig).is_err() {
+        return MagicLink::Invalid;
+    }
+
+    let Some(decoded) = URL_SAFE_NO_PAD
+        .decode(payload)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+    else {
+        return MagicLink::Invalid;
+    };
+    // Invite tokens are base64url with a '.', so the first ':' ends them
+    let mut parts = decoded.splitn(3, ':');
+    let (Some(invite_token), Some(expires_at), Some(nonce)) =
+        (parts.next(), parts.next(), parts.next())
+    else {
+        return MagicLink::Invalid;
+    };
+    let Ok(expires_at) = expires_at.parse::<i64>() else {
+        return MagicLink::Invalid;
+    };
+
+    if now >= expires_at {
+        return MagicLink::Expired;
+    }
+    MagicLink::Valid {
+        invite_token: invite_token.to_string(),
+        nonce: nonce.to_string(),
+        expires_at,
+    }
+}
+
+/// Check an invite token's shape and signature (constant-time compare)
+pub fn verify_invite_token(secret: &str, token: &str) -> InviteToken {
+    let Some((payload, sig)) = token.split_once('.') else {
+        // Legacy tokens were 24 alphanumerics; anything else can't exist in Redis
+        let legacy = !token.is_empty()
+            && token.len() <= 64
+            && token.chars().all(|c| c.is_ascii_alphanumeric());
+        return if legacy {
+            InviteToken::Legacy
+        } else {
+            InviteToken::Invalid
+        };
+    };
+
+    let Ok(sig) = URL_SAFE_NO_PAD.decode(sig) else {
+        return InviteToken::Invalid;
+    };
+    if invite_mac(secret, payload).verify_slice(&sig).is_err() {
+        return InviteToken::Invalid;
+    }
+
+    let room_id = URL_SAFE_NO_PAD
+        .decode(payload)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .and_then(|decoded| decoded.split_once(':').map(|(room, _)| room.to_string()));
+
+    match room_id {
+        Some(room_id) if !room_id.is_empty() => InviteToken::Signed { room_id },
+        _ => InviteToken::Invalid,
+    }
+}
+
+/// Whether a WebSocket upgrade from `origin` may proceed. No allowlist
+/// (dev) admits everything; with one, a browser's `Origin` must be on it
+/// (scheme, host and port, case-insensitive). Requests without an `Origin`
+/// header don't come from a browser page and can't be hijacked cross-site.
+pub fn origin_allowed(origin: Option<&str>, allowed: &[String]) -> bool {
+    let Some(origin) = origin else {
+        return true;
+    };
+    if allowed.is_empty() {
+        return true;
+    }
+
+    let origin = origin.trim().trim_end_matches('/');
+    allowed
+        .iter()
+        .any(|a| a.trim().trim_end_matches('/').eq_ignore_ascii_case(origin))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "test-secret";
+    const ROOM: &str = "6f1c2b1e-8a4d-4c3e-9b0a-1d2e3f405162";
+
+    #[test]
+    fn test_signed_token_round_trip() {
+        let token = sign_invite_token(SECRET, ROOM);
+        assert_eq!(
+            verify_invite_token(SECRET, &token),
+            InviteToken::Signed {
+                room_id: ROOM.to_string()
+            }
+        );
+        // Nonce makes every token unique
+        assert_ne!(token, sign_invite_token(SECRET, ROOM));
+    }
+
+    #[test]
+    fn test_tampered_or_foreign_tokens_are_invalid() {
+        let token = sign_invite_token(SECRET, ROOM);
+        let (payload, sig) = token.split_once('.').unwrap();
+
+        let other_payload = URL_SAFE_NO_PAD.encode(format!("{}:nonce", "other-room"));
+        let forged = format!("{}.{}", other_payload, sig);
+        assert_eq!(verify_invite_token(SECRET, &forged), InviteToken::Invalid);
+
+        assert_eq!(
+            verify_invite_token("other-secret", &token),
+            InviteToken::Invalid
+        );
+        assert_eq!(
+            verify_invite_token(SECRET, &format!("{}.", payload)),
+            InviteToken::Invalid
+        );
+    }
+
+    #[test]
+    fn test_legacy_tokens_fall_through() {
+        assert_eq!(
+            verify_invite_token(SECRET, "AbCdEfGhIjKlMnOpQrStUvWx"),
+            InviteToken::Legacy
+        );
+        assert_eq!(verify_invite_token(SECRET, ""), InviteToken::Invalid);
+        assert_eq!(
+            verify_invite_token(SECRET, "../../etc"),
+            InviteToken::Invalid
+        );
+    }
+
+    #[test]
+    fn test_magic_link_round_trip_and_expiry() {
+        let invite = sign_invite_token(SECRET, ROOM);
+        let link = sign_magic_link(SECRET, &invite, 1_000);
+
+        let MagicLink::Valid {
+            invite_token,
+            nonce,
+            expires_at,
+        } = verify_magic_link(SECRET, &link, 999)
+        else {
+            panic!("fresh magic link should verify");
+        };
+        assert_eq!(invite_token, invite);
+        assert_eq!(expires_at, 1_000);
+        // Every link is redeemable on its own
+        let other = sign_magic_link(SECRET, &invite, 1_000);
+        assert!(matches!(
+            verify_magic_link(SECRET, &other, 999),
+            MagicLink::Valid { nonce: n, .. } if n != nonce
+        ));
+
+        assert_eq!(verify_magic_link(SECRET, &link, 1_000), MagicLink::Expired);
+        assert_eq!(verify_magic_link(SECRET, &link, 5_000), MagicLink::Expired);
+    }
+
+    #[test]
+    fn test_magic_link_rejects_forgeries() {
+        let invite = sign_invite_token(SECRET, ROOM);
+        let link = sign_magic_link(SECRET, &invite, 1_000);
+        let (_, sig) = link.split_once('.').unwrap();
+
+        // Pushing the expiry out breaks the signature
+        let later = URL_SAFE_NO_PAD.encode(format!("{}:9999:nonce", invite));
+        let forged = format!("{}.{}", later, sig);
+        assert_eq!(verify_magic_link(SECRET, &forged, 0), MagicLink::Invalid);
+        assert_eq!(
+            verify_magic_link("other-secret", &link, 0),
+            MagicLink::Invalid
+        );
+        // An invite token is signed for another purpose and never passes
+        assert_eq!(verify_magic_link(SECRET, &invite, 0), MagicLink::Invalid);
+        assert_eq!(verify_magic_link(SECRET, "garbage", 0), MagicLink::Invalid);
+    }
+
+    #[test]
+    fn test_origin_allowlist() {
+        let allowed = vec!["https://meet.truegather.io".to_string()];
+
+        assert!(origin_allowed(Some("https://meet.truegather.io"), &allowed));
+        let shouted = Some("HTTPS://MEET.truegather.io/");
+        assert!(origin_allowed(shouted, &allowed));
+        assert!(!origin_allowed(Some("https://evil.example"), &allowed));
+        assert!(!origin_allowed(Some("http://meet.truegather.io"), &allowed));
+        assert!(!origin_allowed(Some("null"), &allowed));
+        // Non-browser clients send no Origin
+        assert!(origin_allowed(None, &allowed));
+        // No allowlist configured (dev)
+        assert!(origin_allowed(Some("https://evil.example"), &[]));
+    }
+}
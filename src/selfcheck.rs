@@ -0,0 +1,190 @@
+//! `--check` startup self-test
+//!
+//! Validates the configuration and every external dependency (Redis, TURN,
+//! the mail provider) without starting the server, so a deploy pipeline can
+//! refuse a release that would come up broken. Optional dependencies that are
+//! not configured are reported as skipped, not failed.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::mail::Mailer;
+use crate::redis::{create_pool, RoomRepository};
+use crate::turn::{self, TurnTarget};
+
+/// Upper bound for each network check
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Shortest JWT secret accepted (`openssl rand -hex 32` gives 64)
+const MIN_JWT_SECRET_LEN: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    /// Optional dependency that isn't configured
+    Skip,
+}
+
+impl CheckStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "PASS",
+            CheckStatus::Fail => "FAIL",
+            CheckStatus::Skip => "SKIP",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn new(name: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Outcome of every check, printed one per line
+#[derive(Debug, Clone, Default)]
+pub struct CheckReport {
+    pub results: Vec<CheckResult>,
+}
+
+impl CheckReport {
+    /// True when nothing failed (skipped checks don't count)
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|r| r.status != CheckStatus::Fail)
+    }
+}
+
+impl fmt::Display for CheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for result in &self.results {
+            writeln!(
+                f,
+                "{}  {:<10} {}",
+                result.status.as_str(),
+                result.name,
+                result.detail
+            )?;
+        }
+        write!(f, "{}", if self.passed() { "OK" } else { "FAILED" })
+    }
+}
+
+/// Why a JWT secret is too weak to sign tokens with, if it is
+pub fn jwt_secret_weakness(secret: &str) -> Option<String> {
+    let secret = secret.trim();
+    if secret.to_ascii_lowercase().contains("change_me") {
+        return Some("JWT_SECRET is still the .env.example placeholder".to_string());
+    }
+    if secret.len() < MIN_JWT_SECRET_LEN {
+        return Some(format!(
+            "JWT_SECRET is shorter than {} characters (generate one with `openssl rand -hex 32`)",
+            MIN_JWT_SECRET_LEN
+        ));
+    }
+    None
+}
+
+/// Run every check against `config`, which has already passed `Config::from_env`
+pub async fn run(config: &Config) -> CheckReport {
+    let results = vec![
+        check_config(config),
+        check_redis(config).await,
+        check_turn(config).await,
+        check_mailer().await,
+    ];
+    CheckReport { results }
+}
+
+fn check_config(config: &Config) -> CheckResult {
+    match jwt_secret_weakness(&config.jwt_secret) {
+        Some(weakness) => CheckResult::new("config", CheckStatus::Fail, weakness),
+        None => CheckResult::new("config", CheckStatus::Pass, "valid"),
+    }
+}
+
+async fn check_redis(config: &Config) -> CheckResult {
+    let pool = match create_pool(config) {
+        Ok(pool) => pool,
+        Err(e) => return CheckResult::new("redis", CheckStatus::Fail, e.to_string()),
+    };
+    let repo = RoomRepository::new(pool);
+
+    match tokio::time::timeout(CHECK_TIMEOUT, repo.health_check()).await {
+        Ok(Ok(true)) => CheckResult::new("redis", CheckStatus::Pass, "connected"),
+        Ok(Ok(false)) => CheckResult::new("redis", CheckStatus::Fail, "unexpected PING reply"),
+        Ok(Err(e)) => CheckResult::new("redis", CheckStatus::Fail, e.to_string()),
+        Err(_) => CheckResult::new("redis", CheckStatus::Fail, "timed out"),
+    }
+}
+
+async fn check_turn(config: &Config) -> CheckResult {
+    let Some(uri) = config.turn_server.as_deref() else {
+        return CheckResult::new("turn", CheckStatus::Skip, "TURN_SERVER not set");
+    };
+    let Some(target) = TurnTarget::parse(uri) else {
+        let detail = format!("cannot parse TURN_SERVER {}", uri);
+        return CheckResult::new("turn", CheckStatus::Fail, detail);
+    };
+
+    if turn::probe(&target, CHECK_TIMEOUT).await {
+        CheckResult::new("turn", CheckStatus::Pass, format!("{} reachable", uri))
+    } else {
+        CheckResult::new("turn", CheckStatus::Fail, format!("{} unreachable", uri))
+    }
+}
+
+async fn check_mailer() -> CheckResult {
+    let Ok(mailer) = Mailer::new_from_env() else {
+        return CheckResult::new("mailer", CheckStatus::Skip, "RESEND_API_KEY not set");
+    };
+
+    match tokio::time::timeout(CHECK_TIMEOUT, mailer.verify_credentials()).await {
+        Ok(Ok(())) => CheckResult::new("mailer", CheckStatus::Pass, "API key accepted"),
+        Ok(Err(e)) => CheckResult::new("mailer", CheckStatus::Fail, e.to_string()),
+        Err(_) => CheckResult::new("mailer", CheckStatus::Fail, "timed out"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jwt_secret_strength() {
+        assert!(jwt_secret_weakness(&"ab".repeat(32)).is_none());
+        assert!(jwt_secret_weakness("devsecret").is_some());
+        assert!(jwt_secret_weakness("CHANGE_ME_GENERATE_64_HEX_0000000000000000").is_some());
+    }
+
+    #[test]
+    fn test_report_fails_only_on_failed_checks() {
+        let mut report = CheckReport {
+            results: vec![
+                CheckResult::new("redis", CheckStatus::Pass, "connected"),
+                CheckResult::new("turn", CheckStatus::Skip, "TURN_SERVER not set"),
+            ],
+        };
+        assert!(report.passed());
+        assert!(report.to_string().ends_with("OK"));
+
+        report
+            .results
+            .push(CheckResult::new("mailer", CheckStatus::Fail, "timed out"));
+        assert!(!report.passed());
+        assert!(report.to_string().contains("FAIL  mailer     timed out"));
+        assert!(report.to_string().ends_with("FAILED"));
+    }
+}
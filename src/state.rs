@@ -1,11 +1,18 @@
+use std::sync::atomic::AtomicU64;
 use std::sync::Arc;
 
-use crate::auth::AuthService;
+use chrono::{DateTime, Utc};
+
+use crate::auth::{turn_rest_credentials, AuthService};
 use crate::config::Config;
+use crate::error::Result;
 use crate::mail::Mailer;
 use crate::media::MediaGateway;
+use crate::metrics::{InviteFunnel, InviteStage};
+use crate::models::IceServer;
 use crate::redis::RoomRepository;
-use crate::ws::ConnectionsManager;
+use crate::turn::TurnHealth;
+use crate::ws::{ConnectionsManager, PendingLeaves, PublisherChangeBuffer};
 
 /// Shared application state
 #[derive(Clone)]
@@ -15,7 +22,20 @@ pub struct AppState {
     pub room_repo: Arc<RoomRepository>,
     pub media_gateway: Arc<MediaGateway>,
     pub connections: Arc<ConnectionsManager>,
-    pub mailer: Arc<Mailer>,
+    pub pending_leaves: Arc<PendingLeaves>,
+    pub publisher_changes: Arc<PublisherChangeBuffer>,
+    /// None when email isn't configured (`RESEND_API_KEY` unset)
+    pub mailer: Option<Arc<Mailer>>,
+    pub turn_health: Arc<TurnHealth>,
+    /// Publishers found in only one of Redis and the media gateway, and removed
+    pub publisher_inconsistencies: Arc<AtomicU64>,
+    /// Invitation funnel counters, served at `/metrics`
+    pub invite_funnel: Arc<InviteFunnel>,
+    /// Process start, for uptime reporting
+    pub started_at: DateTime<Utc>,
+    /// Random id of this process, reported so a client's issue can be traced
+    /// to the instance that served it
+    pub instance_id: String,
 }
 
 impl AppState {
@@ -24,7 +44,7 @@ impl AppState {
         auth: AuthService,
         room_repo: RoomRepository,
         media_gateway: MediaGateway,
-        mailer: Mailer,
+        mailer: Option<Mailer>,
     ) -> Self {
         Self {
             config: Arc::new(config),
@@ -32,7 +52,72 @@ impl AppState {
             room_repo: Arc::new(room_repo),
             media_gateway: Arc::new(media_gateway),
             connections: Arc::new(ConnectionsManager::new()),
-            mailer: Arc::new(mailer),
+            pending_leaves: Arc::new(PendingLeaves::new()),
+            publisher_changes: Arc::new(PublisherChangeBuffer::new()),
+            mailer: mailer.map(Arc::new),
+            turn_health: Arc::new(TurnHealth::new()),
+            publisher_inconsistencies: Arc::new(AtomicU64::new(0)),
+            invite_funnel: Arc::new(InviteFunnel::new()),
+            started_at: Utc::now(),
+            instance_id: uuid::Uuid::new_v4().to_string(),
+        }
+    }
+
+    /// `X-Served-By` value: `<region>/<instance_id>`, or the bare instance id
+    /// without `SERVER_REGION`
+    pub fn served_by(&self) -> String {
+        match &self.config.server_region {
+            Some(region) => format!("{}/{}", region, self.instance_id),
+            None => self.instance_id.clone(),
+        }
+    }
+
+    /// Count an invitation reaching `stage` (no-op without `METRICS_ENABLED`)
+    pub fn record_invite(&self, stage: InviteStage) {
+        if self.config.metrics_enabled {
+            self.invite_funnel.record(stage);
+        }
+    }
+
+    /// Maintenance is on through the config or the runtime flag
+    pub async fn in_maintenance(&self) -> Result<bool> {
+        Ok(self.config.maintenance_mode || self.room_repo.is_maintenance().await?)
+    }
+
+    /// ICE servers advertised to a client. TURN credentials are minted fresh
+    /// (TURN REST API) when `turn_secret` is configured, otherwise the static ones are used.
+    /// With `turn_hide_unreachable`, TURN is left out while its last probe failed.
+    pub fn ice_servers(&self, user_id: &str) -> Vec<IceServer> {
+        let mut ice_servers = vec![IceServer {
+            urls: vec![self.config.stun_server.clone()],
+            username: None,
+            credential: None,
+        }];
+
+        let turn_down = self.config.turn_hide_unreachable
+            && self.turn_health.reachable() == Some(false);
+
+        if let Some(turn_server) = self.config.turn_server.as_ref().filter(|_| !turn_down) {
+            let (username, credential) = match &self.config.turn_secret {
+                Some(secret) => {
+                    let expires_at =
+                        Utc::now().timestamp() + self.config.turn_credential_ttl_seconds as i64;
+                    let (username, credential) = turn_rest_credentials(secret, user_id, expires_at);
+                    (Some(username), Some(credential))
+                }
+                None => (
+                    self.config.turn_username.clone(),
+                    self.config.turn_credential.clone(),
+                ),
+            };
+
+            ice_servers.push(IceServer {
+                urls: vec![turn_server.clone()],
+                username,
+                credential,
+            });
         }
+
+        ice_servers
     }
 }
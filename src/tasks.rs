@@ -0,0 +1,560 @@
+//! Background maintenance tasks spawned at startup
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+
+use crate::error::AppError;
+use crate::state::AppState;
+use crate::turn::{self, TurnTarget};
+use crate::ws::{
+    announce_publisher_change, msg_types, PublisherChange, PublisherLeftPayload,
+    RoomClosingPayload, SignalingMessage, ViewerCountPayload,
+};
+
+/// Periodically drop media rooms nobody is connected to anymore.
+///
+/// `cleanup_room` normally runs when the last client leaves, but abnormal
+/// disconnects can skip it and leave closed peer connections in memory.
+/// A room is reaped once it has no signaling clients and all of its peer
+/// connections are closed or failed.
+pub fn spawn_media_reaper(state: AppState) -> JoinHandle<()> {
+    let period = Duration::from_secs(state.config.media_reaper_interval_seconds.max(1));
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            reap_media_rooms(&state).await;
+        }
+    })
+}
+
+/// Periodically ask publishers for a keyframe so subscribers recover from
+/// freezes without waiting for the encoder's next natural keyframe.
+/// Returns `None` when `keyframe_interval_seconds` is 0 (the default).
+pub fn spawn_keyframe_ticker(state: AppState) -> Option<JoinHandle<()>> {
+    if state.config.keyframe_interval_seconds == 0 {
+        return None;
+    }
+    let period = Duration::from_secs(state.config.keyframe_interval_seconds);
+
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            interval.tick().await;
+            state.media_gateway.request_keyframes_all().await;
+        }
+    }))
+}
+
+/// Periodically probe the configured TURN server and record the result in
+/// `state.turn_health`. Failures are only reported, never fatal.
+/// Returns `None` without a TURN server or when the interval is 0.
+pub fn spawn_turn_health_check(state: AppState) -> Option<JoinHandle<()>> {
+    if state.config.turn_health_check_interval_seconds == 0 {
+        return None;
+    }
+    let uri = state.config.turn_server.as_ref()?;
+    let Some(target) = TurnTarget::parse(uri) else {
+        tracing::warn!(turn_server = %uri, "Cannot parse TURN_SERVER, health check disabled");
+        return None;
+    };
+    let period = Duration::from_secs(state.config.turn_health_check_interval_seconds);
+    let timeout = period.min(Duration::from_secs(5));
+
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            let reachable = turn::probe(&target, timeout).await;
+            state.turn_health.record(reachable).await;
+        }
+    }))
+}
+
+/// Periodically prune expired invitation references from every room's
+/// `room:<id>:invites` set, which otherwise only shrinks when the room's
+/// invitations are listed. Returns `None` when `invite_reaper_interval_seconds` is 0.
+pub fn spawn_invite_reaper(state: AppState) -> Option<JoinHandle<()>> {
+    if state.config.invite_reaper_interval_seconds == 0 {
+        return None;
+    }
+    let period = Duration::from_secs(state.config.invite_reaper_interval_seconds);
+
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            if let Err(e) = reap_expired_invites(&state).await {
+                tracing::warn!(error = %e, "Invitation reaper failed");
+            }
+        }
+    }))
+}
+
+async fn reap_expired_invites(state: &AppState) -> crate::error::Result<()> {
+    for room_id in state.room_repo.invite_set_room_ids().await? {
+        state.room_repo.prune_room_invites(&room_id).await?;
+    }
+    Ok(())
+}
+
+/// Delete rooms nobody has joined within `unjoined_room_timeout_seconds` of
+/// their creation instead of letting them sit out their whole TTL.
+/// Returns `None` when the timeout is 0.
+pub fn spawn_unjoined_room_reaper(state: AppState) -> Option<JoinHandle<()>> {
+    if state.config.unjoined_room_timeout_seconds == 0 {
+        return None;
+    }
+    let timeout = state.config.unjoined_room_timeout_seconds;
+    let period = Duration::from_secs((timeout / 4).clamp(1, 60));
+
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            if let Err(e) = reap_unjoined_rooms(&state, timeout).await {
+                tracing::warn!(error = %e, "Unjoined room reaper failed");
+            }
+        }
+    }))
+}
+
+async fn reap_unjoined_rooms(state: &AppState, timeout: u64) -> crate::error::Result<()> {
+    let now = chrono::Utc::now();
+    for room_id in state.room_repo.room_ids().await? {
+        // Someone is connecting right now
+        if state.connections.get_room(&room_id).is_some() {
+            continue;
+        }
+        // Joins record members under the room lock, so one racing this
+        // check either activates the room first or finds it deleted
+        let reaped = state
+            .room_repo
+            .with_room_lock(&room_id, async {
+                let Some(room) = state.room_repo.get_room(&room_id).await? else {
+                    return Ok(());
+                };
+                let activated = state.room_repo.is_activated(&room_id).await?;
+                let members = state.room_repo.get_member_count(&room_id).await?;
+                if room.unjoined_expired(activated, members, timeout, now) {
+                    tracing::info!(room_id = %room_id, "Deleting room nobody joined");
+                    state.room_repo.delete_room(&room_id).await?;
+                }
+                Ok(())
+            })
+            .await;
+        match reaped {
+            // Busy with a join or another change: not unjoined for long
+            Err(AppError::Conflict(_)) => continue,
+            other => other?,
+        }
+    }
+    Ok(())
+}
+
+/// Warn clients of rooms about to expire with `room_closing`, once per room
+/// (again if the room is extended and later nears expiry anew).
+/// Returns `None` when `room_closing_warning_seconds` is 0.
+pub fn spawn_room_closing_warner(state: AppState) -> Option<JoinHandle<()>> {
+    if state.config.room_closing_warning_seconds == 0 {
+        return None;
+    }
+    let warning = state.config.room_closing_warning_seconds;
+    let period = Duration::from_secs((warning / 4).clamp(1, 30));
+
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut warned: HashSet<String> = HashSet::new();
+
+        loop {
+            interval.tick().await;
+            warn_closing_rooms(&state, warning, &mut warned).await;
+        }
+    }))
+}
+
+async fn warn_closing_rooms(state: &AppState, warning: u64, warned: &mut HashSet<String>) {
+    let room_ids = state.connections.room_ids();
+    warned.retain(|room_id| room_ids.contains(room_id));
+
+    for room_id in room_ids {
+        let expires_in = match state.room_repo.room_ttl(&room_id).await {
+            Ok(Some(ttl)) => ttl,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::warn!(room_id = %room_id, error = %e, "Failed to read room TTL");
+                continue;
+            }
+        };
+        if expires_in > warning {
+            warned.remove(&room_id);
+            continue;
+        }
+        if !warned.insert(room_id.clone()) {
+            continue;
+        }
+
+        tracing::info!(room_id = %room_id, expires_in, "Room closing soon");
+        let payload = RoomClosingPayload {
+            room_id: room_id.clone(),
+            expires_in,
+        };
+        match serde_json::to_value(payload) {
+            Ok(payload) => state.connections.broadcast_to_room(
+                &room_id,
+                SignalingMessage::new(msg_types::ROOM_CLOSING, payload),
+                None,
+            ),
+            Err(e) => tracing::error!(room_id = %room_id, error = %e, "Failed to encode room_closing"),
+        }
+    }
+}
+
+/// Remove "ghost" publishers: feeds whose uplink died while the peer
+/// connection still looks alive, leaving subscribers on a frozen frame.
+/// Returns `None` when `publisher_inactivity_timeout_seconds` is 0.
+pub fn spawn_publisher_reaper(state: AppState) -> Option<JoinHandle<()>> {
+    if state.config.publisher_inactivity_timeout_seconds == 0 {
+        return None;
+    }
+    let timeout = Duration::from_secs(state.config.publisher_inactivity_timeout_seconds);
+    let period = (timeout / 4).max(Duration::from_secs(1));
+
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            reap_inactive_publishers(&state, timeout).await;
+        }
+    }))
+}
+
+async fn reap_inactive_publishers(state: &AppState, timeout: Duration) {
+    for publisher in state.media_gateway.inactive_publishers(timeout).await {
+        if !state
+            .media_gateway
+            .remove_publisher(&publisher.room_id, &publisher.user_id)
+            .await
+        {
+            continue;
+        }
+        let _ = state
+            .room_repo
+            .remove_publisher(&publisher.room_id, &publisher.user_id)
+            .await;
+
+        tracing::info!(
+            room_id = %publisher.room_id,
+            user_id = %publisher.user_id,
+            feed_id = %publisher.feed_id,
+            idle_secs = publisher.idle.as_secs(),
+            "Reaped inactive publisher"
+        );
+
+        announce_publisher_change(
+            state,
+            &publisher.room_id,
+            PublisherChange::Left(PublisherLeftPayload {
+                feed_id: publisher.feed_id.clone(),
+                room_id: publisher.room_id.clone(),
+            }),
+            None,
+        );
+
+        if let Some(room) = state.connections.get_room(&publisher.room_id) {
+            room.remove_feed(&publisher.feed_id);
+        }
+    }
+}
+
+/// Which store a publisher was found in when the other has no trace of it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PublisherDrift {
+    /// Listed in `room:<id>:publishers` without a media session (clients see
+    /// a feed nobody can subscribe to)
+    RedisOnly,
+    /// Media session without a Redis entry (a feed late joiners never hear of)
+    GatewayOnly,
+}
+
+/// Publishers, as (user_id, feed_id), present in one store but not the other.
+/// A user listed in both with different feeds is left alone: that is a
+/// republish in flight, not drift.
+pub fn publisher_drift(
+    redis: &[(String, String)],
+    gateway: &[(String, String)],
+) -> Vec<(PublisherDrift, String, String)> {
+    let mut drift = one_sided(PublisherDrift::RedisOnly, redis, gateway);
+    drift.extend(one_sided(PublisherDrift::GatewayOnly, gateway, redis));
+    drift
+}
+
+fn one_sided(
+    side: PublisherDrift,
+    ours: &[(String, String)],
+    theirs: &[(String, String)],
+) -> Vec<(PublisherDrift, String, String)> {
+    ours.iter()
+        .filter(|(user_id, _)| !theirs.iter().any(|(other, _)| other == user_id))
+        .map(|(user_id, feed_id)| (side, user_id.clone(), feed_id.clone()))
+        .collect()
+}
+
+/// Reconcile Redis publisher entries with the media gateway. The publish and
+/// leave flows write to both without a transaction, so one of them can miss;
+/// the stray side is removed and the room told the feed is gone.
+///
+/// A mismatch is only acted on when it is seen on two passes in a row, so a
+/// publish caught between its two writes is never torn down. Rooms are taken
+/// from this instance's connections and media state; a room's media is
+/// assumed to live on one instance, as subscribing already requires.
+/// Returns `None` when `publisher_reconcile_interval_seconds` is 0.
+pub fn spawn_publisher_reconciler(state: AppState) -> Option<JoinHandle<()>> {
+    if state.config.publisher_reconcile_interval_seconds == 0 {
+        return None;
+    }
+    let period = Duration::from_secs(state.config.publisher_reconcile_interval_seconds);
+
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut suspects = HashSet::new();
+
+        loop {
+            interval.tick().await;
+            suspects = reconcile_publishers(&state, &suspects).await;
+        }
+    }))
+}
+
+type DriftKey = (String, PublisherDrift, String, String); // room_id, side, user_id, feed_id
+
+/// One reconciliation pass; returns this pass's mismatches for the next one
+async fn reconcile_publishers(state: &AppState, suspects: &HashSet<DriftKey>) -> HashSet<DriftKey> {
+    let mut room_ids: HashSet<String> = state.connections.room_ids().into_iter().collect();
+    room_ids.extend(state.media_gateway.room_ids());
+
+    let mut seen = HashSet::new();
+    for room_id in room_ids {
+        let redis: Vec<(String, String)> = match state.room_repo.get_publishers(&room_id).await {
+            Ok(publishers) => publishers
+                .into_iter()
+                .map(|p| (p.user_id, p.feed_id))
+                .collect(),
+            Err(e) => {
+                tracing::warn!(room_id = %room_id, error = %e, "Failed to read publishers");
+                continue;
+            }
+        };
+        let gateway = state.media_gateway.publisher_feeds(&room_id).await;
+
+        for (side, user_id, feed_id) in publisher_drift(&redis, &gateway) {
+            let key = (room_id.clone(), side, user_id, feed_id);
+            if suspects.contains(&key) {
+                fix_publisher_drift(state, &key).await;
+            } else {
+                seen.insert(key);
+            }
+        }
+    }
+    seen
+}
+
+async fn fix_publisher_drift(state: &AppState, (room_id, side, user_id, feed_id): &DriftKey) {
+    match side {
+        PublisherDrift::RedisOnly => {
+            if let Err(e) = state.room_repo.remove_publisher(room_id, user_id).await {
+                tracing::warn!(room_id = %room_id, error = %e, "Failed to remove stale publisher");
+                return;
+            }
+        }
+        PublisherDrift::GatewayOnly => {
+            state.media_gateway.remove_publisher(room_id, user_id).await;
+        }
+    }
+    state
+        .publisher_inconsistencies
+        .fetch_add(1, Ordering::Relaxed);
+
+    tracing::warn!(
+        room_id = %room_id,
+        user_id = %user_id,
+        feed_id = %feed_id,
+        drift = ?side,
+        "Removed publisher missing from one store"
+    );
+
+    announce_publisher_change(
+        state,
+        room_id,
+        PublisherChange::Left(PublisherLeftPayload {
+            feed_id: feed_id.clone(),
+            room_id: room_id.clone(),
+        }),
+        None,
+    );
+    if let Some(room) = state.connections.get_room(room_id) {
+        room.remove_feed(feed_id);
+    }
+}
+
+/// Periodically tell rooms how many subscribers each feed has, with one
+/// `viewer_count` per feed whose count changed since the last tick.
+/// Returns `None` when `viewer_count_interval_seconds` is 0.
+pub fn spawn_viewer_count_broadcaster(state: AppState) -> Option<JoinHandle<()>> {
+    if state.config.viewer_count_interval_seconds == 0 {
+        return None;
+    }
+    let period = Duration::from_secs(state.config.viewer_count_interval_seconds);
+
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut last = HashMap::new();
+
+        loop {
+            interval.tick().await;
+            last = broadcast_viewer_counts(&state, &last).await;
+        }
+    }))
+}
+
+type FeedKey = (String, String); // room_id, feed_id
+
+/// Feeds whose subscriber count differs from the previous pass. A feed not
+/// seen before starts from 0 (`publisher_joined` implies no viewers yet);
+/// feeds that are gone are left to `publisher_left`.
+pub fn changed_viewer_counts(
+    last: &HashMap<FeedKey, usize>,
+    current: &HashMap<FeedKey, usize>,
+) -> Vec<(FeedKey, usize)> {
+    let mut changed: Vec<(FeedKey, usize)> = current
+        .iter()
+        .filter(|(key, count)| last.get(*key).copied().unwrap_or(0) != **count)
+        .map(|(key, count)| (key.clone(), *count))
+        .collect();
+    changed.sort();
+    changed
+}
+
+/// One broadcast pass; returns this pass's counts for the next one
+async fn broadcast_viewer_counts(
+    state: &AppState,
+    last: &HashMap<FeedKey, usize>,
+) -> HashMap<FeedKey, usize> {
+    let mut current = HashMap::new();
+    for room_id in state.media_gateway.room_ids() {
+        for (feed_id, count) in state.media_gateway.feed_subscriber_counts(&room_id).await {
+            current.insert((room_id.clone(), feed_id), count);
+        }
+    }
+
+    for ((room_id, feed_id), viewer_count) in changed_viewer_counts(last, &current) {
+        let payload = ViewerCountPayload {
+            room_id: room_id.clone(),
+            feed_id,
+            viewer_count,
+        };
+        match serde_json::to_value(payload) {
+            Ok(payload) => state.connections.broadcast_to_room(
+                &room_id,
+                SignalingMessage::new(msg_types::VIEWER_COUNT, payload),
+                None,
+            ),
+            Err(e) => {
+                tracing::error!(room_id = %room_id, error = %e, "Failed to encode viewer_count")
+            }
+        }
+    }
+    current
+}
+
+async fn reap_media_rooms(state: &AppState) {
+    for room_id in state.media_gateway.room_ids() {
+        let has_clients = state
+            .connections
+            .get_room(&room_id)
+            .is_some_and(|room| !room.is_empty());
+        if has_clients || !state.media_gateway.is_room_dead(&room_id).await {
+            continue;
+        }
+
+        tracing::info!(room_id = %room_id, "Reaping orphaned media room");
+        state.media_gateway.cleanup_room(&room_id).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feeds(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(user_id, feed_id)| (user_id.to_string(), feed_id.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_publisher_drift_finds_one_sided_entries() {
+        let redis = feeds(&[("alice", "f-a"), ("bob", "f-b"), ("carol", "f-c1")]);
+        let gateway = feeds(&[("alice", "f-a"), ("carol", "f-c2"), ("dave", "f-d")]);
+
+        let drift: Vec<_> = publisher_drift(&redis, &gateway)
+            .into_iter()
+            .map(|(side, user_id, _)| (side, user_id))
+            .collect();
+        assert_eq!(
+            drift,
+            [
+                (PublisherDrift::RedisOnly, "bob".to_string()),
+                (PublisherDrift::GatewayOnly, "dave".to_string()),
+            ]
+        );
+        assert!(publisher_drift(&redis, &redis).is_empty());
+    }
+
+    #[test]
+    fn test_viewer_count_follows_subscribers() {
+        let key = |feed_id: &str| ("room".to_string(), feed_id.to_string());
+        let counts = |entries: &[(&str, usize)]| -> HashMap<FeedKey, usize> {
+            entries.iter().map(|(feed, n)| (key(feed), *n)).collect()
+        };
+
+        // A new feed nobody watches yet is not announced
+        let published = counts(&[("f-a", 0)]);
+        assert!(changed_viewer_counts(&HashMap::new(), &published).is_empty());
+
+        // Two subscribe, then one unsubscribes
+        let subscribed = counts(&[("f-a", 2)]);
+        assert_eq!(
+            changed_viewer_counts(&published, &subscribed),
+            [(key("f-a"), 2)]
+        );
+        let unsubscribed = counts(&[("f-a", 1)]);
+        assert_eq!(
+            changed_viewer_counts(&subscribed, &unsubscribed),
+            [(key("f-a"), 1)]
+        );
+
+        // Nothing moved, nothing sent
+        assert!(changed_viewer_counts(&unsubscribed, &unsubscribed).is_empty());
+    }
+}
@@ -0,0 +1,298 @@
+//! TURN server reachability tracking
+//!
+//! A dead TURN server shows up on the client side only as "can't connect"
+//! behind NAT. The probe sends a STUN Binding request (every TURN server
+//! answers those) over the server's transport, or just opens the connection
+//! for `turns:`, and the result is kept in [`TurnHealth`] for `/ready`, the
+//! admin stats and the ICE server list.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{lookup_host, TcpStream, UdpSocket};
+use tokio::sync::RwLock;
+
+const STUN_BINDING_REQUEST: u16 = 0x0001;
+const STUN_BINDING_SUCCESS: u16 = 0x0101;
+const STUN_MAGIC_COOKIE: u32 = 0x2112_A442;
+const STUN_HEADER_LEN: usize = 20;
+
+/// Failed probes in a row before TURN counts as unreachable, so a single lost
+/// UDP datagram doesn't flip `/ready` or hide the server from clients
+const FAILURES_BEFORE_UNREACHABLE: u64 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TurnTransport {
+    Udp,
+    Tcp,
+    Tls,
+}
+
+/// Where to probe, parsed from a `turn:`/`turns:` URI (RFC 7065)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurnTarget {
+    pub host: String,
+    pub port: u16,
+    pub transport: TurnTransport,
+}
+
+impl TurnTarget {
+    /// Parse `turn:host[:port][?transport=udp|tcp]` or `turns:host[:port]`
+    pub fn parse(uri: &str) -> Option<Self> {
+        let (secure, rest) = if let Some(rest) = uri.strip_prefix("turns:") {
+            (true, rest)
+        } else {
+            (false, uri.strip_prefix("turn:")?)
+        };
+
+        let (authority, query) = match rest.split_once('?') {
+            Some((authority, query)) => (authority, Some(query)),
+            None => (rest, None),
+        };
+
+        let transport = if secure {
+            TurnTransport::Tls
+        } else {
+            match query.and_then(|q| q.strip_prefix("transport=")) {
+                Some(t) if t.eq_ignore_ascii_case("tcp") => TurnTransport::Tcp,
+                Some(t) if t.eq_ignore_ascii_case("udp") => TurnTransport::Udp,
+                Some(_) => return None,
+                None => TurnTransport::Udp,
+            }
+        };
+        let default_port = if secure { 5349 } else { 3478 };
+
+        // Bracketed IPv6 literal: [::1]:3478
+        let (host, port) = if let Some(v6) = authority.strip_prefix('[') {
+            let (host, after) = v6.split_once(']')?;
+            match after.strip_prefix(':') {
+                Some(port) => (host, port.parse().ok()?),
+                None if after.is_empty() => (host, default_port),
+                None => return None,
+            }
+        } else {
+            match authority.rsplit_once(':') {
+                Some((host, port)) => (host, port.parse().ok()?),
+                None => (authority, default_port),
+            }
+        };
+
+        if host.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            host: host.to_string(),
+            port,
+            transport,
+        })
+    }
+}
+
+/// Probe a TURN server once; `false` on any failure or timeout
+pub async fn probe(target: &TurnTarget, timeout: Duration) -> bool {
+    match tokio::time::timeout(timeout, probe_inner(target)).await {
+        Ok(Ok(reachable)) => reachable,
+        Ok(Err(e)) => {
+            tracing::debug!(host = %target.host, port = target.port, error = %e, "TURN probe failed");
+            false
+        }
+        Err(_) => false,
+    }
+}
+
+async fn probe_inner(target: &TurnTarget) -> std::io::Result<bool> {
+    let addr = lookup_host((target.host.as_str(), target.port))
+        .await?
+        .next()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no address"))?;
+
+    let (request, transaction_id) = binding_request();
+    let mut response = [0u8; 512];
+
+    match target.transport {
+        TurnTransport::Udp => {
+            let bind_addr = if addr.is_ipv4() {
+                "0.0.0.0:0"
+            } else {
+                "[::]:0"
+            };
+            let socket = UdpSocket::bind(bind_addr).await?;
+            socket.connect(addr).await?;
+            socket.send(&request).await?;
+            let len = socket.recv(&mut response).await?;
+            Ok(is_binding_success(&response[..len], &transaction_id))
+        }
+        TurnTransport::Tcp => {
+            let mut stream = TcpStream::connect(addr).await?;
+            stream.write_all(&request).await?;
+            stream.read_exact(&mut response[..STUN_HEADER_LEN]).await?;
+            Ok(is_binding_success(
+                &response[..STUN_HEADER_LEN],
+                &transaction_id,
+            ))
+        }
+        // A TLS handshake would need a TLS stack; an accepted connection is
+        // enough to tell a dead server from a live one
+        TurnTransport::Tls => {
+            TcpStream::connect(addr).await?;
+            Ok(true)
+        }
+    }
+}
+
+/// STUN Binding request without attributes, and its transaction id
+fn binding_request() -> ([u8; STUN_HEADER_LEN], [u8; 12]) {
+    let mut transaction_id = [0u8; 12];
+    rand::rng().fill(&mut transaction_id);
+
+    let mut request = [0u8; STUN_HEADER_LEN];
+    request[0..2].copy_from_slice(&STUN_BINDING_REQUEST.to_be_bytes());
+    // Message length stays 0
+    request[4..8].copy_from_slice(&STUN_MAGIC_COOKIE.to_be_bytes());
+    request[8..20].copy_from_slice(&transaction_id);
+
+    (request, transaction_id)
+}
+
+fn is_binding_success(response: &[u8], transaction_id: &[u8; 12]) -> bool {
+    response.len() >= STUN_HEADER_LEN
+        && response[0..2] == STUN_BINDING_SUCCESS.to_be_bytes()
+        && response[4..8] == STUN_MAGIC_COOKIE.to_be_bytes()
+        && &response[8..20] == transaction_id
+}
+
+/// Last known TURN reachability, updated by the background checker
+#[derive(Debug, Default)]
+pub struct TurnHealth {
+    checked: AtomicBool,
+    reachable: AtomicBool,
+    failed_checks: AtomicU64,
+    consecutive_failures: AtomicU64,
+    last_checked_at: RwLock<Option<DateTime<Utc>>>,
+}
+
+impl TurnHealth {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the outcome of a probe. A success counts at once; failures only
+    /// mark TURN unreachable after `FAILURES_BEFORE_UNREACHABLE` in a row.
+    pub async fn record(&self, reachable: bool) {
+        let streak = if reachable {
+            self.consecutive_failures.store(0, Ordering::SeqCst);
+            0
+        } else {
+            self.failed_checks.fetch_add(1, Ordering::Relaxed);
+            self.consecutive_failures.fetch_add(1, Ordering::SeqCst) + 1
+        };
+        *self.last_checked_at.write().await = Some(Utc::now());
+
+        if !reachable && streak < FAILURES_BEFORE_UNREACHABLE {
+            tracing::debug!(streak, "TURN probe failed");
+            return;
+        }
+
+        let was_reachable = self.reachable.swap(reachable, Ordering::SeqCst);
+        let first = !self.checked.swap(true, Ordering::SeqCst);
+        if first || was_reachable != reachable {
+            if reachable {
+                tracing::info!("TURN server reachable");
+            } else {
+                tracing::warn!("TURN server unreachable");
+            }
+        }
+    }
+
+    /// `None` until a probe has succeeded or enough have failed in a row
+    pub fn reachable(&self) -> Option<bool> {
+        self.checked
+            .load(Ordering::SeqCst)
+            .then(|| self.reachable.load(Ordering::SeqCst))
+    }
+
+    /// Number of failed checks since startup
+    pub fn failed_checks(&self) -> u64 {
+        self.failed_checks.load(Ordering::Relaxed)
+    }
+
+    pub async fn last_checked_at(&self) -> Option<DateTime<Utc>> {
+        *self.last_checked_at.read().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_turn_uris() {
+        assert_eq!(
+            TurnTarget::parse("turn:turn.example.com"),
+            Some(TurnTarget {
+                host: "turn.example.com".to_string(),
+                port: 3478,
+                transport: TurnTransport::Udp,
+            })
+        );
+        assert_eq!(
+            TurnTarget::parse("turn:10.0.0.1:3479?transport=tcp").map(|t| (t.port, t.transport)),
+            Some((3479, TurnTransport::Tcp))
+        );
+        assert_eq!(
+            TurnTarget::parse("turns:[2001:db8::1]").map(|t| (t.host, t.port, t.transport)),
+            Some(("2001:db8::1".to_string(), 5349, TurnTransport::Tls))
+        );
+        assert_eq!(TurnTarget::parse("stun:stun.example.com"), None);
+        assert_eq!(TurnTarget::parse("turn:host:notaport"), None);
+        assert_eq!(TurnTarget::parse("turn:host?transport=sctp"), None);
+    }
+
+    #[test]
+    fn test_binding_response_must_match_transaction() {
+        let (request, transaction_id) = binding_request();
+        let mut response = request;
+        response[0..2].copy_from_slice(&STUN_BINDING_SUCCESS.to_be_bytes());
+        assert!(is_binding_success(&response, &transaction_id));
+
+        // Echoed request, truncated reply and foreign transaction are rejected
+        assert!(!is_binding_success(&request, &transaction_id));
+        assert!(!is_binding_success(&response[..10], &transaction_id));
+        response[19] ^= 0xff;
+        assert!(!is_binding_success(&response, &transaction_id));
+    }
+
+    #[tokio::test]
+    async fn test_health_unknown_until_checked() {
+        let health = TurnHealth::new();
+        assert_eq!(health.reachable(), None);
+
+        health.record(true).await;
+        assert_eq!(health.reachable(), Some(true));
+        assert!(health.last_checked_at().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_health_needs_consecutive_failures() {
+        let health = TurnHealth::new();
+        for _ in 1..FAILURES_BEFORE_UNREACHABLE {
+            health.record(false).await;
+        }
+        assert_eq!(health.reachable(), None);
+
+        health.record(false).await;
+        assert_eq!(health.reachable(), Some(false));
+
+        // A success resets the streak
+        health.record(true).await;
+        for _ in 1..FAILURES_BEFORE_UNREACHABLE {
+            health.record(false).await;
+        }
+        assert_eq!(health.reachable(), Some(true));
+        assert_eq!(health.failed_checks(), 2 * FAILURES_BEFORE_UNREACHABLE - 1);
+    }
+}
@@ -0,0 +1,589 @@
+use std::fmt;
+
+use crate::config::Config;
+use crate::error::{AppError, Result};
+
+/// Rules applied to user-supplied display names
+#[derive(Debug, Clone)]
+pub struct DisplayNameRules {
+    pub min_len: usize,
+    pub max_len: usize,
+    /// Lowercased words that may not appear in a display name
+    pub blocklist: Vec<String>,
+}
+
+impl DisplayNameRules {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            min_len: config.display_name_min_length,
+            max_len: config.display_name_max_length,
+            blocklist: config
+                .display_name_blocklist
+                .iter()
+                .map(|w| w.to_lowercase())
+                .collect(),
+        }
+    }
+}
+
+/// Paths a slug could be confused with (routes, UI pages)
+const RESERVED_SLUGS: &[&str] = &[
+    "admin", "api", "batch", "create", "health", "invite", "invites", "join", "lobby", "metrics",
+    "new", "room", "rooms", "ws",
+];
+
+/// Normalize and validate a room slug: lowercase ASCII letters, digits and
+/// single hyphens, 3-64 characters, not a reserved word and not UUID-shaped
+/// (so a slug can never shadow a real room_id).
+pub fn normalize_slug(raw: &str) -> Result<String> {
+    let slug = raw.trim().to_ascii_lowercase();
+
+    if slug.len() < 3 || slug.len() > 64 {
+        return Err(AppError::BadRequest(
+            "Slug must be between 3 and 64 characters".to_string(),
+        ));
+    }
+    if !slug
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        || slug.starts_with('-')
+        || slug.ends_with('-')
+        || slug.contains("--")
+    {
+        return Err(AppError::BadRequest(
+            "Slug may only contain letters, digits and single hyphens".to_string(),
+        ));
+    }
+    if RESERVED_SLUGS.contains(&slug.as_str()) || uuid::Uuid::parse_str(&slug).is_ok() {
+        return Err(AppError::BadRequest(format!("Slug '{}' is reserved", slug)));
+    }
+
+    Ok(slug)
+}
+
+/// Validate a room's codec allowlist: SDP codec names (`opus`, `VP8`, ...),
+/// trimmed and de-duplicated case-insensitively. Empty means no restriction.
+pub fn normalize_codec_list(raw: &[String]) -> Result<Vec<String>> {
+    let mut codecs: Vec<String> = Vec::with_capacity(raw.len());
+
+    for name in raw.iter().map(|c| c.trim()).filter(|c| !c.is_empty()) {
+        if name.len() > 32 || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(AppError::BadRequest(format!("Invalid codec name '{}'", name)));
+        }
+        if !codecs.iter().any(|c| c.eq_ignore_ascii_case(name)) {
+            codecs.push(name.to_string());
+        }
+    }
+
+    Ok(codecs)
+}
+
+/// Free-text fields with an operator-configured length limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextField {
+    RoomName,
+    InviteSubject,
+    InviteMessage,
+    Announcement,
+}
+
+impl TextField {
+    fn label(self) -> &'static str {
+        match self {
+            Self::RoomName => "Room name",
+            Self::InviteSubject => "Invitation subject",
+            Self::InviteMessage => "Invitation message",
+            Self::Announcement => "Announcement",
+        }
+    }
+}
+
+/// Maximum lengths, in characters, of user-supplied free text
+#[derive(Debug, Clone, Copy)]
+pub struct TextLimits {
+    pub room_name: usize,
+    pub invite_subject: usize,
+    pub invite_message: usize,
+    pub announcement: usize,
+}
+
+impl TextLimits {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            room_name: config.room_name_max_length,
+            invite_subject: config.invite_subject_max_length,
+            invite_message: config.invite_message_max_length,
+            announcement: config.announcement_max_length,
+        }
+    }
+
+    pub fn max_chars(&self, field: TextField) -> usize {
+        match field {
+            TextField::RoomName => self.room_name,
+            TextField::InviteSubject => self.invite_subject,
+            TextField::InviteMessage => self.invite_message,
+            TextField::Announcement => self.announcement,
+        }
+    }
+
+    /// Reject `text` when it is longer than the field's limit (counted in
+    /// characters, not bytes)
+    pub fn check(&self, field: TextField, text: &str) -> Result<()> {
+        let max = self.max_chars(field);
+        if text.chars().count() > max {
+            return Err(AppError::BadRequest(format!(
+                "{} must be at most {} characters",
+                field.label(),
+                max
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Clean up announcement text: control and zero-width characters are
+/// stripped (line breaks are kept), surrounding whitespace is trimmed and the
+/// result must be non-empty and within the announcement limit. The text stays
+/// plain text; clients must not render it as HTML.
+pub fn sanitize_announcement(raw: &str, limits: &TextLimits) -> Result<String> {
+    let cleaned: String = raw
+        .chars()
+        .filter(|&c| c == '\n' || !(c.is_control() || is_invisible(c)))
+        .collect();
+    let cleaned = cleaned.trim();
+
+    if cleaned.is_empty() {
+        return Err(AppError::BadRequest(
+            "Announcement text is required".to_string(),
+        ));
+    }
+    limits.check(TextField::Announcement, cleaned)?;
+
+    Ok(cleaned.to_string())
+}
+
+/// Operator policy on how many times an invitation may be used
+#[derive(Debug, Clone, Copy)]
+pub struct InviteUsePolicy {
+    pub default: Option<u32>,
+    pub max: Option<u32>,
+}
+
+impl InviteUsePolicy {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            default: config.default_invite_max_uses,
+            max: config.max_invite_max_uses,
+        }
+    }
+
+    /// Resolve a client's requested `max_uses` against the policy:
+    /// explicit values must be within `1..=max`, and an omitted value falls
+    /// back to the default (clamped to `max`), or to `max` itself when no
+    /// default is configured.
+    pub fn resolve(&self, requested: Option<u32>) -> Result<Option<u32>> {
+        match requested {
+            Some(0) => Err(AppError::BadRequest(
+                "max_uses must be at least 1".to_string(),
+            )),
+            Some(n) => match self.max {
+                Some(max) if n > max => Err(AppError::BadRequest(format!(
+                    "max_uses must be at most {}",
+                    max
+                ))),
+                _ => Ok(Some(n)),
+            },
+            None => Ok(match (self.default, self.max) {
+                (Some(default), Some(max)) => Some(default.min(max)),
+                (Some(default), None) => Some(default),
+                (None, max) => max,
+            }),
+        }
+    }
+}
+
+/// Characters that render as nothing but can be used to spoof names
+/// (zero-width joiners, bidi overrides/isolates, BOM, soft hyphen).
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}'
+            | '\u{200B}'..='\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{2064}'
+            | '\u{2066}'..='\u{2069}'
+            | '\u{FEFF}'
+    )
+}
+
+/// Normalize and validate a display name:
+/// - any whitespace (including newlines/tabs) becomes a single space, trimmed at both ends
+/// - other control and zero-width characters are stripped
+/// - length is checked in characters against `min_len`/`max_len`
+/// - names containing a blocklisted word (case-insensitive, whole word) are rejected
+pub fn sanitize_display(raw: &str, rules: &DisplayNameRules) -> Result<String> {
+    let mut cleaned = String::with_capacity(raw.len());
+    let mut pending_space = false;
+
+    for c in raw.chars() {
+        if c.is_whitespace() {
+            pending_space = !cleaned.is_empty();
+            continue;
+        }
+        if c.is_control() || is_invisible(c) {
+            continue;
+        }
+        if pending_space {
+            cleaned.push(' ');
+            pending_space = false;
+        }
+        cleaned.push(c);
+    }
+
+    let len = cleaned.chars().count();
+    if len == 0 {
+        return Err(AppError::BadRequest("Display name is required".to_string()));
+    }
+    if len < rules.min_len {
+        return Err(AppError::BadRequest(format!(
+            "Display name must be at least {} characters",
+            rules.min_len
+        )));
+    }
+    if len > rules.max_len {
+        return Err(AppError::BadRequest(format!(
+            "Display name must be at most {} characters",
+            rules.max_len
+        )));
+    }
+
+    if !rules.blocklist.is_empty() {
+        let lowered = cleaned.to_lowercase();
+        let blocked = lowered
+            .split(|c: char| !c.is_alphanumeric())
+            .any(|word| !word.is_empty() && rules.blocklist.iter().any(|b| b == word));
+        if blocked {
+            return Err(AppError::BadRequest(
+                "Display name contains a disallowed word".to_string(),
+            ));
+        }
+    }
+
+    Ok(cleaned)
+}
+
+/// Cheap sanity check on client SDP before handing it to webrtc-rs:
+/// bounded size, a `v=0` first line and at least one `m=` section.
+pub fn validate_sdp(sdp: &str, max_bytes: usize) -> Result<()> {
+    if sdp.len() > max_bytes {
+        return Err(AppError::BadRequest(format!(
+            "SDP exceeds maximum size of {} bytes",
+            max_bytes
+        )));
+    }
+
+    let mut lines = sdp.lines();
+    if lines.next().map(str::trim_end) != Some("v=0") {
+        return Err(AppError::BadRequest(
+            "Invalid SDP: must start with v=0".to_string(),
+        ));
+    }
+    if !lines.any(|l| l.starts_with("m=")) {
+        return Err(AppError::BadRequest(
+            "Invalid SDP: no media sections".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Validate a client fingerprint: an opaque token of up to 128 printable
+/// ASCII characters. Blank means none was sent.
+pub fn normalize_fingerprint(raw: Option<&str>) -> Result<Option<String>> {
+    let Some(fingerprint) = raw.map(str::trim).filter(|f| !f.is_empty()) else {
+        return Ok(None);
+    };
+    if fingerprint.len() > 128 || !fingerprint.chars().all(|c| c.is_ascii_graphic()) {
+        return Err(AppError::BadRequest("Invalid fingerprint".to_string()));
+    }
+    Ok(Some(fingerprint.to_string()))
+}
+
+/// Header a client reports its version in (`client_version` query parameter
+/// on the WebSocket upgrade, where browsers can't set headers)
+pub const CLIENT_VERSION_HEADER: &str = "x-client-version";
+
+/// A client's `major.minor.patch` version. Missing parts count as 0, a
+/// leading `v` is accepted and a pre-release or build suffix (`-beta.1`,
+/// `+abc`) is ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ClientVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl ClientVersion {
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        let core = raw.strip_prefix('v').unwrap_or(raw);
+        let core = core.split(['-', '+']).next()?;
+
+        let mut parts = core.split('.').map(|p| p.parse::<u64>().ok());
+        let major = parts.next()??;
+        let minor = parts.next().unwrap_or(Some(0))?;
+        let patch = parts.next().unwrap_or(Some(0))?;
+        if parts.next().is_some() {
+            return None;
+        }
+
+        Some(Self {
+            major,
+            minor,
+            patch,
+        })
+    }
+}
+
+impl fmt::Display for ClientVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Refuse clients older than `minimum` with 426 Upgrade Required. Once a
+/// minimum is set, a client reporting no version counts as too old: builds
+/// that predate the header are the ones the gate exists for.
+pub fn ensure_client_version(reported: Option<&str>, minimum: Option<ClientVersion>) -> Result<()> {
+    let Some(minimum) = minimum else {
+        return Ok(());
+    };
+
+    if let Some(raw) = reported {
+        let version = ClientVersion::parse(raw)
+            .ok_or_else(|| AppError::BadRequest(format!("Invalid client version '{}'", raw)))?;
+        if version >= minimum {
+            return Ok(());
+        }
+    }
+
+    Err(AppError::UpgradeRequired(format!(
+        "Client version {} or newer is required",
+        minimum
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> DisplayNameRules {
+        DisplayNameRules {
+            min_len: 2,
+            max_len: 20,
+            blocklist: vec!["badword".to_string()],
+        }
+    }
+
+    fn limits() -> TextLimits {
+        TextLimits {
+            room_name: 10,
+            invite_subject: 20,
+            invite_message: 30,
+            announcement: 500,
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_is_optional_and_bounded() {
+        assert_eq!(normalize_fingerprint(None).unwrap(), None);
+        assert_eq!(normalize_fingerprint(Some("  ")).unwrap(), None);
+        assert_eq!(
+            normalize_fingerprint(Some(" a1b2-c3 ")).unwrap(),
+            Some("a1b2-c3".to_string())
+        );
+        assert!(normalize_fingerprint(Some("has space")).is_err());
+        assert!(normalize_fingerprint(Some(&"f".repeat(129))).is_err());
+    }
+
+    #[test]
+    fn test_client_version_gate() {
+        let minimum = ClientVersion::parse("1.4.0");
+        assert_eq!(
+            minimum,
+            Some(ClientVersion {
+                major: 1,
+                minor: 4,
+                patch: 0
+            })
+        );
+
+        // No minimum configured: everything goes, even no version at all
+        assert!(ensure_client_version(None, None).is_ok());
+        assert!(ensure_client_version(Some("0.1"), None).is_ok());
+
+        assert!(ensure_client_version(Some("1.4.0"), minimum).is_ok());
+        assert!(ensure_client_version(Some("v1.10"), minimum).is_ok());
+        assert!(ensure_client_version(Some("2.0.0-beta.1"), minimum).is_ok());
+
+        for old in [Some("1.3.9"), Some("1"), None] {
+            match ensure_client_version(old, minimum) {
+                Err(AppError::UpgradeRequired(msg)) => assert!(msg.contains("1.4.0")),
+                other => panic!("expected upgrade required for {:?}, got {:?}", old, other),
+            }
+        }
+        assert!(matches!(
+            ensure_client_version(Some("latest"), minimum),
+            Err(AppError::BadRequest(_))
+        ));
+        assert_eq!(ClientVersion::parse("1.2.3.4"), None);
+    }
+
+    #[test]
+    fn test_announcement_is_cleaned_and_bounded() {
+        let text = sanitize_announcement("  Break in 5\u{200B} min\r\nBack at 3\u{7}  ", &limits()).unwrap();
+        assert_eq!(text, "Break in 5 min\nBack at 3");
+        assert!(sanitize_announcement(" \u{200B} ", &limits()).is_err());
+        assert!(sanitize_announcement(&"a".repeat(501), &limits()).is_err());
+    }
+
+    #[test]
+    fn test_text_limits_count_characters() {
+        let limits = limits();
+        assert!(limits.check(TextField::RoomName, &"é".repeat(10)).is_ok());
+        assert!(limits.check(TextField::RoomName, &"é".repeat(11)).is_err());
+        assert!(limits.check(TextField::InviteSubject, &"x".repeat(20)).is_ok());
+        assert!(limits.check(TextField::InviteMessage, &"x".repeat(31)).is_err());
+    }
+
+    #[test]
+    fn test_codec_list_dedupes_and_rejects_garbage() {
+        let raw = vec![" opus".to_string(), "VP8".to_string(), "vp8".to_string(), "".to_string()];
+        assert_eq!(normalize_codec_list(&raw).unwrap(), vec!["opus", "VP8"]);
+        assert!(normalize_codec_list(&["VP8/90000".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_trims_and_collapses_whitespace() {
+        let name = sanitize_display("  Alice \t\n  Smith  ", &rules()).unwrap();
+        assert_eq!(name, "Alice Smith");
+    }
+
+    #[test]
+    fn test_strips_control_and_zero_width() {
+        let name = sanitize_display("Al\u{200B}i\u{0007}ce\u{202E}", &rules()).unwrap();
+        assert_eq!(name, "Alice");
+    }
+
+    #[test]
+    fn test_rejects_only_invisible_characters() {
+        assert!(sanitize_display("\u{200B}\u{FEFF}\u{200D}", &rules()).is_err());
+        assert!(sanitize_display("   \n\t ", &rules()).is_err());
+    }
+
+    #[test]
+    fn test_enforces_length_bounds() {
+        assert!(sanitize_display("A", &rules()).is_err());
+        assert!(sanitize_display(&"x".repeat(21), &rules()).is_err());
+        assert!(sanitize_display(&"x".repeat(20), &rules()).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_blocklisted_word() {
+        assert!(sanitize_display("I am BadWord", &rules()).is_err());
+        assert!(sanitize_display("bad\u{200B}word", &rules()).is_err());
+        // Whole-word match only
+        assert!(sanitize_display("badwords fan", &rules()).is_ok());
+    }
+
+    #[test]
+    fn test_invite_uses_unbounded_by_default() {
+        let policy = InviteUsePolicy {
+            default: None,
+            max: None,
+        };
+        assert_eq!(policy.resolve(None).unwrap(), None);
+        assert_eq!(policy.resolve(Some(1000)).unwrap(), Some(1000));
+        assert!(policy.resolve(Some(0)).is_err());
+    }
+
+    #[test]
+    fn test_invite_uses_default_is_clamped_to_max() {
+        let policy = InviteUsePolicy {
+            default: Some(50),
+            max: Some(20),
+        };
+        assert_eq!(policy.resolve(None).unwrap(), Some(20));
+
+        let no_default = InviteUsePolicy {
+            default: None,
+            max: Some(20),
+        };
+        assert_eq!(no_default.resolve(None).unwrap(), Some(20));
+    }
+
+    #[test]
+    fn test_invite_uses_above_max_rejected() {
+        let policy = InviteUsePolicy {
+            default: Some(5),
+            max: Some(20),
+        };
+        assert_eq!(policy.resolve(Some(20)).unwrap(), Some(20));
+        assert!(policy.resolve(Some(21)).is_err());
+    }
+
+    #[test]
+    fn test_slug_normalized() {
+        assert_eq!(normalize_slug("  Daily-Standup ").unwrap(), "daily-standup");
+        assert_eq!(normalize_slug("team42").unwrap(), "team42");
+    }
+
+    #[test]
+    fn test_slug_charset_and_length() {
+        assert!(normalize_slug("ab").is_err());
+        assert!(normalize_slug(&"a".repeat(65)).is_err());
+        assert!(normalize_slug("daily standup").is_err());
+        assert!(normalize_slug("-standup").is_err());
+        assert!(normalize_slug("daily--standup").is_err());
+        assert!(normalize_slug("café").is_err());
+    }
+
+    #[test]
+    fn test_slug_reserved_and_uuid_rejected() {
+        assert!(normalize_slug("API").is_err());
+        assert!(normalize_slug("invite").is_err());
+        assert!(normalize_slug("6f1c2b1e-8a4d-4c3e-9b0a-1d2e3f405162").is_err());
+    }
+
+    const MAX_SDP: usize = 64 * 1024;
+
+    #[test]
+    fn test_sdp_rejects_empty() {
+        assert!(validate_sdp("", MAX_SDP).is_err());
+    }
+
+    #[test]
+    fn test_sdp_rejects_oversized() {
+        let sdp = format!(
+            "v=0\r\nm=audio 9 UDP/TLS/RTP/SAVPF 111\r\n{}",
+            "a=x\r\n".repeat(MAX_SDP)
+        );
+        assert!(validate_sdp(&sdp, MAX_SDP).is_err());
+    }
+
+    #[test]
+    fn test_sdp_accepts_minimal_offer() {
+        let sdp = "v=0\r\no=- 1 2 IN IP4 127.0.0.1\r\ns=-\r\nt=0 0\r\nm=audio 9 UDP/TLS/RTP/SAVPF 111\r\n";
+        assert!(validate_sdp(sdp, MAX_SDP).is_ok());
+    }
+
+    #[test]
+    fn test_sdp_requires_version_and_media() {
+        assert!(validate_sdp(
+            "o=- 1 2 IN IP4 127.0.0.1\r\nm=audio 9 RTP/AVP 0\r\n",
+            MAX_SDP
+        )
+        .is_err());
+        assert!(validate_sdp("v=0\r\ns=-\r\n", MAX_SDP).is_err());
+    }
+}
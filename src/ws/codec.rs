@@ -0,0 +1,106 @@
+use axum::extract::ws::Message;
+
+use crate::error::{AppError, Result};
+use crate::ws::SignalingMessage;
+
+/// WebSocket subprotocol that switches a connection to MessagePack frames
+pub const MSGPACK_SUBPROTOCOL: &str = "truegather-msgpack";
+
+/// Wire encoding of signaling messages, fixed per connection at upgrade time
+///
+/// JSON text frames are the default. Clients that offer the
+/// `truegather-msgpack` subprotocol get binary MessagePack frames carrying the
+/// same `{ type, request_id, payload }` map. For a typical trickle_ice message
+/// that is roughly 10% smaller (~235 vs ~260 bytes): the candidate string
+/// dominates, the saving comes from keys and framing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireCodec {
+    Json,
+    MsgPack,
+}
+
+impl WireCodec {
+    /// Pick the codec from the negotiated subprotocol
+    pub fn from_protocol(protocol: Option<&str>) -> Self {
+        match protocol {
+            Some(MSGPACK_SUBPROTOCOL) => WireCodec::MsgPack,
+            _ => WireCodec::Json,
+        }
+    }
+
+    pub fn encode(&self, msg: &SignalingMessage) -> Result<Message> {
+        match self {
+            WireCodec::Json => Ok(Message::Text(serde_json::to_string(msg)?.into())),
+            // Named encoding keeps structs as maps, so payloads round-trip through `Value`
+            WireCodec::MsgPack => rmp_serde::to_vec_named(msg)
+                .map(|bytes| Message::Binary(bytes.into()))
+                .map_err(|e| AppError::InternalError(format!("MessagePack encode error: {}", e))),
+        }
+    }
+
+    /// Decode a data frame. Returns `Ok(None)` for frames this codec doesn't carry.
+    pub fn decode(&self, frame: &Message) -> Result<Option<SignalingMessage>> {
+        match (self, frame) {
+            (WireCodec::Json, Message::Text(text)) => Ok(Some(serde_json::from_str(text)?)),
+            (WireCodec::MsgPack, Message::Binary(bytes)) => rmp_serde::from_slice(bytes)
+                .map(Some)
+                .map_err(|e| AppError::BadRequest(format!("MessagePack error: {}", e))),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ice_message() -> SignalingMessage {
+        SignalingMessage::new(
+            "trickle_ice",
+            serde_json::json!({
+                "candidate": "candidate:842163049 1 udp 1677729535 203.0.113.7 54321 typ srflx raddr 192.168.1.20 rport 54321 generation 0 ufrag 4ZcD network-cost 999",
+                "sdp_mid": "0",
+                "sdp_mline_index": 0,
+                "target": "publisher"
+            }),
+        )
+        .with_request_id(Some("r-42".to_string()))
+    }
+
+    #[test]
+    fn test_msgpack_round_trip() {
+        let msg = ice_message();
+        let frame = WireCodec::MsgPack.encode(&msg).unwrap();
+        let decoded = WireCodec::MsgPack.decode(&frame).unwrap().unwrap();
+
+        assert_eq!(decoded.msg_type, msg.msg_type);
+        assert_eq!(decoded.request_id, msg.request_id);
+        assert_eq!(decoded.payload, msg.payload);
+    }
+
+    #[test]
+    fn test_msgpack_is_smaller_for_ice() {
+        let msg = ice_message();
+        let json = match WireCodec::Json.encode(&msg).unwrap() {
+            Message::Text(text) => text.len(),
+            _ => unreachable!(),
+        };
+        let msgpack = match WireCodec::MsgPack.encode(&msg).unwrap() {
+            Message::Binary(bytes) => bytes.len(),
+            _ => unreachable!(),
+        };
+
+        assert!(msgpack < json, "msgpack {} >= json {}", msgpack, json);
+    }
+
+    #[test]
+    fn test_codec_ignores_other_frame_kind() {
+        let frame = WireCodec::Json.encode(&ice_message()).unwrap();
+        assert!(WireCodec::MsgPack.decode(&frame).unwrap().is_none());
+        assert_eq!(WireCodec::from_protocol(None), WireCodec::Json);
+        assert_eq!(
+            WireCodec::from_protocol(Some(MSGPACK_SUBPROTOCOL)),
+            WireCodec::MsgPack
+        );
+    }
+}
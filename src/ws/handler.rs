@@ -1,8 +1,12 @@
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
 use axum::{
     extract::{
-        ws::{Message, WebSocket},
-        Query, State, WebSocketUpgrade,
+        ws::{CloseFrame, Message, WebSocket},
+        State, WebSocketUpgrade,
     },
+    http::{header::ORIGIN, HeaderMap},
     response::Response,
     routing::get,
     Router,
@@ -13,14 +17,29 @@ use tokio::sync::mpsc;
 use uuid::Uuid;
 
 
-use crate::api::rooms::create_publisher_info;
+use crate::api::extract::AppQuery;
+use crate::api::rooms::{
+    create_publisher_info, ensure_can_publish, ensure_subscription_limit, ensure_unlocked,
+    join_member_checked, member_is_banned, preemption_victim, rename_member_checked,
+    resolve_room_ref, seat_available, spotlight_feed, token_room_matches,
+};
 use crate::error::AppError;
+use crate::models::{participant_color, sort_publishers_by_join_time, Claims};
+use crate::media::sdp;
+use crate::security::origin_allowed;
 use crate::state::AppState;
+use crate::validation::{
+    ensure_client_version, normalize_codec_list, sanitize_display, validate_sdp, DisplayNameRules,
+    CLIENT_VERSION_HEADER,
+};
+use crate::ws::codec::{WireCodec, MSGPACK_SUBPROTOCOL};
 //Remplacer 
 use crate::ws::{
-    msg_types, ClientHandle, JoinRoomPayload, JoinedPayload, LeftRoomPayload, PublishAnswerPayload,
-    PublishOfferPayload, PublisherJoinedPayload, PublisherLeftPayload, PublisherPayload,
-    MemberJoinedPayload, MemberLeftPayload, SignalingMessage, SubscribeOfferPayload, SubscribePayload, TrickleIcePayload, WsSessionState,
+    msg_types, CandidatesPayload, ClientErrorPayload, ClientHandle, ConnectionSlot, DebugSessionInfoPayload, DemotedPayload, DuplicateSessionPolicy, GetCandidatesPayload, JoinRoomPayload, JoinedPayload, LayoutPayload, LeftRoomPayload, PublishAnswerPayload,
+    PublishOfferPayload, PublisherChange, PublisherJoinedPayload, PublisherLeftPayload, PublisherPayload,
+    PublishersChangedPayload, PublisherRenamedPayload, RemoteCandidatePayload, RenamePayload,
+    MemberJoinedPayload, MemberLeftPayload, MemberRenamedPayload, RequestKeyframePayload, SessionReplacedPayload, RoomEndedPayload, SignalingMessage, SpotlightPayload, SubscribeOfferPayload, SubscribePayload, TokenExpiringPayload, TrickleIceBatchPayload, TrickleIcePayload, WsSessionState,
+    token_warning_delay,
 };
 
 /// Query parameters for WebSocket connection
@@ -28,6 +47,9 @@ use crate::ws::{
 pub struct WsQueryParams {
     pub room_id: String,
     pub token: String,
+    /// Same as the `X-Client-Version` header, which browsers can't set here
+    #[serde(default)]
+    pub client_version: Option<String>,
 }
 
 /// WebSocket routes
@@ -36,56 +58,137 @@ pub fn ws_routes() -> Router<AppState> {
 }
 
 /// WebSocket upgrade handler
+///
+/// Compression: permessage-deflate is not negotiated. axum's WebSocket
+/// support sits on tungstenite, which does not implement the extension, so a
+/// client's `Sec-WebSocket-Extensions` offer is ignored and the connection
+/// runs uncompressed. Enabling it means swapping the WS stack (or terminating
+/// WebSockets in a proxy that supports the extension), not a flag here.
 async fn ws_upgrade(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
-    Query(params): Query<WsQueryParams>,
+    AppQuery(params): AppQuery<WsQueryParams>,
+    headers: HeaderMap,
 ) -> Result<Response, AppError> {
+    // Cross-site WebSocket hijacking: browsers attach the user's token from
+    // any page, so the page's origin must be one of ours
+    let origin = headers.get(ORIGIN).and_then(|v| v.to_str().ok());
+    if !origin_allowed(origin, &state.config.cors_allowed_origins) {
+        tracing::warn!(origin = ?origin, "WebSocket upgrade from a disallowed origin");
+        return Err(AppError::Forbidden("Origin not allowed".to_string()));
+    }
+
+    let client_version = headers
+        .get(CLIENT_VERSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .or(params.client_version.as_deref());
+    ensure_client_version(client_version, state.config.min_client_version)?;
+
     // Validate JWT token
     let claims = state.auth.validate_token(&params.token)?;
 
-    // Verify room_id matches
-    if claims.room_id != params.room_id {
+    // Verify room_id matches (the room's slug counts as the room)
+    if !token_covers_room(&state, &claims.room_id, &params.room_id).await {
         return Err(AppError::Unauthorized(
             "Token room_id does not match".to_string(),
         ));
     }
 
     // Check room exists
-    let _room = state
+    let room = state
         .room_repo
-        .get_room(&params.room_id)
+        .get_room(&claims.room_id)
         .await?
         .ok_or_else(|| AppError::NotFound("Room not found".to_string()))?;
 
+    // The room may have filled up since the REST join, or the client skipped
+    // it. Browsers can't read the status of a refused upgrade, so the socket
+    // is accepted and closed with CLOSE_ROOM_FULL instead.
+    let members = state.room_repo.get_member_infos(&room.room_id).await?;
+    let member = members.iter().any(|m| m.user_id == claims.sub);
+    let seats_taken = members.iter().filter(|m| !m.observer).count();
+    // The host of a room with `moderator_preemption` makes room by publishing
+    let preempts = room.moderator_preemption && is_current_host(&state, &claims).await?;
+    if !preempts && !seat_available(member, claims.observer, seats_taken, room.max_publishers) {
+        tracing::info!(room_id = %claims.room_id, user_id = %claims.sub, "Room full, closing WebSocket");
+        return Ok(ws.on_upgrade(|socket| close_with(socket, CLOSE_ROOM_FULL, "Room is full")));
+    }
+
+    // `MAX_CONNECTIONS` is this instance's limit, so it is enforced here,
+    // where the socket lands; the REST join only checks it as a fast path
+    let Some(slot) = state
+        .connections
+        .reserve_connection(state.config.max_connections)
+    else {
+        tracing::warn!(room_id = %claims.room_id, user_id = %claims.sub, "Server at capacity, closing WebSocket");
+        return Ok(
+            ws.on_upgrade(|socket| close_with(socket, CLOSE_SERVER_FULL, "Server at capacity"))
+        );
+    };
+
     tracing::info!(
-        room_id = %params.room_id,
+        room_id = %claims.room_id,
         user_id = %claims.sub,
         display = %claims.display,
         "WebSocket upgrade request"
     );
 
-    Ok(ws.on_upgrade(move |socket| handle_socket(socket, state, claims)))
+    // Offered subprotocols are matched against ours; JSON if none match
+    Ok(ws
+        .protocols([MSGPACK_SUBPROTOCOL])
+        .on_upgrade(move |socket| handle_socket(socket, state, claims, slot)))
 }
 
-/// Handle WebSocket connection
-async fn handle_socket(socket: WebSocket, state: AppState, claims: crate::models::Claims) {
+/// Close code sent when the room has no seat left (4000-4999: application-defined)
+pub const CLOSE_ROOM_FULL: u16 = 4009;
+
+/// Close code sent when the instance is at `MAX_CONNECTIONS` (after 1013,
+/// "try again later")
+pub const CLOSE_SERVER_FULL: u16 = 4013;
+
+async fn close_with(mut socket: WebSocket, code: u16, reason: &'static str) {
+    let frame = CloseFrame {
+        code,
+        reason: reason.into(),
+    };
+    let _ = socket.send(Message::Close(Some(frame))).await;
+}
+
+/// Whether a token issued for `claim_room_id` may be used with the client's
+/// `requested` room. With slugs enabled the requested reference is resolved to
+/// the canonical id first; without them the comparison is strict.
+async fn token_covers_room(state: &AppState, claim_room_id: &str, requested: &str) -> bool {
+    let resolved = if state.config.room_slugs_enabled && claim_room_id != requested {
+        resolve_room_ref(state, requested).await.ok()
+    } else {
+        None
+    };
+    token_room_matches(claim_room_id, requested, resolved.as_deref())
+}
+
+/// Handle WebSocket connection; `_slot` is held until it closes
+async fn handle_socket(socket: WebSocket, state: AppState, claims: Claims, _slot: ConnectionSlot) {
     let conn_id = Uuid::new_v4().to_string();
     let room_id = claims.room_id.clone();
     let user_id = claims.sub.clone();
     let display = claims.display.clone();
+    let codec = WireCodec::from_protocol(socket.protocol().and_then(|p| p.to_str().ok()));
 
     tracing::info!(
         conn_id = %conn_id,
         room_id = %room_id,
         user_id = %user_id,
+        codec = ?codec,
         "WebSocket connected"
     );
 
     // Create message channel for sending to this client
     let (tx, mut rx) = mpsc::unbounded_channel::<SignalingMessage>();
 
-    // Create session state
+    // Create session state; a host token left over from before a host
+    // transfer connects as a participant
+    let host = matches!(is_current_host(&state, &claims).await, Ok(true));
+    let observer = claims.observer;
     let mut session = WsSessionState::new(conn_id.clone(), claims);
 
     // Create client handle and add to room
@@ -94,53 +197,116 @@ async fn handle_socket(socket: WebSocket, state: AppState, claims: crate::models
         user_id.clone(),
         room_id.clone(),
         display.clone(),
+        host,
+        observer,
         tx,
     );
 
     let room_connections = state.connections.get_or_create_room(&room_id);
+    if state.config.duplicate_session_policy == DuplicateSessionPolicy::Replace {
+        for replaced in room_connections.take_user_clients(&user_id) {
+            tracing::info!(
+                conn_id = %replaced.conn_id,
+                room_id = %room_id,
+                user_id = %user_id,
+                "Closing connection replaced by a newer one"
+            );
+            if let Ok(payload) = serde_json::to_value(SessionReplacedPayload {
+                room_id: room_id.clone(),
+            }) {
+                let _ = replaced.send(SignalingMessage::new(msg_types::SESSION_REPLACED, payload));
+            }
+            replaced.close();
+        }
+    }
+    let close_signal = client_handle.close_signal();
     room_connections.add_client(client_handle);
 
     // Split socket into sender and receiver
     let (mut ws_sender, mut ws_receiver) = socket.split();
 
-    // Task for sending messages to client
-    let send_task = tokio::spawn(async move {
-        while let Some(msg) = rx.recv().await {
-            if let Ok(json) = serde_json::to_string(&msg) {
-                if ws_sender.send(Message::Text(json.into())).await.is_err() {
-                    break;
+    // Task for sending messages to client. Queued messages go out before a
+    // `ClientHandle::close` is honored.
+    let mut send_task = tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                biased;
+                msg = rx.recv() => {
+                    let Some(msg) = msg else { break };
+                    if let Ok(frame) = codec.encode(&msg) {
+                        if ws_sender.send(frame).await.is_err() {
+                            break;
+                        }
+                    }
                 }
+                _ = close_signal.notified() => break,
             }
         }
+        // Closed by the server side (e.g. replaced session, ended room)
+        let _ = ws_sender.send(Message::Close(None)).await;
     });
 
-    // Process incoming messages
-    while let Some(result) = ws_receiver.next().await {
-        match result {
-            Ok(Message::Text(text)) => {
-                if let Err(e) = handle_message(&text, &mut session, &state).await {
-                    tracing::error!(error = %e, "Error handling message");
-                    // Send error to client
-                    if let Some(room) = state.connections.get_room(&room_id) {
-                        if let Some(client) = room.get_client(&conn_id) {
-                            let _ = client.send(SignalingMessage::error(500, &e.to_string(), None));
+    // Process incoming messages until the client goes away or the send task
+    // dies (send error / channel closed); either way we fall through to the
+    // single cleanup path below.
+    // `token_expiring` goes out once per token; the delay is recomputed from
+    // the session's claims each turn, so a replaced token is re-armed
+    let mut warned_exp: Option<i64> = None;
+    loop {
+        let token_warning = token_warning_delay(
+            session.claims.exp,
+            chrono::Utc::now().timestamp(),
+            state.config.token_expiry_warning_seconds,
+        )
+        .filter(|_| warned_exp != Some(session.claims.exp));
+
+        tokio::select! {
+            _ = &mut send_task => {
+                tracing::info!(conn_id = %conn_id, "Send task finished, closing connection");
+                break;
+            }
+            _ = tokio::time::sleep(token_warning.unwrap_or_default()), if token_warning.is_some() => {
+                warned_exp = Some(session.claims.exp);
+                send_token_expiring(&session, &state);
+            }
+            next = ws_receiver.next() => {
+                let Some(result) = next else { break };
+                match result {
+                    Ok(frame @ (Message::Text(_) | Message::Binary(_))) => {
+                        let result = match codec.decode(&frame) {
+                            Ok(Some(msg)) => handle_message(msg, &mut session, &state).await,
+                            Ok(None) => {
+                                tracing::debug!(conn_id = %conn_id, "Ignoring frame not matching negotiated codec");
+                                Ok(())
+                            }
+                            Err(e) => Err(e),
+                        };
+                        if let Err(e) = result {
+                            tracing::error!(error = %e, "Error handling message");
+                            // Send error to client
+                            if let Some(room) = state.connections.get_room(&room_id) {
+                                if let Some(client) = room.get_client(&conn_id) {
+                                    let (status, _) = e.status_and_message();
+                                    let _ = client.send(SignalingMessage::error(status.as_u16(), &e.to_string(), None));
+                                }
+                            }
                         }
                     }
+                    Ok(Message::Ping(_data)) => {
+                        // Respond with pong automatically handled by axum
+                        tracing::trace!(conn_id = %conn_id, "Ping received");
+                    }
+                    Ok(Message::Close(_)) => {
+                        tracing::info!(conn_id = %conn_id, "WebSocket close received");
+                        break;
+                    }
+                    Err(e) => {
+                        tracing::error!(conn_id = %conn_id, error = %e, "WebSocket error");
+                        break;
+                    }
+                    _ => {}
                 }
             }
-            Ok(Message::Ping(_data)) => {
-                // Respond with pong automatically handled by axum
-                tracing::trace!(conn_id = %conn_id, "Ping received");
-            }
-            Ok(Message::Close(_)) => {
-                tracing::info!(conn_id = %conn_id, "WebSocket close received");
-                break;
-            }
-            Err(e) => {
-                tracing::error!(conn_id = %conn_id, error = %e, "WebSocket error");
-                break;
-            }
-            _ => {}
         }
     }
 
@@ -158,9 +324,9 @@ async fn handle_socket(socket: WebSocket, state: AppState, claims: crate::models
         .remove_client_from_room(&room_id, &conn_id);
 
     // Only remove from Redis and broadcast MEMBER_LEFT if the session had completed join
-    if session.is_joined() {
-        let _ = state.room_repo.remove_member(&room_id, &user_id).await;
-        let _ = state.room_repo.remove_member_info(&room_id, &user_id).await;
+    // and this was the user's last connection
+    if session.is_joined() && !user_still_connected(&state, &room_id, &user_id, &conn_id) {
+        let _ = state.room_repo.leave_member(&room_id, &user_id).await;
 
         // Broadcast member left to others
         let left_msg = SignalingMessage::new(
@@ -176,52 +342,219 @@ async fn handle_socket(socket: WebSocket, state: AppState, claims: crate::models
             .broadcast_to_room(&room_id, left_msg, Some(&conn_id));
     }
 
-    // If publishing, remove publisher and notify others
+    // A departing host may end the room (`end_on_host_leave`)
+    if session.is_joined() && matches!(is_current_host(&state, &session.claims).await, Ok(true)) {
+        schedule_host_leave_end(&state, &room_id);
+    }
+
+    // If publishing, remove publisher and notify others, after the reconnection
+    // grace period when one is configured. Media sessions are per user, so a
+    // feed another connection of this user published since is left alone.
     if session.is_publishing {
-        if let Some(feed_id) = &session.feed_id {
-            let _ = state.room_repo.remove_publisher(&room_id, &user_id).await;
+        let live_feed = state
+            .media_gateway
+            .publisher_feed_id(&room_id, &user_id)
+            .await;
+        if let Some(feed_id) = session
+            .feed_id
+            .clone()
+            .filter(|feed_id| live_feed.as_ref().is_none_or(|live| live == feed_id))
+        {
+            let grace = Duration::from_millis(state.config.publisher_reconnect_grace_ms);
+            if grace.is_zero() {
+                retire_publisher(&state, &room_id, &user_id, &feed_id).await;
+            } else {
+                let ticket = state.pending_leaves.schedule(&room_id, &user_id, &feed_id);
+                let state = state.clone();
+                let (room_id, user_id) = (room_id.clone(), user_id.clone());
+                tokio::spawn(async move {
+                    tokio::time::sleep(grace).await;
+                    if state.pending_leaves.expire(&room_id, &user_id, ticket) {
+                        retire_publisher(&state, &room_id, &user_id, &feed_id).await;
+                    }
+                });
+            }
+        }
+    }
 
-            // Remove from media gateway
-            state
-                .media_gateway
-                .remove_publisher(&room_id, &user_id)
-                .await;
-
-            // Broadcast publisher left
-            let msg = SignalingMessage::new(
-                msg_types::PUBLISHER_LEFT,
-                serde_json::to_value(PublisherLeftPayload {
-                    feed_id: feed_id.clone(),
-                    room_id: room_id.clone(),
-                })
-                .unwrap(),
-            );
+    // Cleanup the subscriber in media gateway, unless another connection of
+    // this user has subscribed since
+    state
+        .media_gateway
+        .remove_subscriber(&room_id, &user_id, Some(&conn_id))
+        .await;
 
-            state
-                .connections
-                .broadcast_to_room(&room_id, msg, Some(&conn_id));
+    // Cancel send task (no-op if it already finished)
+    send_task.abort();
+}
+
+/// End a room created with `end_on_host_leave` unless a host connection is
+/// open again after `HOST_LEAVE_GRACE_SECONDS` (a reload or network blip)
+fn schedule_host_leave_end(state: &AppState, room_id: &str) {
+    let state = state.clone();
+    let room_id = room_id.to_string();
+    tokio::spawn(async move {
+        let ends = match state.room_repo.get_room(&room_id).await {
+            Ok(Some(room)) => room.end_on_host_leave,
+            _ => false,
+        };
+        if !ends {
+            return;
+        }
+
+        tokio::time::sleep(Duration::from_secs(state.config.host_leave_grace_seconds)).await;
+        // The host may be back on another instance: its membership says so.
+        // A failed lookup keeps the room rather than ending it on a guess.
+        let host_back = state
+            .connections
+            .get_room(&room_id)
+            .is_some_and(|room| room.has_host())
+            || state
+                .room_repo
+                .has_host_member(&room_id)
+                .await
+                .unwrap_or(true);
+        if host_back {
+            tracing::debug!(room_id = %room_id, "Host reconnected, room kept");
+            return;
+        }
+
+        end_room(&state, &room_id, "host_left").await;
+    });
+}
+
+/// Close every connection with `room_ended`, then drop the room's media and
+/// Redis state
+async fn end_room(state: &AppState, room_id: &str, reason: &str) {
+    tracing::info!(room_id = %room_id, reason = %reason, "Ending room");
+
+    if let Some(room) = state.connections.remove_room(room_id) {
+        let payload = RoomEndedPayload {
+            room_id: room_id.to_string(),
+            reason: reason.to_string(),
+        };
+        if let Ok(payload) = serde_json::to_value(payload) {
+            room.broadcast(SignalingMessage::new(msg_types::ROOM_ENDED, payload), None);
         }
+        room.close_all();
     }
 
-    // Cleanup subscriptions in media gateway
-    for feed_id in &session.subscribed_feeds {
-        state
-            .media_gateway
-            .remove_subscriber(&room_id, &user_id, feed_id)
-            .await;
+    state.media_gateway.cleanup_room(room_id).await;
+    if let Err(e) = state.room_repo.delete_room(room_id).await {
+        tracing::warn!(room_id = %room_id, error = %e, "Failed to delete ended room");
     }
+}
 
-    // Cancel send task
-    send_task.abort();
+/// Whether the token's holder is still a host of its room. The host claim
+/// outlives a host transfer, so the room's host set has the final say.
+async fn is_current_host(state: &AppState, claims: &Claims) -> Result<bool, AppError> {
+    if !claims.host {
+        return Ok(false);
+    }
+    state.room_repo.is_host(&claims.room_id, &claims.sub).await
+}
+
+/// Whether the user keeps another connection to the room (`allow` policy, or
+/// the replacement of this connection), in which case they haven't left
+fn user_still_connected(state: &AppState, room_id: &str, user_id: &str, conn_id: &str) -> bool {
+    state
+        .connections
+        .get_room(room_id)
+        .is_some_and(|room| room.has_other_connection(user_id, conn_id))
+}
+
+/// Remove a departed publisher from Redis and the media gateway and announce it
+async fn retire_publisher(state: &AppState, room_id: &str, user_id: &str, feed_id: &str) {
+    let _ = state.room_repo.remove_publisher(room_id, user_id).await;
+
+    // Remove from media gateway; if the inactivity reaper got there first
+    // it already announced publisher_left
+    let removed = state.media_gateway.remove_publisher(room_id, user_id).await;
+    if !removed {
+        return;
+    }
+
+    // Broadcast publisher left
+    announce_publisher_change(
+        state,
+        room_id,
+        PublisherChange::Left(PublisherLeftPayload {
+            feed_id: feed_id.to_string(),
+            room_id: room_id.to_string(),
+        }),
+        None,
+    );
+    if let Some(room) = state.connections.get_room(room_id) {
+        room.remove_feed(feed_id);
+    }
+}
+
+/// Tell the room a publisher joined or left. With `publisher_event_batch_ms`
+/// set, changes are held for that window and sent as one `publishers_changed`
+/// per client, in the order they happened.
+pub fn announce_publisher_change(
+    state: &AppState,
+    room_id: &str,
+    change: PublisherChange,
+    exclude_conn_id: Option<&str>,
+) {
+    let batch_ms = state.config.publisher_event_batch_ms;
+    if batch_ms == 0 {
+        match change.into_message() {
+            Ok(msg) => state.connections.broadcast_to_room(room_id, msg, exclude_conn_id),
+            Err(e) => tracing::error!(room_id = %room_id, error = %e, "Failed to encode publisher change"),
+        }
+        return;
+    }
+
+    if state.publisher_changes.push(room_id, change, exclude_conn_id) {
+        let state = state.clone();
+        let room_id = room_id.to_string();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(batch_ms)).await;
+            flush_publisher_changes(&state, &room_id);
+        });
+    }
+}
+
+/// Send a room's buffered publisher changes, skipping each client's own events
+fn flush_publisher_changes(state: &AppState, room_id: &str) {
+    let batch = state.publisher_changes.drain(room_id);
+    let Some(room) = state.connections.get_room(room_id) else {
+        return;
+    };
+
+    for conn_id in room.get_all_client_ids() {
+        let changes: Vec<PublisherChange> = batch
+            .iter()
+            .filter(|(_, exclude)| exclude.as_deref() != Some(conn_id.as_str()))
+            .map(|(change, _)| change.clone())
+            .collect();
+        if changes.is_empty() {
+            continue;
+        }
+
+        let payload = PublishersChangedPayload {
+            room_id: room_id.to_string(),
+            changes,
+        };
+        match serde_json::to_value(payload) {
+            Ok(payload) => {
+                if let Some(client) = room.get_client(&conn_id) {
+                    let _ = client.send(SignalingMessage::new(msg_types::PUBLISHERS_CHANGED, payload));
+                }
+            }
+            Err(e) => tracing::error!(room_id = %room_id, error = %e, "Failed to encode publishers_changed"),
+        }
+    }
 }
 
 /// Handle incoming signaling message
 async fn handle_message(
-    text: &str,
+    msg: SignalingMessage,
     session: &mut WsSessionState,
     state: &AppState,
 ) -> Result<(), AppError> {
-    let msg: SignalingMessage = serde_json::from_str(text)?;
     let request_id = msg.request_id.clone();
 
     tracing::debug!(
@@ -235,9 +568,15 @@ async fn handle_message(
         msg.msg_type.as_str(),
         msg_types::PUBLISH_OFFER
             | msg_types::TRICKLE_ICE
+            | msg_types::TRICKLE_ICE_BATCH
             | msg_types::SUBSCRIBE
             | msg_types::SUBSCRIBE_ANSWER
             | msg_types::PUBLISH_ANSWER
+            | msg_types::REQUEST_KEYFRAME
+            | msg_types::RENAME
+            | msg_types::RESYNC_SUBSCRIBER
+            | msg_types::GET_CANDIDATES
+            | msg_types::SPOTLIGHT
     );
 
     if msg_requires_join && !session.is_joined() {
@@ -256,18 +595,42 @@ async fn handle_message(
         msg_types::TRICKLE_ICE => {
             handle_trickle_ice(msg.payload, session, state).await?;
         }
+        msg_types::TRICKLE_ICE_BATCH => {
+            handle_trickle_ice_batch(msg.payload, session, state).await?;
+        }
         msg_types::SUBSCRIBE => {
             handle_subscribe(msg.payload, request_id, session, state).await?;
         }
         msg_types::SUBSCRIBE_ANSWER => {
             handle_subscribe_answer(msg.payload, session, state).await?;
         }
+        msg_types::RESYNC_SUBSCRIBER => {
+            handle_resync_subscriber(request_id, session, state).await?;
+        }
+        msg_types::GET_CANDIDATES => {
+            handle_get_candidates(msg.payload, request_id, session, state).await?;
+        }
+        msg_types::REQUEST_KEYFRAME => {
+            handle_request_keyframe(msg.payload, session, state).await?;
+        }
         msg_types::LEAVE => {
             handle_leave(request_id, session, state).await?;
         }
         msg_types::PING => {
             handle_ping(request_id, session, state).await?;
         }
+        msg_types::CLIENT_ERROR => {
+            handle_client_error(msg.payload, session)?;
+        }
+        msg_types::RENAME => {
+            handle_rename(msg.payload, session, state).await?;
+        }
+        msg_types::SPOTLIGHT => {
+            handle_spotlight(msg.payload, session, state).await?;
+        }
+        msg_types::DEBUG_SESSION => {
+            handle_debug_session(request_id, session, state).await?;
+        }
         _ => {
             tracing::warn!(msg_type = %msg.msg_type, "Unknown message type");
             send_error(400, "Unknown message type", request_id, session, state);
@@ -285,44 +648,87 @@ async fn handle_join_room(
     state: &AppState,
 ) -> Result<(), AppError> {
     let join_payload: JoinRoomPayload = serde_json::from_value(payload)?;
+    let display = sanitize_display(
+        &join_payload.display,
+        &DisplayNameRules::from_config(&state.config),
+    )?;
 
     // Verify room matches token
-    if join_payload.room_id != session.room_id {
+    if !token_covers_room(state, &session.room_id, &join_payload.room_id).await {
         return Err(AppError::Unauthorized(
             "Room ID does not match token".to_string(),
         ));
     }
 
-    // Get existing publishers
-    let publishers = state.room_repo.get_publishers(&session.room_id).await?;
-    let publisher_payloads: Vec<PublisherPayload> = publishers
-        .iter()
-        .map(|p| PublisherPayload {
+    // A banned user's token stays valid until it expires; the host can't be banned out
+    if !is_current_host(state, &session.claims).await?
+        && member_is_banned(state, &session.room_id, &session.user_id).await?
+    {
+        return Err(AppError::Forbidden(
+            "You are banned from this room".to_string(),
+        ));
+    }
+
+    // Locked rooms: the host, co-hosts and members admitted before the lock
+    // (their REST join already passed the gate) may still connect
+    if state.room_repo.is_locked(&session.room_id).await? {
+        let privileged = is_current_host(state, &session.claims).await?
+            || state
+                .room_repo
+                .is_member(&session.room_id, &session.user_id)
+                .await?
+            || state
+                .room_repo
+                .is_cohost(&session.room_id, &session.user_id)
+                .await?;
+        ensure_unlocked(true, privileged)?;
+    }
+
+    // Get existing publishers; the media flags stored at publish time come from the offer,
+    // refine them with the tracks the gateway has actually received since.
+    let mut publishers = state.room_repo.get_publishers(&session.room_id).await?;
+    sort_publishers_by_join_time(&mut publishers);
+    let mut publisher_payloads: Vec<PublisherPayload> = Vec::with_capacity(publishers.len());
+    for p in &publishers {
+        let (live_audio, live_video) = state
+            .media_gateway
+            .publisher_media_kinds(&session.room_id, &p.user_id)
+            .await
+            .unwrap_or((false, false));
+
+        publisher_payloads.push(PublisherPayload {
             feed_id: p.feed_id.clone(),
             user_id: p.user_id.clone(),
             display: p.display.clone(),
-        })
-        .collect();
+            color: participant_color(&p.user_id),
+            has_audio: p.has_audio || live_audio,
+            has_video: p.has_video || live_video,
+            joined_at: p.joined_at,
+            viewer_count: state
+                .media_gateway
+                .feed_subscriber_count(&session.room_id, &p.feed_id)
+                .await,
+        });
+    }
 
     // First, register the member in Redis and create a WS session record so the room's authoritative state
     // includes this participant *before* we send the JOINED message. This avoids race conditions where the
     // joining client doesn't appear in the server's participant list.
-    state
-        .room_repo
-        .add_member(&session.room_id, &session.user_id)
-        .await?;
-
-    // Persist display name and joined_at
-    state
-        .room_repo
-        .set_member_info(&session.room_id, &session.user_id, &join_payload.display)
-        .await?;
+    // (membership and display/joined_at are written together)
+    join_member_checked(
+        state,
+        &session.room_id,
+        &session.user_id,
+        &display,
+        session.claims.observer,
+    )
+    .await?;
 
     // Create WS session metadata with TTL so ghost sessions will eventually expire
     let ws_session = crate::models::user::WsSession {
         user_id: session.user_id.clone(),
         room_id: session.room_id.clone(),
-        display: join_payload.display.clone(),
+        display: display.clone(),
         connected_at: chrono::Utc::now().timestamp(),
         last_ping: chrono::Utc::now().timestamp(),
     };
@@ -342,6 +748,8 @@ async fn handle_join_room(
                 display: m.display,
                 room_id: session.room_id.clone(),
                 joined_at: m.joined_at,
+                color: m.color,
+                observer: m.observer,
             })
         }
     }
@@ -354,7 +762,12 @@ async fn handle_join_room(
                     // Ensure we have persisted info for active in-memory clients
                     let _ = state
                         .room_repo
-                        .set_member_info(&session.room_id, &client.user_id, &client.display)
+                        .set_member_info(
+                            &session.room_id,
+                            &client.user_id,
+                            &client.display,
+                            client.observer,
+                        )
                         .await;
 
                     participants_payloads.push(MemberJoinedPayload {
@@ -362,12 +775,23 @@ async fn handle_join_room(
                         display: client.display.clone(),
                         room_id: session.room_id.clone(),
                         joined_at: now,
+                        color: participant_color(&client.user_id),
+                        observer: client.observer,
                     })
                 }
             }
         }
     }
 
+    // A spotlighted feed that has since stopped publishing is dropped; clients
+    // already in the room learned that from publisher_left
+    let spotlight_feed_id = state
+        .room_repo
+        .get_spotlight(&session.room_id)
+        .await
+        .unwrap_or_default()
+        .filter(|feed_id| publishers.iter().any(|p| &p.feed_id == feed_id));
+
     // Send joined response (include participants + count)
     let participant_count = participants_payloads.len();
 
@@ -376,9 +800,18 @@ async fn handle_join_room(
         serde_json::to_value(JoinedPayload {
             room_id: session.room_id.clone(),
             user_id: session.user_id.clone(),
+            color: participant_color(&session.user_id),
             publishers: publisher_payloads,
             participant_count,
             participants: Some(participants_payloads),
+            announcement: state
+                .room_repo
+                .get_announcement(&session.room_id)
+                .await
+                .unwrap_or_default(),
+            spotlight_feed_id,
+            instance_id: state.instance_id.clone(),
+            region: state.config.server_region.clone(),
         })?,
     )
     .with_request_id(request_id);
@@ -393,9 +826,11 @@ async fn handle_join_room(
         msg_types::MEMBER_JOINED,
         serde_json::to_value(MemberJoinedPayload {
             user_id: session.user_id.clone(),
-            display: join_payload.display.clone(),
+            display: display.clone(),
             room_id: session.room_id.clone(),
             joined_at: chrono::Utc::now().timestamp(),
+            color: participant_color(&session.user_id),
+            observer: session.claims.observer,
         })?,
     );
 
@@ -421,30 +856,94 @@ async fn handle_publish_offer(
 ) -> Result<(), AppError> {
     let offer_payload: PublishOfferPayload = serde_json::from_value(payload)?;
 
-    // Check if already publishing
-    if session.is_publishing {
+    ensure_can_publish(session.claims.observer)?;
+
+    // Check if already publishing (a feed reaped for inactivity may be republished)
+    if session.is_publishing
+        && state
+            .media_gateway
+            .has_publisher(&session.room_id, &session.user_id)
+    {
         return Err(AppError::BadRequest("Already publishing".to_string()));
     }
 
-    // Generate feed_id
-    let feed_id = Uuid::new_v4().to_string();
+    validate_sdp(&offer_payload.sdp, state.config.max_sdp_bytes)?;
+    ensure_bundled(&offer_payload.sdp)?;
+
+    let allowed_codecs = room_allowed_codecs(state, &session.room_id).await?;
+    if !allowed_codecs.is_empty() && !sdp::has_allowed_codec(&offer_payload.sdp, &allowed_codecs) {
+        return Err(AppError::BadRequest(format!(
+            "Offer contains none of the room's allowed codecs ({})",
+            allowed_codecs.join(", ")
+        )));
+    }
+
+    let moderator = is_current_host(state, &session.claims).await?
+        || state
+            .room_repo
+            .is_cohost(&session.room_id, &session.user_id)
+            .await?;
+    make_room_for_publisher(state, session, moderator).await?;
+
+    // A publisher coming back within the reconnection grace period keeps its
+    // feed_id; the gateway replaces its previous media session without a
+    // publisher_left once the new one is up
+    let resumed = state
+        .pending_leaves
+        .cancel(&session.room_id, &session.user_id);
+    let feed_id = resumed
+        .clone()
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    // Media kinds the publisher is about to send (audio-only feeds have no video section,
+    // sections without an allowed codec get rejected in our answer)
+    let (has_audio, has_video) =
+        sdp::sending_media_kinds(&sdp::restrict_codecs(&offer_payload.sdp, &allowed_codecs));
 
     // Create publisher in media gateway
-    let answer_sdp = state
+    let created = state
         .media_gateway
         .create_publisher(
             &session.room_id,
             &session.user_id,
             &feed_id,
             &offer_payload.sdp,
+            &allowed_codecs,
+            current_client(session, state),
         )
-        .await?;
+        .await;
+    let answer_sdp = match created {
+        Ok(answer_sdp) => answer_sdp,
+        Err(e) => {
+            // The held feed wasn't taken over: retire it now rather than leave
+            // it in Redis without a media session
+            if let Some(feed_id) = &resumed {
+                retire_publisher(state, &session.room_id, &session.user_id, feed_id).await;
+            }
+            return Err(e);
+        }
+    };
+    if resumed.is_some() {
+        tracing::info!(
+            room_id = %session.room_id,
+            user_id = %session.user_id,
+            feed_id = %feed_id,
+            "Publisher resumed within grace period"
+        );
+    }
 
     // Update session state
     session.set_publishing(feed_id.clone());
 
     // Save publisher to Redis
-    let publisher_info = create_publisher_info(&session.user_id, &feed_id, &session.display);
+    let publisher_info = create_publisher_info(
+        &session.user_id,
+        &feed_id,
+        &session.display,
+        has_audio,
+        has_video,
+        moderator,
+    );
     state
         .room_repo
         .set_publisher(&session.room_id, &session.user_id, &publisher_info)
@@ -460,20 +959,22 @@ async fn handle_publish_offer(
     send_to_client(response, session, state);
 
     // Broadcast publisher_joined to other clients
-    let broadcast_msg = SignalingMessage::new(
-        msg_types::PUBLISHER_JOINED,
-        serde_json::to_value(PublisherJoinedPayload {
+    announce_publisher_change(
+        state,
+        &session.room_id,
+        PublisherChange::Joined(PublisherJoinedPayload {
             feed_id: feed_id.clone(),
             user_id: session.user_id.clone(),
             display: session.display.clone(),
+            color: participant_color(&session.user_id),
             room_id: session.room_id.clone(),
-        })?,
+            has_audio,
+            has_video,
+            joined_at: publisher_info.joined_at,
+        }),
+        Some(&session.conn_id),
     );
 
-    state
-        .connections
-        .broadcast_to_room(&session.room_id, broadcast_msg, Some(&session.conn_id));
-
     tracing::info!(
         room_id = %session.room_id,
         user_id = %session.user_id,
@@ -483,6 +984,65 @@ async fn handle_publish_offer(
     Ok(())
 }
 
+/// Enforce the room's publisher cap. In rooms with `moderator_preemption`, a
+/// moderator publishing into a full room demotes the oldest guest publisher;
+/// everyone else is turned away.
+async fn make_room_for_publisher(
+    state: &AppState,
+    session: &WsSessionState,
+    moderator: bool,
+) -> Result<(), AppError> {
+    let Some(room) = state.room_repo.get_room(&session.room_id).await? else {
+        return Err(AppError::NotFound(format!("Room {} not found", session.room_id)));
+    };
+
+    // Our own entry (a publisher resuming within the grace period) doesn't count
+    let publishers: Vec<_> = state
+        .room_repo
+        .get_publishers(&session.room_id)
+        .await?
+        .into_iter()
+        .filter(|p| p.user_id != session.user_id)
+        .collect();
+    if publishers.len() < room.max_publishers as usize {
+        return Ok(());
+    }
+
+    if !(moderator && room.moderator_preemption) {
+        return Err(AppError::RoomFull);
+    }
+    let Some(victim) = preemption_victim(&publishers) else {
+        return Err(AppError::RoomFull);
+    };
+
+    tracing::info!(
+        room_id = %session.room_id,
+        user_id = %victim.user_id,
+        feed_id = %victim.feed_id,
+        by = %session.user_id,
+        "Demoting guest publisher to make room for a moderator"
+    );
+
+    // Tell the guest (on every connection) first so the publisher_left that
+    // follows makes sense to it
+    if let Some(room) = state.connections.get_room(&session.room_id) {
+        let demoted = SignalingMessage::new(
+            msg_types::DEMOTED,
+            serde_json::to_value(DemotedPayload {
+                room_id: session.room_id.clone(),
+                feed_id: victim.feed_id.clone(),
+                reason: "preempted".to_string(),
+            })?,
+        );
+        for client in room.get_clients_by_user_id(&victim.user_id) {
+            let _ = client.send(demoted.clone());
+        }
+    }
+
+    retire_publisher(state, &session.room_id, &victim.user_id, &victim.feed_id).await;
+    Ok(())
+}
+
 /// Handle trickle_ice message
 async fn handle_trickle_ice(
     payload: serde_json::Value,
@@ -490,7 +1050,42 @@ async fn handle_trickle_ice(
     state: &AppState,
 ) -> Result<(), AppError> {
     let ice_payload: TrickleIcePayload = serde_json::from_value(payload)?;
+    apply_trickled_candidate(&ice_payload, session, state).await
+}
+
+/// Most candidates one `trickle_ice_batch` may carry
+const MAX_CANDIDATES_PER_BATCH: usize = 64;
+
+/// Handle trickle_ice_batch message. Every candidate is tried; the first
+/// failure is reported once the batch is through.
+async fn handle_trickle_ice_batch(
+    payload: serde_json::Value,
+    session: &WsSessionState,
+    state: &AppState,
+) -> Result<(), AppError> {
+    let batch: TrickleIceBatchPayload = serde_json::from_value(payload)?;
+    if batch.candidates.len() > MAX_CANDIDATES_PER_BATCH {
+        return Err(AppError::BadRequest(format!(
+            "A candidate batch carries at most {} candidates ({} sent)",
+            MAX_CANDIDATES_PER_BATCH,
+            batch.candidates.len()
+        )));
+    }
 
+    let mut first_error = None;
+    for ice_payload in &batch.candidates {
+        if let Err(e) = apply_trickled_candidate(ice_payload, session, state).await {
+            first_error.get_or_insert(e);
+        }
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+async fn apply_trickled_candidate(
+    ice_payload: &TrickleIcePayload,
+    session: &WsSessionState,
+    state: &AppState,
+) -> Result<(), AppError> {
     if ice_payload.target == "publisher" {
         // ICE for publisher peer connection
         state
@@ -504,25 +1099,70 @@ async fn handle_trickle_ice(
             )
             .await?;
     } else if ice_payload.target == "subscriber" {
-        // ICE for subscriber peer connection
-        if let Some(feed_id) = &ice_payload.feed_id {
-            state
-                .media_gateway
-                .add_ice_candidate_subscriber(
-                    &session.room_id,
-                    &session.user_id,
-                    feed_id,
-                    &ice_payload.candidate,
-                    ice_payload.sdp_mid.as_deref(),
-                    ice_payload.sdp_mline_index,
-                )
-                .await?;
-        }
+        // ICE for subscriber peer connection (one bundled connection per user,
+        // so `feed_id` is not needed to route the candidate)
+        state
+            .media_gateway
+            .add_ice_candidate_subscriber(
+                &session.room_id,
+                &session.user_id,
+                &ice_payload.candidate,
+                ice_payload.sdp_mid.as_deref(),
+                ice_payload.sdp_mline_index,
+            )
+            .await?;
     }
 
     Ok(())
 }
 
+/// Handle get_candidates message: the server candidates of the publisher or
+/// subscriber connection that its SDP didn't carry, in one response. For
+/// clients that don't trickle; the list is normally empty since gathering
+/// completes before the SDP is sent.
+async fn handle_get_candidates(
+    payload: serde_json::Value,
+    request_id: Option<String>,
+    session: &WsSessionState,
+    state: &AppState,
+) -> Result<(), AppError> {
+    let candidates_payload: GetCandidatesPayload = serde_json::from_value(payload)?;
+    let subscriber = match candidates_payload.target.as_str() {
+        "publisher" => false,
+        "subscriber" => true,
+        other => {
+            return Err(AppError::BadRequest(format!(
+                "Unknown candidate target '{}'",
+                other
+            )))
+        }
+    };
+
+    let candidates = state
+        .media_gateway
+        .unsent_candidates(&session.room_id, &session.user_id, subscriber)
+        .await?
+        .into_iter()
+        .map(|(feed_id, c)| RemoteCandidatePayload {
+            candidate: c.candidate,
+            sdp_mid: c.sdp_mid,
+            sdp_mline_index: Some(c.sdp_mline_index),
+            feed_id,
+        })
+        .collect();
+
+    let response = SignalingMessage::new(
+        msg_types::CANDIDATES,
+        serde_json::to_value(CandidatesPayload {
+            target: candidates_payload.target,
+            candidates,
+        })?,
+    )
+    .with_request_id(request_id);
+    send_to_client(response, session, state);
+    Ok(())
+}
+
 /// Handle subscribe message
 async fn handle_subscribe(
     payload: serde_json::Value,
@@ -538,15 +1178,32 @@ async fn handle_subscribe(
         .map(|f| f.feed_id.clone())
         .collect();
 
-    // Create subscriber in media gateway
+    // A subscribe replaces the connection's subscription set (below, and
+    // the gateway's subscriber), so only the feeds it names count toward the
+    // per-subscriber limit
+    let requested: HashSet<&str> = feed_ids.iter().map(String::as_str).collect();
+    ensure_subscription_limit(requested.len(), state.config.max_subscribed_feeds)?;
+
+    // Create subscriber in media gateway (which enforces
+    // `max_subscribers_per_feed`)
+    session.preferred_codecs = normalize_codec_list(&sub_payload.preferred_codecs)?;
+    let allowed_codecs = room_allowed_codecs(state, &session.room_id).await?;
     let offer_sdp = state
         .media_gateway
-        .create_subscriber(&session.room_id, &session.user_id, &feed_ids)
+        .create_subscriber(
+            &session.room_id,
+            &session.user_id,
+            &feed_ids,
+            &allowed_codecs,
+            &session.preferred_codecs,
+            current_client(session, state),
+        )
         .await?;
 
-    // Update session state
-    for feed_id in &feed_ids {
-        session.add_subscription(feed_id.clone());
+    // Replace the session's feeds and its entries in the room's subscriber index
+    session.set_subscriptions(&feed_ids);
+    if let Some(room) = state.connections.get_room(&session.room_id) {
+        room.set_subscriptions(&session.conn_id, &feed_ids);
     }
 
     // Send offer to subscriber
@@ -570,6 +1227,80 @@ async fn handle_subscribe(
     Ok(())
 }
 
+/// Handle resync_subscriber message: tear the subscriber connection down and
+/// send a fresh subscribe_offer for every subscribed feed still published.
+/// Candidates trickled for the old connection are dropped by the gateway once
+/// the new answer (and its ICE ufrag) is applied.
+async fn handle_resync_subscriber(
+    request_id: Option<String>,
+    session: &mut WsSessionState,
+    state: &AppState,
+) -> Result<(), AppError> {
+    let live: HashSet<String> = state
+        .media_gateway
+        .publisher_feeds(&session.room_id)
+        .await
+        .into_iter()
+        .map(|(_, feed_id)| feed_id)
+        .collect();
+    let room = state.connections.get_room(&session.room_id);
+    let gone: Vec<String> = session
+        .subscribed_feeds
+        .iter()
+        .filter(|feed_id| !live.contains(*feed_id))
+        .cloned()
+        .collect();
+    for feed_id in &gone {
+        session.remove_subscription(feed_id);
+        if let Some(room) = &room {
+            room.remove_subscription(feed_id, &session.conn_id);
+        }
+    }
+    if session.subscribed_feeds.is_empty() {
+        return Err(AppError::BadRequest(
+            "No subscribed feeds to resync".to_string(),
+        ));
+    }
+
+    state
+        .media_gateway
+        .remove_subscriber(&session.room_id, &session.user_id, None)
+        .await;
+
+    let feed_ids = session.subscribed_feeds.clone();
+    let allowed_codecs = room_allowed_codecs(state, &session.room_id).await?;
+    let offer_sdp = state
+        .media_gateway
+        .create_subscriber(
+            &session.room_id,
+            &session.user_id,
+            &feed_ids,
+            &allowed_codecs,
+            &session.preferred_codecs,
+            current_client(session, state),
+        )
+        .await?;
+
+    let response = SignalingMessage::new(
+        msg_types::SUBSCRIBE_OFFER,
+        serde_json::to_value(SubscribeOfferPayload {
+            sdp: offer_sdp,
+            feed_ids,
+        })?,
+    )
+    .with_request_id(request_id);
+    send_to_client(response, session, state);
+
+    tracing::debug!(
+        room_id = %session.room_id,
+        user_id = %session.user_id,
+        dropped_feeds = gone.len(),
+        "Subscriber connection resynced"
+    );
+
+    Ok(())
+}
+
 /// Handle subscribe_answer message
 async fn handle_subscribe_answer(
     payload: serde_json::Value,
@@ -578,9 +1309,23 @@ async fn handle_subscribe_answer(
 ) -> Result<(), AppError> {
     let answer_payload: crate::ws::SubscribeAnswerPayload = serde_json::from_value(payload)?;
 
+    validate_sdp(&answer_payload.sdp, state.config.max_sdp_bytes)?;
+    ensure_bundled(&answer_payload.sdp)?;
+
+    let reported_mids: Vec<(String, String)> = answer_payload
+        .feed_mids
+        .into_iter()
+        .map(|m| (m.feed_id, m.mid))
+        .collect();
+
     state
         .media_gateway
-        .set_subscriber_answer(&session.room_id, &session.user_id, &answer_payload.sdp)
+        .set_subscriber_answer(
+            &session.room_id,
+            &session.user_id,
+            &answer_payload.sdp,
+            &reported_mids,
+        )
         .await?;
 
     tracing::debug!(
@@ -592,6 +1337,37 @@ async fn handle_subscribe_answer(
     Ok(())
 }
 
+/// Handle request_keyframe message: forwarded to the feed's publisher as a PLI
+/// (rate-limited per feed in the gateway, so excess requests are dropped silently)
+async fn handle_request_keyframe(
+    payload: serde_json::Value,
+    session: &WsSessionState,
+    state: &AppState,
+) -> Result<(), AppError> {
+    let keyframe_payload: RequestKeyframePayload = serde_json::from_value(payload)?;
+
+    if !session.subscribed_feeds.contains(&keyframe_payload.feed_id) {
+        return Err(AppError::BadRequest(
+            "Not subscribed to this feed".to_string(),
+        ));
+    }
+
+    let sent = state
+        .media_gateway
+        .request_keyframe(&session.room_id, &keyframe_payload.feed_id)
+        .await;
+
+    tracing::debug!(
+        room_id = %session.room_id,
+        user_id = %session.user_id,
+        feed_id = %keyframe_payload.feed_id,
+        sent,
+        "Keyframe requested by subscriber"
+    );
+
+    Ok(())
+}
+
 /// Handle leave message
 async fn handle_leave(
     request_id: Option<String>,
@@ -608,9 +1384,10 @@ async fn handle_leave(
     send_to_client(response, session, state);
 
     // Perform cleanup now so the client leaves immediately
-    if session.is_joined() {
-        let _ = state.room_repo.remove_member(&session.room_id, &session.user_id).await;
-        let _ = state.room_repo.remove_member_info(&session.room_id, &session.user_id).await;
+    if session.is_joined()
+        && !user_still_connected(state, &session.room_id, &session.user_id, &session.conn_id)
+    {
+        let _ = state.room_repo.leave_member(&session.room_id, &session.user_id).await;
 
         let left_msg = SignalingMessage::new(
             msg_types::MEMBER_LEFT,
@@ -637,6 +1414,211 @@ async fn handle_leave(
     Ok(())
 }
 
+/// Longest `client_error` context / message kept in logs, in characters
+const CLIENT_ERROR_CONTEXT_MAX: usize = 64;
+const CLIENT_ERROR_MESSAGE_MAX: usize = 1000;
+
+/// Handle client_error message: log the client's failure (rate-limited), no reply
+fn handle_client_error(
+    payload: serde_json::Value,
+    session: &mut WsSessionState,
+) -> Result<(), AppError> {
+    let report: ClientErrorPayload = serde_json::from_value(payload)?;
+
+    let (allowed, dropped) = session.client_error_limiter.allow(Instant::now());
+    if dropped > 0 {
+        tracing::warn!(
+            conn_id = %session.conn_id,
+            dropped,
+            "Dropped client error reports over the rate limit"
+        );
+    }
+    if !allowed {
+        return Ok(());
+    }
+
+    // Strip control characters so a report can't forge extra log lines
+    let clean = |s: &str, max: usize| -> String {
+        s.chars().filter(|c| !c.is_control()).take(max).collect()
+    };
+
+    tracing::warn!(
+        room_id = %session.room_id,
+        user_id = %session.user_id,
+        conn_id = %session.conn_id,
+        context = %clean(&report.context, CLIENT_ERROR_CONTEXT_MAX),
+        message = %clean(&report.message, CLIENT_ERROR_MESSAGE_MAX),
+        "Client reported an error"
+    );
+
+    Ok(())
+}
+
+/// Handle rename message: change the live display name (rate-limited). The
+/// JWT keeps the name given at join; peers learn the new one from
+/// `member_renamed` (and `publisher_renamed` while the member publishes).
+async fn handle_rename(
+    payload: serde_json::Value,
+    session: &mut WsSessionState,
+    state: &AppState,
+) -> Result<(), AppError> {
+    let rename: RenamePayload = serde_json::from_value(payload)?;
+
+    let (allowed, _) = session.rename_limiter.allow(Instant::now());
+    if !allowed {
+        return Err(AppError::BadRequest(
+            "Too many renames, try again in a minute".to_string(),
+        ));
+    }
+
+    let display = sanitize_display(
+        &rename.display,
+        &DisplayNameRules::from_config(&state.config),
+    )?;
+    if display == session.display {
+        return Ok(());
+    }
+
+    // Redis first: in rooms with unique names this is where a taken name is refused
+    if !rename_member_checked(state, &session.room_id, &session.user_id, &display).await? {
+        return Err(AppError::NotFound(format!(
+            "User {} is not a member of room {}",
+            session.user_id, session.room_id
+        )));
+    }
+    session.display = display.clone();
+    if let Some(room) = state.connections.get_room(&session.room_id) {
+        room.set_user_display(&session.user_id, &display);
+    }
+
+    let renamed = SignalingMessage::new(
+        msg_types::MEMBER_RENAMED,
+        serde_json::to_value(MemberRenamedPayload {
+            user_id: session.user_id.clone(),
+            room_id: session.room_id.clone(),
+            display: display.clone(),
+        })?,
+    );
+    state
+        .connections
+        .broadcast_to_room(&session.room_id, renamed, None);
+
+    if let Some(feed_id) = session.feed_id.clone().filter(|_| session.is_publishing) {
+        let renamed = SignalingMessage::new(
+            msg_types::PUBLISHER_RENAMED,
+            serde_json::to_value(PublisherRenamedPayload {
+                feed_id,
+                user_id: session.user_id.clone(),
+                room_id: session.room_id.clone(),
+                display,
+            })?,
+        );
+        state
+            .connections
+            .broadcast_to_room(&session.room_id, renamed, None);
+    }
+
+    tracing::info!(
+        room_id = %session.room_id,
+        user_id = %session.user_id,
+        "Member renamed"
+    );
+
+    Ok(())
+}
+
+/// Handle spotlight message (host and co-hosts): store the feed every client
+/// should pin, or clear it, and tell the room with `layout`
+async fn handle_spotlight(
+    payload: serde_json::Value,
+    session: &mut WsSessionState,
+    state: &AppState,
+) -> Result<(), AppError> {
+    let spotlight: SpotlightPayload = serde_json::from_value(payload)?;
+
+    let moderator = is_current_host(state, &session.claims).await?
+        || state
+            .room_repo
+            .is_cohost(&session.room_id, &session.user_id)
+            .await?;
+    if !moderator {
+        return Err(AppError::Forbidden(
+            "Only the host or a co-host can change the spotlight".to_string(),
+        ));
+    }
+
+    let room = state
+        .room_repo
+        .get_room(&session.room_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Room not found".to_string()))?;
+    let publishers = state.room_repo.get_publishers(&session.room_id).await?;
+    let feed_id = spotlight_feed(spotlight.feed_id.as_deref(), &publishers)?;
+
+    state
+        .room_repo
+        .set_spotlight(&session.room_id, feed_id.as_deref(), room.ttl_seconds)
+        .await?;
+
+    let layout = SignalingMessage::new(
+        msg_types::LAYOUT,
+        serde_json::to_value(LayoutPayload {
+            room_id: session.room_id.clone(),
+            spotlight_feed_id: feed_id.clone(),
+        })?,
+    );
+    state
+        .connections
+        .broadcast_to_room(&session.room_id, layout, None);
+
+    tracing::info!(
+        room_id = %session.room_id,
+        user_id = %session.user_id,
+        feed_id = ?feed_id,
+        "Spotlight changed"
+    );
+
+    Ok(())
+}
+
+/// Handle debug_session message: report this connection's session and peer
+/// connection states as the server sees them (`enable_debug_signaling` only)
+async fn handle_debug_session(
+    request_id: Option<String>,
+    session: &WsSessionState,
+    state: &AppState,
+) -> Result<(), AppError> {
+    if !state.config.enable_debug_signaling {
+        return Err(AppError::Forbidden(
+            "Debug signaling is disabled".to_string(),
+        ));
+    }
+
+    let gateway = &state.media_gateway;
+    let info = DebugSessionInfoPayload {
+        conn_id: session.conn_id.clone(),
+        user_id: session.user_id.clone(),
+        room_id: session.room_id.clone(),
+        is_joined: session.is_joined(),
+        is_publishing: session.is_publishing,
+        feed_id: session.feed_id.clone(),
+        subscribed_feeds: session.subscribed_feeds.clone(),
+        publisher: gateway
+            .publisher_peer_state(&session.room_id, &session.user_id)
+            .await,
+        subscriber: gateway
+            .subscriber_peer_state(&session.room_id, &session.user_id)
+            .await,
+    };
+
+    let payload = serde_json::to_value(info)?;
+    let response =
+        SignalingMessage::new(msg_types::DEBUG_SESSION_INFO, payload).with_request_id(request_id);
+    send_to_client(response, session, state);
+
+    Ok(())
+}
+
 /// Handle ping message
 async fn handle_ping(
     request_id: Option<String>,
@@ -657,12 +1639,57 @@ async fn handle_ping(
     Ok(())
 }
 
+/// Refuse SDP whose media doesn't share one rtcp-muxed transport; the gateway
+/// would otherwise negotiate a session that never forwards anything
+fn ensure_bundled(description: &str) -> Result<(), AppError> {
+    match sdp::transport_problem(description) {
+        Some(problem) => Err(AppError::BadRequest(format!(
+            "Unsupported SDP ({}): enable BUNDLE and rtcp-mux (bundlePolicy \"max-bundle\", rtcpMuxPolicy \"require\")",
+            problem
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// The room's codec allowlist (empty = unrestricted)
+async fn room_allowed_codecs(state: &AppState, room_id: &str) -> Result<Vec<String>, AppError> {
+    Ok(state
+        .room_repo
+        .get_room(room_id)
+        .await?
+        .map(|room| room.allowed_codecs)
+        .unwrap_or_default())
+}
+
+/// Handle of the current client's connection
+fn current_client(session: &WsSessionState, state: &AppState) -> Option<ClientHandle> {
+    state
+        .connections
+        .get_room(&session.room_id)
+        .and_then(|room| room.get_client(&session.conn_id))
+}
+
 /// Send a message to the current client
 fn send_to_client(msg: SignalingMessage, session: &WsSessionState, state: &AppState) {
-    if let Some(room) = state.connections.get_room(&session.room_id) {
-        if let Some(client) = room.get_client(&session.conn_id) {
-            let _ = client.send(msg);
-        }
+    if let Some(client) = current_client(session, state) {
+        let _ = client.send(msg);
+    }
+}
+
+/// Tell the client its token is about to expire
+fn send_token_expiring(session: &WsSessionState, state: &AppState) {
+    let expires_in = (session.claims.exp - chrono::Utc::now().timestamp()).max(0) as u64;
+    let payload = TokenExpiringPayload {
+        expires_at: session.claims.exp,
+        expires_in,
+    };
+    if let Ok(payload) = serde_json::to_value(payload) {
+        tracing::debug!(conn_id = %session.conn_id, expires_in, "Token expiring soon");
+        send_to_client(
+            SignalingMessage::new(msg_types::TOKEN_EXPIRING, payload),
+            session,
+            state,
+        );
     }
 }
 
@@ -1,5 +1,8 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::models::Announcement;
+
 /// Wrapper for all WebSocket messages
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SignalingMessage {
@@ -67,10 +70,27 @@ pub struct TrickleIcePayload {
     pub sdp_mline_index: Option<u16>,
     #[serde(default = "default_target")]
     pub target: String,
+    /// Accepted for compatibility; subscriber candidates apply to the user's
+    /// single bundled subscriber connection regardless of feed
     #[serde(skip_serializing_if = "Option::is_none")]
     pub feed_id: Option<String>,
 }
 
+/// trickle_ice_batch message payload: a burst of candidates in one message,
+/// each applied as if sent in its own `trickle_ice`
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrickleIceBatchPayload {
+    pub candidates: Vec<TrickleIcePayload>,
+}
+
+/// get_candidates message payload
+#[derive(Debug, Clone, Deserialize)]
+pub struct GetCandidatesPayload {
+    /// "publisher" or "subscriber"
+    #[serde(default = "default_target")]
+    pub target: String,
+}
+
 fn default_target() -> String {
     "publisher".to_string()
 }
@@ -79,6 +99,10 @@ fn default_target() -> String {
 #[derive(Debug, Clone, Deserialize)]
 pub struct SubscribePayload {
     pub feeds: Vec<SubscribeFeed>,
+    /// Codecs this client would rather receive, best first (e.g. `["H264"]`
+    /// for hardware decoding); ranked ahead of the server's preference
+    #[serde(default)]
+    pub preferred_codecs: Vec<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -92,6 +116,44 @@ pub struct SubscribeFeed {
 #[derive(Debug, Clone, Deserialize)]
 pub struct SubscribeAnswerPayload {
     pub sdp: String,
+    /// Which `m=` section carries which feed; omitted = subscribe order
+    #[serde(default)]
+    pub feed_mids: Vec<FeedMid>,
+}
+
+/// A feed's `m=` section in the subscriber's SDP
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeedMid {
+    pub feed_id: String,
+    pub mid: String,
+}
+
+/// request_keyframe message payload (subscriber detected a frozen feed)
+#[derive(Debug, Clone, Deserialize)]
+pub struct RequestKeyframePayload {
+    pub feed_id: String,
+}
+
+/// client_error message payload (client-side failure reported for server logs)
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClientErrorPayload {
+    /// Where it failed, e.g. "set_remote_description", "get_user_media"
+    pub context: String,
+    pub message: String,
+}
+
+/// rename message payload (new live display name)
+#[derive(Debug, Clone, Deserialize)]
+pub struct RenamePayload {
+    pub display: String,
+}
+
+/// spotlight message payload (moderators): the feed every client should pin,
+/// or null to clear it
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpotlightPayload {
+    #[serde(default)]
+    pub feed_id: Option<String>,
 }
 
 /// unsubscribe message payload
@@ -107,11 +169,23 @@ pub struct UnsubscribePayload {
 pub struct JoinedPayload {
     pub room_id: String,
     pub user_id: String,
+    /// The joining user's own color (see `models::participant_color`)
+    pub color: String,
     pub publishers: Vec<PublisherPayload>,
     /// Number of participants currently in the room (source of truth server-side)
     pub participant_count: usize,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub participants: Option<Vec<MemberJoinedPayload>>,
+    /// Latest host announcement, for clients joining after it was sent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub announcement: Option<Announcement>,
+    /// Feed a moderator spotlighted, for clients joining after the `layout` event
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spotlight_feed_id: Option<String>,
+    /// Instance holding this connection (and its region), for support
+    pub instance_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
 }
 
 /// Member joined / left payloads (for presence)
@@ -122,6 +196,9 @@ pub struct MemberJoinedPayload {
     pub room_id: String,
     /// Unix timestamp (seconds) when the member joined
     pub joined_at: i64,
+    pub color: String,
+    /// Watch-only participant, styled apart by clients
+    pub observer: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -130,12 +207,38 @@ pub struct MemberLeftPayload {
     pub room_id: String,
 }
 
+/// member_renamed event payload
+#[derive(Debug, Clone, Serialize)]
+pub struct MemberRenamedPayload {
+    pub user_id: String,
+    pub room_id: String,
+    pub display: String,
+}
+
+/// publisher_renamed event payload (the renamed member is publishing `feed_id`)
+#[derive(Debug, Clone, Serialize)]
+pub struct PublisherRenamedPayload {
+    pub feed_id: String,
+    pub user_id: String,
+    pub room_id: String,
+    pub display: String,
+}
+
 /// Publisher information in messages
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PublisherPayload {
     pub feed_id: String,
     pub user_id: String,
     pub display: String,
+    pub color: String,
+    /// Lets clients render an avatar instead of an empty tile for audio-only feeds
+    pub has_audio: bool,
+    pub has_video: bool,
+    /// When the feed started; `joined` lists publishers in this order
+    pub joined_at: DateTime<Utc>,
+    /// Subscribers the feed is sent to; kept current by `viewer_count`
+    #[serde(default)]
+    pub viewer_count: usize,
 }
 
 /// publisher_joined event payload
@@ -144,7 +247,11 @@ pub struct PublisherJoinedPayload {
     pub feed_id: String,
     pub user_id: String,
     pub display: String,
+    pub color: String,
     pub room_id: String,
+    pub has_audio: bool,
+    pub has_video: bool,
+    pub joined_at: DateTime<Utc>,
 }
 
 /// publisher_left event payload
@@ -154,6 +261,127 @@ pub struct PublisherLeftPayload {
     pub room_id: String,
 }
 
+/// One entry of a `publishers_changed` batch
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum PublisherChange {
+    Joined(PublisherJoinedPayload),
+    Left(PublisherLeftPayload),
+}
+
+impl PublisherChange {
+    pub fn feed_id(&self) -> &str {
+        match self {
+            PublisherChange::Joined(p) => &p.feed_id,
+            PublisherChange::Left(p) => &p.feed_id,
+        }
+    }
+
+    /// The equivalent single-event message
+    pub fn into_message(self) -> serde_json::Result<SignalingMessage> {
+        Ok(match self {
+            PublisherChange::Joined(p) => {
+                SignalingMessage::new(msg_types::PUBLISHER_JOINED, serde_json::to_value(p)?)
+            }
+            PublisherChange::Left(p) => {
+                SignalingMessage::new(msg_types::PUBLISHER_LEFT, serde_json::to_value(p)?)
+            }
+        })
+    }
+}
+
+/// publishers_changed event payload: coalesced publisher_joined/left, in order
+#[derive(Debug, Clone, Serialize)]
+pub struct PublishersChangedPayload {
+    pub room_id: String,
+    pub changes: Vec<PublisherChange>,
+}
+
+/// demoted event payload: the receiver's feed was unpublished to make room
+#[derive(Debug, Clone, Serialize)]
+pub struct DemotedPayload {
+    pub room_id: String,
+    pub feed_id: String,
+    pub reason: String,
+}
+
+/// session_replaced event payload: the same user connected again elsewhere
+/// and this connection is being closed
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionReplacedPayload {
+    pub room_id: String,
+}
+
+/// banned event payload: a moderator banned this user and the connection is
+/// being closed
+#[derive(Debug, Clone, Serialize)]
+pub struct BannedPayload {
+    pub room_id: String,
+    /// When the ban ends (None = for as long as the room lives)
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// token_expiring event payload: this connection's token expires soon and
+/// should be replaced before the socket is dropped
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenExpiringPayload {
+    /// Unix seconds (the token's `exp`)
+    pub expires_at: i64,
+    pub expires_in: u64,
+}
+
+/// room_closing event payload: the room expires in `expires_in` seconds
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomClosingPayload {
+    pub room_id: String,
+    pub expires_in: u64,
+}
+
+/// viewer_count event payload: how many subscribers a feed now has
+#[derive(Debug, Clone, Serialize)]
+pub struct ViewerCountPayload {
+    pub room_id: String,
+    pub feed_id: String,
+    pub viewer_count: usize,
+}
+
+/// layout event payload: the spotlighted feed changed (None = cleared)
+#[derive(Debug, Clone, Serialize)]
+pub struct LayoutPayload {
+    pub room_id: String,
+    pub spotlight_feed_id: Option<String>,
+}
+
+/// States of one of the server-side peer connections
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PeerStatePayload {
+    pub connection_state: String,
+    pub ice_connection_state: String,
+}
+
+/// debug_session_info response payload: the server's view of this connection
+#[derive(Debug, Clone, Serialize)]
+pub struct DebugSessionInfoPayload {
+    pub conn_id: String,
+    pub user_id: String,
+    pub room_id: String,
+    pub is_joined: bool,
+    pub is_publishing: bool,
+    pub feed_id: Option<String>,
+    pub subscribed_feeds: Vec<String>,
+    /// None when the user has no publisher / subscriber peer connection
+    pub publisher: Option<PeerStatePayload>,
+    pub subscriber: Option<PeerStatePayload>,
+}
+
+/// room_ended event payload: the room is gone and the socket closes next
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomEndedPayload {
+    pub room_id: String,
+    /// Why it ended, e.g. "host_left"
+    pub reason: String,
+}
+
 /// publish_answer response payload
 #[derive(Debug, Clone, Serialize)]
 pub struct PublishAnswerPayload {
@@ -178,33 +406,100 @@ pub struct RemoteCandidatePayload {
     pub feed_id: String,
 }
 
+/// candidates response payload: server candidates the SDP didn't carry
+#[derive(Debug, Clone, Serialize)]
+pub struct CandidatesPayload {
+    pub target: String,
+    pub candidates: Vec<RemoteCandidatePayload>,
+}
+
+/// media_state event payload: a server-side peer connection changed state
+/// (e.g. "failed" after an ICE failure), so the client can ICE-restart or rejoin
+#[derive(Debug, Clone, Serialize)]
+pub struct MediaStatePayload {
+    /// "publisher" or "subscriber"
+    pub role: String,
+    /// "connecting", "connected", "disconnected" or "failed"
+    pub state: String,
+}
+
 /// left_room response payload
 #[derive(Debug, Clone, Serialize)]
 pub struct LeftRoomPayload {
     pub success: bool,
 }
 
+/// role_changed event payload
+#[derive(Debug, Clone, Serialize)]
+pub struct RoleChangedPayload {
+    pub room_id: String,
+    pub user_id: String,
+    /// "cohost" or "participant"
+    pub role: String,
+}
+
+/// host_transferred event payload
+#[derive(Debug, Clone, Serialize)]
+pub struct HostTransferredPayload {
+    pub room_id: String,
+    /// The new host, when the transfer named one
+    pub user_id: Option<String>,
+}
+
+/// room_locked / room_unlocked event payload
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomLockPayload {
+    pub room_id: String,
+}
+
 /// Message types enum for matching
 pub mod msg_types {
     pub const JOIN_ROOM: &str = "join_room";
     pub const PUBLISH_OFFER: &str = "publish_offer";
     pub const TRICKLE_ICE: &str = "trickle_ice";
+    pub const TRICKLE_ICE_BATCH: &str = "trickle_ice_batch";
     pub const SUBSCRIBE: &str = "subscribe";
     pub const SUBSCRIBE_ANSWER: &str = "subscribe_answer";
     pub const UNSUBSCRIBE: &str = "unsubscribe";
     pub const LEAVE: &str = "leave";
     pub const PING: &str = "ping";
+    pub const REQUEST_KEYFRAME: &str = "request_keyframe";
+    pub const CLIENT_ERROR: &str = "client_error";
+    pub const RENAME: &str = "rename";
+    pub const RESYNC_SUBSCRIBER: &str = "resync_subscriber";
+    pub const GET_CANDIDATES: &str = "get_candidates";
+    pub const SPOTLIGHT: &str = "spotlight";
+    pub const DEBUG_SESSION: &str = "debug_session";
 
     // Server -> Client
     pub const JOINED: &str = "joined";
     pub const PUBLISHER_JOINED: &str = "publisher_joined";
     pub const PUBLISHER_LEFT: &str = "publisher_left";
+    pub const PUBLISHERS_CHANGED: &str = "publishers_changed";
     pub const MEMBER_JOINED: &str = "member_joined";
     pub const MEMBER_LEFT: &str = "member_left";
+    pub const MEMBER_RENAMED: &str = "member_renamed";
+    pub const PUBLISHER_RENAMED: &str = "publisher_renamed";
     pub const PUBLISH_ANSWER: &str = "publish_answer";
     pub const SUBSCRIBE_OFFER: &str = "subscribe_offer";
     pub const REMOTE_CANDIDATE: &str = "remote_candidate";
+    pub const CANDIDATES: &str = "candidates";
+    pub const MEDIA_STATE: &str = "media_state";
     pub const LEFT_ROOM: &str = "left_room";
+    pub const ROLE_CHANGED: &str = "role_changed";
+    pub const DEMOTED: &str = "demoted";
+    pub const HOST_TRANSFERRED: &str = "host_transferred";
+    pub const SESSION_REPLACED: &str = "session_replaced";
+    pub const BANNED: &str = "banned";
+    pub const ROOM_LOCKED: &str = "room_locked";
+    pub const ROOM_UNLOCKED: &str = "room_unlocked";
+    pub const ROOM_CLOSING: &str = "room_closing";
+    pub const VIEWER_COUNT: &str = "viewer_count";
+    pub const LAYOUT: &str = "layout";
+    pub const DEBUG_SESSION_INFO: &str = "debug_session_info";
+    pub const ROOM_ENDED: &str = "room_ended";
+    pub const TOKEN_EXPIRING: &str = "token_expiring";
+    pub const ANNOUNCEMENT: &str = "announcement";
     pub const ERROR: &str = "error";
     pub const PONG: &str = "pong";
 }
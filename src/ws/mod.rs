@@ -1,3 +1,4 @@
+pub mod codec;
 pub mod handler;
 pub mod messages;
 pub mod session;
@@ -1,8 +1,11 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::mpsc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Notify};
 
 use crate::models::Claims;
-use crate::ws::SignalingMessage;
+use crate::ws::{PublisherChange, SignalingMessage};
 
 /// WebSocket session state
 #[derive(Debug)]
@@ -15,7 +18,13 @@ pub struct WsSessionState {
     pub is_publishing: bool,
     pub feed_id: Option<String>,
     pub subscribed_feeds: Vec<String>,
+    /// Receive codec preference from the last `subscribe`, reused on resync
+    pub preferred_codecs: Vec<String>,
     pub is_joined: bool,
+    /// Throttles `client_error` reports from this connection
+    pub client_error_limiter: ReportLimiter,
+    /// Throttles `rename` requests from this connection
+    pub rename_limiter: ReportLimiter,
 }
 
 impl WsSessionState {
@@ -29,7 +38,10 @@ impl WsSessionState {
             is_publishing: false,
             feed_id: None,
             subscribed_feeds: Vec::new(),
+            preferred_codecs: Vec::new(),
             is_joined: false,
+            client_error_limiter: ReportLimiter::new(CLIENT_ERROR_REPORTS_PER_MINUTE, Duration::from_secs(60)),
+            rename_limiter: ReportLimiter::new(RENAMES_PER_MINUTE, Duration::from_secs(60)),
         }
     }
 
@@ -48,6 +60,15 @@ impl WsSessionState {
         self.subscribed_feeds.retain(|f| f != feed_id);
     }
 
+    /// Replace the subscribed feeds, as a new subscribe replaces the
+    /// subscriber connection
+    pub fn set_subscriptions(&mut self, feed_ids: &[String]) {
+        self.subscribed_feeds.clear();
+        for feed_id in feed_ids {
+            self.add_subscription(feed_id.clone());
+        }
+    }
+
     /// Mark this session as having completed the room join handshake
     pub fn set_joined(&mut self, joined: bool) {
         self.is_joined = joined;
@@ -59,6 +80,92 @@ impl WsSessionState {
     }
 }
 
+/// `client_error` reports logged per connection and minute; extra ones are dropped
+pub const CLIENT_ERROR_REPORTS_PER_MINUTE: u32 = 10;
+
+/// `rename` requests accepted per connection and minute
+pub const RENAMES_PER_MINUTE: u32 = 5;
+
+/// How long until a connection whose token expires at `exp` (unix seconds)
+/// should get `token_expiring`, `lead_seconds` ahead of it: zero when already
+/// inside that window, None when warnings are off or the token has expired
+pub fn token_warning_delay(exp: i64, now: i64, lead_seconds: u64) -> Option<Duration> {
+    if lead_seconds == 0 || exp <= now {
+        return None;
+    }
+    let warn_at = exp.saturating_sub(lead_seconds as i64);
+    let delay = warn_at.saturating_sub(now).max(0) as u64;
+    Some(Duration::from_secs(delay))
+}
+
+/// Fixed-window counter keeping client reports from flooding the logs
+#[derive(Debug)]
+pub struct ReportLimiter {
+    max: u32,
+    window: Duration,
+    window_start: Instant,
+    count: u32,
+    dropped: u32,
+}
+
+impl ReportLimiter {
+    pub fn new(max: u32, window: Duration) -> Self {
+        Self {
+            max,
+            window,
+            window_start: Instant::now(),
+            count: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Whether a report arriving at `now` may be logged. On the first accepted
+    /// report of a new window, also returns how many were dropped in the previous one.
+    pub fn allow(&mut self, now: Instant) -> (bool, u32) {
+        let mut dropped = 0;
+        if now.duration_since(self.window_start) >= self.window {
+            self.window_start = now;
+            self.count = 0;
+            dropped = std::mem::take(&mut self.dropped);
+        }
+
+        if self.count >= self.max {
+            self.dropped += 1;
+            return (false, dropped);
+        }
+        self.count += 1;
+        (true, dropped)
+    }
+}
+
+/// What happens when a user opens another connection to a room they are
+/// already connected to (two tabs, a reconnect racing the old socket)
+///
+/// Presence is per user either way: `member_left` is only sent once the
+/// user's last connection is gone. Media sessions are keyed by user in the
+/// gateway, so a publish from one connection replaces the other's feed; a
+/// closing connection only tears down the feed and subscriber it created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateSessionPolicy {
+    /// The new connection wins; older ones get `session_replaced` and are closed
+    #[default]
+    Replace,
+    /// Keep every connection; messages aimed at the user (e.g. `demoted`)
+    /// reach all of them
+    Allow,
+}
+
+impl DuplicateSessionPolicy {
+    /// Parse `replace` or `allow` (case-insensitive)
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "replace" => Some(Self::Replace),
+            "allow" => Some(Self::Allow),
+            _ => None,
+        }
+    }
+}
+
 /// Client connection handle for sending messages
 #[derive(Clone)]
 pub struct ClientHandle {
@@ -66,7 +173,14 @@ pub struct ClientHandle {
     pub user_id: String,
     pub room_id: String,
     pub display: String,
+    /// Joined through the host (creator_key) flow and not demoted since by a
+    /// host transfer
+    pub host: bool,
+    /// Watch-only participant (observer role from the token)
+    pub observer: bool,
     pub sender: mpsc::UnboundedSender<SignalingMessage>,
+    /// Fired by `close`; the connection's send task ends the socket on it
+    closer: Arc<Notify>,
 }
 
 impl ClientHandle {
@@ -75,6 +189,8 @@ impl ClientHandle {
         user_id: String,
         room_id: String,
         display: String,
+        host: bool,
+        observer: bool,
         sender: mpsc::UnboundedSender<SignalingMessage>,
     ) -> Self {
         Self {
@@ -82,10 +198,25 @@ impl ClientHandle {
             user_id,
             room_id,
             display,
+            host,
+            observer,
             sender,
+            closer: Arc::new(Notify::new()),
         }
     }
 
+    /// Close this connection's socket once the messages already queued for
+    /// it are out. Dropping handles doesn't do it: clones held by media
+    /// callbacks keep the channel open.
+    pub fn close(&self) {
+        self.closer.notify_one();
+    }
+
+    /// What `close` fires, for the connection's send task
+    pub fn close_signal(&self) -> Arc<Notify> {
+        self.closer.clone()
+    }
+
     pub fn send(
         &self,
         msg: SignalingMessage,
@@ -97,12 +228,14 @@ impl ClientHandle {
 /// Room connections manager - tracks all clients in a room
 pub struct RoomConnections {
     clients: dashmap::DashMap<String, ClientHandle>, // conn_id -> ClientHandle
+    subscribers: dashmap::DashMap<String, HashSet<String>>, // feed_id -> conn_ids
 }
 
 impl RoomConnections {
     pub fn new() -> Self {
         Self {
             clients: dashmap::DashMap::new(),
+            subscribers: dashmap::DashMap::new(),
         }
     }
 
@@ -111,9 +244,55 @@ impl RoomConnections {
     }
 
     pub fn remove_client(&self, conn_id: &str) -> Option<ClientHandle> {
+        self.clear_subscriptions(conn_id);
         self.clients.remove(conn_id).map(|(_, v)| v)
     }
 
+    /// Forget every feed a connection is watching
+    fn clear_subscriptions(&self, conn_id: &str) {
+        self.subscribers.retain(|_, conns| {
+            conns.remove(conn_id);
+            !conns.is_empty()
+        });
+    }
+
+    /// Make `feed_ids` exactly the feeds a connection is watching
+    pub fn set_subscriptions(&self, conn_id: &str, feed_ids: &[String]) {
+        self.clear_subscriptions(conn_id);
+        for feed_id in feed_ids {
+            self.add_subscription(feed_id, conn_id);
+        }
+    }
+
+    /// Record that a connection is watching a feed
+    pub fn add_subscription(&self, feed_id: &str, conn_id: &str) {
+        self.subscribers
+            .entry(feed_id.to_string())
+            .or_default()
+            .insert(conn_id.to_string());
+    }
+
+    /// Forget that a connection is watching a feed
+    pub fn remove_subscription(&self, feed_id: &str, conn_id: &str) {
+        self.subscribers.remove_if_mut(feed_id, |_, conns| {
+            conns.remove(conn_id);
+            conns.is_empty()
+        });
+    }
+
+    /// Drop the subscriber set of a feed that is no longer published
+    pub fn remove_feed(&self, feed_id: &str) {
+        self.subscribers.remove(feed_id);
+    }
+
+    /// Connection ids currently subscribed to a feed
+    pub fn subscriber_ids(&self, feed_id: &str) -> Vec<String> {
+        self.subscribers
+            .get(feed_id)
+            .map(|conns| conns.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
     pub fn get_client(&self, conn_id: &str) -> Option<ClientHandle> {
         self.clients.get(conn_id).map(|r| r.clone())
     }
@@ -125,6 +304,57 @@ impl RoomConnections {
             .map(|r| r.clone())
     }
 
+    /// Every connection of a user, for messages aimed at the user
+    pub fn get_clients_by_user_id(&self, user_id: &str) -> Vec<ClientHandle> {
+        self.clients
+            .iter()
+            .filter(|r| r.user_id == user_id)
+            .map(|r| r.clone())
+            .collect()
+    }
+
+    /// Update the live display name on every connection of a user
+    pub fn set_user_display(&self, user_id: &str, display: &str) {
+        for mut client in self.clients.iter_mut().filter(|r| r.user_id == user_id) {
+            client.display = display.to_string();
+        }
+    }
+
+    /// Update the host flag on every connection of a user (a host transfer
+    /// demotes the outgoing host)
+    pub fn set_user_host(&self, user_id: &str, host: bool) {
+        for mut client in self.clients.iter_mut().filter(|r| r.user_id == user_id) {
+            client.host = host;
+        }
+    }
+
+    /// Whether a host connection is open in the room on this instance
+    pub fn has_host(&self) -> bool {
+        self.clients.iter().any(|c| c.value().host)
+    }
+
+    /// Whether the user has a connection other than `conn_id`
+    pub fn has_other_connection(&self, user_id: &str, conn_id: &str) -> bool {
+        self.clients
+            .iter()
+            .any(|r| r.user_id == user_id && r.conn_id != conn_id)
+    }
+
+    /// Detach every connection of a user; `close` the returned handles to end
+    /// those sockets
+    pub fn take_user_clients(&self, user_id: &str) -> Vec<ClientHandle> {
+        let conn_ids: Vec<String> = self
+            .clients
+            .iter()
+            .filter(|r| r.user_id == user_id)
+            .map(|r| r.conn_id.clone())
+            .collect();
+        conn_ids
+            .iter()
+            .filter_map(|conn_id| self.remove_client(conn_id))
+            .collect()
+    }
+
     pub fn broadcast(&self, msg: SignalingMessage, exclude_conn_id: Option<&str>) {
         for client in self.clients.iter() {
             if let Some(exclude) = exclude_conn_id {
@@ -136,14 +366,28 @@ impl RoomConnections {
         }
     }
 
+    /// Send to the connections subscribed to `feed_id` only
     pub fn broadcast_to_subscribers(
         &self,
         msg: SignalingMessage,
-        _feed_id: &str,
+        feed_id: &str,
         exclude_conn_id: Option<&str>,
     ) {
-        // For now, broadcast to all - would need subscriber tracking for optimization
-        self.broadcast(msg, exclude_conn_id);
+        for conn_id in self.subscriber_ids(feed_id) {
+            if exclude_conn_id == Some(conn_id.as_str()) {
+                continue;
+            }
+            if let Some(client) = self.clients.get(&conn_id) {
+                let _ = client.send(msg.clone());
+            }
+        }
+    }
+
+    /// Close every connection once the messages queued for it are out
+    pub fn close_all(&self) {
+        for client in self.clients.iter() {
+            client.close();
+        }
     }
 
     pub fn client_count(&self) -> usize {
@@ -165,18 +409,114 @@ impl Default for RoomConnections {
     }
 }
 
+/// Publishers that disconnected and whose `publisher_left` is being held back
+/// for the reconnection grace period
+#[derive(Default)]
+pub struct PendingLeaves {
+    entries: dashmap::DashMap<String, (String, u64)>, // "room:user" -> (feed_id, ticket)
+    next_ticket: AtomicU64,
+}
+
+impl PendingLeaves {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn key(room_id: &str, user_id: &str) -> String {
+        format!("{}:{}", room_id, user_id)
+    }
+
+    /// Hold a publisher's departure; the ticket identifies this hold when it expires
+    pub fn schedule(&self, room_id: &str, user_id: &str, feed_id: &str) -> u64 {
+        let ticket = self.next_ticket.fetch_add(1, Ordering::Relaxed);
+        self.entries
+            .insert(Self::key(room_id, user_id), (feed_id.to_string(), ticket));
+        ticket
+    }
+
+    /// The publisher came back: drop the hold and return the feed_id to reuse
+    pub fn cancel(&self, room_id: &str, user_id: &str) -> Option<String> {
+        self.entries
+            .remove(&Self::key(room_id, user_id))
+            .map(|(_, (feed_id, _))| feed_id)
+    }
+
+    /// Grace period over: true if this hold is still pending (and now consumed)
+    pub fn expire(&self, room_id: &str, user_id: &str, ticket: u64) -> bool {
+        self.entries
+            .remove_if(&Self::key(room_id, user_id), |_, (_, t)| *t == ticket)
+            .is_some()
+    }
+}
+
+/// Publisher changes waiting to go out as one `publishers_changed` per room
+#[derive(Default)]
+pub struct PublisherChangeBuffer {
+    rooms: dashmap::DashMap<String, Vec<(PublisherChange, Option<String>)>>, // room_id -> (change, excluded conn_id)
+}
+
+impl PublisherChangeBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a change; true if it opened a new batch for the room (the caller
+    /// schedules the flush)
+    pub fn push(&self, room_id: &str, change: PublisherChange, exclude_conn_id: Option<&str>) -> bool {
+        let mut pending = self.rooms.entry(room_id.to_string()).or_default();
+        pending.push((change, exclude_conn_id.map(str::to_string)));
+        pending.len() == 1
+    }
+
+    /// Take the room's batch, oldest first
+    pub fn drain(&self, room_id: &str) -> Vec<(PublisherChange, Option<String>)> {
+        self.rooms
+            .remove(room_id)
+            .map(|(_, pending)| pending)
+            .unwrap_or_default()
+    }
+}
+
 /// Global connections manager - tracks all rooms
 pub struct ConnectionsManager {
     rooms: dashmap::DashMap<String, Arc<RoomConnections>>, // room_id -> RoomConnections
+    /// Connection slots held, upgrades in progress included
+    slots: Arc<AtomicUsize>,
+}
+
+/// One of the instance's `MAX_CONNECTIONS` slots, given back on drop
+pub struct ConnectionSlot {
+    slots: Arc<AtomicUsize>,
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.slots.fetch_sub(1, Ordering::SeqCst);
+    }
 }
 
 impl ConnectionsManager {
     pub fn new() -> Self {
         Self {
             rooms: dashmap::DashMap::new(),
+            slots: Arc::new(AtomicUsize::new(0)),
         }
     }
 
+    /// Take a connection slot out of `max` (0 = unlimited), held for the
+    /// socket's lifetime; None when all are taken. Taken before the upgrade,
+    /// so concurrent upgrades can't overshoot the limit.
+    pub fn reserve_connection(&self, max: usize) -> Option<ConnectionSlot> {
+        self.slots
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |held| {
+                (max == 0 || held < max).then_some(held + 1)
+            })
+            .ok()?;
+        Some(ConnectionSlot {
+            slots: self.slots.clone(),
+        })
+    }
+
     pub fn get_or_create_room(&self, room_id: &str) -> Arc<RoomConnections> {
         self.rooms
             .entry(room_id.to_string())
@@ -188,6 +528,11 @@ impl ConnectionsManager {
         self.rooms.get(room_id).map(|r| r.clone())
     }
 
+    /// Drop a whole room; its sockets close once the returned handles are gone
+    pub fn remove_room(&self, room_id: &str) -> Option<Arc<RoomConnections>> {
+        self.rooms.remove(room_id).map(|(_, room)| room)
+    }
+
     pub fn remove_client_from_room(&self, room_id: &str, conn_id: &str) -> Option<ClientHandle> {
         if let Some(room) = self.rooms.get(room_id) {
             let handle = room.remove_client(conn_id);
@@ -214,9 +559,31 @@ impl ConnectionsManager {
         }
     }
 
+    pub fn broadcast_to_subscribers(
+        &self,
+        room_id: &str,
+        feed_id: &str,
+        msg: SignalingMessage,
+        exclude_conn_id: Option<&str>,
+    ) {
+        if let Some(room) = self.rooms.get(room_id) {
+            room.broadcast_to_subscribers(msg, feed_id, exclude_conn_id);
+        }
+    }
+
+    /// Rooms with at least one open connection
+    pub fn room_ids(&self) -> Vec<String> {
+        self.rooms.iter().map(|r| r.key().clone()).collect()
+    }
+
     pub fn room_count(&self) -> usize {
         self.rooms.len()
     }
+
+    /// Open WebSocket connections across all rooms
+    pub fn connection_count(&self) -> usize {
+        self.rooms.iter().map(|room| room.client_count()).sum()
+    }
 }
 
 impl Default for ConnectionsManager {
@@ -224,3 +591,224 @@ impl Default for ConnectionsManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn left(feed_id: &str) -> PublisherChange {
+        PublisherChange::Left(crate::ws::PublisherLeftPayload {
+            feed_id: feed_id.to_string(),
+            room_id: "room".to_string(),
+        })
+    }
+
+    #[test]
+    fn test_connection_slots_are_capped_and_given_back() {
+        let manager = ConnectionsManager::new();
+        let first = manager.reserve_connection(2).unwrap();
+        let _second = manager.reserve_connection(2).unwrap();
+        assert!(manager.reserve_connection(2).is_none());
+
+        drop(first);
+        assert!(manager.reserve_connection(2).is_some());
+        // 0 is unlimited
+        assert!(manager.reserve_connection(0).is_some());
+    }
+
+    #[test]
+    fn test_publisher_change_buffer_keeps_order() {
+        let buffer = PublisherChangeBuffer::new();
+        assert!(buffer.push("room", left("a"), None));
+        assert!(!buffer.push("room", left("b"), Some("conn-1")));
+        assert!(buffer.push("other", left("c"), None));
+
+        let batch = buffer.drain("room");
+        let feeds: Vec<_> = batch.iter().map(|(c, _)| c.feed_id()).collect();
+        assert_eq!(feeds, ["a", "b"]);
+        assert_eq!(batch[1].1.as_deref(), Some("conn-1"));
+
+        // Drained: the next change opens a new batch
+        assert!(buffer.drain("room").is_empty());
+        assert!(buffer.push("room", left("d"), None));
+    }
+
+    #[test]
+    fn test_token_warning_fires_before_expiry() {
+        let now = 1_000_000;
+        // A 90 s token with a 60 s lead is warned 30 s in
+        let delay = token_warning_delay(now + 90, now, 60);
+        assert_eq!(delay, Some(Duration::from_secs(30)));
+        // A short-lived token already inside the window is warned right away
+        assert_eq!(token_warning_delay(now + 20, now, 60), Some(Duration::ZERO));
+        assert_eq!(token_warning_delay(now, now, 60), None);
+        assert_eq!(token_warning_delay(now + 90, now, 0), None);
+    }
+
+    #[test]
+    fn test_report_limiter_window() {
+        let start = Instant::now();
+        let mut limiter = ReportLimiter::new(2, Duration::from_secs(60));
+        limiter.window_start = start;
+
+        assert_eq!(limiter.allow(start), (true, 0));
+        assert_eq!(limiter.allow(start), (true, 0));
+        assert_eq!(limiter.allow(start), (false, 0));
+        assert_eq!(limiter.allow(start + Duration::from_secs(30)), (false, 0));
+
+        // New window: reports flow again and the drops are handed back once
+        let next = start + Duration::from_secs(60);
+        assert_eq!(limiter.allow(next), (true, 2));
+        assert_eq!(limiter.allow(next), (true, 0));
+    }
+
+    fn client(room: &RoomConnections, conn_id: &str) -> mpsc::UnboundedReceiver<SignalingMessage> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        room.add_client(ClientHandle::new(
+            conn_id.to_string(),
+            format!("user-{}", conn_id),
+            "room".to_string(),
+            conn_id.to_string(),
+            conn_id == "host",
+            false,
+            tx,
+        ));
+        rx
+    }
+
+    #[test]
+    fn test_broadcast_to_subscribers_only_reaches_subscribers() {
+        let room = RoomConnections::new();
+        let mut a = client(&room, "a");
+        let mut b = client(&room, "b");
+        let mut c = client(&room, "c");
+
+        room.add_subscription("feed-1", "a");
+        room.add_subscription("feed-1", "b");
+        room.add_subscription("feed-2", "c");
+
+        let msg = SignalingMessage::new("test", serde_json::json!({}));
+        room.broadcast_to_subscribers(msg, "feed-1", Some("b"));
+
+        assert!(a.try_recv().is_ok());
+        assert!(b.try_recv().is_err());
+        assert!(c.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_has_host_follows_host_connection() {
+        let room = RoomConnections::new();
+        let _guest = client(&room, "guest");
+        assert!(!room.has_host());
+
+        let _host = client(&room, "host");
+        assert!(room.has_host());
+        room.remove_client("host");
+        assert!(!room.has_host());
+    }
+
+    #[test]
+    fn test_rename_updates_every_connection_of_user() {
+        let room = RoomConnections::new();
+        let _a = client(&room, "a");
+        let _b = client(&room, "b");
+
+        room.set_user_display("user-a", "Alice (mobile)");
+
+        assert_eq!(room.get_client("a").unwrap().display, "Alice (mobile)");
+        assert_eq!(room.get_client("b").unwrap().display, "b");
+    }
+
+    #[test]
+    fn test_pending_leave_cancel_and_expire() {
+        let pending = PendingLeaves::new();
+
+        let ticket = pending.schedule("room", "user", "feed-1");
+        assert_eq!(pending.cancel("room", "user").as_deref(), Some("feed-1"));
+        assert!(!pending.expire("room", "user", ticket));
+
+        let stale = pending.schedule("room", "user", "feed-1");
+        let fresh = pending.schedule("room", "user", "feed-2");
+        assert!(!pending.expire("room", "user", stale));
+        assert!(pending.expire("room", "user", fresh));
+    }
+
+    #[test]
+    fn test_duplicate_user_connections() {
+        let room = RoomConnections::new();
+        let mut first = client(&room, "a");
+        let _b = client(&room, "b");
+
+        assert!(!room.has_other_connection("user-a", "a"));
+        assert!(room.has_other_connection("user-a", "a2"));
+
+        // Replacing: the old connection is detached and its channel closes
+        // once the handle is gone
+        let old = room.take_user_clients("user-a");
+        assert_eq!(old.len(), 1);
+        let _ = old[0].send(SignalingMessage::new("session_replaced", serde_json::json!({})));
+        drop(old);
+        assert!(first.try_recv().is_ok());
+        assert!(matches!(
+            first.try_recv(),
+            Err(mpsc::error::TryRecvError::Disconnected)
+        ));
+        assert_eq!(room.get_all_client_ids(), vec!["b".to_string()]);
+        assert_eq!(DuplicateSessionPolicy::parse(" Allow "), Some(DuplicateSessionPolicy::Allow));
+        assert!(DuplicateSessionPolicy::parse("reject").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_close_reaches_connections_whose_handle_lives_on() {
+        let room = RoomConnections::new();
+        let _rx = client(&room, "a");
+        let signal = room.get_client("a").unwrap().close_signal();
+
+        // A media callback's clone keeps the channel open, close still fires
+        let _held = room.get_client("a").unwrap();
+        room.close_all();
+        tokio::time::timeout(Duration::from_secs(1), signal.notified())
+            .await
+            .expect("Close should be signalled");
+    }
+
+    #[test]
+    fn test_remove_client_clears_subscriptions() {
+        let room = RoomConnections::new();
+        let _a = client(&room, "a");
+
+        room.add_subscription("feed-1", "a");
+        room.remove_client("a");
+
+        assert!(room.subscriber_ids("feed-1").is_empty());
+    }
+
+    #[test]
+    fn test_subscribe_after_subscribe_leaves_only_the_new_set() {
+        let room = RoomConnections::new();
+        let _a = client(&room, "a");
+        let _b = client(&room, "b");
+        let claims = Claims {
+            sub: "user-a".to_string(),
+            room_id: "room".to_string(),
+            display: "a".to_string(),
+            host: false,
+            observer: false,
+            iat: 0,
+            exp: 0,
+        };
+        let mut session = WsSessionState::new("a".to_string(), claims);
+        room.add_subscription("feed-1", "b");
+
+        let feeds = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        for subscribe in [feeds(&["feed-1", "feed-2"]), feeds(&["feed-3"])] {
+            session.set_subscriptions(&subscribe);
+            room.set_subscriptions("a", &subscribe);
+        }
+
+        assert_eq!(session.subscribed_feeds, ["feed-3"]);
+        assert_eq!(room.subscriber_ids("feed-1"), ["b"]);
+        assert!(room.subscriber_ids("feed-2").is_empty());
+        assert_eq!(room.subscriber_ids("feed-3"), ["a"]);
+    }
+}
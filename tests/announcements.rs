@@ -0,0 +1,46 @@
+//! Needs a live Redis; set `REDIS_TEST_URL` (e.g. redis://127.0.0.1:6379/15) to run.
+
+mod common;
+
+use truegather_backend::models::{Announcement, AnnouncementLevel, Room};
+
+#[tokio::test]
+async fn test_latest_announcement_is_kept() {
+    let Some(repo) = common::test_repo() else {
+        return;
+    };
+
+    let room = Room::new("announce".to_string(), 4, 60);
+    repo.create_room(&room).await.expect("Should create room");
+    assert!(repo
+        .get_announcement(&room.room_id)
+        .await
+        .unwrap()
+        .is_none());
+
+    for (text, level) in [
+        ("First", AnnouncementLevel::Info),
+        ("Second", AnnouncementLevel::Warning),
+    ] {
+        let announcement = Announcement {
+            room_id: room.room_id.clone(),
+            text: text.to_string(),
+            level,
+            sent_at: 0,
+        };
+        repo.set_announcement(&announcement, room.ttl_seconds)
+            .await
+            .expect("Should store announcement");
+    }
+
+    let latest = repo.get_announcement(&room.room_id).await.unwrap().unwrap();
+    assert_eq!(latest.text, "Second");
+    assert_eq!(latest.level, AnnouncementLevel::Warning);
+
+    repo.delete_room(&room.room_id).await.unwrap();
+    assert!(repo
+        .get_announcement(&room.room_id)
+        .await
+        .unwrap()
+        .is_none());
+}
@@ -0,0 +1,125 @@
+//! Needs a live Redis; set `REDIS_TEST_URL` (e.g. redis://127.0.0.1:6379/15) to run.
+
+mod common;
+
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use common::WsClient;
+use truegather_backend::api::rooms::ban_identities;
+use truegather_backend::models::Room;
+
+#[tokio::test]
+async fn test_ban_and_unban_by_user_and_fingerprint() {
+    let Some(repo) = common::test_repo() else {
+        return;
+    };
+
+    let room = Room::new("bans".to_string(), 4, 60);
+    repo.create_room(&room).await.expect("Should create room");
+    repo.set_member_fingerprint(&room.room_id, "user-1", "device-1", 60)
+        .await
+        .unwrap();
+
+    // Banning the member also bans the device they joined from
+    let fingerprint = repo
+        .get_member_fingerprint(&room.room_id, "user-1")
+        .await
+        .unwrap();
+    let fingerprints: Vec<String> = fingerprint.into_iter().collect();
+    let identities = ban_identities(Some("user-1"), &fingerprints);
+    repo.add_bans(&room.room_id, &identities, None, 60)
+        .await
+        .unwrap();
+
+    // A rejoin mints a new user id, but the device is still recognized
+    let rejoin = ban_identities(Some("user-2"), &["device-1".to_string()]);
+    assert!(repo.is_banned(&room.room_id, &rejoin).await.unwrap());
+    let stranger = ban_identities(Some("user-3"), &["device-3".to_string()]);
+    assert!(!repo.is_banned(&room.room_id, &stranger).await.unwrap());
+
+    let removed = repo.remove_bans(&room.room_id, &identities).await.unwrap();
+    assert_eq!(removed, 2);
+    assert!(!repo.is_banned(&room.room_id, &rejoin).await.unwrap());
+    let removed = repo.remove_bans(&room.room_id, &identities).await.unwrap();
+    assert_eq!(removed, 0);
+
+    repo.delete_room(&room.room_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_timed_ban_lapses() {
+    let Some(repo) = common::test_repo() else {
+        return;
+    };
+
+    let room = Room::new("timed-ban".to_string(), 4, 60);
+    repo.create_room(&room).await.expect("Should create room");
+
+    let identities = ban_identities(None, &["device-1".to_string()]);
+    let expires_at = chrono::Utc::now().timestamp() + 1;
+    repo.add_bans(&room.room_id, &identities, Some(expires_at), 60)
+        .await
+        .unwrap();
+    assert!(repo.is_banned(&room.room_id, &identities).await.unwrap());
+
+    tokio::time::sleep(Duration::from_millis(2100)).await;
+
+    assert!(!repo.is_banned(&room.room_id, &identities).await.unwrap());
+
+    repo.delete_room(&room.room_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_ban_frees_the_seat_and_closes_the_socket() {
+    let Some(state) = common::test_state(|_| {}) else {
+        return;
+    };
+    let addr = common::serve(&state).await;
+
+    let (room_id, creator_key) = common::create_room(&state, json!({ "name": "ban" })).await;
+    let (_, host_token) = common::host_join(&state, &room_id, &creator_key).await;
+    let mut host = WsClient::join(addr, &room_id, &host_token, "host").await;
+
+    // A guest who publishes (media callbacks then hold a handle to the
+    // connection) and a member who joined over REST but never connected
+    let (guest_id, guest_token) = common::guest_token(&state, &room_id, "guest");
+    let mut guest = WsClient::join(addr, &room_id, &guest_token, "guest").await;
+    let offer = common::audio_offer().await;
+    guest.send("publish_offer", json!({ "sdp": offer })).await;
+    guest.expect("publish_answer").await;
+    state
+        .room_repo
+        .join_member(&room_id, "rest-only", "rest", false)
+        .await
+        .unwrap();
+
+    let uri = format!("/api/v1/rooms/{}/ban", room_id);
+    for user_id in [guest_id.as_str(), "rest-only"] {
+        let request = json!({ "creator_key": creator_key, "user_id": user_id });
+        let (status, body) = common::send_json(&state, "POST", &uri, request).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+    }
+
+    guest.expect("banned").await;
+    while let Some(msg) = guest.next_message().await {
+        assert_ne!(msg["type"], "error", "{}", msg);
+    }
+    let mut gone = Vec::new();
+    for _ in 0..2 {
+        let left = host.expect("member_left").await;
+        gone.push(left["user_id"].as_str().unwrap().to_string());
+    }
+    gone.sort();
+    let mut expected = vec![guest_id.clone(), "rest-only".to_string()];
+    expected.sort();
+    assert_eq!(gone, expected);
+    let repo = &state.room_repo;
+    assert!(!repo.is_member(&room_id, &guest_id).await.unwrap());
+    assert!(!repo.is_member(&room_id, "rest-only").await.unwrap());
+
+    host.close().await;
+    state.room_repo.delete_room(&room_id).await.unwrap();
+}
@@ -0,0 +1,247 @@
+//! Fixtures shared by the integration tests. They need a live Redis; set
+//! `REDIS_TEST_URL` (e.g. redis://127.0.0.1:6379/15) to run, otherwise every
+//! test using them is skipped.
+
+#![allow(dead_code)]
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use deadpool_redis::{Config as RedisConfig, Runtime};
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tower::ServiceExt;
+use webrtc::api::media_engine::MediaEngine;
+use webrtc::api::APIBuilder;
+use webrtc::peer_connection::configuration::RTCConfiguration;
+use webrtc::rtp_transceiver::rtp_codec::RTPCodecType;
+
+use truegather_backend::api::{create_router, served_by_layer};
+use truegather_backend::auth::AuthService;
+use truegather_backend::config::Config;
+use truegather_backend::media::MediaGateway;
+use truegather_backend::redis::RoomRepository;
+use truegather_backend::state::AppState;
+use truegather_backend::ws::ws_routes;
+
+/// Repository on `REDIS_TEST_URL`, or None (after saying so) to skip the test
+pub fn test_repo() -> Option<RoomRepository> {
+    let Ok(url) = std::env::var("REDIS_TEST_URL") else {
+        eprintln!("REDIS_TEST_URL not set, skipping");
+        return None;
+    };
+    let pool = RedisConfig::from_url(url)
+        .create_pool(Some(Runtime::Tokio1))
+        .expect("Should create Redis pool");
+    Some(RoomRepository::new(pool))
+}
+
+const TEST_JWT_SECRET: &str = "integration-tests-only-0123456789abcdef0123456789";
+
+/// App state on `REDIS_TEST_URL` with the environment's config (a throwaway
+/// JWT secret if none is set), adjusted by `configure`
+pub fn test_state(configure: impl FnOnce(&mut Config)) -> Option<AppState> {
+    let room_repo = test_repo()?;
+    if std::env::var("JWT_SECRET").is_err() {
+        std::env::set_var("JWT_SECRET", TEST_JWT_SECRET);
+    }
+    let mut config = Config::from_env().expect("Should load config");
+    configure(&mut config);
+
+    let auth = AuthService::new(&config);
+    let media_gateway = MediaGateway::new(&config).expect("Should create media gateway");
+    Some(AppState::new(config, auth, room_repo, media_gateway, None))
+}
+
+/// Send a JSON request through the REST router; returns the status and the
+/// JSON body (`Null` if there is none)
+pub async fn send_json(
+    state: &AppState,
+    method: &str,
+    uri: &str,
+    body: Value,
+) -> (StatusCode, Value) {
+    send_json_with(state, method, uri, &[], body).await
+}
+
+/// `send_json` with `Authorization: Bearer <token>`
+pub async fn send_json_as(
+    state: &AppState,
+    token: &str,
+    method: &str,
+    uri: &str,
+    body: Value,
+) -> (StatusCode, Value) {
+    let authorization = format!("Bearer {}", token);
+    let headers = [("authorization", authorization.as_str())];
+    send_json_with(state, method, uri, &headers, body).await
+}
+
+/// `send_json` with extra request headers
+pub async fn send_json_with(
+    state: &AppState,
+    method: &str,
+    uri: &str,
+    headers: &[(&str, &str)],
+    body: Value,
+) -> (StatusCode, Value) {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json");
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let request = request.body(Body::from(body.to_string())).unwrap();
+    let response = create_router(state.clone()).oneshot(request).await.unwrap();
+
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+    (status, body)
+}
+
+/// Create a room through the API; returns its id and creator key
+pub async fn create_room(state: &AppState, request: Value) -> (String, String) {
+    let (status, body) = send_json(state, "POST", "/api/v1/rooms", request).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    (
+        body["room_id"].as_str().unwrap().to_string(),
+        body["creator_key"].as_str().unwrap().to_string(),
+    )
+}
+
+/// Join through the API with the creator key; returns the host's user id and token
+pub async fn host_join(state: &AppState, room_id: &str, creator_key: &str) -> (String, String) {
+    let uri = format!("/api/v1/rooms/{}/join", room_id);
+    let request = json!({ "display": "host", "creator_key": creator_key });
+    let (status, body) = send_json(state, "POST", &uri, request).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    (
+        body["user_id"].as_str().unwrap().to_string(),
+        body["token"].as_str().unwrap().to_string(),
+    )
+}
+
+/// Token for a new guest, as a REST join with an invitation would hand out;
+/// returns the guest's user id and token
+pub fn guest_token(state: &AppState, room_id: &str, display: &str) -> (String, String) {
+    let user_id = uuid::Uuid::new_v4().to_string();
+    let (token, _) = state
+        .auth
+        .generate_guest_token(&user_id, room_id, display, false)
+        .unwrap();
+    (user_id, token)
+}
+
+/// Serve the REST and WebSocket routes on an ephemeral local port
+pub async fn serve(state: &AppState) -> SocketAddr {
+    let app = create_router(state.clone())
+        .merge(ws_routes().with_state(state.clone()))
+        .layer(served_by_layer(&state.served_by()));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    addr
+}
+
+/// How long a test waits for the server to send something
+const WS_WAIT: Duration = Duration::from_secs(5);
+
+/// JSON signaling client for a server started by `serve`
+pub struct WsClient {
+    stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+}
+
+impl WsClient {
+    pub async fn connect(addr: SocketAddr, room_id: &str, token: &str) -> Self {
+        let url = format!("ws://{}/ws?room_id={}&token={}", addr, room_id, token);
+        let (stream, _) = connect_async(url).await.expect("Should open WebSocket");
+        Self { stream }
+    }
+
+    /// Connect and complete `join_room`
+    pub async fn join(addr: SocketAddr, room_id: &str, token: &str, display: &str) -> Self {
+        let mut client = Self::connect(addr, room_id, token).await;
+        let payload = json!({ "room_id": room_id, "display": display });
+        client.send("join_room", payload).await;
+        client.expect("joined").await;
+        client
+    }
+
+    pub async fn send(&mut self, msg_type: &str, payload: Value) {
+        let msg = json!({ "type": msg_type, "payload": payload });
+        self.stream
+            .send(Message::text(msg.to_string()))
+            .await
+            .expect("Should send");
+    }
+
+    /// Payload of the next `msg_type` message, skipping others. Panics on an
+    /// `error` message, a closed socket or a timeout.
+    pub async fn expect(&mut self, msg_type: &str) -> Value {
+        loop {
+            let Some(msg) = self.next_message().await else {
+                panic!("Socket closed while waiting for {}", msg_type);
+            };
+            if msg["type"] == msg_type {
+                return msg["payload"].clone();
+            }
+            assert_ne!(msg["type"], "error", "Waiting for {}: {}", msg_type, msg);
+        }
+    }
+
+    /// Next JSON message, or None once the socket is closed
+    pub async fn next_message(&mut self) -> Option<Value> {
+        loop {
+            match self.next_frame().await? {
+                Message::Text(text) => return Some(serde_json::from_str(text.as_str()).unwrap()),
+                Message::Close(_) => return None,
+                _ => {}
+            }
+        }
+    }
+
+    /// Code of the close frame the server ends the connection with, skipping
+    /// messages before it; None if it closes without one
+    pub async fn close_code(&mut self) -> Option<u16> {
+        loop {
+            if let Message::Close(frame) = self.next_frame().await? {
+                return frame.map(|frame| u16::from(frame.code));
+            }
+        }
+    }
+
+    pub async fn close(mut self) {
+        let _ = self.stream.close(None).await;
+    }
+
+    async fn next_frame(&mut self) -> Option<Message> {
+        tokio::time::timeout(WS_WAIT, self.stream.next())
+            .await
+            .expect("Timed out waiting for the server")?
+            .ok()
+    }
+}
+
+/// SDP offer sending one audio track
+pub async fn audio_offer() -> String {
+    let mut media_engine = MediaEngine::default();
+    media_engine.register_default_codecs().unwrap();
+    let api = APIBuilder::new().with_media_engine(media_engine).build();
+    let pc = api
+        .new_peer_connection(RTCConfiguration::default())
+        .await
+        .unwrap();
+    pc.add_transceiver_from_kind(RTPCodecType::Audio, None)
+        .await
+        .unwrap();
+    let offer = pc.create_offer(None).await.unwrap().sdp;
+    pc.close().await.unwrap();
+    offer
+}
@@ -0,0 +1,46 @@
+//! Needs a live Redis; set `REDIS_TEST_URL` (e.g. redis://127.0.0.1:6379/15) to run.
+
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_rotate_key_retires_the_old_key() {
+    let Some(state) = common::test_state(|_| {}) else {
+        return;
+    };
+    let (room_id, old_key) = common::create_room(&state, json!({ "name": "rotation" })).await;
+
+    let rotate = format!("/api/v1/rooms/{}/rotate-key", room_id);
+    let (status, body) =
+        common::send_json(&state, "POST", &rotate, json!({ "creator_key": old_key })).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let new_key = body["creator_key"].as_str().unwrap().to_string();
+    assert_ne!(new_key, old_key);
+
+    // The old key no longer joins as host, nor rotates again
+    let join = format!("/api/v1/rooms/{}/join", room_id);
+    let (status, _) = common::send_json(
+        &state,
+        "POST",
+        &join,
+        json!({ "display": "Host", "creator_key": old_key }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) =
+        common::send_json(&state, "POST", &rotate, json!({ "creator_key": old_key })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = common::send_json(
+        &state,
+        "POST",
+        &join,
+        json!({ "display": "Host", "creator_key": new_key }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    state.room_repo.delete_room(&room_id).await.unwrap();
+}
@@ -0,0 +1,101 @@
+//! Needs a live Redis; set `REDIS_TEST_URL` (e.g. redis://127.0.0.1:6379/15) to run.
+
+mod common;
+
+use std::time::Duration;
+
+use serde_json::json;
+
+use common::WsClient;
+use truegather_backend::state::AppState;
+use truegather_backend::ws::DuplicateSessionPolicy;
+
+/// Wait until the server has dropped all but `remaining` of the user's
+/// connections, then give the disconnect cleanup time to finish
+async fn wait_for_disconnect(state: &AppState, room_id: &str, user_id: &str, remaining: usize) {
+    for _ in 0..50 {
+        let open = state
+            .connections
+            .get_room(room_id)
+            .map_or(0, |room| room.get_clients_by_user_id(user_id).len());
+        if open <= remaining {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(300)).await;
+}
+
+#[tokio::test]
+async fn test_closing_one_connection_keeps_the_others_media() {
+    let Some(state) = common::test_state(|config| {
+        config.duplicate_session_policy = DuplicateSessionPolicy::Allow;
+        config.publisher_reconnect_grace_ms = 0;
+    }) else {
+        return;
+    };
+    let addr = common::serve(&state).await;
+    let (room_id, _) = common::create_room(&state, json!({ "name": "duplicates" })).await;
+
+    // Someone to subscribe to
+    let (speaker_id, token) = common::guest_token(&state, &room_id, "speaker");
+    let mut speaker = WsClient::join(addr, &room_id, &token, "speaker").await;
+    let offer = common::audio_offer().await;
+    speaker.send("publish_offer", json!({ "sdp": offer })).await;
+    speaker.expect("publish_answer").await;
+    let speaker_feed = state
+        .media_gateway
+        .publisher_feed_id(&room_id, &speaker_id)
+        .await
+        .unwrap();
+
+    // Two tabs of one user; the second tab's publish and subscribe replace the first's
+    let (user_id, token) = common::guest_token(&state, &room_id, "tabs");
+    let mut first = WsClient::join(addr, &room_id, &token, "tabs").await;
+    let mut second = WsClient::join(addr, &room_id, &token, "tabs").await;
+    for tab in [&mut first, &mut second] {
+        let offer = common::audio_offer().await;
+        tab.send("publish_offer", json!({ "sdp": offer })).await;
+        tab.expect("publish_answer").await;
+        let feeds = json!({ "feeds": [{ "feed_id": speaker_feed }] });
+        tab.send("subscribe", feeds).await;
+        tab.expect("subscribe_offer").await;
+    }
+    let live_feed = state
+        .media_gateway
+        .publisher_feed_id(&room_id, &user_id)
+        .await;
+
+    first.close().await;
+    wait_for_disconnect(&state, &room_id, &user_id, 1).await;
+
+    // The second tab still publishes and receives
+    assert!(live_feed.is_some());
+    assert_eq!(
+        state
+            .media_gateway
+            .publisher_feed_id(&room_id, &user_id)
+            .await,
+        live_feed
+    );
+    let publishers = state.room_repo.get_publishers(&room_id).await.unwrap();
+    assert!(publishers
+        .iter()
+        .any(|p| p.user_id == user_id && Some(&p.feed_id) == live_feed.as_ref()));
+    assert_eq!(state.media_gateway.get_subscriber_count(&room_id), 1);
+
+    // Its own disconnect cleans up
+    second.close().await;
+    wait_for_disconnect(&state, &room_id, &user_id, 0).await;
+    assert_eq!(
+        state
+            .media_gateway
+            .publisher_feed_id(&room_id, &user_id)
+            .await,
+        None
+    );
+    assert_eq!(state.media_gateway.get_subscriber_count(&room_id), 0);
+
+    speaker.close().await;
+    state.room_repo.delete_room(&room_id).await.unwrap();
+}
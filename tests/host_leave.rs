@@ -0,0 +1,67 @@
+//! Needs a live Redis; set `REDIS_TEST_URL` (e.g. redis://127.0.0.1:6379/15) to run.
+
+mod common;
+
+use std::time::Duration;
+
+use serde_json::json;
+
+use common::WsClient;
+
+#[tokio::test]
+async fn test_host_leave_ends_the_room_and_closes_every_socket() {
+    let Some(state) = common::test_state(|config| config.host_leave_grace_seconds = 0) else {
+        return;
+    };
+    let addr = common::serve(&state).await;
+
+    let request = json!({ "name": "one-to-one", "end_on_host_leave": true });
+    let (room_id, creator_key) = common::create_room(&state, request).await;
+    let (_, host_token) = common::host_join(&state, &room_id, &creator_key).await;
+    let host = WsClient::join(addr, &room_id, &host_token, "host").await;
+    let (_, guest_token) = common::guest_token(&state, &room_id, "guest");
+    let mut guest = WsClient::join(addr, &room_id, &guest_token, "guest").await;
+
+    // Publishing leaves a handle to the guest's connection in the media
+    // callbacks, so only an explicit close ends its socket
+    let offer = common::audio_offer().await;
+    guest.send("publish_offer", json!({ "sdp": offer })).await;
+    guest.expect("publish_answer").await;
+
+    host.close().await;
+    let ended = guest.expect("room_ended").await;
+    assert_eq!(ended["reason"], "host_left");
+    while let Some(msg) = guest.next_message().await {
+        assert_ne!(msg["type"], "error", "{}", msg);
+    }
+    assert!(state.room_repo.get_room(&room_id).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_host_back_on_another_instance_keeps_the_room() {
+    let Some(state) = common::test_state(|config| config.host_leave_grace_seconds = 1) else {
+        return;
+    };
+    let addr = common::serve(&state).await;
+
+    let request = json!({ "name": "one-to-one", "end_on_host_leave": true });
+    let (room_id, creator_key) = common::create_room(&state, request).await;
+    let (host_id, host_token) = common::host_join(&state, &room_id, &creator_key).await;
+    let host = WsClient::join(addr, &room_id, &host_token, "host").await;
+
+    host.close().await;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!state.room_repo.is_member(&room_id, &host_id).await.unwrap());
+
+    // Reconnecting through another instance joins the host back in Redis
+    // without any connection here
+    state
+        .room_repo
+        .join_member(&room_id, &host_id, "host", false)
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert!(state.room_repo.get_room(&room_id).await.unwrap().is_some());
+
+    state.room_repo.delete_room(&room_id).await.unwrap();
+}
@@ -0,0 +1,148 @@
+//! Needs a live Redis; set `REDIS_TEST_URL` (e.g. redis://127.0.0.1:6379/15) to run.
+
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use common::WsClient;
+use truegather_backend::models::Room;
+
+#[tokio::test]
+async fn test_old_creator_key_fails_after_transfer() {
+    let Some(repo) = common::test_repo() else {
+        return;
+    };
+
+    let room = Room::new("transfer".to_string(), 4, 60);
+    repo.create_room(&room).await.expect("Should create room");
+    repo.set_creator_key_hash(&room.room_id, "old-hash", 60)
+        .await
+        .unwrap();
+
+    // The transfer swaps the hash under the room lock
+    let swapped = repo
+        .with_room_lock(&room.room_id, async {
+            repo.rotate_creator_key_hash(&room.room_id, "old-hash", "new-hash")
+                .await
+        })
+        .await
+        .unwrap();
+    assert!(swapped);
+
+    // The outgoing host can neither act nor transfer again with the old key
+    assert_eq!(
+        repo.get_creator_key_hash(&room.room_id).await.unwrap(),
+        Some("new-hash".to_string())
+    );
+    let stale = repo
+        .with_room_lock(&room.room_id, async {
+            repo.rotate_creator_key_hash(&room.room_id, "old-hash", "other-hash")
+                .await
+        })
+        .await
+        .unwrap();
+    assert!(!stale);
+    assert_eq!(
+        repo.get_creator_key_hash(&room.room_id).await.unwrap(),
+        Some("new-hash".to_string())
+    );
+
+    repo.delete_room(&room.room_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_creator_key_outlives_room_by_grace() {
+    let Some(repo) = common::test_repo() else {
+        return;
+    };
+
+    let grace = 30;
+    let room = Room::new("grace".to_string(), 4, 60);
+    repo.create_room(&room).await.expect("Should create room");
+    repo.set_creator_key_hash(&room.room_id, "hash", room.ttl_seconds + grace)
+        .await
+        .unwrap();
+
+    let room_ttl = repo.room_ttl(&room.room_id).await.unwrap().unwrap();
+    let key_ttl = repo.creator_key_ttl(&room.room_id).await.unwrap().unwrap();
+    assert!(key_ttl >= room_ttl + grace - 1);
+
+    // Refreshing the room keeps the lead
+    repo.refresh_room_ttl(&room, 120, grace).await.unwrap();
+    let key_ttl = repo.creator_key_ttl(&room.room_id).await.unwrap().unwrap();
+    assert!(key_ttl > 120);
+
+    // Deleting the room takes the key with it, grace or not
+    repo.delete_room(&room.room_id).await.unwrap();
+    let key = repo.get_creator_key_hash(&room.room_id).await.unwrap();
+    assert_eq!(key, None);
+}
+
+#[tokio::test]
+async fn test_transfer_demotes_the_outgoing_host() {
+    let Some(state) = common::test_state(|_| {}) else {
+        return;
+    };
+    let addr = common::serve(&state).await;
+
+    let (room_id, old_key) = common::create_room(&state, json!({ "name": "handover" })).await;
+    let (host_id, host_token) = common::host_join(&state, &room_id, &old_key).await;
+    let mut host = WsClient::join(addr, &room_id, &host_token, "host").await;
+    let (guest_id, guest_token) = common::guest_token(&state, &room_id, "guest");
+    let guest = WsClient::join(addr, &room_id, &guest_token, "guest").await;
+
+    let uri = format!("/api/v1/rooms/{}/transfer-host", room_id);
+    let request = json!({ "creator_key": old_key, "user_id": guest_id });
+    let (status, body) = common::send_json(&state, "POST", &uri, request).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let new_key = body["creator_key"].as_str().unwrap().to_string();
+
+    // The outgoing host's live connection is told, and loses host powers
+    // although its token still carries the host claim
+    let role = host.expect("role_changed").await;
+    assert_eq!(role["user_id"], host_id.as_str());
+    assert_eq!(role["role"], "participant");
+    host.send("spotlight", json!({ "feed_id": null })).await;
+    loop {
+        let msg = host.next_message().await.expect("Socket should stay open");
+        if msg["type"] == "error" {
+            break;
+        }
+        assert_ne!(msg["type"], "layout", "Demoted host changed the spotlight");
+    }
+
+    // The old key is refused, the new one admits the new host
+    let request = json!({ "creator_key": old_key });
+    let uri = format!("/api/v1/rooms/{}/rotate-key", room_id);
+    let (status, _) = common::send_json(&state, "POST", &uri, request).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    common::host_join(&state, &room_id, &new_key).await;
+
+    host.close().await;
+    guest.close().await;
+    state.room_repo.delete_room(&room_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_refused_host_join_is_not_registered_as_host() {
+    let Some(state) = common::test_state(|config| config.max_connections = 1) else {
+        return;
+    };
+    let addr = common::serve(&state).await;
+    let (room_id, creator_key) = common::create_room(&state, json!({ "name": "busy" })).await;
+    let (host_id, host_token) = common::host_join(&state, &room_id, &creator_key).await;
+    let host = WsClient::join(addr, &room_id, &host_token, "host").await;
+
+    // The instance is at MAX_CONNECTIONS, so a second host join is refused
+    let uri = format!("/api/v1/rooms/{}/join", room_id);
+    let request = json!({ "display": "host-2", "creator_key": creator_key });
+    let (status, _) = common::send_json(&state, "POST", &uri, request).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+    let hosts = state.room_repo.clear_hosts(&room_id).await.unwrap();
+    assert_eq!(hosts, [host_id]);
+
+    host.close().await;
+    state.room_repo.delete_room(&room_id).await.unwrap();
+}
@@ -0,0 +1,171 @@
+//! Needs a live Redis; set `REDIS_TEST_URL` (e.g. redis://127.0.0.1:6379/15) to run.
+
+mod common;
+
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use truegather_backend::models::{Room, RoomInvitation};
+use truegather_backend::security::sign_magic_link;
+
+fn invitation(room_id: &str, ttl_seconds: u64) -> RoomInvitation {
+    RoomInvitation::new_with_code_hash(
+        uuid::Uuid::new_v4().to_string(),
+        room_id.to_string(),
+        "system".to_string(),
+        ttl_seconds,
+        None,
+        None,
+        "hash".to_string(),
+    )
+}
+
+#[tokio::test]
+async fn test_expired_invites_are_pruned_from_room_set() {
+    let Some(repo) = common::test_repo() else {
+        return;
+    };
+
+    let room = Room::new("invites".to_string(), 4, 60);
+    repo.create_room(&room).await.expect("Should create room");
+
+    let short = invitation(&room.room_id, 1);
+    let long = invitation(&room.room_id, 60);
+    repo.create_invitation(&short).await.unwrap();
+    repo.create_invitation(&long).await.unwrap();
+    assert_eq!(repo.prune_room_invites(&room.room_id).await.unwrap(), 2);
+
+    tokio::time::sleep(Duration::from_millis(2100)).await;
+
+    assert_eq!(repo.prune_room_invites(&room.room_id).await.unwrap(), 1);
+    assert!(repo
+        .invite_set_room_ids()
+        .await
+        .unwrap()
+        .contains(&room.room_id));
+
+    // The set goes away with its last reference
+    repo.delete_invitation(&long.token).await.unwrap();
+    assert_eq!(repo.prune_room_invites(&room.room_id).await.unwrap(), 0);
+    assert!(!repo
+        .invite_set_room_ids()
+        .await
+        .unwrap()
+        .contains(&room.room_id));
+
+    repo.delete_room(&room.room_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_magic_link_is_single_use() {
+    let Some(repo) = common::test_repo() else {
+        return;
+    };
+
+    let nonce = uuid::Uuid::new_v4().to_string();
+    assert!(repo.claim_magic_link(&nonce, 1).await.unwrap());
+    assert!(!repo.claim_magic_link(&nonce, 1).await.unwrap());
+
+    // The claim expires along with the link
+    tokio::time::sleep(Duration::from_millis(2100)).await;
+    assert!(repo.claim_magic_link(&nonce, 1).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_viewing_an_invitation_never_rewrites_it() {
+    let Some(repo) = common::test_repo() else {
+        return;
+    };
+
+    let room = Room::new("views".to_string(), 4, 60);
+    repo.create_room(&room).await.expect("Should create room");
+    let mut capped = invitation(&room.room_id, 60);
+    capped.max_uses = Some(1);
+    repo.create_invitation(&capped).await.unwrap();
+
+    // A view after the join keeps the use; the first view's stamp stays
+    repo.mark_invitation_viewed(&capped.token).await.unwrap();
+    let viewed = repo.get_invitation(&capped.token).await.unwrap().unwrap();
+    let first_viewed_at = viewed.first_viewed_at.expect("Should be stamped");
+    assert!(repo.use_invitation(&capped.token).await.unwrap());
+    repo.mark_invitation_viewed(&capped.token).await.unwrap();
+    let used = repo.get_invitation(&capped.token).await.unwrap().unwrap();
+    assert_eq!(used.uses, 1);
+    assert_eq!(used.first_viewed_at, Some(first_viewed_at));
+    assert!(!repo.use_invitation(&capped.token).await.unwrap());
+
+    // A view after a revoke doesn't bring the invitation back
+    repo.delete_invitation(&capped.token).await.unwrap();
+    repo.mark_invitation_viewed(&capped.token).await.unwrap();
+    assert!(repo.get_invitation(&capped.token).await.unwrap().is_none());
+
+    repo.delete_room(&room.room_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_old_invite_code_fails_after_rotation() {
+    let Some(state) = common::test_state(|_| {}) else {
+        return;
+    };
+    let (room_id, creator_key) = common::create_room(&state, json!({ "name": "rotation" })).await;
+
+    let uri = format!("/api/v1/rooms/{}/invite", room_id);
+    let (status, body) = common::send_json(&state, "POST", &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let token = body["token"].as_str().unwrap().to_string();
+
+    let rotate_uri = format!("/api/v1/rooms/{}/invite/{}/rotate-code", room_id, token);
+    let mut codes = Vec::new();
+    for _ in 0..2 {
+        let request = json!({ "creator_key": creator_key });
+        let (status, body) = common::send_json(&state, "POST", &rotate_uri, request).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        codes.push(body["invite_code"].as_str().unwrap().to_string());
+    }
+
+    let join_uri = format!("/api/v1/rooms/{}/join", room_id);
+    let join =
+        |code: &str| json!({ "display": "guest", "invite_token": token, "invite_code": code });
+    let (status, body) = common::send_json(&state, "POST", &join_uri, join(&codes[0])).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"], "Invalid invitation code");
+    let (status, body) = common::send_json(&state, "POST", &join_uri, join(&codes[1])).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    state.room_repo.delete_room(&room_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_failed_magic_link_join_keeps_the_link() {
+    let Some(state) = common::test_state(|c| c.magic_link_ttl_seconds = 600) else {
+        return;
+    };
+    let (room_id, _) = common::create_room(&state, json!({ "name": "magic" })).await;
+
+    let uri = format!("/api/v1/rooms/{}/invite", room_id);
+    let (status, body) = common::send_json(&state, "POST", &uri, json!({ "max_uses": 5 })).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let token = body["token"].as_str().unwrap();
+    let expires_at = chrono::Utc::now().timestamp() + 600;
+    let magic_token = sign_magic_link(&state.config.invite_code_salt, token, expires_at);
+
+    // The join can't take the room lock, so it fails after claiming the link
+    let resource = format!("room:{}", room_id);
+    let lock = state.room_repo.try_lock(&resource).await.unwrap().unwrap();
+    let join_uri = format!("/api/v1/rooms/{}/join", room_id);
+    let join = json!({ "display": "guest", "magic_token": magic_token });
+    let (status, _) = common::send_json(&state, "POST", &join_uri, join.clone()).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(state.room_repo.unlock(&resource, &lock).await.unwrap());
+
+    // The link is still good for its one join
+    let (status, body) = common::send_json(&state, "POST", &join_uri, join.clone()).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (status, body) = common::send_json(&state, "POST", &join_uri, join).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"], "Magic link has already been used");
+
+    state.room_repo.delete_room(&room_id).await.unwrap();
+}
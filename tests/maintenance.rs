@@ -0,0 +1,80 @@
+//! Needs a live Redis; set `REDIS_TEST_URL` (e.g. redis://127.0.0.1:6379/15) to run.
+
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use common::WsClient;
+use truegather_backend::models::Room;
+use truegather_backend::state::AppState;
+
+const ADMIN_KEY: &str = "admin-test-key";
+
+async fn set_maintenance(state: &AppState, enabled: bool) -> (StatusCode, Value) {
+    let uri = "/api/v1/admin/maintenance";
+    let headers = [("x-admin-key", ADMIN_KEY)];
+    common::send_json_with(state, "PUT", uri, &headers, json!({ "enabled": enabled })).await
+}
+
+#[tokio::test]
+async fn test_admin_endpoint_toggles_maintenance() {
+    let Some(state) = common::test_state(|config| {
+        config.maintenance_mode = false;
+        config.admin_api_key = Some(ADMIN_KEY.to_string());
+    }) else {
+        return;
+    };
+
+    let (status, body) = set_maintenance(&state, true).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["enabled"], true);
+    let (_, ready) = common::send_json(&state, "GET", "/ready", json!({})).await;
+    assert_eq!(ready["maintenance"], true);
+    let request = json!({ "name": "paused" });
+    let (status, _) = common::send_json(&state, "POST", "/api/v1/rooms", request).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+    let (status, body) = set_maintenance(&state, false).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["enabled"], false);
+    let (_, ready) = common::send_json(&state, "GET", "/ready", json!({})).await;
+    assert_eq!(ready["maintenance"], false);
+    let (room_id, _) = common::create_room(&state, json!({ "name": "resumed" })).await;
+
+    state.room_repo.delete_room(&room_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_maintenance_refuses_creates_and_joins_but_keeps_sessions() {
+    let Some(state) = common::test_state(|config| config.maintenance_mode = true) else {
+        return;
+    };
+    let addr = common::serve(&state).await;
+
+    // A room, and a host holding a token, from before maintenance started
+    let room = Room::new("maintenance".to_string(), 4, 60);
+    state.room_repo.create_room(&room).await.unwrap();
+    let (token, _) = state
+        .auth
+        .generate_host_token("host-1", &room.room_id, "host")
+        .unwrap();
+
+    let request = json!({ "name": "during-maintenance" });
+    let (status, _) = common::send_json(&state, "POST", "/api/v1/rooms", request).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+    let uri = format!("/api/v1/rooms/{}/join", room.room_id);
+    let request = json!({ "display": "guest", "invite_token": "t", "invite_code": "c" });
+    let (status, _) = common::send_json(&state, "POST", &uri, request).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+    // The admitted host still connects and publishes
+    let mut host = WsClient::join(addr, &room.room_id, &token, "host").await;
+    let offer = common::audio_offer().await;
+    host.send("publish_offer", json!({ "sdp": offer })).await;
+    host.expect("publish_answer").await;
+
+    host.close().await;
+    state.room_repo.delete_room(&room.room_id).await.unwrap();
+}
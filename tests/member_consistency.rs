@@ -0,0 +1,38 @@
+//! Needs a live Redis; set `REDIS_TEST_URL` (e.g. redis://127.0.0.1:6379/15) to run.
+
+mod common;
+
+use truegather_backend::models::Room;
+
+#[tokio::test]
+async fn test_joined_member_appears_in_both_views() {
+    let Some(repo) = common::test_repo() else {
+        return;
+    };
+
+    let room = Room::new("consistency".to_string(), 4, 60);
+    repo.create_room(&room).await.expect("Should create room");
+
+    repo.join_member(&room.room_id, "user-1", "Alice", false)
+        .await
+        .expect("Should join");
+
+    assert!(repo.is_member(&room.room_id, "user-1").await.unwrap());
+    let infos = repo.get_member_infos(&room.room_id).await.unwrap();
+    assert_eq!(infos.len(), 1);
+    assert_eq!(infos[0].user_id, "user-1");
+    assert_eq!(infos[0].display, "Alice");
+
+    repo.leave_member(&room.room_id, "user-1")
+        .await
+        .expect("Should leave");
+
+    assert!(!repo.is_member(&room.room_id, "user-1").await.unwrap());
+    assert!(repo
+        .get_member_infos(&room.room_id)
+        .await
+        .unwrap()
+        .is_empty());
+
+    repo.delete_room(&room.room_id).await.unwrap();
+}
@@ -0,0 +1,65 @@
+//! Needs a live Redis; set `REDIS_TEST_URL` (e.g. redis://127.0.0.1:6379/15) to run.
+
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use common::WsClient;
+
+#[tokio::test]
+async fn test_host_preempts_guest_publisher_in_full_room() {
+    let Some(state) = common::test_state(|_| {}) else {
+        return;
+    };
+    let addr = common::serve(&state).await;
+    let request =
+        json!({ "name": "preemption", "max_publishers": 1, "moderator_preemption": true });
+    let (room_id, creator_key) = common::create_room(&state, request).await;
+
+    // The guest takes the only seat and publishes
+    let (guest_id, guest_token) = common::guest_token(&state, &room_id, "guest");
+    let mut guest = WsClient::join(addr, &room_id, &guest_token, "guest").await;
+    let offer = common::audio_offer().await;
+    guest.send("publish_offer", json!({ "sdp": offer })).await;
+    guest.expect("publish_answer").await;
+
+    // The host still gets in, and publishing demotes the guest
+    let (host_id, host_token) = common::host_join(&state, &room_id, &creator_key).await;
+    let mut host = WsClient::join(addr, &room_id, &host_token, "host").await;
+    let offer = common::audio_offer().await;
+    host.send("publish_offer", json!({ "sdp": offer })).await;
+    host.expect("publish_answer").await;
+
+    let demoted = guest.expect("demoted").await;
+    assert_eq!(demoted["reason"], "preempted");
+    let publishers = state.room_repo.get_publishers(&room_id).await.unwrap();
+    let publisher_ids: Vec<_> = publishers.iter().map(|p| p.user_id.as_str()).collect();
+    assert_eq!(publisher_ids, [host_id.as_str()]);
+    assert!(!state.media_gateway.has_publisher(&room_id, &guest_id));
+
+    host.close().await;
+    guest.close().await;
+    state.room_repo.delete_room(&room_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_full_room_turns_host_away_without_preemption() {
+    let Some(state) = common::test_state(|_| {}) else {
+        return;
+    };
+    let addr = common::serve(&state).await;
+    let request = json!({ "name": "no-preemption", "max_publishers": 1 });
+    let (room_id, creator_key) = common::create_room(&state, request).await;
+
+    let (_, guest_token) = common::guest_token(&state, &room_id, "guest");
+    let guest = WsClient::join(addr, &room_id, &guest_token, "guest").await;
+
+    let uri = format!("/api/v1/rooms/{}/join", room_id);
+    let request = json!({ "display": "host", "creator_key": creator_key });
+    let (status, _) = common::send_json(&state, "POST", &uri, request).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    guest.close().await;
+    state.room_repo.delete_room(&room_id).await.unwrap();
+}
@@ -0,0 +1,63 @@
+//! Needs a live Redis; set `REDIS_TEST_URL` (e.g. redis://127.0.0.1:6379/15) to run.
+
+mod common;
+
+use serde_json::json;
+
+use common::WsClient;
+use truegather_backend::models::Room;
+
+#[tokio::test]
+async fn test_observer_flag_is_persisted() {
+    let Some(repo) = common::test_repo() else {
+        return;
+    };
+
+    let room = Room::new("observers".to_string(), 4, 60);
+    repo.create_room(&room).await.expect("Should create room");
+
+    repo.join_member(&room.room_id, "screen", "Lobby screen", true)
+        .await
+        .expect("Should join");
+    repo.join_member(&room.room_id, "user-1", "Alice", false)
+        .await
+        .expect("Should join");
+
+    let infos = repo.get_member_infos(&room.room_id).await.unwrap();
+    let observers: Vec<_> = infos.iter().filter(|m| m.observer).collect();
+    assert_eq!(observers.len(), 1);
+    assert_eq!(observers[0].user_id, "screen");
+
+    repo.delete_room(&room.room_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_observer_publish_is_refused() {
+    let Some(state) = common::test_state(|_| {}) else {
+        return;
+    };
+    let addr = common::serve(&state).await;
+    let (room_id, _) = common::create_room(&state, json!({ "name": "observers" })).await;
+
+    let user_id = uuid::Uuid::new_v4().to_string();
+    let (token, _) = state
+        .auth
+        .generate_guest_token(&user_id, &room_id, "Lobby screen", true)
+        .unwrap();
+    let mut screen = WsClient::join(addr, &room_id, &token, "Lobby screen").await;
+
+    let offer = common::audio_offer().await;
+    screen.send("publish_offer", json!({ "sdp": offer })).await;
+    let error = screen.expect("error").await;
+    assert_eq!(error["code"], 403);
+    assert!(!state.media_gateway.has_publisher(&room_id, &user_id));
+    assert!(state
+        .room_repo
+        .get_publishers(&room_id)
+        .await
+        .unwrap()
+        .is_empty());
+
+    screen.close().await;
+    state.room_repo.delete_room(&room_id).await.unwrap();
+}
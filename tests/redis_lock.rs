@@ -0,0 +1,30 @@
+//! Needs a live Redis; set `REDIS_TEST_URL` (e.g. redis://127.0.0.1:6379/15) to run.
+
+mod common;
+
+use truegather_backend::error::AppError;
+
+#[tokio::test]
+async fn test_contending_lock_acquisitions() {
+    let Some(repo) = common::test_repo() else {
+        return;
+    };
+    let resource = format!("test:{}", uuid::Uuid::new_v4());
+
+    let first = repo.try_lock(&resource).await.unwrap();
+    let token = first.expect("First acquisition should win");
+    assert!(repo.try_lock(&resource).await.unwrap().is_none());
+
+    // A stale token can't release someone else's lock
+    assert!(!repo.unlock(&resource, "not-the-holder").await.unwrap());
+    assert!(repo.try_lock(&resource).await.unwrap().is_none());
+
+    // with_lock gives up with Conflict while the lock is held
+    let busy = repo.with_lock(&resource, async { Ok(()) }).await;
+    assert!(matches!(busy, Err(AppError::Conflict(_))));
+
+    assert!(repo.unlock(&resource, &token).await.unwrap());
+    let ran = repo.with_lock(&resource, async { Ok(42) }).await.unwrap();
+    assert_eq!(ran, 42);
+    assert!(repo.try_lock(&resource).await.unwrap().is_some());
+}
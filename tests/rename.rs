@@ -0,0 +1,44 @@
+//! Needs a live Redis; set `REDIS_TEST_URL` (e.g. redis://127.0.0.1:6379/15) to run.
+
+mod common;
+
+use truegather_backend::api::rooms::create_publisher_info;
+use truegather_backend::models::Room;
+
+#[tokio::test]
+async fn test_rename_propagates_to_presence() {
+    let Some(repo) = common::test_repo() else {
+        return;
+    };
+
+    let room = Room::new("rename".to_string(), 4, 60);
+    repo.create_room(&room).await.expect("Should create room");
+    repo.join_member(&room.room_id, "user-1", "Alice", false)
+        .await
+        .unwrap();
+    let publisher = create_publisher_info("user-1", "feed-1", "Alice", true, true, false);
+    repo.set_publisher(&room.room_id, "user-1", &publisher)
+        .await
+        .unwrap();
+    let joined_at = repo.get_member_infos(&room.room_id).await.unwrap()[0].joined_at;
+
+    assert!(repo
+        .rename_member(&room.room_id, "user-1", "Alice B.")
+        .await
+        .unwrap());
+
+    // What a late joiner is sent in `joined`
+    let infos = repo.get_member_infos(&room.room_id).await.unwrap();
+    assert_eq!(infos[0].display, "Alice B.");
+    assert_eq!(infos[0].joined_at, joined_at);
+    let publishers = repo.get_publishers(&room.room_id).await.unwrap();
+    assert_eq!(publishers[0].display, "Alice B.");
+
+    // Nobody to rename
+    assert!(!repo
+        .rename_member(&room.room_id, "ghost", "Casper")
+        .await
+        .unwrap());
+
+    repo.delete_room(&room.room_id).await.unwrap();
+}
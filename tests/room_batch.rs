@@ -0,0 +1,35 @@
+//! Needs a live Redis; set `REDIS_TEST_URL` (e.g. redis://127.0.0.1:6379/15) to run.
+
+mod common;
+
+use truegather_backend::models::Room;
+
+#[tokio::test]
+async fn test_batch_writes_rooms_and_creator_keys() {
+    let Some(repo) = common::test_repo() else {
+        return;
+    };
+
+    let rooms: Vec<Room> = (0..3)
+        .map(|i| Room::new(format!("event-{}", i), 4, 60))
+        .collect();
+    let batch: Vec<(&Room, &str)> = rooms.iter().map(|room| (room, "hash")).collect();
+    repo.create_rooms(&batch, 30)
+        .await
+        .expect("Should create rooms");
+
+    for room in &rooms {
+        let stored = repo.get_room(&room.room_id).await.unwrap().unwrap();
+        assert_eq!(stored.name, room.name);
+        let key = repo.get_creator_key_hash(&room.room_id).await.unwrap();
+        assert_eq!(key.as_deref(), Some("hash"));
+
+        let room_ttl = repo.room_ttl(&room.room_id).await.unwrap().unwrap();
+        let key_ttl = repo.creator_key_ttl(&room.room_id).await.unwrap().unwrap();
+        assert!(key_ttl > room_ttl);
+    }
+
+    for room in &rooms {
+        repo.delete_room(&room.room_id).await.unwrap();
+    }
+}
@@ -0,0 +1,49 @@
+//! Needs a live Redis; set `REDIS_TEST_URL` (e.g. redis://127.0.0.1:6379/15) to run.
+
+mod common;
+
+use serde_json::json;
+
+use common::WsClient;
+use truegather_backend::ws::handler::{CLOSE_ROOM_FULL, CLOSE_SERVER_FULL};
+
+#[tokio::test]
+async fn test_connecting_to_a_full_room_closes_with_room_full() {
+    let Some(state) = common::test_state(|_| {}) else {
+        return;
+    };
+    let addr = common::serve(&state).await;
+
+    let request = json!({ "name": "full", "max_publishers": 1 });
+    let (room_id, creator_key) = common::create_room(&state, request).await;
+    let (_, host_token) = common::host_join(&state, &room_id, &creator_key).await;
+    let host = WsClient::join(addr, &room_id, &host_token, "host").await;
+
+    // The host holds the only seat
+    let (_, guest_token) = common::guest_token(&state, &room_id, "guest");
+    let mut guest = WsClient::connect(addr, &room_id, &guest_token).await;
+    assert_eq!(guest.close_code().await, Some(CLOSE_ROOM_FULL));
+
+    host.close().await;
+    state.room_repo.delete_room(&room_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_connecting_past_max_connections_closes_with_server_full() {
+    let Some(state) = common::test_state(|config| config.max_connections = 1) else {
+        return;
+    };
+    let addr = common::serve(&state).await;
+
+    let (room_id, creator_key) = common::create_room(&state, json!({ "name": "busy" })).await;
+    let (_, host_token) = common::host_join(&state, &room_id, &creator_key).await;
+    let host = WsClient::join(addr, &room_id, &host_token, "host").await;
+
+    // The token holder skips the REST join; the upgrade still refuses
+    let (_, guest_token) = common::guest_token(&state, &room_id, "guest");
+    let mut guest = WsClient::connect(addr, &room_id, &guest_token).await;
+    assert_eq!(guest.close_code().await, Some(CLOSE_SERVER_FULL));
+
+    host.close().await;
+    state.room_repo.delete_room(&room_id).await.unwrap();
+}
@@ -0,0 +1,70 @@
+//! Needs a live Redis; set `REDIS_TEST_URL` (e.g. redis://127.0.0.1:6379/15) to run.
+
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use common::WsClient;
+
+#[tokio::test]
+async fn test_locked_room_turns_guests_away_but_admits_hosts() {
+    let Some(state) = common::test_state(|_| {}) else {
+        return;
+    };
+    let addr = common::serve(&state).await;
+    let (room_id, creator_key) = common::create_room(&state, json!({ "name": "lock" })).await;
+
+    // An invitation, and a member promoted to co-host before the lock
+    let uri = format!("/api/v1/rooms/{}/invite", room_id);
+    let (status, body) = common::send_json(&state, "POST", &uri, json!({ "max_uses": 5 })).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let invite_token = body["token"].as_str().unwrap().to_string();
+    let uri = format!(
+        "/api/v1/rooms/{}/invite/{}/rotate-code",
+        room_id, invite_token
+    );
+    let request = json!({ "creator_key": creator_key });
+    let (status, body) = common::send_json(&state, "POST", &uri, request).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let invite_code = body["invite_code"].as_str().unwrap().to_string();
+
+    let (cohost_id, cohost_token) = common::guest_token(&state, &room_id, "cohost");
+    let cohost = WsClient::join(addr, &room_id, &cohost_token, "cohost").await;
+    let uri = format!("/api/v1/rooms/{}/cohosts", room_id);
+    let request = json!({ "creator_key": creator_key, "user_id": cohost_id });
+    let (status, body) = common::send_json(&state, "POST", &uri, request).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let uri = format!("/api/v1/rooms/{}/lock", room_id);
+    let request = json!({ "creator_key": creator_key });
+    let (status, body) = common::send_json(&state, "POST", &uri, request).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    // A guest is refused over REST and over the WebSocket
+    let join_uri = format!("/api/v1/rooms/{}/join", room_id);
+    let join = |display: &str| {
+        let mut request = json!({ "invite_token": invite_token, "invite_code": invite_code });
+        request["display"] = json!(display);
+        request
+    };
+    let (status, _) = common::send_json(&state, "POST", &join_uri, join("guest")).await;
+    assert_eq!(status, StatusCode::LOCKED);
+
+    let (_, guest_token) = common::guest_token(&state, &room_id, "guest");
+    let mut guest = WsClient::connect(addr, &room_id, &guest_token).await;
+    let payload = json!({ "room_id": room_id, "display": "guest" });
+    guest.send("join_room", payload).await;
+    let error = guest.expect("error").await;
+    assert_eq!(error["code"], 423);
+
+    // The host (creator key) and a co-host (bearer JWT) still get in
+    common::host_join(&state, &room_id, &creator_key).await;
+    let (status, body) =
+        common::send_json_as(&state, &cohost_token, "POST", &join_uri, join("cohost-2")).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    guest.close().await;
+    cohost.close().await;
+    state.room_repo.delete_room(&room_id).await.unwrap();
+}
@@ -0,0 +1,57 @@
+//! Needs a live Redis; set `REDIS_TEST_URL` (e.g. redis://127.0.0.1:6379/15) to run.
+
+mod common;
+
+use truegather_backend::models::{Room, RoomStatus};
+
+#[tokio::test]
+async fn test_room_summary_counts_match_info() {
+    let Some(repo) = common::test_repo() else {
+        return;
+    };
+
+    let room = Room::new("summary".to_string(), 3, 60);
+    repo.create_room(&room).await.expect("Should create room");
+
+    let summary = repo.get_room_summary(&room.room_id).await.unwrap().unwrap();
+    assert_eq!(summary.participants_count, 0);
+    assert_eq!(summary.publishers_count, 0);
+
+    repo.join_member(&room.room_id, "user-1", "Alice", false)
+        .await
+        .unwrap();
+    repo.join_member(&room.room_id, "user-2", "Bob", false)
+        .await
+        .unwrap();
+
+    let summary = repo.get_room_summary(&room.room_id).await.unwrap().unwrap();
+    let info = repo.get_room_info(&room.room_id).await.unwrap().unwrap();
+    assert_eq!(summary.participants_count, 2);
+    assert_eq!(summary.participants_count, info.participants_count);
+    assert_eq!(summary.publishers_count, info.publishers.len());
+    assert_eq!(summary.status, RoomStatus::Active);
+
+    // An observer doesn't take a seat; the next seat fills the room
+    repo.join_member(&room.room_id, "user-3", "Carol", true)
+        .await
+        .unwrap();
+    let summary = repo.get_room_summary(&room.room_id).await.unwrap().unwrap();
+    assert_eq!(summary.seats_taken, 2);
+    assert_eq!(summary.status, RoomStatus::Active);
+    repo.join_member(&room.room_id, "user-4", "Dan", false)
+        .await
+        .unwrap();
+    let summary = repo.get_room_summary(&room.room_id).await.unwrap().unwrap();
+    let info = repo.get_room_info(&room.room_id).await.unwrap().unwrap();
+    assert_eq!(summary.seats_taken, 3);
+    assert_eq!(summary.max_publishers, 3);
+    assert_eq!(summary.status, RoomStatus::Full);
+    assert_eq!(summary.status, info.status);
+
+    repo.delete_room(&room.room_id).await.unwrap();
+    assert!(repo
+        .get_room_summary(&room.room_id)
+        .await
+        .unwrap()
+        .is_none());
+}
@@ -0,0 +1,41 @@
+//! Needs a live Redis; set `REDIS_TEST_URL` (e.g. redis://127.0.0.1:6379/15) to run.
+
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_refresh_stops_at_the_maximum_lifetime() {
+    let Some(state) = common::test_state(|config| {
+        config.max_room_ttl_seconds = 0;
+        config.max_room_lifetime_seconds = 600;
+    }) else {
+        return;
+    };
+    let (room_id, creator_key) = common::create_room(&state, json!({ "name": "ttl" })).await;
+    let uri = format!("/api/v1/rooms/{}", room_id);
+
+    // A refresh restarts the TTL, capped by the lifetime left
+    let request = json!({ "creator_key": creator_key, "ttl_seconds": 100_000 });
+    let (status, body) = common::send_json(&state, "PATCH", &uri, request).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let ttl = body["ttl_seconds"].as_u64().unwrap();
+    assert!(ttl > 590 && ttl <= 600, "ttl {}", ttl);
+    let stored = state.room_repo.room_ttl(&room_id).await.unwrap().unwrap();
+    assert!(stored <= ttl && stored + 5 > ttl);
+
+    let request = json!({ "creator_key": "wrong", "ttl_seconds": 60 });
+    let (status, _) = common::send_json(&state, "PATCH", &uri, request).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Once the lifetime is used up the room is left to expire
+    let mut room = state.room_repo.get_room(&room_id).await.unwrap().unwrap();
+    room.created_at -= chrono::Duration::seconds(600);
+    state.room_repo.create_room(&room).await.unwrap();
+    let request = json!({ "creator_key": creator_key, "ttl_seconds": 60 });
+    let (status, _) = common::send_json(&state, "PATCH", &uri, request).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    state.room_repo.delete_room(&room_id).await.unwrap();
+}
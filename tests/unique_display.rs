@@ -0,0 +1,66 @@
+//! Needs a live Redis; set `REDIS_TEST_URL` (e.g. redis://127.0.0.1:6379/15) to run.
+
+mod common;
+
+use serde_json::json;
+
+use common::WsClient;
+
+#[tokio::test]
+async fn test_taken_display_is_refused_only_when_names_must_be_unique() {
+    let Some(state) = common::test_state(|_| {}) else {
+        return;
+    };
+    let addr = common::serve(&state).await;
+
+    for unique in [true, false] {
+        let request = json!({ "name": "unique", "require_unique_display": unique });
+        let (room_id, _) = common::create_room(&state, request).await;
+        let (_, alice_token) = common::guest_token(&state, &room_id, "Alice");
+        let alice = WsClient::join(addr, &room_id, &alice_token, "Alice").await;
+
+        // Names compare case-insensitively
+        let (_, other_token) = common::guest_token(&state, &room_id, "alice");
+        let mut other = WsClient::connect(addr, &room_id, &other_token).await;
+        let payload = json!({ "room_id": room_id, "display": "alice" });
+        other.send("join_room", payload).await;
+        if unique {
+            let error = other.expect("error").await;
+            assert_eq!(error["code"], 409);
+        } else {
+            other.expect("joined").await;
+        }
+
+        other.close().await;
+        alice.close().await;
+        state.room_repo.delete_room(&room_id).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_rename_to_a_taken_display_is_refused() {
+    let Some(state) = common::test_state(|_| {}) else {
+        return;
+    };
+    let addr = common::serve(&state).await;
+    let request = json!({ "name": "unique", "require_unique_display": true });
+    let (room_id, _) = common::create_room(&state, request).await;
+
+    let (_, alice_token) = common::guest_token(&state, &room_id, "Alice");
+    let alice = WsClient::join(addr, &room_id, &alice_token, "Alice").await;
+    let (bob_id, bob_token) = common::guest_token(&state, &room_id, "Bob");
+    let mut bob = WsClient::join(addr, &room_id, &bob_token, "Bob").await;
+
+    bob.send("rename", json!({ "display": "Alice" })).await;
+    let error = bob.expect("error").await;
+    assert_eq!(error["code"], 409);
+
+    // The name stays as it was
+    let members = state.room_repo.get_member_infos(&room_id).await.unwrap();
+    let bob_info = members.iter().find(|m| m.user_id == bob_id).unwrap();
+    assert_eq!(bob_info.display, "Bob");
+
+    bob.close().await;
+    alice.close().await;
+    state.room_repo.delete_room(&room_id).await.unwrap();
+}
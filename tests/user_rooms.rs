@@ -0,0 +1,78 @@
+//! Needs a live Redis; set `REDIS_TEST_URL` (e.g. redis://127.0.0.1:6379/15) to run.
+
+mod common;
+
+use redis::AsyncCommands;
+use truegather_backend::models::Room;
+
+#[tokio::test]
+async fn test_user_rooms_index_reconciles_stale_rooms() {
+    let Some(repo) = common::test_repo() else {
+        return;
+    };
+    let user_id = format!("user-{}", uuid::Uuid::new_v4());
+
+    let kept = Room::new("kept".to_string(), 4, 60);
+    let deleted = Room::new("deleted".to_string(), 4, 60);
+    for room in [&kept, &deleted] {
+        repo.create_room(room).await.expect("Should create room");
+        repo.join_member(&room.room_id, &user_id, "Alice", false)
+            .await
+            .expect("Should join");
+    }
+
+    let mut rooms: Vec<_> = repo
+        .get_user_rooms(&user_id)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|r| r.room_id)
+        .collect();
+    rooms.sort();
+    let mut expected = vec![kept.room_id.clone(), deleted.room_id.clone()];
+    expected.sort();
+    assert_eq!(rooms, expected);
+
+    // A deleted room drops out on read; leaving removes the entry directly
+    repo.delete_room(&deleted.room_id).await.unwrap();
+    let rooms = repo.get_user_rooms(&user_id).await.unwrap();
+    assert_eq!(rooms.len(), 1);
+    assert_eq!(rooms[0].room_id, kept.room_id);
+
+    repo.leave_member(&kept.room_id, &user_id).await.unwrap();
+    assert!(repo.get_user_rooms(&user_id).await.unwrap().is_empty());
+
+    repo.delete_room(&kept.room_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_user_rooms_index_outlives_its_longest_room() {
+    let Some(repo) = common::test_repo() else {
+        return;
+    };
+    let user_id = format!("user-{}", uuid::Uuid::new_v4());
+
+    // Joining a short-lived room after a long-lived one keeps the longer TTL
+    let long = Room::new("long".to_string(), 4, 600);
+    let short = Room::new("short".to_string(), 4, 60);
+    for room in [&long, &short] {
+        repo.create_room(room).await.expect("Should create room");
+        repo.join_member(&room.room_id, &user_id, "Alice", false)
+            .await
+            .expect("Should join");
+    }
+
+    let url = std::env::var("REDIS_TEST_URL").unwrap();
+    let mut conn = redis::Client::open(url)
+        .unwrap()
+        .get_multiplexed_async_connection()
+        .await
+        .unwrap();
+    let ttl: i64 = conn.ttl(format!("user:{}:rooms", user_id)).await.unwrap();
+    assert!(ttl > 60, "index TTL shrank to {}", ttl);
+
+    for room in [&long, &short] {
+        repo.leave_member(&room.room_id, &user_id).await.unwrap();
+        repo.delete_room(&room.room_id).await.unwrap();
+    }
+}